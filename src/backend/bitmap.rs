@@ -0,0 +1,377 @@
+use super::{string::string_range, Backend, BackendError, StringValue, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// The unit of the offsets of BITCOUNT and BITPOS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitUnit {
+    #[default]
+    Byte,
+    Bit,
+}
+
+/// The range of BITCOUNT and BITPOS, both ends inclusive. Negative offsets count from the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitRange {
+    pub start: i64,
+    // BITPOS may leave out the end, which then is the end of the string
+    pub end: Option<i64>,
+    pub unit: BitUnit,
+}
+
+/// The type of a BITFIELD integer: "i1" to "i64" or "u1" to "u63".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitfieldType {
+    pub signed: bool,
+    pub bits: u32,
+}
+
+/// What BITFIELD SET and INCRBY do with a value which doesn't fit in the type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitfieldOverflow {
+    // wrap around, the same as the integer arithmetic of most languages
+    #[default]
+    Wrap,
+    // saturate to the minimum or maximum value of the type
+    Sat,
+    // leave the value alone and reply nil
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitfieldOp {
+    Get {
+        ty: BitfieldType,
+        offset: u64,
+    },
+    Set {
+        ty: BitfieldType,
+        offset: u64,
+        value: i64,
+        overflow: BitfieldOverflow,
+    },
+    IncrBy {
+        ty: BitfieldType,
+        offset: u64,
+        increment: i64,
+        overflow: BitfieldOverflow,
+    },
+}
+
+impl BitfieldType {
+    fn min(&self) -> i128 {
+        if self.signed {
+            -(1i128 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    fn max(&self) -> i128 {
+        if self.signed {
+            (1i128 << (self.bits - 1)) - 1
+        } else {
+            (1i128 << self.bits) - 1
+        }
+    }
+
+    // sign extend the bits read from the string
+    fn decode(&self, raw: u64) -> i64 {
+        if self.signed && self.bits < 64 && (raw >> (self.bits - 1)) & 1 == 1 {
+            (raw | (u64::MAX << self.bits)) as i64
+        } else {
+            raw as i64
+        }
+    }
+
+    // the low bits of the value, in two's complement for negative ones
+    fn encode(&self, value: i64) -> u64 {
+        match self.bits {
+            64 => value as u64,
+            bits => value as u64 & ((1 << bits) - 1),
+        }
+    }
+
+    // apply the overflow policy, None if the value doesn't fit and the policy is FAIL
+    fn fit(&self, value: i128, overflow: BitfieldOverflow) -> Option<i64> {
+        let (min, max) = (self.min(), self.max());
+        if (min..=max).contains(&value) {
+            return Some(value as i64);
+        }
+
+        match overflow {
+            BitfieldOverflow::Wrap => Some(((value - min).rem_euclid(1 << self.bits) + min) as i64),
+            BitfieldOverflow::Sat => Some(value.clamp(min, max) as i64),
+            BitfieldOverflow::Fail => None,
+        }
+    }
+}
+
+impl Backend {
+    /// Set or clear the bit at the offset, the string grows as needed. Returns the old bit.
+    pub fn setbit(&self, key: String, offset: u64, bit: bool) -> Result<bool, BackendError> {
+        self.upsert(
+            key,
+            || Value::String(StringValue::Raw(Vec::new())),
+            |v| {
+                let bytes = v.as_string_mut()?.as_bytes_mut();
+                let old = get_bit(bytes, offset);
+                set_bit(bytes, offset, bit);
+                Ok(old)
+            },
+        )
+    }
+
+    /// The bit at the offset, bits past the end of the string are 0.
+    pub fn getbit(&self, key: &str, offset: u64) -> Result<bool, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(get_bit(&v.as_string()?.as_bytes(), offset)))?
+            .unwrap_or_default())
+    }
+
+    /// The number of set bits in the range, or in the whole string.
+    pub fn bitcount(&self, key: &str, range: Option<BitRange>) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let bytes = v.as_string()?.as_bytes();
+                let Some(range) = range else {
+                    return Ok(count_ones(&bytes, 0, bytes.len() as u64 * 8));
+                };
+
+                let end = range.end.unwrap_or(-1);
+                Ok(match range.unit {
+                    BitUnit::Byte => {
+                        string_range(range.start, end, bytes.len()).map(|(start, end)| {
+                            count_ones(&bytes, start as u64 * 8, end as u64 * 8 + 8)
+                        })
+                    }
+                    BitUnit::Bit => string_range(range.start, end, bytes.len() * 8)
+                        .map(|(start, end)| count_ones(&bytes, start as u64, end as u64 + 1)),
+                }
+                .unwrap_or_default())
+            })?
+            .unwrap_or_default())
+    }
+
+    /// The position of the first bit set to `bit` in the range, or in the whole string. A
+    /// missing key is an empty string padded with zeros.
+    pub fn bitpos(
+        &self,
+        key: &str,
+        bit: bool,
+        range: Option<BitRange>,
+    ) -> Result<i64, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                Ok(find_bit(&v.as_string()?.as_bytes(), bit, range))
+            })?
+            .unwrap_or(if bit { -1 } else { 0 }))
+    }
+
+    /// Store the result of the bitwise operation between the strings at the destination, the
+    /// shorter strings are padded with zeros. Returns the length of the result, an empty result
+    /// deletes the destination.
+    pub fn bitop(
+        &self,
+        op: BitOperation,
+        destination: String,
+        keys: &[String],
+    ) -> Result<usize, BackendError> {
+        let sources = keys
+            .iter()
+            .map(|key| Ok(self.get(key)?.unwrap_or_default()))
+            .collect::<Result<Vec<_>, BackendError>>()?;
+
+        let len = sources.iter().map(Vec::len).max().unwrap_or_default();
+        let ret = match op {
+            BitOperation::Not => sources
+                .first()
+                .map(|v| v.iter().map(|b| !b).collect())
+                .unwrap_or_default(),
+            op => (0..len)
+                .map(|i| {
+                    let mut bytes = sources.iter().map(|v| v.get(i).copied().unwrap_or(0));
+                    let first = bytes.next().unwrap_or(0);
+                    bytes.fold(first, |acc, b| match op {
+                        BitOperation::And => acc & b,
+                        BitOperation::Or => acc | b,
+                        _ => acc ^ b,
+                    })
+                })
+                .collect::<Vec<_>>(),
+        };
+
+        let len = ret.len();
+        if ret.is_empty() {
+            self.remove(&destination);
+        } else {
+            self.put(destination, Value::String(ret.into()));
+        }
+        Ok(len)
+    }
+
+    /// Run the BITFIELD operations in order, atomically. Returns the value of each operation,
+    /// None for the SET and INCRBY operations which failed with OVERFLOW FAIL.
+    pub fn bitfield(
+        &self,
+        key: String,
+        ops: &[BitfieldOp],
+    ) -> Result<Vec<Option<i64>>, BackendError> {
+        // a read only call must not create the key
+        if ops.iter().all(|op| matches!(op, BitfieldOp::Get { .. })) {
+            return Ok(self
+                .read(&key, |v| {
+                    let bytes = v.as_string()?.as_bytes();
+                    Ok(ops.iter().map(|op| bitfield_get(&bytes, op)).collect())
+                })?
+                .unwrap_or_else(|| vec![Some(0); ops.len()]));
+        }
+
+        self.upsert(
+            key,
+            || Value::String(StringValue::Raw(Vec::new())),
+            |v| {
+                let bytes = v.as_string_mut()?.as_bytes_mut();
+                Ok(ops.iter().map(|op| bitfield_apply(bytes, op)).collect())
+            },
+        )
+    }
+}
+
+fn bitfield_get(bytes: &[u8], op: &BitfieldOp) -> Option<i64> {
+    match *op {
+        BitfieldOp::Get { ty, offset }
+        | BitfieldOp::Set { ty, offset, .. }
+        | BitfieldOp::IncrBy { ty, offset, .. } => {
+            Some(ty.decode(get_bits(bytes, offset, ty.bits)))
+        }
+    }
+}
+
+fn bitfield_apply(bytes: &mut Vec<u8>, op: &BitfieldOp) -> Option<i64> {
+    match *op {
+        BitfieldOp::Get { .. } => bitfield_get(bytes, op),
+        // SET replies the old value
+        BitfieldOp::Set {
+            ty,
+            offset,
+            value,
+            overflow,
+        } => {
+            let old = ty.decode(get_bits(bytes, offset, ty.bits));
+            let value = ty.fit(value as i128, overflow)?;
+            set_bits(bytes, offset, ty.bits, ty.encode(value));
+            Some(old)
+        }
+        // INCRBY replies the new value
+        BitfieldOp::IncrBy {
+            ty,
+            offset,
+            increment,
+            overflow,
+        } => {
+            let old = ty.decode(get_bits(bytes, offset, ty.bits));
+            let value = ty.fit(old as i128 + increment as i128, overflow)?;
+            set_bits(bytes, offset, ty.bits, ty.encode(value));
+            Some(value)
+        }
+    }
+}
+
+// bits are numbered from the most significant bit of the first byte
+fn get_bit(bytes: &[u8], offset: u64) -> bool {
+    bytes
+        .get((offset / 8) as usize)
+        .is_some_and(|b| b & (0x80 >> (offset % 8)) != 0)
+}
+
+fn set_bit(bytes: &mut Vec<u8>, offset: u64, bit: bool) {
+    let i = (offset / 8) as usize;
+    if bytes.len() <= i {
+        bytes.resize(i + 1, 0);
+    }
+    let mask = 0x80 >> (offset % 8);
+    if bit {
+        bytes[i] |= mask;
+    } else {
+        bytes[i] &= !mask;
+    }
+}
+
+// read `bits` bits from the offset as an unsigned big endian integer
+fn get_bits(bytes: &[u8], offset: u64, bits: u32) -> u64 {
+    (0..bits as u64).fold(0, |acc, i| (acc << 1) | get_bit(bytes, offset + i) as u64)
+}
+
+fn set_bits(bytes: &mut Vec<u8>, offset: u64, bits: u32, value: u64) {
+    for i in 0..bits {
+        let bit = (value >> (bits - 1 - i)) & 1 == 1;
+        set_bit(bytes, offset + i as u64, bit);
+    }
+}
+
+// the number of set bits between the bit offsets, the end is exclusive. Whole bytes are
+// counted at once, which matters for bitmaps of several megabytes
+fn count_ones(bytes: &[u8], start: u64, end: u64) -> usize {
+    let (first, last) = (start.div_ceil(8), end / 8);
+    if first >= last {
+        return (start..end).filter(|i| get_bit(bytes, *i)).count();
+    }
+
+    let head = (start..first * 8).filter(|i| get_bit(bytes, *i)).count();
+    let body = bytes[first as usize..last as usize]
+        .iter()
+        .map(|b| b.count_ones() as usize)
+        .sum::<usize>();
+    let tail = (last * 8..end).filter(|i| get_bit(bytes, *i)).count();
+    head + body + tail
+}
+
+fn find_bit(bytes: &[u8], bit: bool, range: Option<BitRange>) -> i64 {
+    let unit = range.map(|r| r.unit).unwrap_or_default();
+    let len = match unit {
+        BitUnit::Byte => bytes.len() as i64,
+        BitUnit::Bit => bytes.len() as i64 * 8,
+    };
+    let end = range.and_then(|r| r.end);
+
+    // unlike BITCOUNT, the end is always clamped into the string
+    let resolve = |i: i64| if i < 0 { (i + len).max(0) } else { i };
+    let start = resolve(range.map(|r| r.start).unwrap_or(0));
+    let stop = resolve(end.unwrap_or(-1)).min(len - 1);
+    if start > stop {
+        return -1;
+    }
+
+    let (first, last) = match unit {
+        BitUnit::Byte => (start as u64 * 8, stop as u64 * 8 + 7),
+        BitUnit::Bit => (start as u64, stop as u64),
+    };
+    let skip = if bit { 0x00 } else { 0xff };
+    let mut pos = first;
+    while pos <= last {
+        // skip the whole bytes without the bit we are looking for
+        if pos % 8 == 0 && pos + 7 <= last && bytes[(pos / 8) as usize] == skip {
+            pos += 8;
+            continue;
+        }
+        if get_bit(bytes, pos) == bit {
+            return pos as i64;
+        }
+        pos += 1;
+    }
+
+    // looking for a clear bit without an explicit end, the string is considered padded with
+    // zeros on the right
+    if !bit && end.is_none() {
+        last as i64 + 1
+    } else {
+        -1
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{
+    sync::{oneshot, Notify},
+    time::Instant,
+};
+
+use super::{Backend, BackendError, ListEnd};
+
+/// What a blocked client does once one of the lists it waits on is not empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockingOp {
+    // BLPOP, BRPOP, BLMPOP: pop up to count elements from one end of the list
+    Pop {
+        end: ListEnd,
+        count: usize,
+    },
+    // BLMOVE: move an element to the destination list
+    Move {
+        destination: String,
+        from: ListEnd,
+        to: ListEnd,
+    },
+}
+
+/// The key which served a blocked client, together with the elements popped from it.
+pub type ServedKey = (String, Vec<Vec<u8>>);
+
+type Served = Result<ServedKey, BackendError>;
+
+#[derive(Debug, Default)]
+pub(crate) struct BlockingState {
+    next_id: u64,
+    waiters: HashMap<u64, Waiter>,
+    // ids of the clients blocked on each key, in the order they blocked
+    queues: HashMap<String, VecDeque<u64>>,
+    // clients blocked on streams, e.g. by XREAD BLOCK. Reading a stream doesn't consume it, so
+    // they are all woken up when an entry is added to one of their keys
+    readers: HashMap<u64, Reader>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    keys: Vec<String>,
+    op: BlockingOp,
+    tx: oneshot::Sender<Served>,
+}
+
+#[derive(Debug)]
+struct Reader {
+    keys: Vec<String>,
+    notify: Arc<Notify>,
+}
+
+// unregisters the blocked client when it stops waiting, whether it timed out or the connection
+// was closed while it was blocked
+struct BlockedClient<'a> {
+    backend: &'a Backend,
+    id: u64,
+}
+
+struct BlockedReader<'a> {
+    backend: &'a Backend,
+    id: u64,
+}
+
+impl BlockingState {
+    fn register(&mut self, keys: Vec<String>, op: BlockingOp, tx: oneshot::Sender<Served>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        for key in keys.iter() {
+            self.queues.entry(key.clone()).or_default().push_back(id);
+        }
+        self.waiters.insert(id, Waiter { keys, op, tx });
+        id
+    }
+
+    fn register_reader(&mut self, keys: Vec<String>, notify: Arc<Notify>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.readers.insert(id, Reader { keys, notify });
+        id
+    }
+
+    fn remove(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+        for key in waiter.keys.iter() {
+            if let Some(queue) = self.queues.get_mut(key) {
+                queue.retain(|v| *v != id);
+                if queue.is_empty() {
+                    self.queues.remove(key);
+                }
+            }
+        }
+        Some(waiter)
+    }
+}
+
+impl Drop for BlockedClient<'_> {
+    fn drop(&mut self) {
+        self.backend.blocking().lock().unwrap().remove(self.id);
+    }
+}
+
+impl Drop for BlockedReader<'_> {
+    fn drop(&mut self) {
+        self.backend
+            .blocking()
+            .lock()
+            .unwrap()
+            .readers
+            .remove(&self.id);
+    }
+}
+
+impl Backend {
+    /// Run the operation against the first non-empty list among the keys without blocking.
+    /// Returns the key which was served together with the popped elements, None if all the
+    /// lists are empty.
+    pub fn try_blocking_op(
+        &self,
+        keys: &[String],
+        op: &BlockingOp,
+    ) -> Result<Option<ServedKey>, BackendError> {
+        let mut state = self.blocking().lock().unwrap();
+        self.try_serve(&mut state, keys, op)
+    }
+
+    /// Run the operation against the first non-empty list among the keys, if they are all empty
+    /// wait until one of them is pushed to. Clients blocked on the same key are served in the
+    /// order they blocked. A timeout of None waits forever, None is returned on timeout.
+    pub async fn blocking_op(
+        &self,
+        keys: Vec<String>,
+        op: BlockingOp,
+        timeout: Option<Duration>,
+    ) -> Result<Option<ServedKey>, BackendError> {
+        let (client, mut rx) = {
+            let _guard = self.shared_guard();
+            let mut locked = keys.clone();
+            if let BlockingOp::Move { destination, .. } = &op {
+                locked.push(destination.clone());
+            }
+            let _keys = self.lock_keys(&locked, true);
+            let mut state = self.blocking().lock().unwrap();
+            // registering in the same critical section as the attempt, a push in between can't
+            // be missed
+            if let Some(ret) = self.try_serve(&mut state, &keys, &op)? {
+                return Ok(Some(ret));
+            }
+
+            let (tx, rx) = oneshot::channel();
+            let id = state.register(keys, op, tx);
+            (BlockedClient { backend: self, id }, rx)
+        };
+
+        let ret = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, &mut rx).await.ok(),
+            None => Some((&mut rx).await),
+        };
+        // unregister before looking at the channel again, so that a client served right when the
+        // timeout fired still gets its elements
+        drop(client);
+        match ret.and_then(|v| v.ok()).or_else(|| rx.try_recv().ok()) {
+            Some(served) => served.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The number of clients currently blocked.
+    pub fn blocked_clients(&self) -> usize {
+        let state = self.blocking().lock().unwrap();
+        state.waiters.len() + state.readers.len()
+    }
+
+    /// Serve the clients blocked on the key, it must be called whenever a list is pushed to.
+    pub(crate) fn signal_ready(&self, key: &str) {
+        let mut state = self.blocking().lock().unwrap();
+        if state.queues.contains_key(key) {
+            self.serve(&mut state, key.to_string());
+        }
+    }
+
+    /// Serve the clients blocked on any key of the database and wake up the stream readers,
+    /// once its data was swapped.
+    pub(crate) fn signal_all(&self) {
+        let mut state = self.blocking().lock().unwrap();
+        let keys = state.queues.keys().cloned().collect::<Vec<_>>();
+        for key in keys {
+            self.serve(&mut state, key);
+        }
+        for reader in state.readers.values() {
+            reader.notify.notify_one();
+        }
+    }
+
+    /// Run `read` until it returns something, waiting for an entry to be added to one of the
+    /// streams in between. A timeout of None waits forever, None is returned on timeout.
+    /// `read` gets the database selected again on each attempt, its data may have been swapped
+    /// or flushed during the wait.
+    pub(crate) async fn block_on_streams<T>(
+        &self,
+        keys: Vec<String>,
+        timeout: Option<Duration>,
+        mut read: impl FnMut(&Backend) -> Result<Option<T>, BackendError>,
+    ) -> Result<Option<T>, BackendError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let notify = Arc::new(Notify::new());
+        let _reader = {
+            let _guard = self.shared_guard();
+            let mut state = self.blocking().lock().unwrap();
+            // like blocking_op, an entry added in between can't be missed
+            if let Some(ret) = read(self)? {
+                return Ok(Some(ret));
+            }
+
+            let id = state.register_reader(keys, notify.clone());
+            BlockedReader { backend: self, id }
+        };
+
+        loop {
+            match deadline {
+                Some(deadline) => {
+                    if tokio::time::timeout_at(deadline, notify.notified())
+                        .await
+                        .is_err()
+                    {
+                        return Ok(None);
+                    }
+                }
+                None => notify.notified().await,
+            }
+
+            // the new entries may already be trimmed or deleted, keep waiting then
+            let ret = {
+                let _guard = self.shared_guard();
+                read(&self.select(self.index)?)?
+            };
+            if let Some(ret) = ret {
+                return Ok(Some(ret));
+            }
+        }
+    }
+
+    /// Wake up the clients blocked on the stream, it must be called whenever an entry is added.
+    pub(crate) fn signal_stream(&self, key: &str) {
+        let state = self.blocking().lock().unwrap();
+        for reader in state.readers.values() {
+            if reader.keys.iter().any(|v| v == key) {
+                // the permit is kept if the reader is not waiting right now
+                reader.notify.notify_one();
+            }
+        }
+    }
+
+    fn try_serve(
+        &self,
+        state: &mut BlockingState,
+        keys: &[String],
+        op: &BlockingOp,
+    ) -> Result<Option<ServedKey>, BackendError> {
+        for key in keys {
+            if let Some(elements) = self.run_blocking_op(key, op)? {
+                if let BlockingOp::Move { destination, .. } = op {
+                    self.serve(state, destination.clone());
+                }
+                return Ok(Some((key.clone(), elements)));
+            }
+        }
+        Ok(None)
+    }
+
+    // serve the clients blocked on the key in FIFO order until the list is empty, the lists
+    // pushed to by BLMOVE are served in turn
+    fn serve(&self, state: &mut BlockingState, key: String) {
+        let mut ready = VecDeque::from([key]);
+        while let Some(key) = ready.pop_front() {
+            while let Some(id) = state.queues.get(&key).and_then(|v| v.front().copied()) {
+                // the key may have been overwritten since it was pushed to
+                if self.key_type(&key) != Some("list") {
+                    break;
+                }
+
+                let waiter = &state.waiters[&id];
+                if waiter.tx.is_closed() {
+                    state.remove(id);
+                    continue;
+                }
+
+                let op = waiter.op.clone();
+                let served = match self.run_blocking_op(&key, &op) {
+                    Ok(None) => break,
+                    Ok(Some(elements)) => Ok((key.clone(), elements)),
+                    Err(e) => Err(e),
+                };
+                if let (Ok(_), BlockingOp::Move { destination, .. }) = (&served, op) {
+                    ready.push_back(destination);
+                }
+                if let Some(waiter) = state.remove(id) {
+                    let _ = waiter.tx.send(served);
+                }
+            }
+        }
+    }
+
+    fn run_blocking_op(
+        &self,
+        key: &str,
+        op: &BlockingOp,
+    ) -> Result<Option<Vec<Vec<u8>>>, BackendError> {
+        match op {
+            BlockingOp::Pop { end, count } => self.list_pop(key, *end, *count),
+            BlockingOp::Move {
+                destination,
+                from,
+                to,
+            } => Ok(self
+                .move_element(key, destination.clone(), *from, *to)?
+                .map(|element| vec![element])),
+        }
+    }
+}
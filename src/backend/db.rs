@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+
+use super::{blocking::BlockingState, lazy_free, Backend, BackendError, BackendInner};
+
+impl Backend {
+    /// The index of the selected database.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn database_count(&self) -> usize {
+        self.inner.blocking.len()
+    }
+
+    /// A handle to the database at the index. The handle keeps the data the database holds
+    /// right now, so a command run through it is ordered before a later SWAPDB or FLUSHDB:
+    /// select again before each command.
+    pub fn select(&self, index: usize) -> Result<Backend, BackendError> {
+        Self::from_inner(self.inner.clone(), index)
+    }
+
+    pub(super) fn from_inner(inner: Arc<BackendInner>, index: usize) -> Result<Self, BackendError> {
+        let db = inner
+            .databases
+            .read()
+            .unwrap()
+            .get(index)
+            .cloned()
+            .ok_or(BackendError::DbIndexOutOfRange)?;
+        Ok(Backend { inner, index, db })
+    }
+
+    /// Point the handle to the data the database holds right now, which a SWAPDB or FLUSHDB
+    /// may have replaced since it was selected.
+    pub fn reselect(&mut self) {
+        if let Ok(backend) = self.select(self.index) {
+            *self = backend;
+        }
+    }
+
+    /// The number of keys in the selected database, including the expired keys which are not
+    /// reclaimed yet.
+    pub fn dbsize(&self) -> usize {
+        self.keyspace.len()
+    }
+
+    /// Swap the data of two databases, the connections which selected one of them see the data
+    /// of the other one from their next command. The caller runs it with `exclusive`, so that
+    /// no command is running against the data which moves. The clients blocked on keys of the databases are served
+    /// if the new data allows it, and the watched keys which exist on either side are
+    /// modified.
+    pub fn swapdb(&self, a: usize, b: usize) -> Result<(), BackendError> {
+        let swapped = {
+            let mut databases = self.inner.databases.write().unwrap();
+            if a >= databases.len() || b >= databases.len() {
+                return Err(BackendError::DbIndexOutOfRange);
+            }
+            databases.swap(a, b);
+            [databases[a].clone(), databases[b].clone()]
+        };
+
+        for index in [a, b] {
+            for data in swapped.iter() {
+                self.touch_existing(index, data);
+            }
+            self.select(index)?.signal_all();
+        }
+        Ok(())
+    }
+
+    /// Move the key together with its timeout to another database. Returns false if the key
+    /// does not exist or if it exists in the other database. The caller runs it with the key
+    /// locked by `shared`, which locks it in every database, so the key can't be created in
+    /// the other database between the check and the move.
+    pub fn move_key(&self, key: &str, index: usize) -> Result<bool, BackendError> {
+        if index == self.index {
+            return Err(BackendError::SameObject);
+        }
+        let target = self.select(index)?;
+        if target.exists(key) {
+            return Ok(false);
+        }
+
+        let Some((value, at)) = self.take(key) else {
+            return Ok(false);
+        };
+        target.put_with_expire(key.to_string(), value, at);
+        Ok(true)
+    }
+
+    /// Delete every key of the selected database. If `lazy` is set the data is freed on a
+    /// background thread. Like `swapdb`, the caller runs it with `exclusive`.
+    pub fn flushdb(&self, lazy: bool) {
+        let old = std::mem::take(&mut self.inner.databases.write().unwrap()[self.index]);
+        self.touch_existing(self.index, &old);
+        // the connections still running a command against the old data keep it alive until
+        // they are done
+        if lazy {
+            lazy_free(old);
+        }
+    }
+
+    /// Delete every key of every database, see `flushdb`.
+    pub fn flushall(&self, lazy: bool) {
+        let old = self
+            .inner
+            .databases
+            .write()
+            .unwrap()
+            .iter_mut()
+            .map(std::mem::take)
+            .collect::<Vec<_>>();
+        for (index, data) in old.iter().enumerate() {
+            self.touch_existing(index, data);
+        }
+        if lazy {
+            lazy_free(old);
+        }
+    }
+
+    pub(crate) fn blocking(&self) -> &Mutex<BlockingState> {
+        &self.inner.blocking[self.index]
+    }
+}
@@ -0,0 +1,141 @@
+use super::{lazy_free, scan_map::Entry, Backend, BackendError, Value};
+
+// values which take more than this many allocations to free are freed in the background by
+// UNLINK, the same threshold redis uses
+const LAZYFREE_THRESHOLD: usize = 64;
+
+impl Backend {
+    /// Delete the keys, whatever the type of their values. Returns the number of keys deleted.
+    pub fn del(&self, keys: &[String]) -> usize {
+        keys.iter().filter(|key| self.remove(key)).count()
+    }
+
+    /// Delete the keys like DEL, but free the large values on a background thread, so that the
+    /// caller isn't stalled by dropping millions of elements.
+    pub fn unlink(&self, keys: &[String]) -> usize {
+        let values = keys
+            .iter()
+            .filter_map(|key| self.take(key))
+            .map(|(value, _)| value)
+            .collect::<Vec<_>>();
+        let n = values.len();
+
+        let large = values
+            .into_iter()
+            .filter(|v| free_effort(v) > LAZYFREE_THRESHOLD)
+            .collect::<Vec<_>>();
+        if !large.is_empty() {
+            lazy_free(large);
+        }
+        n
+    }
+
+    /// Move the value of the key to the new key together with its timeout, the new key is
+    /// replaced unless `nx` is set. Returns false if the new key exists and `nx` is set. The
+    /// caller runs it with both keys locked by `shared`, so that no other command sees the key
+    /// under neither name or writes the new key between the check and the move.
+    pub fn rename(&self, key: &str, newkey: String, nx: bool) -> Result<bool, BackendError> {
+        if !self.exists(key) {
+            return Err(BackendError::NoSuchKey);
+        }
+        if key == newkey {
+            return Ok(!nx);
+        }
+        if nx && self.exists(&newkey) {
+            return Ok(false);
+        }
+
+        let (value, at) = self.take(key).ok_or(BackendError::NoSuchKey)?;
+        self.put_with_expire(newkey, value, at);
+        Ok(true)
+    }
+
+    /// Copy the value of the source to the destination together with its timeout, into the
+    /// database at `db` if it is set. The destination is only replaced if `replace` is set.
+    /// Returns whether the value was copied. Like `rename`, the caller runs it with both keys
+    /// locked, the lock of a key covers it in every database.
+    pub fn copy(
+        &self,
+        source: &str,
+        destination: String,
+        db: Option<usize>,
+        replace: bool,
+    ) -> Result<bool, BackendError> {
+        let target = match db {
+            Some(index) => self.select(index)?,
+            None => self.clone(),
+        };
+        if target.index() == self.index() && source == destination {
+            return Err(BackendError::SameObject);
+        }
+
+        self.expire_if_needed(source);
+        let Some(value) = self.keyspace.get(source).map(|v| v.value().clone()) else {
+            return Ok(false);
+        };
+        let at = self.expires.get(source).map(|v| *v.value());
+        if !replace && target.exists(&destination) {
+            return Ok(false);
+        }
+
+        target.put_with_expire(destination, value, at);
+        Ok(true)
+    }
+
+    /// A random live key, None if the keyspace is empty.
+    pub fn random_key(&self) -> Option<String> {
+        // a few tries, in case the picked keys have just expired
+        for _ in 0..100 {
+            let key = self.keyspace.random_key()?;
+            if !self.expire_if_needed(&key) {
+                return Some(key);
+            }
+        }
+        None
+    }
+
+    // store the value with the timeout, replacing the key, and wake up the clients blocked on it
+    pub(super) fn put_with_expire(&self, key: String, value: Value, at: Option<u64>) {
+        let volatile = matches!(&value, Value::Hash(hash) if hash.next_expire_time().is_some());
+        let type_name = value.type_name();
+        {
+            // NOTE: lock order is always expires -> keyspace
+            let expires = self.expires.entry(key.clone());
+            self.keyspace.insert(key.clone(), value);
+            match (expires, at) {
+                (Entry::Occupied(mut v), Some(at)) => {
+                    v.insert(at);
+                }
+                (Entry::Occupied(v), None) => {
+                    v.remove();
+                }
+                (Entry::Vacant(v), Some(at)) => {
+                    v.insert(at);
+                }
+                (Entry::Vacant(_), None) => {}
+            }
+        }
+
+        if volatile {
+            self.volatile_hashes.insert(key.clone(), ());
+        }
+        self.touch(&key);
+        match type_name {
+            "list" => self.signal_ready(&key),
+            "stream" => self.signal_stream(&key),
+            _ => {}
+        }
+    }
+}
+
+// roughly the number of allocations freeing the value takes
+fn free_effort(value: &Value) -> usize {
+    match value {
+        Value::String(_) => 1,
+        Value::Hash(v) => v.len(),
+        Value::Set(v) => v.len(),
+        Value::List(v) => v.len(),
+        Value::ZSet(v) => v.len(),
+        Value::Stream(v) => v.len(),
+    }
+}
@@ -0,0 +1,448 @@
+use super::{Backend, BackendError, ScoreBound, SortedSet, Value, ZAddOptions, ZRangeBy};
+
+// a geo index is a sorted set scored by the 52 bits geohash of the members, the same encoding
+// redis uses: 26 bits of latitude interleaved with 26 bits of longitude
+const GEO_STEP_MAX: u32 = 26;
+const GEO_LAT_MIN: f64 = -85.05112878;
+const GEO_LAT_MAX: f64 = 85.05112878;
+const GEO_LONG_MIN: f64 = -180.0;
+const GEO_LONG_MAX: f64 = 180.0;
+const EARTH_RADIUS_IN_METERS: f64 = 6372797.560856;
+const MERCATOR_MAX: f64 = 20037726.37;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GeoUnit {
+    #[default]
+    Meters,
+    Kilometers,
+    Feet,
+    Miles,
+}
+
+/// The center of a GEOSEARCH, FROMMEMBER or FROMLONLAT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoOrigin {
+    Member(Vec<u8>),
+    LonLat(f64, f64),
+}
+
+/// The area of a GEOSEARCH, in the unit of the search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoShape {
+    Radius(f64),
+    Box { width: f64, height: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeoSort {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoQuery {
+    pub origin: GeoOrigin,
+    pub shape: GeoShape,
+    pub unit: GeoUnit,
+    // None leaves the matches in the order they were found, unless there is a COUNT
+    pub sort: Option<GeoSort>,
+    pub count: Option<usize>,
+    // ANY: stop as soon as COUNT matches are found, instead of returning the closest ones
+    pub any: bool,
+}
+
+/// A member found by GEOSEARCH, the distance is in the unit of the search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMatch {
+    pub member: Vec<u8>,
+    pub distance: f64,
+    pub hash: u64,
+    pub longitude: f64,
+    pub latitude: f64,
+}
+
+// a cell of the grid of a geohash step
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GeoArea {
+    lon_min: f64,
+    lon_max: f64,
+    lat_min: f64,
+    lat_max: f64,
+}
+
+impl GeoUnit {
+    fn meters(&self) -> f64 {
+        match self {
+            GeoUnit::Meters => 1.0,
+            GeoUnit::Kilometers => 1000.0,
+            GeoUnit::Feet => 0.3048,
+            GeoUnit::Miles => 1609.34,
+        }
+    }
+}
+
+impl Backend {
+    /// Add the members at their coordinates, the options are the ones of ZADD. Returns the
+    /// number of added members, or of changed members if the CH option is set.
+    pub fn geoadd(
+        &self,
+        key: String,
+        points: Vec<(f64, f64, Vec<u8>)>,
+        options: ZAddOptions,
+    ) -> Result<usize, BackendError> {
+        // nothing is added if any of the coordinates is invalid
+        if let Some((lon, lat, _)) = points.iter().find(|(lon, lat, _)| !valid(*lon, *lat)) {
+            return Err(BackendError::InvalidLonLat(format!(
+                "{:.6},{:.6}",
+                lon, lat
+            )));
+        }
+
+        let members = points
+            .into_iter()
+            .map(|(lon, lat, member)| (encode(lon, lat) as f64, member))
+            .collect();
+        self.zadd(key, members, options)
+    }
+
+    /// The coordinates of the members, as (longitude, latitude).
+    pub fn geopos(
+        &self,
+        key: &str,
+        members: &[Vec<u8>],
+    ) -> Result<Vec<Option<(f64, f64)>>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let zset = v.as_zset()?;
+                Ok(members
+                    .iter()
+                    .map(|m| zset.score(m).map(|score| decode(score as u64)))
+                    .collect())
+            })?
+            .unwrap_or_else(|| vec![None; members.len()]))
+    }
+
+    /// The distance between the two members, None if any of them is missing.
+    pub fn geodist(
+        &self,
+        key: &str,
+        member1: &[u8],
+        member2: &[u8],
+        unit: GeoUnit,
+    ) -> Result<Option<f64>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let zset = v.as_zset()?;
+                Ok(zset.score(member1).zip(zset.score(member2)).map(|(a, b)| {
+                    let ((lon1, lat1), (lon2, lat2)) = (decode(a as u64), decode(b as u64));
+                    distance(lon1, lat1, lon2, lat2) / unit.meters()
+                }))
+            })?
+            .flatten())
+    }
+
+    /// The members in the area of the query.
+    pub fn geosearch(&self, key: &str, query: &GeoQuery) -> Result<Vec<GeoMatch>, BackendError> {
+        Ok(self
+            .read(key, |v| search(v.as_zset()?, query))?
+            .unwrap_or_default())
+    }
+
+    /// Store the members in the area of the query at the destination, scored by their geohash
+    /// or by their distance if `store_dist` is set. Returns the number of stored members, an
+    /// empty result deletes the destination.
+    pub fn geosearchstore(
+        &self,
+        destination: String,
+        key: &str,
+        query: &GeoQuery,
+        store_dist: bool,
+    ) -> Result<usize, BackendError> {
+        let mut zset = SortedSet::new();
+        for m in self.geosearch(key, query)? {
+            let score = if store_dist {
+                m.distance
+            } else {
+                m.hash as f64
+            };
+            zset.insert(m.member, score);
+        }
+
+        let n = zset.len();
+        if zset.is_empty() {
+            self.remove(&destination);
+        } else {
+            self.put(destination, Value::ZSet(zset));
+        }
+        Ok(n)
+    }
+}
+
+fn valid(lon: f64, lat: f64) -> bool {
+    (GEO_LONG_MIN..=GEO_LONG_MAX).contains(&lon) && (GEO_LAT_MIN..=GEO_LAT_MAX).contains(&lat)
+}
+
+// spread the bits of v to the even bits of the result
+fn spread(v: u32) -> u64 {
+    let mut v = v as u64;
+    v = (v | (v << 16)) & 0x0000ffff0000ffff;
+    v = (v | (v << 8)) & 0x00ff00ff00ff00ff;
+    v = (v | (v << 4)) & 0x0f0f0f0f0f0f0f0f;
+    v = (v | (v << 2)) & 0x3333333333333333;
+    (v | (v << 1)) & 0x5555555555555555
+}
+
+// the reverse of spread, the odd bits are dropped
+fn squash(v: u64) -> u32 {
+    let mut v = v & 0x5555555555555555;
+    v = (v | (v >> 1)) & 0x3333333333333333;
+    v = (v | (v >> 2)) & 0x0f0f0f0f0f0f0f0f;
+    v = (v | (v >> 4)) & 0x00ff00ff00ff00ff;
+    v = (v | (v >> 8)) & 0x0000ffff0000ffff;
+    ((v | (v >> 16)) & 0x00000000ffffffff) as u32
+}
+
+// the latitude takes the even bits and the longitude the odd ones
+fn interleave(lat: u32, lon: u32) -> u64 {
+    spread(lat) | (spread(lon) << 1)
+}
+
+// the cell of the coordinates in the grid of the step, as (latitude, longitude) indexes
+fn cell(lon: f64, lat: f64, step: u32) -> (u32, u32) {
+    let cells = (1u64 << step) as f64;
+    let max = (1u32 << step) - 1;
+    let lat = (lat - GEO_LAT_MIN) / (GEO_LAT_MAX - GEO_LAT_MIN);
+    let lon = (lon - GEO_LONG_MIN) / (GEO_LONG_MAX - GEO_LONG_MIN);
+    // the maximum coordinates belong to the last cell
+    (
+        ((lat * cells) as u32).min(max),
+        ((lon * cells) as u32).min(max),
+    )
+}
+
+fn encode(lon: f64, lat: f64) -> u64 {
+    let (lat, lon) = cell(lon, lat, GEO_STEP_MAX);
+    interleave(lat, lon)
+}
+
+fn area(lat: u32, lon: u32, step: u32) -> GeoArea {
+    let cells = (1u64 << step) as f64;
+    let lat_scale = GEO_LAT_MAX - GEO_LAT_MIN;
+    let lon_scale = GEO_LONG_MAX - GEO_LONG_MIN;
+    GeoArea {
+        lat_min: GEO_LAT_MIN + (lat as f64 / cells) * lat_scale,
+        lat_max: GEO_LAT_MIN + ((lat as f64 + 1.0) / cells) * lat_scale,
+        lon_min: GEO_LONG_MIN + (lon as f64 / cells) * lon_scale,
+        lon_max: GEO_LONG_MIN + ((lon as f64 + 1.0) / cells) * lon_scale,
+    }
+}
+
+// a geohash stands for the center of its cell
+fn decode(hash: u64) -> (f64, f64) {
+    let area = area(squash(hash), squash(hash >> 1), GEO_STEP_MAX);
+    let lon = ((area.lon_min + area.lon_max) / 2.0).clamp(GEO_LONG_MIN, GEO_LONG_MAX);
+    let lat = ((area.lat_min + area.lat_max) / 2.0).clamp(GEO_LAT_MIN, GEO_LAT_MAX);
+    (lon, lat)
+}
+
+fn lat_distance(lat1: f64, lat2: f64) -> f64 {
+    EARTH_RADIUS_IN_METERS * (lat2.to_radians() - lat1.to_radians()).abs()
+}
+
+// the haversine distance in meters
+fn distance(lon1: f64, lat1: f64, lon2: f64, lat2: f64) -> f64 {
+    let (lon1, lat1, lon2, lat2) = (
+        lon1.to_radians(),
+        lat1.to_radians(),
+        lon2.to_radians(),
+        lat2.to_radians(),
+    );
+    let v = ((lon2 - lon1) / 2.0).sin();
+    // the same meridian, which doesn't need the expensive math
+    if v == 0.0 {
+        return EARTH_RADIUS_IN_METERS * (lat2 - lat1).abs();
+    }
+    let u = ((lat2 - lat1) / 2.0).sin();
+    let a = u * u + lat1.cos() * lat2.cos() * v * v;
+    2.0 * EARTH_RADIUS_IN_METERS * a.sqrt().asin()
+}
+
+// the smallest step whose cells are larger than the radius
+fn estimate_step(mut radius: f64, lat: f64) -> u32 {
+    if radius == 0.0 {
+        return GEO_STEP_MAX;
+    }
+    let mut step: i32 = 1;
+    while radius < MERCATOR_MAX {
+        radius *= 2.0;
+        step += 1;
+    }
+    // make sure the radius is covered in most cases
+    step -= 2;
+    // the cells are narrower towards the poles
+    if !(-66.0..=66.0).contains(&lat) {
+        step -= 1;
+        if !(-80.0..=80.0).contains(&lat) {
+            step -= 1;
+        }
+    }
+    step.clamp(1, GEO_STEP_MAX as i32) as u32
+}
+
+// the score ranges of the cells to scan: the cell of the center and its neighbours, found the
+// same way redis does, the neighbours out of the bounding box are left out
+fn search_ranges(lon: f64, lat: f64, radius: f64, bounds: &GeoArea) -> Vec<(u64, u64)> {
+    let neighbour = |v: u32, d: i64, step: u32| (v as i64 + d).rem_euclid(1 << step) as u32;
+
+    let mut step = estimate_step(radius, lat);
+    let (mut lat_cell, mut lon_cell) = cell(lon, lat, step);
+    // the estimated step may be too large when the area is near the edge of its cell
+    let north = area(neighbour(lat_cell, 1, step), lon_cell, step);
+    let south = area(neighbour(lat_cell, -1, step), lon_cell, step);
+    let east = area(lat_cell, neighbour(lon_cell, 1, step), step);
+    let west = area(lat_cell, neighbour(lon_cell, -1, step), step);
+    if step > 1
+        && (north.lat_max < bounds.lat_max
+            || south.lat_min > bounds.lat_min
+            || east.lon_max < bounds.lon_max
+            || west.lon_min > bounds.lon_min)
+    {
+        step -= 1;
+        (lat_cell, lon_cell) = cell(lon, lat, step);
+    }
+
+    let center = area(lat_cell, lon_cell, step);
+    let skip = |dlat: i64, dlon: i64| {
+        step >= 2
+            && ((dlat < 0 && center.lat_min < bounds.lat_min)
+                || (dlat > 0 && center.lat_max > bounds.lat_max)
+                || (dlon < 0 && center.lon_min < bounds.lon_min)
+                || (dlon > 0 && center.lon_max > bounds.lon_max))
+    };
+
+    let shift = 2 * (GEO_STEP_MAX - step);
+    let mut ranges = Vec::with_capacity(9);
+    // the center, north, south, east, west, north east, north west, south east, south west
+    for (dlat, dlon) in [
+        (0, 0),
+        (1, 0),
+        (-1, 0),
+        (0, 1),
+        (0, -1),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ] {
+        if skip(dlat, dlon) {
+            continue;
+        }
+        let bits = interleave(
+            neighbour(lat_cell, dlat, step),
+            neighbour(lon_cell, dlon, step),
+        );
+        let range = (bits << shift, (bits + 1) << shift);
+        // near the poles, or with a small step, the neighbours may be the same cell
+        if !ranges.contains(&range) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+fn search(zset: &SortedSet, query: &GeoQuery) -> Result<Vec<GeoMatch>, BackendError> {
+    let (lon, lat) = match &query.origin {
+        GeoOrigin::LonLat(lon, lat) if !valid(*lon, *lat) => {
+            return Err(BackendError::InvalidLonLat(format!(
+                "{:.6},{:.6}",
+                lon, lat
+            )))
+        }
+        GeoOrigin::LonLat(lon, lat) => (*lon, *lat),
+        GeoOrigin::Member(member) => {
+            decode(zset.score(member).ok_or(BackendError::UndecodableMember)? as u64)
+        }
+    };
+
+    let meters = query.unit.meters();
+    let (half_width, half_height, radius) = match query.shape {
+        GeoShape::Radius(radius) => (radius * meters, radius * meters, radius * meters),
+        GeoShape::Box { width, height } => {
+            let (w, h) = (width * meters / 2.0, height * meters / 2.0);
+            (w, h, (w * w + h * h).sqrt())
+        }
+    };
+
+    // the bounding box of the area, the meridians get closer towards the poles
+    let lat_delta = (half_height / EARTH_RADIUS_IN_METERS).to_degrees();
+    let lon_delta_top =
+        (half_width / EARTH_RADIUS_IN_METERS / (lat + lat_delta).to_radians().cos()).to_degrees();
+    let lon_delta_bottom =
+        (half_width / EARTH_RADIUS_IN_METERS / (lat - lat_delta).to_radians().cos()).to_degrees();
+    let lon_delta = if lat < 0.0 {
+        lon_delta_bottom
+    } else {
+        lon_delta_top
+    };
+    let bounds = GeoArea {
+        lon_min: lon - lon_delta,
+        lon_max: lon + lon_delta,
+        lat_min: lat - lat_delta,
+        lat_max: lat + lat_delta,
+    };
+
+    let mut matches = Vec::new();
+    'scan: for (min, max) in search_ranges(lon, lat, radius, &bounds) {
+        let by = ZRangeBy::Score(
+            ScoreBound::Inclusive(min as f64),
+            ScoreBound::Exclusive(max as f64),
+        );
+        for (member, score) in zset.range(&by, false, None) {
+            let (member_lon, member_lat) = decode(score as u64);
+            let distance = match query.shape {
+                GeoShape::Radius(_) => {
+                    let distance = distance(lon, lat, member_lon, member_lat);
+                    if distance > radius {
+                        continue;
+                    }
+                    distance
+                }
+                // the cheaper latitude distance is checked first
+                GeoShape::Box { .. } => {
+                    if lat_distance(member_lat, lat) > half_height
+                        || distance(member_lon, member_lat, lon, member_lat) > half_width
+                    {
+                        continue;
+                    }
+                    distance(lon, lat, member_lon, member_lat)
+                }
+            };
+
+            matches.push(GeoMatch {
+                member,
+                distance: distance / meters,
+                hash: score as u64,
+                longitude: member_lon,
+                latitude: member_lat,
+            });
+            if query.any && Some(matches.len()) == query.count {
+                break 'scan;
+            }
+        }
+    }
+
+    // COUNT without ANY returns the closest matches
+    let sort = match (query.sort, query.count) {
+        (None, Some(_)) if !query.any => Some(GeoSort::Asc),
+        (sort, _) => sort,
+    };
+    match sort {
+        Some(GeoSort::Asc) => matches.sort_by(|a, b| a.distance.total_cmp(&b.distance)),
+        Some(GeoSort::Desc) => matches.sort_by(|a, b| b.distance.total_cmp(&a.distance)),
+        None => {}
+    }
+    if let Some(count) = query.count {
+        matches.truncate(count);
+    }
+    Ok(matches)
+}
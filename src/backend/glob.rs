@@ -0,0 +1,77 @@
+/// Match the string against a glob-style pattern with the rules of redis: `*` matches any
+/// sequence, `?` any single byte, `[abc]`, `[a-z]` and `[^a]` match a class of bytes, and `\`
+/// escapes the next byte.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // where to resume after the last `*`: the pattern after it, and the string position it
+    // has consumed up to
+    let mut star = None;
+    while s < string.len() {
+        if pattern.get(p) == Some(&b'*') {
+            p += 1;
+            star = Some((p, s));
+            continue;
+        }
+
+        if p < pattern.len() {
+            let (matched, next) = match_one(pattern, p, string[s]);
+            if matched {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+
+        // let the last `*` swallow one more byte, every token but `*` matches a single byte
+        // so there is no need to go back further
+        match star {
+            Some((after, consumed)) => {
+                p = after;
+                s = consumed + 1;
+                star = Some((after, s));
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// match a single byte against the token at `p`, which is not `*`. Returns whether it matched
+// and the position of the next token
+fn match_one(pattern: &[u8], p: usize, c: u8) -> (bool, usize) {
+    match pattern[p] {
+        b'?' => (true, p + 1),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c, p + 2),
+        b'[' => {
+            let mut i = p + 1;
+            let not = pattern.get(i) == Some(&b'^');
+            if not {
+                i += 1;
+            }
+
+            let mut found = false;
+            // an unterminated class extends to the end of the pattern
+            while i < pattern.len() && pattern[i] != b']' {
+                if pattern[i] == b'\\' && i + 1 < pattern.len() {
+                    found |= pattern[i + 1] == c;
+                    i += 2;
+                } else if pattern.get(i + 1) == Some(&b'-') && i + 2 < pattern.len() {
+                    let (start, end) = (pattern[i], pattern[i + 2]);
+                    let (start, end) = if start > end {
+                        (end, start)
+                    } else {
+                        (start, end)
+                    };
+                    found |= (start..=end).contains(&c);
+                    i += 3;
+                } else {
+                    found |= pattern[i] == c;
+                    i += 1;
+                }
+            }
+            (found != not, (i + 1).min(pattern.len()))
+        }
+        v => (v == c, p + 1),
+    }
+}
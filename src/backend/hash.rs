@@ -0,0 +1,490 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::atomic::Ordering,
+    time::Instant,
+};
+
+use crate::{BulkString, RespFrame};
+
+use super::{
+    now_ms, random_picks, scan::ScanOrder, Backend, BackendError, Value, ACTIVE_EXPIRE_SAMPLES,
+};
+
+/// A hash: fields and their values, some fields may have a time to live of their own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hash {
+    fields: HashMap<String, RespFrame>,
+    // the fields in the order HSCAN visits them
+    order: ScanOrder<String>,
+    // absolute expiration time (unix milliseconds) of volatile fields
+    expires: HashMap<String, u64>,
+    // the volatile fields by their expiration time, the next one to expire first
+    expire_order: BTreeSet<(u64, String)>,
+}
+
+/// The condition of HEXPIRE for a field to get its new expiration time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpireCondition {
+    // NX: the field has no expiration time
+    NotExists,
+    // XX: the field already has an expiration time
+    Exists,
+    // GT: the new time is greater than the current one, a field without one never matches
+    Greater,
+    // LT: the new time is less than the current one, a field without one always matches
+    Less,
+}
+
+impl Hash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&RespFrame> {
+        self.fields.get(field)
+    }
+
+    /// Update the value of the field in place, its expiration time is kept.
+    pub fn get_mut(&mut self, field: &str) -> Option<&mut RespFrame> {
+        self.fields.get_mut(field)
+    }
+
+    pub fn contains_key(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    /// Set the value of the field, the field becomes persistent. Returns the old value.
+    pub fn insert(&mut self, field: String, value: RespFrame) -> Option<RespFrame> {
+        self.remove_expire_time(&field);
+        if !self.fields.contains_key(&field) {
+            self.order.insert(field.clone());
+        }
+        self.fields.insert(field, value)
+    }
+
+    pub fn remove(&mut self, field: &str) -> Option<RespFrame> {
+        self.remove_expire_time(field);
+        let value = self.fields.remove(field)?;
+        self.order.remove(field.to_string());
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&String, &RespFrame)> {
+        self.fields.iter()
+    }
+
+    /// The fields of the next HSCAN page, see `ScanOrder::page`.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<&String>, Option<u64>) {
+        self.order.page(cursor, count)
+    }
+
+    /// The absolute expiration time (unix milliseconds) of the field, None if it is persistent.
+    pub fn expire_time(&self, field: &str) -> Option<u64> {
+        self.expires.get(field).copied()
+    }
+
+    /// Set the absolute expiration time of the field according to the condition. Returns the
+    /// HEXPIRE result code: -2 if the field does not exist, 0 if the condition is not met, 1 if
+    /// the time was set and 2 if the field was deleted as the time is in the past.
+    pub fn expire_at(
+        &mut self,
+        field: &str,
+        at: u64,
+        condition: Option<ExpireCondition>,
+        now: u64,
+    ) -> i64 {
+        if !self.contains_key(field) {
+            return -2;
+        }
+
+        let current = self.expire_time(field);
+        let matched = match condition {
+            None => true,
+            Some(ExpireCondition::NotExists) => current.is_none(),
+            Some(ExpireCondition::Exists) => current.is_some(),
+            Some(ExpireCondition::Greater) => current.is_some_and(|v| at > v),
+            Some(ExpireCondition::Less) => current.is_none_or(|v| at < v),
+        };
+        if !matched {
+            return 0;
+        }
+
+        if at <= now {
+            self.remove(field);
+            return 2;
+        }
+        self.remove_expire_time(field);
+        self.expires.insert(field.to_string(), at);
+        self.expire_order.insert((at, field.to_string()));
+        1
+    }
+
+    /// Make the field persistent. Returns true if it had an expiration time.
+    pub fn persist(&mut self, field: &str) -> bool {
+        self.remove_expire_time(field).is_some()
+    }
+
+    /// The earliest expiration time of the fields, None if they are all persistent.
+    pub fn next_expire_time(&self) -> Option<u64> {
+        self.expire_order.first().map(|(at, _)| *at)
+    }
+
+    /// Delete the fields whose time to live has elapsed, the earliest first. Returns the
+    /// number of fields deleted.
+    pub fn remove_expired(&mut self, now: u64) -> usize {
+        let mut n = 0;
+        while let Some((_, field)) = self.expire_order.first().filter(|(at, _)| *at <= now) {
+            let field = field.clone();
+            self.remove(&field);
+            n += 1;
+        }
+        n
+    }
+
+    fn remove_expire_time(&mut self, field: &str) -> Option<u64> {
+        let at = self.expires.remove(field)?;
+        self.expire_order.remove(&(at, field.to_string()));
+        Some(at)
+    }
+}
+
+impl Backend {
+    pub fn hget(&self, key: &str, field: &str) -> Result<Option<RespFrame>, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_hash()?.get(field).cloned()))?
+            .flatten())
+    }
+
+    /// Set the fields, overwriting the existing ones. Returns the number of fields added.
+    pub fn hset(
+        &self,
+        key: String,
+        fields: Vec<(String, RespFrame)>,
+    ) -> Result<usize, BackendError> {
+        self.upsert(
+            key,
+            || Value::Hash(Hash::new()),
+            |v| {
+                let hash = v.as_hash_mut()?;
+                let mut added = 0;
+                for (field, value) in fields {
+                    if hash.insert(field, value).is_none() {
+                        added += 1;
+                    }
+                }
+                Ok(added)
+            },
+        )
+    }
+
+    /// Set the field only if it does not exist yet. Returns true if the field was set.
+    pub fn hsetnx(
+        &self,
+        key: String,
+        field: String,
+        value: RespFrame,
+    ) -> Result<bool, BackendError> {
+        self.upsert(
+            key,
+            || Value::Hash(Hash::new()),
+            |v| {
+                let hash = v.as_hash_mut()?;
+                if hash.contains_key(&field) {
+                    return Ok(false);
+                }
+                hash.insert(field, value);
+                Ok(true)
+            },
+        )
+    }
+
+    /// Returns the number of fields removed, the key is deleted with its last field.
+    pub fn hdel(&self, key: &str, fields: &[String]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let hash = v.as_hash_mut()?;
+                Ok(fields.iter().filter(|f| hash.remove(f).is_some()).count())
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn hexists(&self, key: &str, field: &str) -> Result<bool, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_hash()?.contains_key(field)))?
+            .unwrap_or_default())
+    }
+
+    pub fn hlen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_hash()?.len()))?
+            .unwrap_or_default())
+    }
+
+    pub fn hkeys(&self, key: &str) -> Result<Vec<String>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                Ok(v.as_hash()?.iter().map(|(f, _)| f.clone()).collect())
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn hvals(&self, key: &str) -> Result<Vec<RespFrame>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                Ok(v.as_hash()?.iter().map(|(_, v)| v.clone()).collect())
+            })?
+            .unwrap_or_default())
+    }
+
+    /// The length of the value of the field, 0 if the field does not exist.
+    pub fn hstrlen(&self, key: &str, field: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                Ok(v.as_hash()?
+                    .get(field)
+                    .and_then(frame_to_string)
+                    .map(|v| v.len()))
+            })?
+            .flatten()
+            .unwrap_or_default())
+    }
+
+    /// Add the increment to the integer value of the field, a missing field counts as 0.
+    /// Returns the new value.
+    pub fn hincrby(&self, key: String, field: String, increment: i64) -> Result<i64, BackendError> {
+        self.upsert(
+            key,
+            || Value::Hash(Hash::new()),
+            |v| {
+                let hash = v.as_hash_mut()?;
+                let current = match hash.get(&field) {
+                    Some(value) => frame_to_string(value)
+                        .and_then(|v| v.parse::<i64>().ok())
+                        .ok_or(BackendError::HashValueNotInteger)?,
+                    None => 0,
+                };
+                let value = current
+                    .checked_add(increment)
+                    .ok_or(BackendError::IncrementOverflow)?;
+                set_keep_ttl(hash, field, BulkString::from(value.to_string()).into());
+                Ok(value)
+            },
+        )
+    }
+
+    /// Add the increment to the float value of the field, a missing field counts as 0. Returns
+    /// the new value.
+    pub fn hincrbyfloat(
+        &self,
+        key: String,
+        field: String,
+        increment: f64,
+    ) -> Result<f64, BackendError> {
+        self.upsert(
+            key,
+            || Value::Hash(Hash::new()),
+            |v| {
+                let hash = v.as_hash_mut()?;
+                let current = match hash.get(&field) {
+                    Some(value) => frame_to_string(value)
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .filter(|v| !v.is_nan())
+                        .ok_or(BackendError::HashValueNotFloat)?,
+                    None => 0.0,
+                };
+                let value = current + increment;
+                if !value.is_finite() {
+                    return Err(BackendError::NotFinite);
+                }
+                set_keep_ttl(hash, field, BulkString::from(value.to_string()).into());
+                Ok(value)
+            },
+        )
+    }
+
+    /// Random fields with their values. A positive count returns up to `count` distinct fields,
+    /// a negative one returns exactly `-count` fields which may repeat.
+    pub fn hrandfield(
+        &self,
+        key: &str,
+        count: i64,
+    ) -> Result<Vec<(String, RespFrame)>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let hash = v.as_hash()?;
+                let fields = hash.iter().map(|(f, v)| (f.clone(), v.clone()));
+                Ok(random_picks(fields, count))
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn hgetall(&self, key: &str) -> Result<Option<HashMap<String, RespFrame>>, BackendError> {
+        self.read(key, |v| {
+            Ok(v.as_hash()?
+                .iter()
+                .map(|(f, v)| (f.clone(), v.clone()))
+                .collect())
+        })
+    }
+
+    /// Set the absolute expiration time (unix milliseconds) of the fields, see
+    /// [`Hash::expire_at`] for the result codes. Every field gets -2 if the key does not exist.
+    pub fn hexpire_at(
+        &self,
+        key: &str,
+        fields: &[String],
+        at: u64,
+        condition: Option<ExpireCondition>,
+    ) -> Result<Vec<i64>, BackendError> {
+        let now = now_ms();
+        let ret = self
+            .update(key, |v| {
+                let hash = v.as_hash_mut()?;
+                Ok(fields
+                    .iter()
+                    .map(|field| hash.expire_at(field, at, condition, now))
+                    .collect::<Vec<_>>())
+            })?
+            .unwrap_or_else(|| vec![-2; fields.len()]);
+
+        // NOTE: the key is indexed once it is unlocked and has its times set, see
+        // expire_fields_if_needed
+        if ret.contains(&1) {
+            self.volatile_hashes.insert(key.to_string(), ());
+        }
+        Ok(ret)
+    }
+
+    /// Remaining time to live of the fields in milliseconds, -1 if the field has no expiration
+    /// time and -2 if it does not exist.
+    pub fn hpttl(&self, key: &str, fields: &[String]) -> Result<Vec<i64>, BackendError> {
+        let now = now_ms();
+        Ok(self
+            .read(key, |v| {
+                let hash = v.as_hash()?;
+                Ok(fields
+                    .iter()
+                    .map(
+                        |field| match (hash.contains_key(field), hash.expire_time(field)) {
+                            (false, _) => -2,
+                            (true, None) => -1,
+                            (true, Some(at)) => at.saturating_sub(now) as i64,
+                        },
+                    )
+                    .collect())
+            })?
+            .unwrap_or_else(|| vec![-2; fields.len()]))
+    }
+
+    /// Make the fields persistent. Returns 1 for the fields whose expiration time was removed,
+    /// -1 for the fields which had none and -2 for the ones which do not exist.
+    pub fn hpersist(&self, key: &str, fields: &[String]) -> Result<Vec<i64>, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let hash = v.as_hash_mut()?;
+                Ok(fields
+                    .iter()
+                    .map(|field| match hash.contains_key(field) {
+                        false => -2,
+                        true if hash.persist(field) => 1,
+                        true => -1,
+                    })
+                    .collect())
+            })?
+            .unwrap_or_else(|| vec![-2; fields.len()]))
+    }
+
+    /// Delete the expired fields of the hash, the key goes with its last field. Returns true if
+    /// the key was deleted by this call.
+    pub(crate) fn expire_fields_if_needed(&self, key: &str, now: u64) -> bool {
+        if !self.volatile_hashes.contains_key(key) {
+            return false;
+        }
+
+        let deleted = match self.next_field_expire_time(key) {
+            Some(at) if at <= now => {
+                if let Some(mut v) = self.keyspace.get_mut(key) {
+                    if let Value::Hash(hash) = v.value_mut() {
+                        hash.remove_expired(now);
+                    }
+                }
+                self.remove_if_empty(key);
+                self.touch(key);
+                !self.keyspace.contains_key(key)
+            }
+            Some(_) => return false,
+            None => false,
+        };
+
+        // the key is gone or has no volatile field left. NOTE: hexpire_at indexes the key after
+        // setting the times, so checking again under the index lock never drops a key which
+        // has just got one
+        self.volatile_hashes
+            .remove_if(key, |key, _| self.next_field_expire_time(key).is_none());
+        deleted
+    }
+
+    /// Actively reclaim the expired fields of the hashes, so that fields which are never
+    /// accessed again don't leak. Like `expire_cycle`, a few hashes are sampled at once until
+    /// few of them had expired fields or the deadline is reached. Returns the number of keys
+    /// deleted with their last field.
+    pub(crate) fn active_expire_fields_cycle(&mut self, deadline: Instant) -> usize {
+        let mut n = 0;
+        loop {
+            let cursor = self.fields_expire_cursor.load(Ordering::Relaxed);
+            let (keys, next) = self.volatile_hashes.scan(cursor, ACTIVE_EXPIRE_SAMPLES);
+            let expired = self.shared(&keys, true, |db| {
+                db.fields_expire_cursor.store(next, Ordering::Relaxed);
+                let now = now_ms();
+                let mut expired = 0;
+                for key in &keys {
+                    if db.next_field_expire_time(key).is_some_and(|at| at <= now) {
+                        expired += 1;
+                        n += usize::from(db.expire_fields_if_needed(key, now));
+                    }
+                }
+                expired
+            });
+            if expired * 4 <= keys.len() || Instant::now() >= deadline {
+                return n;
+            }
+        }
+    }
+
+    // the earliest expiration time of the fields of the hash
+    fn next_field_expire_time(&self, key: &str) -> Option<u64> {
+        self.keyspace.get(key).and_then(|v| match v.value() {
+            Value::Hash(hash) => hash.next_expire_time(),
+            _ => None,
+        })
+    }
+}
+
+// HINCRBY and HINCRBYFLOAT keep the expiration time of the field, unlike HSET
+fn set_keep_ttl(hash: &mut Hash, field: String, value: RespFrame) {
+    match hash.get_mut(&field) {
+        Some(v) => *v = value,
+        None => {
+            hash.insert(field, value);
+        }
+    }
+}
+
+// the values are usually bulk strings, but any frame given to HSET is kept as is
+fn frame_to_string(frame: &RespFrame) -> Option<String> {
+    match frame {
+        RespFrame::BulkString(v) => Some(String::from_utf8_lossy(v).into_owned()),
+        RespFrame::SimpleString(v) => Some(v.0.clone()),
+        RespFrame::Integer(v) => Some(v.to_string()),
+        RespFrame::Double(v) => Some(v.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,361 @@
+use super::{Backend, BackendError, StringValue, Value};
+
+// the representation is the one of redis, so that a HyperLogLog could be moved between the two
+// as a plain string: a 16 bytes header, "HYLL", the encoding, 3 unused bytes and the cached
+// cardinality, followed by the registers in the dense or in the sparse encoding
+const HLL_P: u32 = 14;
+const HLL_Q: u32 = 64 - HLL_P;
+const HLL_REGISTERS: usize = 1 << HLL_P;
+const HLL_BITS: usize = 6;
+const HLL_HEADER_LEN: usize = 16;
+const HLL_DENSE_LEN: usize = HLL_HEADER_LEN + (HLL_REGISTERS * HLL_BITS).div_ceil(8);
+const HLL_DENSE: u8 = 0;
+const HLL_SPARSE: u8 = 1;
+// a sparse HyperLogLog longer than this is promoted to dense, the default of redis'
+// hll-sparse-max-bytes
+const HLL_SPARSE_MAX_LEN: usize = 3000;
+// the largest register value a sparse VAL opcode could hold
+const HLL_SPARSE_VAL_MAX: u8 = 32;
+// the most significant bit of the cached cardinality tells it is stale
+const HLL_CACHE_INVALID: u8 = 1 << 7;
+const HLL_SEED: u64 = 0xadc83b19;
+const HLL_ALPHA_INF: f64 = 0.721_347_520_444_481_7;
+
+impl Backend {
+    /// Add the elements to the HyperLogLog, a missing key is created first. Returns whether a
+    /// register changed, in which case the estimated cardinality may have changed too.
+    pub fn pfadd(&self, key: String, elements: &[Vec<u8>]) -> Result<bool, BackendError> {
+        let mut created = false;
+        let updated = self.upsert(
+            key,
+            || {
+                created = true;
+                let mut bytes = encode_sparse(&[0; HLL_REGISTERS]).unwrap_or_default();
+                bytes[15] |= HLL_CACHE_INVALID;
+                Value::String(StringValue::Raw(bytes))
+            },
+            |v| {
+                let value = v.as_string_mut()?;
+                check(&value.as_bytes())?;
+                let bytes = value.as_bytes_mut();
+                let updated = add(bytes, elements)?;
+                if updated {
+                    bytes[15] |= HLL_CACHE_INVALID;
+                }
+                Ok(updated)
+            },
+        )?;
+        Ok(updated || created)
+    }
+
+    /// The estimated cardinality of the union of the HyperLogLogs, missing keys are empty. The
+    /// cardinality of a single key is cached in its header until the next update.
+    pub fn pfcount(&self, keys: &[String]) -> Result<u64, BackendError> {
+        if let [key] = keys {
+            return Ok(self
+                .update(key, |v| {
+                    let value = v.as_string_mut()?;
+                    check(&value.as_bytes())?;
+                    let bytes = value.as_bytes_mut();
+                    if bytes[15] & HLL_CACHE_INVALID == 0 {
+                        let cache = bytes[8..HLL_HEADER_LEN].try_into().unwrap_or_default();
+                        return Ok(u64::from_le_bytes(cache));
+                    }
+
+                    let card = estimate(&registers(bytes)?);
+                    bytes[8..HLL_HEADER_LEN].copy_from_slice(&card.to_le_bytes());
+                    Ok(card)
+                })?
+                .unwrap_or_default());
+        }
+
+        let mut union = vec![0; HLL_REGISTERS];
+        for key in keys {
+            self.read(key, |v| {
+                let bytes = v.as_string()?.as_bytes();
+                check(&bytes)?;
+                merge(&mut union, &registers(&bytes)?);
+                Ok(())
+            })?;
+        }
+        Ok(estimate(&union))
+    }
+
+    /// Store the union of the HyperLogLogs at the destination, which is one of the sources if
+    /// it exists. The result stays sparse only if all the sources are sparse.
+    pub fn pfmerge(&self, destination: String, keys: &[String]) -> Result<(), BackendError> {
+        let mut union = vec![0; HLL_REGISTERS];
+        let mut dense = false;
+        for key in std::iter::once(&destination).chain(keys) {
+            self.read(key, |v| {
+                let bytes = v.as_string()?.as_bytes();
+                check(&bytes)?;
+                dense |= bytes[4] == HLL_DENSE;
+                merge(&mut union, &registers(&bytes)?);
+                Ok(())
+            })?;
+        }
+
+        let sparse = if dense { None } else { encode_sparse(&union) };
+        let mut bytes = sparse.unwrap_or_else(|| encode_dense(&union));
+        bytes[15] |= HLL_CACHE_INVALID;
+        self.upsert(
+            destination,
+            || Value::String(StringValue::Raw(Vec::new())),
+            |v| {
+                *v.as_string_mut()?.as_bytes_mut() = bytes;
+                Ok(())
+            },
+        )
+    }
+}
+
+// the same validation redis does before touching a string as a HyperLogLog, a sparse
+// representation is only validated while it is decoded
+fn check(bytes: &[u8]) -> Result<(), BackendError> {
+    match bytes.get(..HLL_HEADER_LEN) {
+        Some(header) if header.starts_with(b"HYLL") => match header[4] {
+            HLL_DENSE if bytes.len() == HLL_DENSE_LEN => Ok(()),
+            HLL_SPARSE => Ok(()),
+            _ => Err(BackendError::NotHyperLogLog),
+        },
+        _ => Err(BackendError::NotHyperLogLog),
+    }
+}
+
+fn header(encoding: u8) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HLL_DENSE_LEN);
+    bytes.extend_from_slice(b"HYLL");
+    bytes.extend_from_slice(&[encoding, 0, 0, 0]);
+    bytes.extend_from_slice(&[0; 8]);
+    bytes
+}
+
+// MurmurHash64A, the hash function of redis' HyperLogLog
+fn murmurhash64a(key: &[u8], seed: u64) -> u64 {
+    const M: u64 = 0xc6a4a7935bd1e995;
+    const R: u32 = 47;
+
+    let mut h = seed ^ (key.len() as u64).wrapping_mul(M);
+    let mut chunks = key.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut k = u64::from_le_bytes(chunk.try_into().unwrap_or_default());
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h ^= k;
+        h = h.wrapping_mul(M);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, b) in tail.iter().enumerate() {
+            h ^= (*b as u64) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> R;
+    h = h.wrapping_mul(M);
+    h ^= h >> R;
+    h
+}
+
+// the register of the element, and the length of the run of zeros in the rest of its hash plus
+// one, which is the value the register is raised to
+fn position(element: &[u8]) -> (usize, u8) {
+    let hash = murmurhash64a(element, HLL_SEED);
+    let index = (hash & (HLL_REGISTERS as u64 - 1)) as usize;
+    // the sentinel bit caps the value to Q + 1
+    let hash = (hash >> HLL_P) | (1 << HLL_Q);
+    (index, hash.trailing_zeros() as u8 + 1)
+}
+
+// dense registers are packed 6 bits each, starting from the least significant bit of a byte
+fn dense_get(registers: &[u8], i: usize) -> u8 {
+    let (byte, shift) = (i * HLL_BITS / 8, (i * HLL_BITS % 8) as u32);
+    let b0 = registers[byte] as u16;
+    let b1 = registers.get(byte + 1).copied().unwrap_or_default() as u16;
+    (((b0 >> shift) | (b1 << (8 - shift))) & 63) as u8
+}
+
+fn dense_set(registers: &mut [u8], i: usize, value: u8) {
+    let (byte, shift) = (i * HLL_BITS / 8, (i * HLL_BITS % 8) as u32);
+    let value = value as u16;
+    registers[byte] &= !((63u16 << shift) as u8);
+    registers[byte] |= (value << shift) as u8;
+    // the last register doesn't spill over to the next byte
+    if let Some(b) = registers.get_mut(byte + 1) {
+        *b &= !((63u16 >> (8 - shift)) as u8);
+        *b |= (value >> (8 - shift)) as u8;
+    }
+}
+
+// decode the registers of either encoding
+fn registers(bytes: &[u8]) -> Result<Vec<u8>, BackendError> {
+    let data = &bytes[HLL_HEADER_LEN..];
+    if bytes[4] == HLL_DENSE {
+        return Ok((0..HLL_REGISTERS).map(|i| dense_get(data, i)).collect());
+    }
+
+    // the sparse opcodes are ZERO (00xxxxxx) for up to 64 zero registers, XZERO (01xxxxxx
+    // yyyyyyyy) for up to 16384 zero registers and VAL (1vvvvvxx) for up to 4 registers set to
+    // the same value, up to 32
+    let mut registers = Vec::with_capacity(HLL_REGISTERS);
+    let mut ops = data.iter();
+    while let Some(&op) = ops.next() {
+        let (value, len) = match op >> 6 {
+            0b00 => (0, (op & 0x3f) as usize + 1),
+            0b01 => {
+                let next = *ops.next().ok_or(BackendError::CorruptedHyperLogLog)?;
+                (0, (((op & 0x3f) as usize) << 8 | next as usize) + 1)
+            }
+            _ => (((op >> 2) & 0x1f) + 1, (op & 0x03) as usize + 1),
+        };
+        if registers.len() + len > HLL_REGISTERS {
+            return Err(BackendError::CorruptedHyperLogLog);
+        }
+        registers.resize(registers.len() + len, value);
+    }
+
+    if registers.len() != HLL_REGISTERS {
+        return Err(BackendError::CorruptedHyperLogLog);
+    }
+    Ok(registers)
+}
+
+// None if a register doesn't fit in a VAL opcode, or the result would be too long
+fn encode_sparse(registers: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = header(HLL_SPARSE);
+    let mut i = 0;
+    while i < registers.len() {
+        let value = registers[i];
+        let run = registers[i..].iter().take_while(|v| **v == value).count();
+        i += run;
+
+        let mut run = run;
+        while run > 0 {
+            let len = match value {
+                0 if run > 64 => {
+                    let len = run.min(HLL_REGISTERS);
+                    bytes.push(0x40 | ((len - 1) >> 8) as u8);
+                    bytes.push(((len - 1) & 0xff) as u8);
+                    len
+                }
+                0 => {
+                    bytes.push((run - 1) as u8);
+                    run
+                }
+                value if value > HLL_SPARSE_VAL_MAX => return None,
+                value => {
+                    let len = run.min(4);
+                    bytes.push(0x80 | (value - 1) << 2 | (len - 1) as u8);
+                    len
+                }
+            };
+            run -= len;
+        }
+
+        if bytes.len() > HLL_SPARSE_MAX_LEN {
+            return None;
+        }
+    }
+    Some(bytes)
+}
+
+fn encode_dense(registers: &[u8]) -> Vec<u8> {
+    let mut bytes = header(HLL_DENSE);
+    bytes.resize(HLL_DENSE_LEN, 0);
+    let data = &mut bytes[HLL_HEADER_LEN..];
+    for (i, value) in registers.iter().enumerate() {
+        dense_set(data, i, *value);
+    }
+    bytes
+}
+
+// a dense representation is updated in place, a sparse one is decoded and encoded again, and
+// promoted to dense once it doesn't fit
+fn add(bytes: &mut Vec<u8>, elements: &[Vec<u8>]) -> Result<bool, BackendError> {
+    let mut updated = false;
+    if bytes[4] == HLL_DENSE {
+        let data = &mut bytes[HLL_HEADER_LEN..];
+        for element in elements {
+            let (i, value) = position(element);
+            if value > dense_get(data, i) {
+                dense_set(data, i, value);
+                updated = true;
+            }
+        }
+        return Ok(updated);
+    }
+
+    let mut registers = registers(bytes)?;
+    for element in elements {
+        let (i, value) = position(element);
+        if value > registers[i] {
+            registers[i] = value;
+            updated = true;
+        }
+    }
+    if updated {
+        *bytes = encode_sparse(&registers).unwrap_or_else(|| encode_dense(&registers));
+    }
+    Ok(updated)
+}
+
+fn merge(union: &mut [u8], registers: &[u8]) {
+    for (max, value) in union.iter_mut().zip(registers) {
+        *max = (*max).max(*value);
+    }
+}
+
+// the improved estimator from Otmar Ertl's "New cardinality estimation algorithms for
+// HyperLogLog sketches", which redis uses since 5.0
+fn estimate(registers: &[u8]) -> u64 {
+    let mut histogram = [0u32; 64];
+    for value in registers {
+        histogram[(*value & 63) as usize] += 1;
+    }
+
+    let m = HLL_REGISTERS as f64;
+    let q = HLL_Q as usize;
+    let mut z = m * tau((m - histogram[q + 1] as f64) / m);
+    for j in (1..=q).rev() {
+        z += histogram[j] as f64;
+        z *= 0.5;
+    }
+    z += m * sigma(histogram[0] as f64 / m);
+    (HLL_ALPHA_INF * m * m / z).round() as u64
+}
+
+fn sigma(mut x: f64) -> f64 {
+    if x == 1.0 {
+        return f64::INFINITY;
+    }
+    let (mut y, mut z) = (1.0, x);
+    loop {
+        x *= x;
+        let prev = z;
+        z += x * y;
+        y += y;
+        if prev == z {
+            return z;
+        }
+    }
+}
+
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x == 1.0 {
+        return 0.0;
+    }
+    let (mut y, mut z) = (1.0, 1.0 - x);
+    loop {
+        x = x.sqrt();
+        let prev = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if prev == z {
+            return z / 3.0;
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+
+use super::{index_range, Backend, BackendError, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEnd {
+    // LEFT: the head of the list
+    Left,
+    // RIGHT: the tail of the list
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertPosition {
+    Before,
+    After,
+}
+
+impl Backend {
+    /// Push the elements one after the other to the given end of the list, a missing key is
+    /// created. Returns the length of the list after the push.
+    pub fn list_push(
+        &self,
+        key: String,
+        end: ListEnd,
+        elements: Vec<Vec<u8>>,
+    ) -> Result<usize, BackendError> {
+        let len = self.push_elements(key.clone(), end, elements)?;
+        self.signal_ready(&key);
+        Ok(len)
+    }
+
+    // push without serving the clients blocked on the key
+    pub(super) fn push_elements(
+        &self,
+        key: String,
+        end: ListEnd,
+        elements: Vec<Vec<u8>>,
+    ) -> Result<usize, BackendError> {
+        self.upsert(
+            key,
+            || Value::List(VecDeque::new()),
+            |v| {
+                let list = v.as_list_mut()?;
+                push(list, end, elements);
+                Ok(list.len())
+            },
+        )
+    }
+
+    /// Remove and return up to `count` elements from the given end of the list. Returns None if
+    /// the key does not exist.
+    pub fn list_pop(
+        &self,
+        key: &str,
+        end: ListEnd,
+        count: usize,
+    ) -> Result<Option<Vec<Vec<u8>>>, BackendError> {
+        self.update(key, |v| {
+            let list = v.as_list_mut()?;
+            Ok((0..count).map_while(|_| pop(list, end)).collect())
+        })
+    }
+
+    pub fn llen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_list()?.len()))?
+            .unwrap_or_default())
+    }
+
+    /// The elements between start and stop (both inclusive), negative indexes count from the
+    /// tail of the list.
+    pub fn lrange(&self, key: &str, start: i64, stop: i64) -> Result<Vec<Vec<u8>>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let list = v.as_list()?;
+                Ok(match index_range(start, stop, list.len()) {
+                    Some((start, stop)) => list.range(start..=stop).cloned().collect(),
+                    None => vec![],
+                })
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn lindex(&self, key: &str, index: i64) -> Result<Option<Vec<u8>>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let list = v.as_list()?;
+                Ok(position(index, list.len()).and_then(|i| list.get(i).cloned()))
+            })?
+            .flatten())
+    }
+
+    pub fn lset(&self, key: &str, index: i64, element: Vec<u8>) -> Result<(), BackendError> {
+        self.update(key, |v| {
+            let list = v.as_list_mut()?;
+            match position(index, list.len()) {
+                Some(i) => {
+                    list[i] = element;
+                    Ok(())
+                }
+                None => Err(BackendError::IndexOutOfRange),
+            }
+        })?
+        .ok_or(BackendError::NoSuchKey)
+    }
+
+    /// Remove the elements equal to `element`. A positive count removes up to `count` elements
+    /// from head to tail, a negative one from tail to head, and zero removes all of them.
+    /// Returns the number of elements removed.
+    pub fn lrem(&self, key: &str, count: i64, element: &[u8]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let list = v.as_list_mut()?;
+                let limit = match count {
+                    0 => usize::MAX,
+                    count => count.unsigned_abs() as usize,
+                };
+
+                let mut matched = list
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| v.as_slice() == element)
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                if count < 0 {
+                    matched.reverse();
+                }
+                matched.truncate(limit);
+                matched.sort_unstable();
+
+                // remove from the back so that the remaining indexes stay valid
+                for i in matched.iter().rev() {
+                    list.remove(*i);
+                }
+                Ok(matched.len())
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Keep only the elements between start and stop (both inclusive), an empty range deletes
+    /// the key.
+    pub fn ltrim(&self, key: &str, start: i64, stop: i64) -> Result<(), BackendError> {
+        self.update(key, |v| {
+            let list = v.as_list_mut()?;
+            match index_range(start, stop, list.len()) {
+                Some((start, stop)) => {
+                    list.truncate(stop + 1);
+                    list.drain(..start);
+                }
+                None => list.clear(),
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Insert the element before or after the first occurrence of pivot. Returns the length of
+    /// the list after the insertion, -1 if the pivot was not found and 0 if the key does not
+    /// exist.
+    pub fn linsert(
+        &self,
+        key: &str,
+        position: InsertPosition,
+        pivot: &[u8],
+        element: Vec<u8>,
+    ) -> Result<i64, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let list = v.as_list_mut()?;
+                let Some(i) = list.iter().position(|v| v.as_slice() == pivot) else {
+                    return Ok(-1);
+                };
+                match position {
+                    InsertPosition::Before => list.insert(i, element),
+                    InsertPosition::After => list.insert(i + 1, element),
+                }
+                Ok(list.len() as i64)
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Pop an element from one end of the source list and push it to one end of the
+    /// destination list. Returns None if the source list does not exist.
+    pub fn lmove(
+        &self,
+        source: &str,
+        destination: String,
+        from: ListEnd,
+        to: ListEnd,
+    ) -> Result<Option<Vec<u8>>, BackendError> {
+        let ret = self.move_element(source, destination.clone(), from, to)?;
+        if ret.is_some() {
+            self.signal_ready(&destination);
+        }
+        Ok(ret)
+    }
+
+    // move without serving the clients blocked on the destination
+    pub(super) fn move_element(
+        &self,
+        source: &str,
+        destination: String,
+        from: ListEnd,
+        to: ListEnd,
+    ) -> Result<Option<Vec<u8>>, BackendError> {
+        if source == destination {
+            // rotate in place, so that the key is never deleted in between and keeps its timeout
+            return self
+                .update(source, |v| {
+                    let list = v.as_list_mut()?;
+                    Ok(pop(list, from).inspect(|element| push(list, to, vec![element.clone()])))
+                })
+                .map(Option::flatten);
+        }
+
+        // the destination must be checked before anything is removed from the source
+        if self.llen(source)? == 0 {
+            return Ok(None);
+        }
+        self.read(&destination, |v| v.as_list().map(|_| ()))?;
+        let Some(element) = self
+            .list_pop(source, from, 1)?
+            .and_then(|v| v.into_iter().next())
+        else {
+            return Ok(None);
+        };
+        self.push_elements(destination, to, vec![element.clone()])?;
+        Ok(Some(element))
+    }
+}
+
+fn push(list: &mut VecDeque<Vec<u8>>, end: ListEnd, elements: Vec<Vec<u8>>) {
+    match end {
+        ListEnd::Left => elements.into_iter().for_each(|e| list.push_front(e)),
+        ListEnd::Right => list.extend(elements),
+    }
+}
+
+fn pop(list: &mut VecDeque<Vec<u8>>, end: ListEnd) -> Option<Vec<u8>> {
+    match end {
+        ListEnd::Left => list.pop_front(),
+        ListEnd::Right => list.pop_back(),
+    }
+}
+
+// resolve a possibly negative index against the length of the list
+fn position(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
@@ -1,79 +1,453 @@
+mod bitmap;
+mod blocking;
+mod db;
+mod generic;
+mod geo;
+mod glob;
+mod hash;
+mod hyperloglog;
+mod list;
+mod pubsub;
+mod ranked;
+mod scan;
+mod scan_map;
+mod set;
+mod slot;
+mod stream;
+mod stream_group;
+mod string;
+mod transaction;
+mod value;
+mod zset;
+
 use dashmap::DashMap;
-use std::{ops::Deref, sync::Arc};
+use rand::seq::{IteratorRandom, SliceRandom};
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+use scan_map::{Entry, ScanMap};
+
+pub use bitmap::{BitOperation, BitRange, BitUnit, BitfieldOp, BitfieldOverflow, BitfieldType};
+pub use blocking::{BlockingOp, ServedKey};
+pub use geo::{GeoMatch, GeoOrigin, GeoQuery, GeoShape, GeoSort, GeoUnit};
+pub use glob::glob_match;
+pub use hash::{ExpireCondition, Hash};
+pub use list::{InsertPosition, ListEnd};
+pub use pubsub::{PubSubMessage, Subscriber, DEFAULT_PUBSUB_BUFFER};
+pub use scan::ScanPage;
+pub use set::{Set, SetOperation};
+pub use slot::{key_hash_slot, CLUSTER_SLOTS};
+pub use stream::{
+    Stream, StreamEntry, StreamFields, StreamId, StreamTrim, StreamTrimStrategy, XAddId,
+};
+pub use stream_group::{
+    Claim, ConsumerGroup, GroupEntry, PendingEntry, PendingRange, PendingSummary, XClaimOptions,
+};
+pub use string::{SetCondition, SetExpiration, StringValue};
+pub use transaction::WatchedKey;
+pub use value::Value;
+pub use zset::{LexBound, ScoreBound, SortedSet, ZAddOptions, ZRangeBy, ZRangeLimit};
+
+/// The number of logical databases when it isn't configured, the same as redis.
+pub const DEFAULT_DATABASES: usize = 16;
 
-use crate::RespFrame;
+// the number of volatile keys the active expire cycle samples at once, and the time it may run
+// for, shared by the databases. The same as redis, which runs the cycle 10 times per second
+const ACTIVE_EXPIRE_SAMPLES: usize = 20;
+const ACTIVE_EXPIRE_BUDGET: Duration = Duration::from_millis(25);
 
+/// A handle to one of the logical databases, cloning it is cheap. Every connection has its own
+/// handle, so that SELECT only changes the database of that connection.
 #[derive(Debug, Clone)]
-pub struct Backend(Arc<BackendInner>);
+pub struct Backend {
+    inner: Arc<BackendInner>,
+    index: usize,
+    // the data of the database when the handle was selected, see `select`
+    db: Arc<Database>,
+}
 
 #[derive(Debug)]
 pub struct BackendInner {
-    pub(crate) map: DashMap<String, RespFrame>,
-    pub(crate) hmap: DashMap<String, DashMap<String, RespFrame>>,
-    pub(crate) set: DashMap<String, Vec<RespFrame>>,
+    // the data of each database, swapped by SWAPDB and replaced by FLUSHDB
+    databases: RwLock<Vec<Arc<Database>>>,
+    // clients blocked on lists or streams of each database, e.g. by BLPOP or XREAD. They stay
+    // with the database index when the data is swapped or flushed
+    blocking: Vec<Mutex<blocking::BlockingState>>,
+    // held for reading while a command runs and for writing while a transaction runs, so that
+    // no command is interleaved with the commands of a transaction
+    commands: RwLock<()>,
+    // the locks of the keys a command runs against, one per hash slot, see `shared`
+    keys: Vec<RwLock<()>>,
+    // the versions of the keys watched by the connections in each database, see `touch`
+    watched: Vec<DashMap<String, transaction::WatchedVersion>>,
+    // the channels and patterns the connections subscribe to, for all the databases
+    pubsub: RwLock<pubsub::PubSubState>,
+}
+
+#[derive(Debug, Default)]
+pub struct Database {
+    // every key lives in a single keyspace, whatever the type of its value
+    pub(crate) keyspace: ScanMap<Value>,
+    // absolute expiration time (unix milliseconds) of volatile keys
+    pub(crate) expires: ScanMap<u64>,
+    // hashes which may have fields with a time to live
+    pub(crate) volatile_hashes: ScanMap<()>,
+    // where the active expire cycle resumes sampling the volatile keys and hashes
+    expire_cursor: AtomicU64,
+    fields_expire_cursor: AtomicU64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BackendError {
+    #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
+    WrongType,
+    #[error("ERR no such key")]
+    NoSuchKey,
+    #[error("ERR index out of range")]
+    IndexOutOfRange,
+    #[error("ERR resulting score is not a number (NaN)")]
+    NotANumber,
+    #[error("ERR value is not an integer or out of range")]
+    NotInteger,
+    #[error("ERR value is not a valid float")]
+    NotAFloat,
+    #[error("ERR string exceeds maximum allowed size (proto-max-bulk-len)")]
+    StringTooLong,
+    #[error("ERR hash value is not an integer")]
+    HashValueNotInteger,
+    #[error("ERR hash value is not a float")]
+    HashValueNotFloat,
+    #[error("ERR increment or decrement would overflow")]
+    IncrementOverflow,
+    #[error("ERR increment would produce NaN or Infinity")]
+    NotFinite,
+    #[error("ERR The ID specified in XADD is equal or smaller than the target stream top item")]
+    StreamIdTooSmall,
+    #[error("ERR The ID specified in XADD must be greater than 0-0")]
+    StreamIdZero,
+    #[error("ERR The stream has exhausted the last possible ID, unable to add more items")]
+    StreamExhausted,
+    #[error("ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.")]
+    NoStream,
+    #[error("NOGROUP No such key '{0}' or consumer group '{1}'")]
+    NoGroup(String, String),
+    #[error("BUSYGROUP Consumer Group name already exists")]
+    BusyGroup,
+    #[error("WRONGTYPE Key is not a valid HyperLogLog string value.")]
+    NotHyperLogLog,
+    #[error("INVALIDOBJ Corrupted HLL object detected")]
+    CorruptedHyperLogLog,
+    #[error("ERR invalid longitude,latitude pair {0}")]
+    InvalidLonLat(String),
+    #[error("ERR could not decode requested zset member")]
+    UndecodableMember,
+    #[error("ERR source and destination objects are the same")]
+    SameObject,
+    #[error("ERR DB index is out of range")]
+    DbIndexOutOfRange,
 }
 
 impl Deref for Backend {
-    type Target = BackendInner;
+    type Target = Database;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.db
     }
 }
 
 impl Default for Backend {
     fn default() -> Self {
-        Self(Arc::new(BackendInner::default()))
+        Self::with_databases(DEFAULT_DATABASES)
     }
 }
 
-impl Default for BackendInner {
-    fn default() -> Self {
+impl Backend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A backend with the given number of logical databases, at least one. The handle selects
+    /// the database 0.
+    pub fn with_databases(n: usize) -> Self {
+        let n = n.max(1);
+        let databases = (0..n).map(|_| Arc::default()).collect::<Vec<_>>();
+        let inner = BackendInner {
+            databases: RwLock::new(databases),
+            blocking: (0..n).map(|_| Mutex::default()).collect(),
+            commands: RwLock::new(()),
+            keys: (0..CLUSTER_SLOTS).map(|_| RwLock::new(())).collect(),
+            watched: (0..n).map(|_| DashMap::new()).collect(),
+            pubsub: RwLock::default(),
+        };
+        let inner = Arc::new(inner);
+        let db = inner.databases.read().unwrap()[0].clone();
         Self {
-            map: DashMap::new(),
-            hmap: DashMap::new(),
-            set: DashMap::new(),
+            inner,
+            index: 0,
+            db,
         }
     }
-}
 
-impl Backend {
-    pub fn new() -> Self {
-        Self::default()
+    /// Run `f` against the value of a live key. Returns None if the key does not exist.
+    pub(crate) fn read<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&Value) -> Result<T, BackendError>,
+    ) -> Result<Option<T>, BackendError> {
+        self.expire_if_needed(key);
+        self.keyspace.get(key).map(|v| f(v.value())).transpose()
+    }
+
+    /// Run `f` against the value of a live key, the key is locked during the call so the
+    /// update is atomic. Returns None if the key does not exist.
+    pub(crate) fn update<T>(
+        &self,
+        key: &str,
+        f: impl FnOnce(&mut Value) -> Result<T, BackendError>,
+    ) -> Result<Option<T>, BackendError> {
+        self.expire_if_needed(key);
+        let ret = self.keyspace.get_mut(key).map(|mut v| f(v.value_mut()));
+        self.remove_if_empty(key);
+        if matches!(ret, Some(Ok(_))) {
+            self.touch(key);
+        }
+        ret.transpose()
+    }
+
+    /// Run `f` against the value of the key, a missing key is created with the value returned
+    /// by `default` first. The key is locked during the call so the update is atomic.
+    pub(crate) fn upsert<T>(
+        &self,
+        key: String,
+        default: impl FnOnce() -> Value,
+        f: impl FnOnce(&mut Value) -> Result<T, BackendError>,
+    ) -> Result<T, BackendError> {
+        self.expire_if_needed(&key);
+        let ret = {
+            let mut v = self.keyspace.entry(key.clone()).or_insert_with(default);
+            f(v.value_mut())
+        };
+        self.remove_if_empty(&key);
+        if ret.is_ok() {
+            self.touch(&key);
+        }
+        ret
+    }
+
+    /// Overwrite the key with the value whatever the type of the old one, the timeout of the
+    /// key is discarded. An empty collection deletes the key instead.
+    pub(crate) fn put(&self, key: String, value: Value) {
+        // NOTE: lock order is always expires -> keyspace
+        let expires = self.expires.entry(key.clone());
+        if value.is_empty() {
+            self.keyspace.remove(&key);
+        } else {
+            self.keyspace.insert(key.clone(), value);
+        }
+        if let Entry::Occupied(v) = expires {
+            v.remove();
+        }
+        self.touch(&key);
+    }
+
+    /// Delete the key together with its timeout. Returns true if the key existed.
+    pub(crate) fn remove(&self, key: &str) -> bool {
+        self.take(key).is_some()
     }
 
-    pub fn get(&self, key: &str) -> Option<RespFrame> {
-        self.map.get(key).map(|v| v.value().clone())
+    /// Delete the key together with its timeout. Returns the value and the timeout, if any.
+    pub(crate) fn take(&self, key: &str) -> Option<(Value, Option<u64>)> {
+        self.expire_if_needed(key);
+        let taken = {
+            // NOTE: lock order is always expires -> keyspace
+            let expires = self.expires.entry(key.to_string());
+            let value = self.keyspace.remove(key).map(|(_, v)| v);
+            let at = match expires {
+                Entry::Occupied(v) => Some(v.remove()),
+                Entry::Vacant(_) => None,
+            };
+            value.map(|v| (v, at))
+        };
+        self.volatile_hashes
+            .remove_if(key, |key, _| !self.keyspace.contains_key(key));
+        if taken.is_some() {
+            self.touch(key);
+        }
+        taken
+    }
+
+    // empty collections are removed from the keyspace together with their timeout
+    fn remove_if_empty(&self, key: &str) {
+        if !self.keyspace.get(key).is_some_and(|v| v.is_empty()) {
+            return;
+        }
+
+        // NOTE: lock order is always expires -> keyspace
+        let expires = self.expires.entry(key.to_string());
+        if self.keyspace.remove_if(key, |_, v| v.is_empty()).is_some() {
+            if let Entry::Occupied(v) = expires {
+                v.remove();
+            }
+        }
     }
 
-    pub fn set(&self, key: String, value: RespFrame) {
-        self.map.insert(key, value);
+    /// The type name of the value stored at key, None if the key does not exist.
+    pub fn key_type(&self, key: &str) -> Option<&'static str> {
+        self.expire_if_needed(key);
+        self.keyspace.get(key).map(|v| v.type_name())
     }
 
-    pub fn hget(&self, key: &str, field: &str) -> Option<RespFrame> {
-        self.hmap
-            .get(key)
-            .and_then(|v| v.get(field).map(|v| v.value().clone()))
+    pub fn exists(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        self.keyspace.contains_key(key)
     }
 
-    pub fn hset(&self, key: String, field: String, value: RespFrame) {
-        let hmap = self.hmap.entry(key).or_default();
-        hmap.insert(field, value);
+    /// Set the absolute expiration time (unix milliseconds) of an existing key.
+    /// A time in the past deletes the key right away. Returns false if the key does not exist.
+    pub fn expire_at(&self, key: &str, at: u64) -> bool {
+        if !self.exists(key) {
+            return false;
+        }
+
+        self.expires.insert(key.to_string(), at);
+        self.touch(key);
+        self.expire_if_needed(key);
+        true
     }
 
-    pub fn hgetall(&self, key: &str) -> Option<DashMap<String, RespFrame>> {
-        self.hmap.get(key).map(|v| v.clone())
+    /// Remaining time to live in milliseconds, -1 if the key has no timeout and -2 if the key
+    /// does not exist.
+    pub fn pttl(&self, key: &str) -> i64 {
+        if !self.exists(key) {
+            return -2;
+        }
+
+        match self.expires.get(key) {
+            Some(at) => at.saturating_sub(now_ms()) as i64,
+            None => -1,
+        }
     }
 
-    pub fn sadd(&self, key: String, member: RespFrame) {
-        let mut set = self.set.entry(key).or_default();
-        set.push(member);
+    pub fn persist(&self, key: &str) -> bool {
+        self.expire_if_needed(key);
+        let persisted = self.expires.remove(key).is_some();
+        if persisted {
+            self.touch(key);
+        }
+        persisted
     }
 
-    pub fn sismember(&self, key: &str, member: &str) -> bool {
-        self.set
-            .get(key)
-            .map(|v| v.iter().any(|m| *m == RespFrame::from(member)))
-            .unwrap_or(false)
+    /// Remove the key if its timeout has elapsed, or the fields of a hash whose timeout has
+    /// elapsed. Returns true if the key was expired by this call.
+    pub fn expire_if_needed(&self, key: &str) -> bool {
+        let now = now_ms();
+        match self.expires.get(key).map(|v| *v.value()) {
+            Some(at) if at <= now => {}
+            _ => return self.expire_fields_if_needed(key, now),
+        }
+
+        // hold the expires entry while deleting, so that a concurrent write which resets the
+        // timeout can't be interleaved with the removal
+        match self.expires.entry(key.to_string()) {
+            Entry::Occupied(entry) if *entry.get() <= now => {
+                self.keyspace.remove(key);
+                entry.remove();
+                self.touch(key);
+                true
+            }
+            _ => false,
+        }
     }
+
+    /// Actively reclaim expired keys and hash fields of every database, so that the ones which
+    /// are never accessed again don't leak. Returns the number of keys removed.
+    pub fn active_expire_cycle(&self) -> usize {
+        let budget = ACTIVE_EXPIRE_BUDGET / self.database_count() as u32;
+        (0..self.database_count())
+            .filter_map(|index| self.select(index).ok())
+            .map(|mut db| {
+                let deadline = Instant::now() + budget;
+                db.expire_cycle(deadline) + db.active_expire_fields_cycle(deadline)
+            })
+            .sum()
+    }
+
+    // like redis, a few volatile keys are sampled from where the last cycle stopped, and again
+    // while more than a quarter of them had expired, until the time budget runs out
+    fn expire_cycle(&mut self, deadline: Instant) -> usize {
+        let mut n = 0;
+        loop {
+            let cursor = self.expire_cursor.load(Ordering::Relaxed);
+            let (keys, next) = self.expires.scan(cursor, ACTIVE_EXPIRE_SAMPLES);
+            // the keys are locked like the ones of a command, so that a key isn't expired in
+            // the middle of a command or a transaction
+            let expired = self.shared(&keys, true, |db| {
+                db.expire_cursor.store(next, Ordering::Relaxed);
+                keys.iter().filter(|key| db.expire_if_needed(key)).count()
+            });
+            n += expired;
+            if expired * 4 <= keys.len() || Instant::now() >= deadline {
+                return n;
+            }
+        }
+    }
+}
+
+// drop the value on a background thread, without a runtime (e.g. in tests) it is simply
+// dropped here
+pub(crate) fn lazy_free<T: Send + 'static>(value: T) {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn_blocking(move || drop(value));
+    }
+}
+
+/// Current unix time in milliseconds.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// random items for SRANDMEMBER and HRANDFIELD: a positive count picks up to `count` distinct
+// items, a negative one exactly `-count` items which may repeat. The commands bound the
+// negative counts, as the picks are all kept in memory
+pub(crate) fn random_picks<T: Clone>(
+    items: impl ExactSizeIterator<Item = T>,
+    count: i64,
+) -> Vec<T> {
+    let mut rng = rand::thread_rng();
+    if count >= 0 {
+        let amount = (count as usize).min(items.len());
+        return items.choose_multiple(&mut rng, amount);
+    }
+
+    let items = items.collect::<Vec<_>>();
+    (0..count.unsigned_abs())
+        .filter_map(|_| items.choose(&mut rng).cloned())
+        .collect()
+}
+
+// resolve an inclusive range the way redis does: out of range indexes are clamped, and None
+// means the range is empty
+pub(crate) fn index_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (start + len).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        stop + len
+    } else {
+        stop.min(len - 1)
+    };
+    (start <= stop && start < len).then_some((start as usize, stop as usize))
 }
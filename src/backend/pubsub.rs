@@ -0,0 +1,383 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    Notify,
+};
+
+use super::{glob_match, key_hash_slot, Backend, BackendInner};
+
+/// The number of messages a subscriber may have waiting to be sent, the same role the pubsub
+/// client output buffer limit plays in redis.
+pub const DEFAULT_PUBSUB_BUFFER: usize = 1024;
+
+/// A message published to a channel the subscriber listens to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PubSubMessage {
+    // the pattern the channel matched, for the subscriptions made with PSUBSCRIBE
+    pub pattern: Option<String>,
+    // published with SPUBLISH to a shard channel
+    pub sharded: bool,
+    pub channel: String,
+    pub payload: Vec<u8>,
+}
+
+/// The subscriptions of a connection, they all end when it is dropped.
+#[derive(Debug)]
+pub struct Subscriber {
+    inner: Arc<BackendInner>,
+    id: u64,
+    mailbox: Mailbox,
+    receiver: mpsc::Receiver<PubSubMessage>,
+    channels: HashSet<String>,
+    patterns: HashSet<String>,
+    shard_channels: HashSet<String>,
+}
+
+// the subscribers of every channel and pattern, shared by all the databases like in redis
+#[derive(Debug, Default)]
+pub(crate) struct PubSubState {
+    next_id: u64,
+    channels: HashMap<String, HashMap<u64, Mailbox>>,
+    patterns: HashMap<String, HashMap<u64, Mailbox>>,
+    // the shard channels live in their own namespace, grouped by hash slot like the keys of a
+    // cluster
+    shard_channels: HashMap<u16, HashMap<String, HashMap<u64, Mailbox>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Mailbox {
+    sender: mpsc::Sender<PubSubMessage>,
+    // set when the buffer was full, the subscriber is disconnected instead of waited for
+    overflowed: Arc<AtomicBool>,
+    // wakes up the connection waiting in `Subscriber::overflowed`
+    overflow: Arc<Notify>,
+}
+
+impl Backend {
+    /// A subscriber without subscriptions yet, which buffers up to `capacity` messages.
+    pub fn subscriber(&self, capacity: usize) -> Subscriber {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        let id = {
+            let mut state = self.inner.pubsub.write().unwrap();
+            state.next_id += 1;
+            state.next_id
+        };
+        Subscriber {
+            inner: self.inner.clone(),
+            id,
+            mailbox: Mailbox {
+                sender,
+                overflowed: Arc::default(),
+                overflow: Arc::default(),
+            },
+            receiver,
+            channels: HashSet::new(),
+            patterns: HashSet::new(),
+            shard_channels: HashSet::new(),
+        }
+    }
+
+    /// Send the message to the subscribers of the channel and of the patterns it matches, the
+    /// publisher never waits for them. Returns the number of subscriptions which received it.
+    pub fn publish(&self, channel: &str, payload: &[u8]) -> usize {
+        let state = self.inner.pubsub.read().unwrap();
+        let mut n = 0;
+        if let Some(subscribers) = state.channels.get(channel) {
+            for mailbox in subscribers.values() {
+                n += mailbox.deliver(PubSubMessage {
+                    pattern: None,
+                    sharded: false,
+                    channel: channel.to_string(),
+                    payload: payload.to_vec(),
+                }) as usize;
+            }
+        }
+        for (pattern, subscribers) in state.patterns.iter() {
+            if !glob_match(pattern.as_bytes(), channel.as_bytes()) {
+                continue;
+            }
+            for mailbox in subscribers.values() {
+                n += mailbox.deliver(PubSubMessage {
+                    pattern: Some(pattern.clone()),
+                    sharded: false,
+                    channel: channel.to_string(),
+                    payload: payload.to_vec(),
+                }) as usize;
+            }
+        }
+        n
+    }
+
+    /// The channels with at least one subscriber, only the ones matching the pattern if it is
+    /// set.
+    pub fn pubsub_channels(&self, pattern: Option<&[u8]>) -> Vec<String> {
+        let state = self.inner.pubsub.read().unwrap();
+        state
+            .channels
+            .keys()
+            .filter(|channel| pattern.is_none_or(|p| glob_match(p, channel.as_bytes())))
+            .cloned()
+            .collect()
+    }
+
+    /// The number of subscribers of each channel, patterns are not counted.
+    pub fn pubsub_numsub(&self, channels: &[String]) -> Vec<usize> {
+        let state = self.inner.pubsub.read().unwrap();
+        channels
+            .iter()
+            .map(|channel| state.channels.get(channel).map_or(0, |v| v.len()))
+            .collect()
+    }
+
+    /// The number of patterns with at least one subscriber.
+    pub fn pubsub_numpat(&self) -> usize {
+        self.inner.pubsub.read().unwrap().patterns.len()
+    }
+
+    /// Send the message to the subscribers of the shard channel, the patterns don't see it.
+    /// Returns the number of subscribers which received it.
+    pub fn spublish(&self, channel: &str, payload: &[u8]) -> usize {
+        let state = self.inner.pubsub.read().unwrap();
+        let Some(subscribers) = state
+            .shard_channels
+            .get(&key_hash_slot(channel.as_bytes()))
+            .and_then(|v| v.get(channel))
+        else {
+            return 0;
+        };
+        subscribers
+            .values()
+            .filter(|mailbox| {
+                mailbox.deliver(PubSubMessage {
+                    pattern: None,
+                    sharded: true,
+                    channel: channel.to_string(),
+                    payload: payload.to_vec(),
+                })
+            })
+            .count()
+    }
+
+    /// The shard channels with at least one subscriber, see `pubsub_channels`.
+    pub fn pubsub_shardchannels(&self, pattern: Option<&[u8]>) -> Vec<String> {
+        let state = self.inner.pubsub.read().unwrap();
+        state
+            .shard_channels
+            .values()
+            .flat_map(|v| v.keys())
+            .filter(|channel| pattern.is_none_or(|p| glob_match(p, channel.as_bytes())))
+            .cloned()
+            .collect()
+    }
+
+    /// The number of subscribers of each shard channel.
+    pub fn pubsub_shardnumsub(&self, channels: &[String]) -> Vec<usize> {
+        let state = self.inner.pubsub.read().unwrap();
+        channels
+            .iter()
+            .map(|channel| {
+                state
+                    .shard_channels
+                    .get(&key_hash_slot(channel.as_bytes()))
+                    .and_then(|v| v.get(channel))
+                    .map_or(0, |v| v.len())
+            })
+            .collect()
+    }
+}
+
+impl Subscriber {
+    /// Subscribe to the channel. Returns the number of subscriptions, patterns included but not
+    /// shard channels.
+    pub fn subscribe(&mut self, channel: String) -> usize {
+        if self.channels.insert(channel.clone()) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            state
+                .channels
+                .entry(channel)
+                .or_default()
+                .insert(self.id, self.mailbox.clone());
+        }
+        self.channel_count()
+    }
+
+    /// Unsubscribe from the channel. Returns the number of subscriptions left.
+    pub fn unsubscribe(&mut self, channel: &str) -> usize {
+        if self.channels.remove(channel) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            remove(&mut state.channels, channel, self.id);
+        }
+        self.channel_count()
+    }
+
+    /// Subscribe to the channels matching the glob-style pattern. Returns the number of
+    /// subscriptions, channels included.
+    pub fn psubscribe(&mut self, pattern: String) -> usize {
+        if self.patterns.insert(pattern.clone()) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            state
+                .patterns
+                .entry(pattern)
+                .or_default()
+                .insert(self.id, self.mailbox.clone());
+        }
+        self.channel_count()
+    }
+
+    /// Unsubscribe from the pattern. Returns the number of subscriptions left.
+    pub fn punsubscribe(&mut self, pattern: &str) -> usize {
+        if self.patterns.remove(pattern) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            remove(&mut state.patterns, pattern, self.id);
+        }
+        self.channel_count()
+    }
+
+    /// Subscribe to the shard channel. Returns the number of shard channels subscribed to.
+    pub fn ssubscribe(&mut self, channel: String) -> usize {
+        if self.shard_channels.insert(channel.clone()) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            state
+                .shard_channels
+                .entry(key_hash_slot(channel.as_bytes()))
+                .or_default()
+                .entry(channel)
+                .or_default()
+                .insert(self.id, self.mailbox.clone());
+        }
+        self.shard_channel_count()
+    }
+
+    /// Unsubscribe from the shard channel. Returns the number of shard channels left.
+    pub fn sunsubscribe(&mut self, channel: &str) -> usize {
+        if self.shard_channels.remove(channel) {
+            let mut state = self.inner.pubsub.write().unwrap();
+            remove_shard(&mut state.shard_channels, channel, self.id);
+        }
+        self.shard_channel_count()
+    }
+
+    pub fn channels(&self) -> Vec<String> {
+        self.channels.iter().cloned().collect()
+    }
+
+    pub fn patterns(&self) -> Vec<String> {
+        self.patterns.iter().cloned().collect()
+    }
+
+    pub fn shard_channels(&self) -> Vec<String> {
+        self.shard_channels.iter().cloned().collect()
+    }
+
+    /// The number of channels, patterns and shard channels subscribed to.
+    pub fn count(&self) -> usize {
+        self.channel_count() + self.shard_channel_count()
+    }
+
+    /// The number of channels and patterns subscribed to, which SUBSCRIBE and PSUBSCRIBE
+    /// report.
+    pub fn channel_count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+
+    pub fn shard_channel_count(&self) -> usize {
+        self.shard_channels.len()
+    }
+
+    /// Wait for the next message. Returns None if the buffer overflowed, the subscriber doesn't
+    /// keep up with the publishers and should be disconnected.
+    pub async fn recv(&mut self) -> Option<PubSubMessage> {
+        if self.is_overflowed() {
+            return None;
+        }
+        // the subscriber keeps a sender, so the channel is never closed
+        let message = self.receiver.recv().await;
+        // the buffer is full when it overflows, so the wait above is over right away
+        if self.is_overflowed() {
+            return None;
+        }
+        message
+    }
+
+    /// The next message if one is buffered already.
+    pub fn try_recv(&mut self) -> Option<PubSubMessage> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn is_overflowed(&self) -> bool {
+        self.mailbox.overflowed.load(Ordering::Relaxed)
+    }
+
+    /// Wait until the buffer overflows, for a connection stuck writing to a client which
+    /// doesn't read anymore.
+    pub async fn overflowed(&self) {
+        while !self.is_overflowed() {
+            self.mailbox.overflow.notified().await;
+        }
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        let mut state = self.inner.pubsub.write().unwrap();
+        for channel in self.channels.iter() {
+            remove(&mut state.channels, channel, self.id);
+        }
+        for pattern in self.patterns.iter() {
+            remove(&mut state.patterns, pattern, self.id);
+        }
+        for channel in self.shard_channels.iter() {
+            remove_shard(&mut state.shard_channels, channel, self.id);
+        }
+    }
+}
+
+impl Mailbox {
+    // whether the message was buffered, it never waits for the subscriber
+    fn deliver(&self, message: PubSubMessage) -> bool {
+        if self.overflowed.load(Ordering::Relaxed) {
+            return false;
+        }
+        match self.sender.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.overflowed.store(true, Ordering::Relaxed);
+                // keeps a permit if nobody waits yet
+                self.overflow.notify_one();
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+// forget the channel or pattern once nobody subscribes to it, PUBSUB only reports active ones
+fn remove(subscriptions: &mut HashMap<String, HashMap<u64, Mailbox>>, name: &str, id: u64) {
+    if let Some(subscribers) = subscriptions.get_mut(name) {
+        subscribers.remove(&id);
+        if subscribers.is_empty() {
+            subscriptions.remove(name);
+        }
+    }
+}
+
+fn remove_shard(
+    subscriptions: &mut HashMap<u16, HashMap<String, HashMap<u64, Mailbox>>>,
+    channel: &str,
+    id: u64,
+) {
+    let slot = key_hash_slot(channel.as_bytes());
+    if let Some(channels) = subscriptions.get_mut(&slot) {
+        remove(channels, channel, id);
+        if channels.is_empty() {
+            subscriptions.remove(&slot);
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{cmp::Ordering, fmt};
+
+/// An ordered set which also knows the rank of its values, so that the values at an index or
+/// the number of values below a bound are found without walking the set, like the skiplist of
+/// the sorted sets of redis. It is a treap: a binary search tree balanced by random
+/// priorities, each node keeps the size of its subtree.
+#[derive(Clone)]
+pub(crate) struct RankedSet<T> {
+    root: Tree<T>,
+}
+
+type Tree<T> = Option<Box<Node<T>>>;
+
+#[derive(Clone)]
+struct Node<T> {
+    value: T,
+    priority: u64,
+    // the number of values in the subtree of the node, the node included
+    size: usize,
+    left: Tree<T>,
+    right: Tree<T>,
+}
+
+/// Iterate the values from an index, in ascending or descending order.
+pub(crate) struct Iter<'a, T> {
+    // the nodes left to visit, the next one last
+    stack: Vec<&'a Node<T>>,
+    rev: bool,
+}
+
+impl<T> Default for RankedSet<T> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<T: Ord> RankedSet<T> {
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Insert the value, returns false if it was already in the set.
+    pub fn insert(&mut self, value: T) -> bool {
+        if self.rank(&value).1 {
+            return false;
+        }
+        let (left, right) = split(self.root.take(), &|v: &T| *v < value);
+        let node = Box::new(Node {
+            value,
+            priority: rand::random(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+        self.root = merge(merge(left, Some(node)), right);
+        true
+    }
+
+    /// Remove the value, returns false if it wasn't in the set.
+    pub fn remove(&mut self, value: &T) -> bool {
+        let (left, right) = split(self.root.take(), &|v: &T| v < value);
+        let (found, right) = split(right, &|v: &T| v == value);
+        self.root = merge(left, right);
+        found.is_some()
+    }
+
+    /// The number of values less than the value, and whether the value is in the set.
+    pub fn rank(&self, value: &T) -> (usize, bool) {
+        let mut rank = 0;
+        let mut node = &self.root;
+        while let Some(n) = node {
+            match value.cmp(&n.value) {
+                Ordering::Less => node = &n.left,
+                Ordering::Equal => return (rank + size(&n.left), true),
+                Ordering::Greater => {
+                    rank += size(&n.left) + 1;
+                    node = &n.right;
+                }
+            }
+        }
+        (rank, false)
+    }
+
+    /// The number of values for which `pred` is true, `pred` must be true for the lowest values
+    /// and false for the others, like `slice::partition_point`.
+    pub fn partition_point(&self, pred: impl Fn(&T) -> bool) -> usize {
+        let mut point = 0;
+        let mut node = &self.root;
+        while let Some(n) = node {
+            if pred(&n.value) {
+                point += size(&n.left) + 1;
+                node = &n.right;
+            } else {
+                node = &n.left;
+            }
+        }
+        point
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.iter_from(0, false).next()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.iter_from(self.len().checked_sub(1)?, true).next()
+    }
+
+    /// The values from the index up in ascending order, or down in descending order if `rev`
+    /// is set. Empty if the index is out of range.
+    pub fn iter_from(&self, index: usize, rev: bool) -> Iter<'_, T> {
+        let mut stack = Vec::new();
+        let mut index = index;
+        let mut node = &self.root;
+        while let Some(n) = node {
+            let left = size(&n.left);
+            match index.cmp(&left) {
+                Ordering::Equal => {
+                    stack.push(n.as_ref());
+                    break;
+                }
+                // the nodes after the index are only visited going up, the ones before it only
+                // going down
+                Ordering::Less => {
+                    if !rev {
+                        stack.push(n.as_ref());
+                    }
+                    node = &n.left;
+                }
+                Ordering::Greater => {
+                    if rev {
+                        stack.push(n.as_ref());
+                    }
+                    index -= left + 1;
+                    node = &n.right;
+                }
+            }
+        }
+        // the index is out of range
+        if node.is_none() {
+            stack.clear();
+        }
+        Iter { stack, rev }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.iter_from(0, false)
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        // the nodes between this one and the next on the stack
+        let mut next = if self.rev { &node.left } else { &node.right };
+        while let Some(n) = next {
+            self.stack.push(n.as_ref());
+            next = if self.rev { &n.right } else { &n.left };
+        }
+        Some(&node.value)
+    }
+}
+
+impl<T: Ord> PartialEq for RankedSet<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Ord + fmt::Debug> fmt::Debug for RankedSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+fn size<T>(node: &Tree<T>) -> usize {
+    node.as_ref().map_or(0, |n| n.size)
+}
+
+// split the tree into the values for which `left` is true and the others, `left` must be true
+// for the lowest values only
+fn split<T>(node: Tree<T>, left: &impl Fn(&T) -> bool) -> (Tree<T>, Tree<T>) {
+    let Some(mut n) = node else {
+        return (None, None);
+    };
+    if left(&n.value) {
+        let (l, r) = split(n.right.take(), left);
+        n.right = l;
+        n.size = size(&n.left) + size(&n.right) + 1;
+        (Some(n), r)
+    } else {
+        let (l, r) = split(n.left.take(), left);
+        n.left = r;
+        n.size = size(&n.left) + size(&n.right) + 1;
+        (l, Some(n))
+    }
+}
+
+// join two trees, all the values of the left one are lower than the ones of the right one
+fn merge<T>(left: Tree<T>, right: Tree<T>) -> Tree<T> {
+    match (left, right) {
+        (None, node) | (node, None) => node,
+        (Some(mut l), Some(mut r)) => {
+            if l.priority > r.priority {
+                l.right = merge(l.right.take(), Some(r));
+                l.size = size(&l.left) + size(&l.right) + 1;
+                Some(l)
+            } else {
+                r.left = merge(Some(l), r.left.take());
+                r.size = size(&r.left) + size(&r.right) + 1;
+                Some(r)
+            }
+        }
+    }
+}
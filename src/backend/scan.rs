@@ -0,0 +1,187 @@
+use std::collections::BTreeSet;
+
+use crate::RespFrame;
+
+use super::{glob_match, Backend, BackendError};
+
+/// The elements returned by one call of the SCAN family, with the cursor to pass to the next
+/// call, 0 once the iteration is complete.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanPage<T> {
+    pub cursor: u64,
+    pub elements: Vec<T>,
+}
+
+impl Backend {
+    /// Iterate the keyspace, see `ScanOrder` for the guarantees of the cursor. The pattern and
+    /// the type are applied once the keys are picked, so that a page can be empty while the
+    /// iteration isn't complete.
+    pub fn scan(
+        &self,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+        type_name: Option<&str>,
+    ) -> ScanPage<String> {
+        let (keys, cursor) = self.keyspace.scan(cursor, count);
+        let elements = keys
+            .into_iter()
+            .filter(|key| pattern.is_none_or(|p| glob_match(p, key.as_bytes())))
+            .filter(|key| !self.expire_if_needed(key))
+            .filter(|key| match self.keyspace.get(key) {
+                Some(v) => type_name.is_none_or(|name| name.eq_ignore_ascii_case(v.type_name())),
+                // deleted since it was picked
+                None => false,
+            })
+            .collect();
+        ScanPage { cursor, elements }
+    }
+
+    /// All the live keys matching the pattern.
+    pub fn keys(&self, pattern: &[u8]) -> Vec<String> {
+        let keys = self
+            .keyspace
+            .iter()
+            .filter(|v| glob_match(pattern, v.key().as_bytes()))
+            .map(|v| v.key().clone())
+            .collect::<Vec<_>>();
+        keys.into_iter()
+            .filter(|key| !self.expire_if_needed(key))
+            .collect()
+    }
+
+    /// Iterate the fields of a hash together with their values.
+    pub fn hscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<ScanPage<(String, RespFrame)>, BackendError> {
+        let page = self.read(key, |v| {
+            let hash = v.as_hash()?;
+            let (fields, next) = hash.scan(cursor, count);
+            Ok(ScanPage {
+                cursor: next.unwrap_or(0),
+                elements: fields
+                    .into_iter()
+                    .filter(|f| pattern.is_none_or(|p| glob_match(p, f.as_bytes())))
+                    .filter_map(|f| Some((f.clone(), hash.get(f)?.clone())))
+                    .collect(),
+            })
+        })?;
+        Ok(page.unwrap_or_default())
+    }
+
+    /// Iterate the members of a set.
+    pub fn sscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<ScanPage<Vec<u8>>, BackendError> {
+        let page = self.read(key, |v| {
+            let (members, next) = v.as_set()?.scan(cursor, count);
+            Ok(ScanPage {
+                cursor: next.unwrap_or(0),
+                elements: members
+                    .into_iter()
+                    .filter(|m| pattern.is_none_or(|p| glob_match(p, m)))
+                    .cloned()
+                    .collect(),
+            })
+        })?;
+        Ok(page.unwrap_or_default())
+    }
+
+    /// Iterate the members of a sorted set together with their scores.
+    pub fn zscan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+        pattern: Option<&[u8]>,
+    ) -> Result<ScanPage<(Vec<u8>, f64)>, BackendError> {
+        let page = self.read(key, |v| {
+            let zset = v.as_zset()?;
+            let (members, next) = zset.scan(cursor, count);
+            Ok(ScanPage {
+                cursor: next.unwrap_or(0),
+                elements: members
+                    .into_iter()
+                    .filter(|m| pattern.is_none_or(|p| glob_match(p, m)))
+                    .filter_map(|m| Some((m.clone(), zset.score(m)?)))
+                    .collect(),
+            })
+        })?;
+        Ok(page.unwrap_or_default())
+    }
+}
+
+impl<T> Default for ScanPage<T> {
+    fn default() -> Self {
+        Self {
+            cursor: 0,
+            elements: Vec::new(),
+        }
+    }
+}
+
+/// The names of a collection, e.g. the fields of a hash, in the order SCAN visits them: by the
+/// position of their name, then by the name. The cursor of SCAN is the position to resume
+/// from, so that the order doesn't depend on where the elements live in the collection: an
+/// element present during the whole iteration is returned exactly once, no matter how the
+/// collection grows or shrinks in between, and a page is found without walking the elements
+/// before it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ScanOrder<T: Ord> {
+    names: BTreeSet<(u64, T)>,
+}
+
+impl<T: Ord + Default + AsRef<[u8]>> ScanOrder<T> {
+    pub fn insert(&mut self, name: T) {
+        self.names.insert((position(name.as_ref()), name));
+    }
+
+    pub fn remove(&mut self, name: T) {
+        self.names.remove(&(position(name.as_ref()), name));
+    }
+
+    /// The next `count` names from the cursor, and the cursor of the following page if there
+    /// are names left. Names at the same position are always returned in the same page, so a
+    /// page can hold more than `count` names because of that.
+    pub fn page(&self, cursor: u64, count: usize) -> (Vec<&T>, Option<u64>) {
+        let mut names = Vec::new();
+        let mut last = cursor;
+        // the default name is the lowest one, e.g. an empty string
+        for (at, name) in self.names.range((cursor, T::default())..) {
+            if names.len() >= count.max(1) && *at != last {
+                return (names, Some(*at));
+            }
+            last = *at;
+            names.push(name);
+        }
+        (names, None)
+    }
+
+    /// The names at positions from `from` up to `to` excluded, in order.
+    pub fn between(&self, from: u64, to: u64) -> impl Iterator<Item = &T> {
+        self.names
+            .range((from, T::default())..)
+            .take_while(move |(at, _)| *at < to)
+            .map(|(_, name)| name)
+    }
+}
+
+/// The position of a name in the scan order. The hash is computed with fixed constants (64-bit
+/// FNV-1a, whose bits are then mixed so that close names are far apart), so that the cursors
+/// stay valid across calls, builds and restarts.
+pub(crate) fn position(name: &[u8]) -> u64 {
+    let hash = name.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    let hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
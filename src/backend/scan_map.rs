@@ -0,0 +1,219 @@
+use std::sync::{Mutex, PoisonError};
+
+use rand::seq::SliceRandom;
+
+use dashmap::{
+    iter::Iter,
+    mapref::{
+        entry,
+        one::{Ref, RefMut},
+    },
+    DashMap,
+};
+
+use super::scan::{position, ScanOrder};
+
+// the scan order of the keys is split by the top bits of their position, so that the writers
+// of different keys rarely wait for each other
+const ORDER_SHARDS: usize = 64;
+const ORDER_SHARD_BITS: u32 = ORDER_SHARDS.trailing_zeros();
+// the number of keys RANDOMKEY picks from, the same as redis
+const RANDOM_KEY_SAMPLES: usize = 20;
+
+/// A map of keys, e.g. the keys of a database with their values. Next to the map the keys are
+/// kept in their scan order, so that SCAN resumes from its cursor, RANDOMKEY picks a key and
+/// the active expire cycle samples the volatile keys without walking the map. The order of a
+/// key is only updated while its entry in the map is locked.
+#[derive(Debug)]
+pub struct ScanMap<V> {
+    map: DashMap<String, V>,
+    order: Vec<Mutex<ScanOrder<String>>>,
+}
+
+/// An entry of the map, like the entries of DashMap.
+pub enum Entry<'a, V> {
+    Occupied(OccupiedEntry<'a, V>),
+    Vacant(VacantEntry<'a, V>),
+}
+
+pub struct OccupiedEntry<'a, V> {
+    entry: entry::OccupiedEntry<'a, String, V>,
+    map: &'a ScanMap<V>,
+}
+
+pub struct VacantEntry<'a, V> {
+    entry: entry::VacantEntry<'a, String, V>,
+    map: &'a ScanMap<V>,
+}
+
+impl<V> Default for ScanMap<V> {
+    fn default() -> Self {
+        Self {
+            map: DashMap::new(),
+            order: (0..ORDER_SHARDS).map(|_| Mutex::default()).collect(),
+        }
+    }
+}
+
+impl<V> ScanMap<V> {
+    pub fn get(&self, key: &str) -> Option<Ref<'_, String, V>> {
+        self.map.get(key)
+    }
+
+    pub fn get_mut(&self, key: &str) -> Option<RefMut<'_, String, V>> {
+        self.map.get_mut(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn iter(&self) -> Iter<'_, String, V> {
+        self.map.iter()
+    }
+
+    pub fn entry(&self, key: String) -> Entry<'_, V> {
+        match self.map.entry(key) {
+            entry::Entry::Occupied(entry) => Entry::Occupied(OccupiedEntry { entry, map: self }),
+            entry::Entry::Vacant(entry) => Entry::Vacant(VacantEntry { entry, map: self }),
+        }
+    }
+
+    /// Set the value of the key, returns the old one.
+    pub fn insert(&self, key: String, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&self, key: &str) -> Option<(String, V)> {
+        self.remove_if(key, |_, _| true)
+    }
+
+    /// Remove the key if `f` returns true, the key is locked during the call.
+    pub fn remove_if(&self, key: &str, f: impl FnOnce(&String, &V) -> bool) -> Option<(String, V)> {
+        self.map.remove_if(key, |key, value| {
+            let remove = f(key, value);
+            if remove {
+                self.order(key).remove(key.clone());
+            }
+            remove
+        })
+    }
+
+    /// The next `count` keys from the cursor in the scan order, with the cursor of the next
+    /// page, 0 once all the keys were visited. See `ScanOrder::page`.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<String>, u64) {
+        let mut keys = Vec::new();
+        let mut cursor = cursor;
+        for shard in shard(cursor)..ORDER_SHARDS {
+            if keys.len() >= count.max(1) {
+                return (keys, cursor);
+            }
+
+            let order = self.order[shard]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let (page, next) = order.page(cursor, count.max(1) - keys.len());
+            keys.extend(page.into_iter().cloned());
+            if let Some(next) = next {
+                return (keys, next);
+            }
+            if shard + 1 == ORDER_SHARDS {
+                break;
+            }
+            // the first position of the next shard
+            cursor = (shard as u64 + 1) << (64 - ORDER_SHARD_BITS);
+        }
+        (keys, 0)
+    }
+
+    /// A random key, None if there are no keys. Like the fair random key of redis, a few keys
+    /// are taken in a row from a random position and one of them is picked, so that a key
+    /// after a large gap between positions isn't picked much more often than the others.
+    pub fn random_key(&self) -> Option<String> {
+        let position = rand::random::<u64>();
+        let start = shard(position);
+        let mut keys = Vec::with_capacity(RANDOM_KEY_SAMPLES);
+        // the shard of the position comes again last, for the keys before the position
+        for i in 0..=ORDER_SHARDS {
+            let order = self.order[(start + i) % ORDER_SHARDS]
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let (from, to) = match i {
+                0 => (position, u64::MAX),
+                i if i == ORDER_SHARDS => (0, position),
+                _ => (0, u64::MAX),
+            };
+            let wanted = RANDOM_KEY_SAMPLES - keys.len();
+            keys.extend(order.between(from, to).take(wanted).cloned());
+            if keys.len() == RANDOM_KEY_SAMPLES {
+                break;
+            }
+        }
+        keys.choose(&mut rand::thread_rng()).cloned()
+    }
+
+    fn order(&self, key: &str) -> std::sync::MutexGuard<'_, ScanOrder<String>> {
+        self.order[shard(position(key.as_bytes()))]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a, V> Entry<'a, V> {
+    pub fn insert(self, value: V) -> RefMut<'a, String, V> {
+        match self {
+            Entry::Occupied(mut entry) => {
+                entry.insert(value);
+                entry.entry.into_ref()
+            }
+            Entry::Vacant(entry) => entry.insert(value),
+        }
+    }
+
+    pub fn or_insert_with(self, f: impl FnOnce() -> V) -> RefMut<'a, String, V> {
+        match self {
+            Entry::Occupied(entry) => entry.entry.into_ref(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+}
+
+impl<V> OccupiedEntry<'_, V> {
+    pub fn get(&self) -> &V {
+        self.entry.get()
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        self.entry.insert(value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map
+            .order(self.entry.key())
+            .remove(self.entry.key().clone());
+        self.entry.remove()
+    }
+}
+
+impl<'a, V> VacantEntry<'a, V> {
+    pub fn insert(self, value: V) -> RefMut<'a, String, V> {
+        self.map
+            .order(self.entry.key())
+            .insert(self.entry.key().clone());
+        self.entry.insert(value)
+    }
+}
+
+fn shard(position: u64) -> usize {
+    (position >> (64 - ORDER_SHARD_BITS)) as usize
+}
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use rand::seq::IteratorRandom;
+
+use super::{random_picks, scan::ScanOrder, Backend, BackendError, Value};
+
+/// A set: distinct members in no particular order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Set {
+    members: HashSet<Vec<u8>>,
+    // the members in the order SSCAN visits them
+    order: ScanOrder<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperation {
+    Inter,
+    Union,
+    Diff,
+}
+
+impl Set {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn contains(&self, member: &[u8]) -> bool {
+        self.members.contains(member)
+    }
+
+    /// Add the member, returns true if it is new.
+    pub fn insert(&mut self, member: Vec<u8>) -> bool {
+        if self.members.contains(&member) {
+            return false;
+        }
+        self.order.insert(member.clone());
+        self.members.insert(member)
+    }
+
+    /// Remove the member, returns true if it was in the set.
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        if !self.members.remove(member) {
+            return false;
+        }
+        self.order.remove(member.to_vec());
+        true
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &Vec<u8>> {
+        self.members.iter()
+    }
+
+    pub fn members(&self) -> &HashSet<Vec<u8>> {
+        &self.members
+    }
+
+    /// The members of the next SSCAN page, see `ScanOrder::page`.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<&Vec<u8>>, Option<u64>) {
+        self.order.page(cursor, count)
+    }
+}
+
+impl FromIterator<Vec<u8>> for Set {
+    fn from_iter<I: IntoIterator<Item = Vec<u8>>>(iter: I) -> Self {
+        let mut set = Set::new();
+        for member in iter {
+            set.insert(member);
+        }
+        set
+    }
+}
+
+impl Backend {
+    /// Add the members to the set, returns the number of members that were newly added.
+    pub fn sadd(&self, key: String, members: Vec<Vec<u8>>) -> Result<usize, BackendError> {
+        self.upsert(
+            key,
+            || Value::Set(Set::new()),
+            |v| {
+                let set = v.as_set_mut()?;
+                Ok(members
+                    .into_iter()
+                    .filter(|m| set.insert(m.clone()))
+                    .count())
+            },
+        )
+    }
+
+    /// Remove the members from the set, returns the number of members that were removed.
+    pub fn srem(&self, key: &str, members: &[Vec<u8>]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let set = v.as_set_mut()?;
+                Ok(members.iter().filter(|m| set.remove(m)).count())
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn smembers(&self, key: &str) -> Result<HashSet<Vec<u8>>, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_set()?.members().clone()))?
+            .unwrap_or_default())
+    }
+
+    pub fn scard(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_set()?.len()))?
+            .unwrap_or_default())
+    }
+
+    pub fn sismember(&self, key: &str, member: &[u8]) -> Result<bool, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_set()?.contains(member)))?
+            .unwrap_or(false))
+    }
+
+    pub fn smismember(&self, key: &str, members: &[Vec<u8>]) -> Result<Vec<bool>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let set = v.as_set()?;
+                Ok(members.iter().map(|m| set.contains(m)).collect())
+            })?
+            .unwrap_or_else(|| vec![false; members.len()]))
+    }
+
+    /// Remove and return up to `count` random members of the set.
+    pub fn spop(&self, key: &str, count: usize) -> Result<Vec<Vec<u8>>, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let set = v.as_set_mut()?;
+                let popped = set
+                    .iter()
+                    .cloned()
+                    .choose_multiple(&mut rand::thread_rng(), count);
+                for m in popped.iter() {
+                    set.remove(m);
+                }
+                Ok(popped)
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Return random members of the set. A positive count returns up to `count` distinct
+    /// members, a negative count returns exactly `-count` members which may repeat.
+    pub fn srandmember(&self, key: &str, count: i64) -> Result<Vec<Vec<u8>>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let set = v.as_set()?;
+                Ok(random_picks(set.iter().cloned(), count))
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Move the member from the source set to the destination set. Returns false if the
+    /// member is not in the source set.
+    pub fn smove(
+        &self,
+        source: &str,
+        destination: String,
+        member: Vec<u8>,
+    ) -> Result<bool, BackendError> {
+        // the destination must be checked before anything is removed from the source
+        self.read(&destination, |v| v.as_set().map(|_| ()))?;
+        if !self.sismember(source, &member)? {
+            return Ok(false);
+        }
+        if source == destination {
+            return Ok(true);
+        }
+
+        if self.srem(source, std::slice::from_ref(&member))? == 0 {
+            return Ok(false);
+        }
+        self.sadd(destination, vec![member])?;
+        Ok(true)
+    }
+
+    /// Compute the intersection, union or difference of the sets, missing keys are treated as
+    /// empty sets.
+    pub fn set_operation(
+        &self,
+        op: SetOperation,
+        keys: &[String],
+    ) -> Result<HashSet<Vec<u8>>, BackendError> {
+        let mut sets = Vec::with_capacity(keys.len());
+        for key in keys {
+            sets.push(self.smembers(key)?);
+        }
+
+        let mut sets = sets.into_iter();
+        let mut ret = sets.next().unwrap_or_default();
+        for set in sets {
+            match op {
+                SetOperation::Inter => ret.retain(|m| set.contains(m)),
+                SetOperation::Union => ret.extend(set),
+                SetOperation::Diff => ret.retain(|m| !set.contains(m)),
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Store the result of the set operation in the destination key, overwriting it. Returns
+    /// the number of members in the resulting set.
+    pub fn set_operation_store(
+        &self,
+        op: SetOperation,
+        destination: String,
+        keys: &[String],
+    ) -> Result<usize, BackendError> {
+        let set = self.set_operation(op, keys)?;
+        let len = set.len();
+        self.put(destination, Value::Set(set.into_iter().collect()));
+        Ok(len)
+    }
+}
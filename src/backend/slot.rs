@@ -0,0 +1,29 @@
+/// The number of hash slots of a redis cluster.
+pub const CLUSTER_SLOTS: u16 = 16384;
+
+/// The cluster hash slot of the key: CRC16 of the key modulo 16384. If the key has a non-empty
+/// `{...}` hash tag only the tag is hashed, so that related keys can share a slot.
+pub fn key_hash_slot(key: &[u8]) -> u16 {
+    let tag = key
+        .iter()
+        .position(|&c| c == b'{')
+        .and_then(|start| {
+            let end = key[start + 1..].iter().position(|&c| c == b'}')?;
+            Some(&key[start + 1..start + 1 + end])
+        })
+        .filter(|tag| !tag.is_empty());
+    crc16(tag.unwrap_or(key)) % CLUSTER_SLOTS
+}
+
+// CRC16-CCITT (XModem), the variant redis cluster uses
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, fmt, ops::Bound, time::Duration};
+
+use super::{now_ms, Backend, BackendError, ConsumerGroup, Value};
+
+/// The ID of a stream entry, `<ms>-<seq>`: the creation time in unix milliseconds and a
+/// sequence number for the entries created in the same millisecond.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+/// The fields and values of an entry, in the order they were given to XADD.
+pub type StreamFields = Vec<(Vec<u8>, Vec<u8>)>;
+
+pub type StreamEntry = (StreamId, StreamFields);
+
+/// A stream: an append only log of entries ordered by their ID.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    pub(super) entries: BTreeMap<StreamId, StreamFields>,
+    // the IDs only grow, even when the last entry is deleted
+    pub(super) last_id: StreamId,
+    // consumer groups by name
+    pub(super) groups: BTreeMap<String, ConsumerGroup>,
+}
+
+/// The ID argument of XADD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XAddId {
+    // "*": generate the whole ID
+    Auto,
+    // "<ms>-*": generate the sequence number only
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamTrimStrategy {
+    // MAXLEN: keep at most this many entries
+    MaxLen(usize),
+    // MINID: evict the entries with an ID lower than this one
+    MinId(StreamId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTrim {
+    pub strategy: StreamTrimStrategy,
+    // "~": the entries live in a B-tree rather than in macro nodes, so an approximate trim is as
+    // cheap as an exact one and only differs by honoring LIMIT
+    pub approximate: bool,
+    // LIMIT: evict at most this many entries
+    pub limit: Option<usize>,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        Self { ms, seq }
+    }
+
+    /// The smallest ID greater than this one.
+    pub fn next(&self) -> Option<StreamId> {
+        match (self.ms, self.seq) {
+            (ms, u64::MAX) => ms.checked_add(1).map(|ms| StreamId::new(ms, 0)),
+            (ms, seq) => Some(StreamId::new(ms, seq + 1)),
+        }
+    }
+
+    /// The greatest ID lower than this one.
+    pub fn prev(&self) -> Option<StreamId> {
+        match (self.ms, self.seq) {
+            (ms, 0) => ms.checked_sub(1).map(|ms| StreamId::new(ms, u64::MAX)),
+            (ms, seq) => Some(StreamId::new(ms, seq - 1)),
+        }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+impl Stream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// Append an entry, the ID must be greater than the ID of every entry ever added.
+    pub fn add(&mut self, id: XAddId, fields: StreamFields) -> Result<StreamId, BackendError> {
+        let last = self.last_id;
+        let id = match id {
+            // the clock may go backwards, the IDs must not
+            XAddId::Auto => match now_ms() {
+                now if now > last.ms => StreamId::new(now, 0),
+                _ => last.next().ok_or(BackendError::StreamExhausted)?,
+            },
+            XAddId::AutoSeq(ms) if ms > last.ms => StreamId::new(ms, 0),
+            XAddId::AutoSeq(ms) if ms == last.ms => last
+                .next()
+                .filter(|v| v.ms == ms)
+                .ok_or(BackendError::StreamIdTooSmall)?,
+            XAddId::AutoSeq(_) => return Err(BackendError::StreamIdTooSmall),
+            XAddId::Explicit(StreamId::MIN) => return Err(BackendError::StreamIdZero),
+            XAddId::Explicit(id) if id <= last => return Err(BackendError::StreamIdTooSmall),
+            XAddId::Explicit(id) => id,
+        };
+
+        self.entries.insert(id, fields);
+        self.last_id = id;
+        Ok(id)
+    }
+
+    /// The entries between start and end, in ascending order or descending if `rev` is set.
+    pub fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Vec<StreamEntry> {
+        // resolve to an inclusive range, BTreeMap::range panics on an empty one
+        let start = match start {
+            Bound::Included(id) => Some(id),
+            Bound::Excluded(id) => id.next(),
+            Bound::Unbounded => Some(StreamId::MIN),
+        };
+        let end = match end {
+            Bound::Included(id) => Some(id),
+            Bound::Excluded(id) => id.prev(),
+            Bound::Unbounded => Some(StreamId::MAX),
+        };
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) if start <= end => (start, end),
+            _ => return vec![],
+        };
+
+        let entries = self.entries.range(start..=end);
+        let count = count.unwrap_or(usize::MAX);
+        let clone = |(id, fields): (&StreamId, &StreamFields)| (*id, fields.clone());
+        if rev {
+            entries.rev().take(count).map(clone).collect()
+        } else {
+            entries.take(count).map(clone).collect()
+        }
+    }
+
+    /// Evict the oldest entries according to the strategy. Returns the number of entries
+    /// evicted.
+    pub fn trim(&mut self, trim: &StreamTrim) -> usize {
+        let mut n = match trim.strategy {
+            StreamTrimStrategy::MaxLen(len) => self.len().saturating_sub(len),
+            StreamTrimStrategy::MinId(id) => self.entries.range(..id).count(),
+        };
+        if let (true, Some(limit)) = (trim.approximate, trim.limit) {
+            n = n.min(limit);
+        }
+
+        for _ in 0..n {
+            self.entries.pop_first();
+        }
+        n
+    }
+
+    /// Returns the number of entries deleted, IDs which don't exist are ignored.
+    pub fn delete(&mut self, ids: &[StreamId]) -> usize {
+        ids.iter()
+            .filter(|id| self.entries.remove(id).is_some())
+            .count()
+    }
+}
+
+impl Backend {
+    /// Append an entry to the stream, then trim it if asked to. A missing key is created unless
+    /// `nomkstream` is set, in which case None is returned.
+    pub fn xadd(
+        &self,
+        key: String,
+        id: XAddId,
+        fields: StreamFields,
+        nomkstream: bool,
+        trim: Option<StreamTrim>,
+    ) -> Result<Option<StreamId>, BackendError> {
+        let add = |v: &mut Value| {
+            let stream = v.as_stream_mut()?;
+            let id = stream.add(id, fields)?;
+            if let Some(trim) = trim {
+                stream.trim(&trim);
+            }
+            Ok(id)
+        };
+
+        let id = if nomkstream {
+            self.update(&key, add)?
+        } else {
+            Some(self.upsert(key.clone(), || Value::Stream(Stream::new()), add)?)
+        };
+        if id.is_some() {
+            self.signal_stream(&key);
+        }
+        Ok(id)
+    }
+
+    pub fn xlen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_stream()?.len()))?
+            .unwrap_or_default())
+    }
+
+    pub fn xrange(
+        &self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        count: Option<usize>,
+        rev: bool,
+    ) -> Result<Vec<StreamEntry>, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_stream()?.range(start, end, count, rev)))?
+            .unwrap_or_default())
+    }
+
+    pub fn xtrim(&self, key: &str, trim: &StreamTrim) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| Ok(v.as_stream_mut()?.trim(trim)))?
+            .unwrap_or_default())
+    }
+
+    pub fn xdel(&self, key: &str, ids: &[StreamId]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| Ok(v.as_stream_mut()?.delete(ids)))?
+            .unwrap_or_default())
+    }
+
+    /// The ID of the last entry ever added to the stream, 0-0 if the key does not exist.
+    pub fn stream_last_id(&self, key: &str) -> Result<StreamId, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_stream()?.last_id()))?
+            .unwrap_or_default())
+    }
+
+    /// Up to `count` entries with an ID greater than the given one for each of the streams,
+    /// the streams without such entries are left out.
+    pub fn xread(
+        &self,
+        streams: &[(String, StreamId)],
+        count: Option<usize>,
+    ) -> Result<Vec<(String, Vec<StreamEntry>)>, BackendError> {
+        let mut ret = Vec::new();
+        for (key, id) in streams {
+            let entries = self.xrange(key, Bound::Excluded(*id), Bound::Unbounded, count, false)?;
+            if !entries.is_empty() {
+                ret.push((key.clone(), entries));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Like xread, but if none of the streams has new entries wait until one is added. A
+    /// timeout of None waits forever, None is returned on timeout.
+    pub async fn xread_blocking(
+        &self,
+        streams: Vec<(String, StreamId)>,
+        count: Option<usize>,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Vec<(String, Vec<StreamEntry>)>>, BackendError> {
+        let keys = streams.iter().map(|(key, _)| key.clone()).collect();
+        self.block_on_streams(keys, timeout, |db| {
+            let entries = db.xread(&streams, count)?;
+            Ok((!entries.is_empty()).then_some(entries))
+        })
+        .await
+    }
+}
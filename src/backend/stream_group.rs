@@ -0,0 +1,489 @@
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    ops::Bound,
+    time::Duration,
+};
+
+use super::{now_ms, Backend, BackendError, Stream, StreamEntry, StreamFields, StreamId, Value};
+
+/// A consumer group: every entry of the stream is delivered to a single consumer of the group,
+/// and stays pending until the consumer acknowledges it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsumerGroup {
+    // reading ">" delivers the entries after this one
+    last_delivered: StreamId,
+    // the pending entries list: entries delivered to a consumer but not acknowledged yet
+    pending: BTreeMap<StreamId, PendingEntry>,
+    consumers: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    // the last time the entry was delivered, in unix milliseconds
+    pub delivery_time: u64,
+    pub delivery_count: u64,
+}
+
+/// An entry read by a consumer, the fields are None if the entry was deleted from the stream
+/// while it was pending.
+pub type GroupEntry = (StreamId, Option<StreamFields>);
+
+/// The reply of XPENDING without a range.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PendingSummary {
+    pub count: usize,
+    // the smallest and greatest pending IDs
+    pub ids: Option<(StreamId, StreamId)>,
+    // the number of pending entries of each consumer with at least one
+    pub consumers: Vec<(String, usize)>,
+}
+
+/// The range form of XPENDING: IDLE min-idle-time start end count [consumer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRange {
+    pub min_idle: Option<u64>,
+    pub start: Bound<StreamId>,
+    pub end: Bound<StreamId>,
+    pub count: usize,
+    pub consumer: Option<String>,
+}
+
+/// The consumer claiming pending entries which are idle for at least `min_idle` milliseconds,
+/// shared by XCLAIM and XAUTOCLAIM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claim {
+    pub group: String,
+    pub consumer: String,
+    pub min_idle: u64,
+    // JUSTID: don't count the claim as a delivery
+    pub just_id: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XClaimOptions {
+    // IDLE ms: set the idle time of the claimed entries
+    pub idle: Option<u64>,
+    // TIME unix-time-milliseconds: set the delivery time of the claimed entries
+    pub time: Option<u64>,
+    // RETRYCOUNT count: set the delivery count of the claimed entries
+    pub retry_count: Option<u64>,
+    // FORCE: claim the entries which are not pending yet
+    pub force: bool,
+    // LASTID id: move the last delivered ID of the group forward
+    pub last_id: Option<StreamId>,
+}
+
+impl PendingEntry {
+    fn new(consumer: String) -> Self {
+        Self {
+            consumer,
+            delivery_time: now_ms(),
+            delivery_count: 1,
+        }
+    }
+
+    /// Milliseconds since the entry was last delivered.
+    pub fn idle(&self) -> u64 {
+        now_ms().saturating_sub(self.delivery_time)
+    }
+}
+
+impl ConsumerGroup {
+    fn new(last_delivered: StreamId) -> Self {
+        Self {
+            last_delivered,
+            ..Default::default()
+        }
+    }
+
+    // deliver up to `count` entries which were never delivered to the group
+    fn read_new(
+        &mut self,
+        entries: &BTreeMap<StreamId, StreamFields>,
+        consumer: &str,
+        count: Option<usize>,
+        noack: bool,
+    ) -> Vec<GroupEntry> {
+        let delivered = entries
+            .range((Bound::Excluded(self.last_delivered), Bound::Unbounded))
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, fields)| (*id, Some(fields.clone())))
+            .collect::<Vec<_>>();
+
+        if let Some((id, _)) = delivered.last() {
+            self.last_delivered = *id;
+        }
+        if !noack {
+            for (id, _) in delivered.iter() {
+                // the ID may be pending already if the group was moved back by SETID
+                self.pending
+                    .insert(*id, PendingEntry::new(consumer.to_string()));
+            }
+        }
+        delivered
+    }
+
+    // deliver again up to `count` entries pending for the consumer with an ID greater than `after`
+    fn read_pending(
+        &mut self,
+        entries: &BTreeMap<StreamId, StreamFields>,
+        consumer: &str,
+        after: StreamId,
+        count: Option<usize>,
+    ) -> Vec<GroupEntry> {
+        self.pending
+            .range_mut((Bound::Excluded(after), Bound::Unbounded))
+            .filter(|(_, pending)| pending.consumer == consumer)
+            .take(count.unwrap_or(usize::MAX))
+            .map(|(id, pending)| {
+                pending.delivery_time = now_ms();
+                pending.delivery_count += 1;
+                (*id, entries.get(id).cloned())
+            })
+            .collect()
+    }
+
+    // give a pending entry to the claiming consumer, the entries deleted from the stream are
+    // dropped from the pending entries list instead. Returns None if the entry can't be claimed
+    fn claim(
+        &mut self,
+        entries: &BTreeMap<StreamId, StreamFields>,
+        id: StreamId,
+        claim: &Claim,
+        options: &XClaimOptions,
+    ) -> Option<StreamEntry> {
+        let Some(fields) = entries.get(&id) else {
+            self.pending.remove(&id);
+            return None;
+        };
+
+        let pending = match self.pending.entry(id) {
+            Entry::Occupied(entry) if entry.get().idle() >= claim.min_idle => entry.into_mut(),
+            Entry::Vacant(entry) if options.force => entry.insert(PendingEntry {
+                consumer: claim.consumer.clone(),
+                delivery_time: now_ms(),
+                delivery_count: 0,
+            }),
+            _ => return None,
+        };
+
+        pending.consumer = claim.consumer.clone();
+        pending.delivery_time = match (options.idle, options.time) {
+            (Some(idle), _) => now_ms().saturating_sub(idle),
+            (None, Some(time)) => time,
+            (None, None) => now_ms(),
+        };
+        match options.retry_count {
+            Some(count) => pending.delivery_count = count,
+            None if !claim.just_id => pending.delivery_count += 1,
+            None => {}
+        }
+        Some((id, fields.clone()))
+    }
+}
+
+impl Backend {
+    /// Create a consumer group which delivers the entries after `id`, None is the last ID of the
+    /// stream ("$"). A missing stream is created if `mkstream` is set.
+    pub fn xgroup_create(
+        &self,
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+        mkstream: bool,
+    ) -> Result<(), BackendError> {
+        let create = |v: &mut Value| {
+            let stream = v.as_stream_mut()?;
+            let id = id.unwrap_or(stream.last_id);
+            match stream.groups.entry(group) {
+                Entry::Occupied(_) => Err(BackendError::BusyGroup),
+                Entry::Vacant(entry) => {
+                    entry.insert(ConsumerGroup::new(id));
+                    Ok(())
+                }
+            }
+        };
+
+        if mkstream {
+            self.upsert(key, || Value::Stream(Stream::new()), create)
+        } else {
+            self.update(&key, create)?.ok_or(BackendError::NoStream)
+        }
+    }
+
+    /// Set the last delivered ID of the group, None is the last ID of the stream ("$").
+    pub fn xgroup_setid(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+    ) -> Result<(), BackendError> {
+        self.update(key, |v| {
+            let stream = v.as_stream_mut()?;
+            let id = id.unwrap_or(stream.last_id);
+            let group = stream
+                .groups
+                .get_mut(group)
+                .ok_or_else(|| BackendError::NoGroup(key.to_string(), group.to_string()))?;
+            group.last_delivered = id;
+            Ok(())
+        })?
+        .ok_or(BackendError::NoStream)
+    }
+
+    pub fn xgroup_destroy(&self, key: &str, group: &str) -> Result<bool, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                Ok(v.as_stream_mut()?.groups.remove(group).is_some())
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Returns false if the consumer already exists.
+    pub fn xgroup_create_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: String,
+    ) -> Result<bool, BackendError> {
+        self.update_group(key, group, |group, _| Ok(group.consumers.insert(consumer)))
+    }
+
+    /// Delete the consumer together with its pending entries. Returns the number of pending
+    /// entries it had.
+    pub fn xgroup_del_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<usize, BackendError> {
+        self.update_group(key, group, |group, _| {
+            group.consumers.remove(consumer);
+            let before = group.pending.len();
+            group
+                .pending
+                .retain(|_, pending| pending.consumer != consumer);
+            Ok(before - group.pending.len())
+        })
+    }
+
+    /// Read the streams on behalf of the consumer. An ID of None (">") delivers the entries
+    /// never delivered to the group, which become pending unless `noack` is set, and the
+    /// streams without such entries are left out. Any other ID reads the history of the entries
+    /// pending for the consumer after that ID.
+    pub fn xreadgroup(
+        &self,
+        group: &str,
+        consumer: &str,
+        streams: &[(String, Option<StreamId>)],
+        count: Option<usize>,
+        noack: bool,
+    ) -> Result<Vec<(String, Vec<GroupEntry>)>, BackendError> {
+        let mut ret = Vec::new();
+        for (key, id) in streams {
+            let entries = self.update_group(key, group, |group, entries| {
+                group.consumers.insert(consumer.to_string());
+                Ok(match id {
+                    Some(id) => Some(group.read_pending(entries, consumer, *id, count)),
+                    None => Some(group.read_new(entries, consumer, count, noack))
+                        .filter(|v| !v.is_empty()),
+                })
+            })?;
+            if let Some(entries) = entries {
+                ret.push((key.clone(), entries));
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Like xreadgroup, but if none of the streams has new entries wait until one is added. A
+    /// timeout of None waits forever, None is returned on timeout. Only the reads of new
+    /// entries (">") may block.
+    pub async fn xreadgroup_blocking(
+        &self,
+        group: &str,
+        consumer: &str,
+        streams: Vec<(String, Option<StreamId>)>,
+        count: Option<usize>,
+        noack: bool,
+        timeout: Option<Duration>,
+    ) -> Result<Option<Vec<(String, Vec<GroupEntry>)>>, BackendError> {
+        let keys = streams.iter().map(|(key, _)| key.clone()).collect();
+        self.block_on_streams(keys, timeout, |db| {
+            let entries = db.xreadgroup(group, consumer, &streams, count, noack)?;
+            Ok((!entries.is_empty()).then_some(entries))
+        })
+        .await
+    }
+
+    /// Acknowledge the entries, removing them from the pending entries list. Returns the number
+    /// of entries which were pending.
+    pub fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                Ok(match v.as_stream_mut()?.groups.get_mut(group) {
+                    Some(group) => ids
+                        .iter()
+                        .filter(|id| group.pending.remove(id).is_some())
+                        .count(),
+                    None => 0,
+                })
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn xpending_summary(&self, key: &str, group: &str) -> Result<PendingSummary, BackendError> {
+        self.read_group(key, group, |group| {
+            let mut consumers = BTreeMap::<&str, usize>::new();
+            for pending in group.pending.values() {
+                *consumers.entry(&pending.consumer).or_default() += 1;
+            }
+
+            PendingSummary {
+                count: group.pending.len(),
+                ids: group
+                    .pending
+                    .first_key_value()
+                    .zip(group.pending.last_key_value())
+                    .map(|((first, _), (last, _))| (*first, *last)),
+                consumers: consumers
+                    .into_iter()
+                    .map(|(consumer, n)| (consumer.to_string(), n))
+                    .collect(),
+            }
+        })
+    }
+
+    /// The pending entries in the range, optionally only those of a consumer or idle for at
+    /// least `min_idle` milliseconds.
+    pub fn xpending(
+        &self,
+        key: &str,
+        group: &str,
+        range: &PendingRange,
+    ) -> Result<Vec<(StreamId, PendingEntry)>, BackendError> {
+        self.read_group(key, group, |group| {
+            let (start, end) = (range.start, range.end);
+            if !is_valid_range(start, end) {
+                return vec![];
+            }
+
+            group
+                .pending
+                .range((start, end))
+                .filter(|(_, v)| range.consumer.as_ref().is_none_or(|c| *c == v.consumer))
+                .filter(|(_, v)| range.min_idle.is_none_or(|idle| v.idle() >= idle))
+                .take(range.count)
+                .map(|(id, v)| (*id, v.clone()))
+                .collect()
+        })
+    }
+
+    /// Change the ownership of the pending entries to the claiming consumer. Returns the claimed
+    /// entries.
+    pub fn xclaim(
+        &self,
+        key: &str,
+        claim: &Claim,
+        ids: &[StreamId],
+        options: XClaimOptions,
+    ) -> Result<Vec<StreamEntry>, BackendError> {
+        self.update_group(key, &claim.group, |group, entries| {
+            group.consumers.insert(claim.consumer.clone());
+            if let Some(id) = options.last_id.filter(|id| *id > group.last_delivered) {
+                group.last_delivered = id;
+            }
+
+            Ok(ids
+                .iter()
+                .filter_map(|id| group.claim(entries, *id, claim, &options))
+                .collect())
+        })
+    }
+
+    /// Claim up to `count` of the pending entries from `start` on, like xclaim. Returns the ID
+    /// to start the next call from (0-0 once the whole list was scanned), the claimed entries
+    /// and the IDs of the entries dropped from the list because they were deleted.
+    pub fn xautoclaim(
+        &self,
+        key: &str,
+        claim: &Claim,
+        start: StreamId,
+        count: usize,
+    ) -> Result<(StreamId, Vec<StreamEntry>, Vec<StreamId>), BackendError> {
+        self.update_group(key, &claim.group, |group, entries| {
+            group.consumers.insert(claim.consumer.clone());
+
+            // like redis, bound the work done by a single call
+            let attempts = count.saturating_mul(10);
+            let mut ids = group
+                .pending
+                .range(start..)
+                .map(|(id, _)| *id)
+                .take(attempts.saturating_add(1))
+                .collect::<Vec<_>>()
+                .into_iter();
+            let (mut claimed, mut deleted) = (vec![], vec![]);
+            for _ in 0..attempts {
+                if claimed.len() == count {
+                    break;
+                }
+                let Some(id) = ids.next() else {
+                    break;
+                };
+                if !entries.contains_key(&id) {
+                    deleted.push(id);
+                }
+                if let Some(entry) = group.claim(entries, id, claim, &XClaimOptions::default()) {
+                    claimed.push(entry);
+                }
+            }
+
+            let next = ids.next().unwrap_or_default();
+            Ok((next, claimed, deleted))
+        })
+    }
+
+    // run `f` against the group and the entries of the stream, a missing key or group is an
+    // error
+    fn update_group<T>(
+        &self,
+        key: &str,
+        group: &str,
+        f: impl FnOnce(&mut ConsumerGroup, &BTreeMap<StreamId, StreamFields>) -> Result<T, BackendError>,
+    ) -> Result<T, BackendError> {
+        self.update(key, |v| {
+            let stream = v.as_stream_mut()?;
+            match stream.groups.get_mut(group) {
+                Some(group) => f(group, &stream.entries).map(Some),
+                None => Ok(None),
+            }
+        })?
+        .flatten()
+        .ok_or_else(|| BackendError::NoGroup(key.to_string(), group.to_string()))
+    }
+
+    fn read_group<T>(
+        &self,
+        key: &str,
+        group: &str,
+        f: impl FnOnce(&ConsumerGroup) -> T,
+    ) -> Result<T, BackendError> {
+        self.read(key, |v| Ok(v.as_stream()?.groups.get(group).map(f)))?
+            .flatten()
+            .ok_or_else(|| BackendError::NoGroup(key.to_string(), group.to_string()))
+    }
+}
+
+// BTreeMap::range panics if start is greater than end, or if they are equal and both excluded
+fn is_valid_range(start: Bound<StreamId>, end: Bound<StreamId>) -> bool {
+    match (start, end) {
+        (Bound::Excluded(start), Bound::Excluded(end)) => start < end,
+        (
+            Bound::Included(start) | Bound::Excluded(start),
+            Bound::Included(end) | Bound::Excluded(end),
+        ) => start <= end,
+        _ => true,
+    }
+}
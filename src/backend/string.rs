@@ -0,0 +1,419 @@
+use std::borrow::Cow;
+
+use super::{now_ms, scan_map::Entry, Backend, BackendError, Value};
+
+// the maximum length of a string value, the same as the default proto-max-bulk-len
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+/// The value of a string key. Strings which are the canonical form of a 64-bit integer are kept
+/// as an integer, the same way redis does with its "int" encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StringValue {
+    Int(i64),
+    Raw(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetCondition {
+    // NX: only set the key if it does not already exist
+    NotExists,
+    // XX: only set the key if it already exists
+    Exists,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetExpiration {
+    // expire at the given unix time in milliseconds
+    At(u64),
+    // KEEPTTL: retain the time to live associated with the key
+    Keep,
+}
+
+impl StringValue {
+    pub fn len(&self) -> usize {
+        match self {
+            StringValue::Int(v) => v.to_string().len(),
+            StringValue::Raw(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bytes of the value, borrowed unless it is an integer.
+    pub fn as_bytes(&self) -> Cow<'_, [u8]> {
+        match self {
+            StringValue::Int(v) => Cow::Owned(v.to_string().into_bytes()),
+            StringValue::Raw(v) => Cow::Borrowed(v),
+        }
+    }
+
+    /// The bytes of the value for an update in place, an integer is converted to raw first.
+    pub fn as_bytes_mut(&mut self) -> &mut Vec<u8> {
+        if let StringValue::Int(v) = *self {
+            *self = StringValue::Raw(v.to_string().into_bytes());
+        }
+        match self {
+            StringValue::Raw(v) => v,
+            StringValue::Int(_) => unreachable!("the value was converted to raw above"),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            StringValue::Int(v) => v.to_string().into_bytes(),
+            StringValue::Raw(v) => v.clone(),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            StringValue::Int(v) => v.to_string().into_bytes(),
+            StringValue::Raw(v) => v,
+        }
+    }
+
+    /// The value as an integer, None if it is not one.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            StringValue::Int(v) => Some(*v),
+            // a raw value updated in place, e.g. by SETBIT, may look like an integer
+            StringValue::Raw(v) => parse_int(v),
+        }
+    }
+
+    /// The value as a float, None if it is not one.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            StringValue::Int(v) => Some(*v as f64),
+            StringValue::Raw(v) => std::str::from_utf8(v)
+                .ok()?
+                .parse::<f64>()
+                .ok()
+                .filter(|v| !v.is_nan()),
+        }
+    }
+}
+
+impl From<Vec<u8>> for StringValue {
+    fn from(v: Vec<u8>) -> Self {
+        match parse_int(&v) {
+            Some(i) => StringValue::Int(i),
+            None => StringValue::Raw(v),
+        }
+    }
+}
+
+impl From<i64> for StringValue {
+    fn from(v: i64) -> Self {
+        StringValue::Int(v)
+    }
+}
+
+impl Backend {
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, BackendError> {
+        self.read(key, |v| Ok(v.as_string()?.to_bytes()))
+    }
+
+    pub fn set(&self, key: String, value: Vec<u8>) {
+        // without GET the old value is never inspected, so this can't fail
+        let _ = self.set_with_options(key, value, None, None, false);
+    }
+
+    /// Set the value of a key if the condition holds, the check and the write are atomic.
+    /// Returns whether the value was written, together with the old value of the key if `get`
+    /// is set, in which case the old value must be a string.
+    pub fn set_with_options(
+        &self,
+        key: String,
+        value: Vec<u8>,
+        condition: Option<SetCondition>,
+        expiration: Option<SetExpiration>,
+        get: bool,
+    ) -> Result<(bool, Option<Vec<u8>>), BackendError> {
+        // NOTE: lock order is always expires -> keyspace, the same as expire_if_needed
+        let expires = self.expires.entry(key.clone());
+        let expired = matches!(&expires, Entry::Occupied(at) if *at.get() <= now_ms());
+        if expired {
+            self.keyspace.remove(&key);
+        }
+
+        let entry = self.keyspace.entry(key.clone());
+        let (exists, old) = match &entry {
+            Entry::Occupied(v) if get => (true, Some(v.get().as_string()?.to_bytes())),
+            Entry::Occupied(_) => (true, None),
+            Entry::Vacant(_) => (false, None),
+        };
+
+        let ok = match condition {
+            Some(SetCondition::NotExists) => !exists,
+            Some(SetCondition::Exists) => exists,
+            None => true,
+        };
+        if !ok {
+            if expired {
+                if let Entry::Occupied(v) = expires {
+                    v.remove();
+                }
+                self.touch(&key);
+            }
+            return Ok((false, old));
+        }
+
+        entry.insert(Value::String(value.into()));
+        match (expiration, expires) {
+            (Some(SetExpiration::At(at)), expires) => {
+                expires.insert(at);
+            }
+            (Some(SetExpiration::Keep), Entry::Occupied(v)) if expired => {
+                v.remove();
+            }
+            (Some(SetExpiration::Keep), _) => {}
+            (None, Entry::Occupied(v)) => {
+                v.remove();
+            }
+            (None, Entry::Vacant(_)) => {}
+        }
+        self.touch(&key);
+
+        Ok((true, old))
+    }
+
+    /// Add the increment to the integer value of the key, a missing key counts as 0. The key
+    /// keeps its timeout. Returns the new value.
+    pub fn incr_by(&self, key: String, increment: i64) -> Result<i64, BackendError> {
+        self.upsert(
+            key,
+            || Value::String(StringValue::Int(0)),
+            |v| {
+                let s = v.as_string_mut()?;
+                let value = s
+                    .as_int()
+                    .ok_or(BackendError::NotInteger)?
+                    .checked_add(increment)
+                    .ok_or(BackendError::IncrementOverflow)?;
+                *s = StringValue::Int(value);
+                Ok(value)
+            },
+        )
+    }
+
+    /// Add the increment to the float value of the key, a missing key counts as 0. The key
+    /// keeps its timeout. Returns the new value as it is stored.
+    pub fn incr_by_float(&self, key: String, increment: f64) -> Result<Vec<u8>, BackendError> {
+        // checked first, so that a missing key is not created by a failed increment
+        if !increment.is_finite() {
+            return Err(BackendError::NotFinite);
+        }
+
+        self.upsert(
+            key,
+            || Value::String(StringValue::Int(0)),
+            |v| {
+                let s = v.as_string_mut()?;
+                let value = s.as_float().ok_or(BackendError::NotAFloat)? + increment;
+                if !value.is_finite() {
+                    return Err(BackendError::NotFinite);
+                }
+                // a whole number such as "3" goes back to the integer encoding
+                *s = StringValue::from(format_float(value).into_bytes());
+                Ok(s.to_bytes())
+            },
+        )
+    }
+
+    /// Append to the value of the key, a missing key is created. Returns the new length.
+    pub fn append(&self, key: String, value: &[u8]) -> Result<usize, BackendError> {
+        self.upsert(
+            key,
+            || Value::String(StringValue::Raw(Vec::new())),
+            |v| {
+                let s = v.as_string_mut()?;
+                if s.len() + value.len() > MAX_STRING_LEN {
+                    return Err(BackendError::StringTooLong);
+                }
+                let mut bytes = std::mem::replace(s, StringValue::Raw(Vec::new())).into_bytes();
+                bytes.extend_from_slice(value);
+                *s = StringValue::from(bytes);
+                Ok(s.len())
+            },
+        )
+    }
+
+    pub fn strlen(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_string()?.len()))?
+            .unwrap_or_default())
+    }
+
+    /// The substring between the offsets, both inclusive. Negative offsets count from the end.
+    pub fn getrange(&self, key: &str, start: i64, end: i64) -> Result<Vec<u8>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let bytes = v.as_string()?.as_bytes();
+                Ok(match string_range(start, end, bytes.len()) {
+                    Some((start, end)) => bytes[start..=end].to_vec(),
+                    None => Vec::new(),
+                })
+            })?
+            .unwrap_or_default())
+    }
+
+    /// Overwrite the value of the key from the offset, the value is padded with zero bytes if it
+    /// is too short. A missing key is created unless the value is empty. Returns the new length.
+    pub fn setrange(
+        &self,
+        key: String,
+        offset: usize,
+        value: &[u8],
+    ) -> Result<usize, BackendError> {
+        if value.is_empty() {
+            return self.strlen(&key);
+        }
+        if offset.saturating_add(value.len()) > MAX_STRING_LEN {
+            return Err(BackendError::StringTooLong);
+        }
+
+        self.upsert(
+            key,
+            || Value::String(StringValue::Raw(Vec::new())),
+            |v| {
+                let s = v.as_string_mut()?;
+                let mut bytes = std::mem::replace(s, StringValue::Raw(Vec::new())).into_bytes();
+                let end = offset + value.len();
+                if bytes.len() < end {
+                    bytes.resize(end, 0);
+                }
+                bytes[offset..end].copy_from_slice(value);
+                *s = StringValue::from(bytes);
+                Ok(s.len())
+            },
+        )
+    }
+
+    /// Get the value of the key and delete it, the key must hold a string.
+    pub fn getdel(&self, key: &str) -> Result<Option<Vec<u8>>, BackendError> {
+        self.expire_if_needed(key);
+        // NOTE: lock order is always expires -> keyspace
+        let expires = self.expires.entry(key.to_string());
+        let Entry::Occupied(entry) = self.keyspace.entry(key.to_string()) else {
+            return Ok(None);
+        };
+        let value = entry.get().as_string()?.to_bytes();
+
+        entry.remove();
+        if let Entry::Occupied(v) = expires {
+            v.remove();
+        }
+        self.touch(key);
+        Ok(Some(value))
+    }
+
+    /// Get the value of the key and set its timeout to the absolute time, or remove the timeout
+    /// if `persist` is set. A time in the past deletes the key.
+    pub fn getex(
+        &self,
+        key: &str,
+        at: Option<u64>,
+        persist: bool,
+    ) -> Result<Option<Vec<u8>>, BackendError> {
+        self.expire_if_needed(key);
+        // NOTE: lock order is always expires -> keyspace
+        let expires = self.expires.entry(key.to_string());
+        let Entry::Occupied(entry) = self.keyspace.entry(key.to_string()) else {
+            return Ok(None);
+        };
+        let value = entry.get().as_string()?.to_bytes();
+
+        match (at, expires) {
+            (Some(at), expires) if at <= now_ms() => {
+                entry.remove();
+                if let Entry::Occupied(v) = expires {
+                    v.remove();
+                }
+            }
+            (Some(at), expires) => {
+                expires.insert(at);
+            }
+            (None, Entry::Occupied(v)) if persist => {
+                v.remove();
+            }
+            (None, _) => return Ok(Some(value)),
+        }
+        self.touch(key);
+        Ok(Some(value))
+    }
+
+    /// The values of the keys, None for the keys which are missing or don't hold a string.
+    pub fn mget(&self, keys: &[String]) -> Vec<Option<Vec<u8>>> {
+        keys.iter()
+            .map(|key| self.get(key).ok().flatten())
+            .collect()
+    }
+
+    /// Set the keys to their values, discarding their timeouts. The keys are set one after the
+    /// other: the caller runs it with the keys locked by `shared` so that no client sees only some
+    /// of them set.
+    pub fn mset(&self, pairs: Vec<(String, Vec<u8>)>) {
+        for (key, value) in pairs {
+            self.put(key, Value::String(value.into()));
+        }
+    }
+
+    /// Set the keys to their values only if none of them exists. Returns true if they were set.
+    /// Like `mset`, the check and the writes are atomic only when run with the keys locked.
+    pub fn msetnx(&self, pairs: Vec<(String, Vec<u8>)>) -> bool {
+        if pairs.iter().any(|(key, _)| self.exists(key)) {
+            return false;
+        }
+        self.mset(pairs);
+        true
+    }
+}
+
+// only the canonical form of an integer, so that the value reads back byte for byte: "007" or
+// "+1" are not integers
+fn parse_int(v: &[u8]) -> Option<i64> {
+    // i64::MIN is 20 bytes long, anything longer can't be an integer
+    if v.is_empty() || v.len() > 20 {
+        return None;
+    }
+    std::str::from_utf8(v)
+        .ok()?
+        .parse::<i64>()
+        .ok()
+        .filter(|i| i.to_string().as_bytes() == v)
+}
+
+// format the result of INCRBYFLOAT like redis, which prints at most 17 decimals and trims the
+// trailing zeros: 1e-20 is "0", never an exponent. The shortest form is kept when it is shorter
+pub(super) fn format_float(value: f64) -> String {
+    let shortest = value.to_string();
+    let decimals = shortest.find('.').map_or(0, |dot| shortest.len() - dot - 1);
+    let s = if decimals <= 17 {
+        shortest
+    } else {
+        let s = format!("{:.17}", value);
+        s.trim_end_matches('0').trim_end_matches('.').to_string()
+    };
+    // a tiny negative number rounds to "-0"
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s
+    }
+}
+
+// resolve the offsets of GETRANGE and BITCOUNT the way redis does, which differs from the list
+// ranges: an end before the start of the string is clamped to the first byte
+pub(super) fn string_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return None;
+    }
+
+    let len = len as i64;
+    let start = if start < 0 { start + len } else { start }.max(0);
+    let end = if end < 0 { end + len } else { end }.clamp(0, len - 1);
+    (start <= end).then_some((start as usize, end as usize))
+}
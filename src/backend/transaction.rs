@@ -0,0 +1,167 @@
+use std::sync::{Arc, PoisonError, RwLockReadGuard, RwLockWriteGuard};
+
+use super::{key_hash_slot, Backend, BackendInner, Database};
+
+/// A key watched by a connection with WATCH, the watch ends when it is dropped.
+#[derive(Debug)]
+pub struct WatchedKey {
+    inner: Arc<BackendInner>,
+    index: usize,
+    key: String,
+    version: u64,
+}
+
+// the key locks held while a command runs, they are released when it is dropped
+#[derive(Default)]
+pub(super) struct KeyLocks<'a> {
+    _read: Vec<RwLockReadGuard<'a, ()>>,
+    _write: Vec<RwLockWriteGuard<'a, ()>>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct WatchedVersion {
+    version: u64,
+    // the number of connections watching the key, it is forgotten when none is left
+    watchers: usize,
+}
+
+impl Backend {
+    /// Run a command, a transaction is never executed at the same time. The keys of the command
+    /// are locked for writing if it writes them and for reading otherwise, so that a command
+    /// writing several keys changes them all at once.
+    pub fn shared<T>(
+        &mut self,
+        keys: &[String],
+        write: bool,
+        f: impl FnOnce(&mut Backend) -> T,
+    ) -> T {
+        let inner = self.inner.clone();
+        let _guard = inner
+            .commands
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        let _keys = lock_keys(&inner, keys, write);
+        self.reselect();
+        f(self)
+    }
+
+    /// Run the commands of a transaction, no other command is executed at the same time.
+    pub fn exclusive<T>(&mut self, f: impl FnOnce(&mut Backend) -> T) -> T {
+        let inner = self.inner.clone();
+        // the lock guards no data, so a command which panicked while holding it doesn't leave
+        // anything behind and must not stop the other connections
+        let _guard = inner
+            .commands
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.reselect();
+        f(self)
+    }
+
+    // for the blocking commands, which only run like a command between their waits
+    pub(crate) fn shared_guard(&self) -> RwLockReadGuard<'_, ()> {
+        self.inner
+            .commands
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // for the blocking commands, which lock their keys like `shared` on each attempt
+    pub(super) fn lock_keys(&self, keys: &[String], write: bool) -> KeyLocks<'_> {
+        lock_keys(&self.inner, keys, write)
+    }
+
+    /// Watch the key of the selected database until the returned value is dropped.
+    pub fn watch(&self, key: String) -> WatchedKey {
+        // a key which is already expired doesn't count as modified when it is reclaimed
+        self.expire_if_needed(&key);
+        let version = {
+            let mut watched = self.inner.watched[self.index]
+                .entry(key.clone())
+                .or_default();
+            watched.watchers += 1;
+            watched.version
+        };
+        WatchedKey {
+            inner: self.inner.clone(),
+            index: self.index,
+            key,
+            version,
+        }
+    }
+
+    // bump the version of the key if a connection watches it, every write to a key, including
+    // its expiration, must call it
+    pub(crate) fn touch(&self, key: &str) {
+        touch(&self.inner, self.index, key);
+    }
+
+    // the data of the database was flushed or swapped, the watched keys it holds are modified
+    pub(super) fn touch_existing(&self, index: usize, data: &Database) {
+        // NOTE: the watched versions are always locked last, so collect the keys first
+        let keys = self.inner.watched[index]
+            .iter()
+            .map(|v| v.key().clone())
+            .collect::<Vec<_>>();
+        for key in keys {
+            if data.keyspace.contains_key(&key) {
+                touch(&self.inner, index, &key);
+            }
+        }
+    }
+}
+
+impl WatchedKey {
+    /// Whether the key was modified since it was watched, an expiration counts as a
+    /// modification even if the key was not accessed since.
+    pub fn is_modified(&self) -> bool {
+        if let Ok(db) = Backend::from_inner(self.inner.clone(), self.index) {
+            db.expire_if_needed(&self.key);
+        }
+        self.inner.watched[self.index]
+            .get(&self.key)
+            .is_none_or(|v| v.version != self.version)
+    }
+}
+
+impl Drop for WatchedKey {
+    fn drop(&mut self) {
+        let watched = &self.inner.watched[self.index];
+        if let Some(mut v) = watched.get_mut(&self.key) {
+            v.watchers -= 1;
+        }
+        watched.remove_if(&self.key, |_, v| v.watchers == 0);
+    }
+}
+
+// the locks of the keys, one per hash slot so that a key has the same lock in every database.
+// They are taken in the order of the slots, two commands sharing keys can't wait for each other
+fn lock_keys<'a>(inner: &'a BackendInner, keys: &[String], write: bool) -> KeyLocks<'a> {
+    let mut slots = keys
+        .iter()
+        .map(|key| key_hash_slot(key.as_bytes()) as usize)
+        .collect::<Vec<_>>();
+    slots.sort_unstable();
+    slots.dedup();
+
+    let mut locks = KeyLocks::default();
+    for slot in slots {
+        let lock = &inner.keys[slot];
+        if write {
+            locks
+                ._write
+                .push(lock.write().unwrap_or_else(PoisonError::into_inner));
+        } else {
+            locks
+                ._read
+                .push(lock.read().unwrap_or_else(PoisonError::into_inner));
+        }
+    }
+    locks
+}
+
+fn touch(inner: &BackendInner, index: usize, key: &str) {
+    if let Some(mut v) = inner.watched[index].get_mut(key) {
+        v.version += 1;
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+
+use super::{BackendError, Hash, Set, SortedSet, Stream, StringValue};
+
+/// The value stored for a key, every key holds exactly one kind of value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(StringValue),
+    Hash(Hash),
+    Set(Set),
+    List(VecDeque<Vec<u8>>),
+    ZSet(SortedSet),
+    Stream(Stream),
+}
+
+impl Value {
+    /// The name of the type as reported by the TYPE command.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::List(_) => "list",
+            Value::ZSet(_) => "zset",
+            Value::Stream(_) => "stream",
+        }
+    }
+
+    /// Collections without any element are never kept in the keyspace, except for streams
+    /// which keep their last ID.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Value::String(_) | Value::Stream(_) => false,
+            Value::Hash(v) => v.is_empty(),
+            Value::Set(v) => v.is_empty(),
+            Value::List(v) => v.is_empty(),
+            Value::ZSet(v) => v.is_empty(),
+        }
+    }
+
+    pub fn as_string(&self) -> Result<&StringValue, BackendError> {
+        match self {
+            Value::String(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_string_mut(&mut self) -> Result<&mut StringValue, BackendError> {
+        match self {
+            Value::String(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_hash(&self) -> Result<&Hash, BackendError> {
+        match self {
+            Value::Hash(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_hash_mut(&mut self) -> Result<&mut Hash, BackendError> {
+        match self {
+            Value::Hash(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_set(&self) -> Result<&Set, BackendError> {
+        match self {
+            Value::Set(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_set_mut(&mut self) -> Result<&mut Set, BackendError> {
+        match self {
+            Value::Set(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&VecDeque<Vec<u8>>, BackendError> {
+        match self {
+            Value::List(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Result<&mut VecDeque<Vec<u8>>, BackendError> {
+        match self {
+            Value::List(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_zset(&self) -> Result<&SortedSet, BackendError> {
+        match self {
+            Value::ZSet(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_zset_mut(&mut self) -> Result<&mut SortedSet, BackendError> {
+        match self {
+            Value::ZSet(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_stream(&self) -> Result<&Stream, BackendError> {
+        match self {
+            Value::Stream(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+
+    pub fn as_stream_mut(&mut self) -> Result<&mut Stream, BackendError> {
+        match self {
+            Value::Stream(v) => Ok(v),
+            _ => Err(BackendError::WrongType),
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::{
+    index_range, ranked::RankedSet, scan::ScanOrder, Backend, BackendError, SetCondition, Value,
+};
+
+/// A sorted set: members ordered by (score, member), with a member -> score index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortedSet {
+    scores: HashMap<Vec<u8>, f64>,
+    ordered: RankedSet<(Score, Vec<u8>)>,
+    // the members in the order ZSCAN visits them
+    order: ScanOrder<Vec<u8>>,
+}
+
+// scores are never NaN, so that they have a total order
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ZAddOptions {
+    // NX: only add new members, XX: only update existing members
+    pub condition: Option<SetCondition>,
+    // GT / LT: only update existing members if the new score is greater / less than the
+    // current one
+    pub comparison: Option<Ordering>,
+    // CH: count the changed members instead of only the added ones
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScoreBound {
+    Inclusive(f64),
+    Exclusive(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    // "-"
+    Min,
+    // "+"
+    Max,
+    // "[member"
+    Inclusive(Vec<u8>),
+    // "(member"
+    Exclusive(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZRangeBy {
+    // ranks, negative ones count from the highest score
+    Index(i64, i64),
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+/// LIMIT offset count, a negative count returns all the elements from the offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZRangeLimit {
+    pub offset: i64,
+    pub count: i64,
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl ScoreBound {
+    // whether the score is in a range which has this bound as its minimum
+    fn fits_min(&self, score: f64) -> bool {
+        match self {
+            ScoreBound::Inclusive(v) => score >= *v,
+            ScoreBound::Exclusive(v) => score > *v,
+        }
+    }
+
+    // whether the score is in a range which has this bound as its maximum
+    fn fits_max(&self, score: f64) -> bool {
+        match self {
+            ScoreBound::Inclusive(v) => score <= *v,
+            ScoreBound::Exclusive(v) => score < *v,
+        }
+    }
+}
+
+impl LexBound {
+    fn fits_min(&self, member: &[u8]) -> bool {
+        match self {
+            LexBound::Min => true,
+            LexBound::Max => false,
+            LexBound::Inclusive(v) => member >= v.as_slice(),
+            LexBound::Exclusive(v) => member > v.as_slice(),
+        }
+    }
+
+    fn fits_max(&self, member: &[u8]) -> bool {
+        match self {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(v) => member <= v.as_slice(),
+            LexBound::Exclusive(v) => member < v.as_slice(),
+        }
+    }
+}
+
+impl SortedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// The members with their scores, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, f64)> {
+        self.scores.iter().map(|(m, &score)| (m, score))
+    }
+
+    /// Insert the member or update its score, returns true if the member is new.
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> bool {
+        // -0.0 and 0.0 are the same score
+        let score = score + 0.0;
+        let old = self.scores.insert(member.clone(), score);
+        match old {
+            Some(old) => {
+                self.ordered.remove(&(Score(old), member.clone()));
+            }
+            None => self.order.insert(member.clone()),
+        }
+        self.ordered.insert((Score(score), member));
+        old.is_none()
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.order.remove(member.to_vec());
+                self.ordered.remove(&(Score(score), member.to_vec()))
+            }
+            None => false,
+        }
+    }
+
+    /// The members of the next ZSCAN page, see `ScanOrder::page`.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<&Vec<u8>>, Option<u64>) {
+        self.order.page(cursor, count)
+    }
+
+    /// Add the member, or add the score to its current score if `incr` is set, according to the
+    /// options. Returns the new score, None if the member was left alone.
+    pub fn add(
+        &mut self,
+        member: Vec<u8>,
+        score: f64,
+        incr: bool,
+        options: &ZAddOptions,
+    ) -> Result<Option<f64>, BackendError> {
+        let current = self.score(&member);
+        match (options.condition, current) {
+            (Some(SetCondition::NotExists), Some(_)) | (Some(SetCondition::Exists), None) => {
+                return Ok(None)
+            }
+            _ => {}
+        }
+
+        let score = match (incr, current) {
+            (true, Some(current)) => current + score,
+            _ => score,
+        };
+        if score.is_nan() {
+            return Err(BackendError::NotANumber);
+        }
+        if let (Some(comparison), Some(current)) = (options.comparison, current) {
+            if score.partial_cmp(&current) != Some(comparison) {
+                return Ok(None);
+            }
+        }
+
+        self.insert(member, score);
+        Ok(Some(score))
+    }
+
+    /// The rank of the member, ranks start at 0 from the lowest score, or the highest if `rev`
+    /// is set.
+    pub fn rank(&self, member: &[u8], rev: bool) -> Option<usize> {
+        let score = self.score(member)?;
+        let (rank, _) = self.ordered.rank(&(Score(score), member.to_vec()));
+        Some(if rev { self.len() - rank - 1 } else { rank })
+    }
+
+    /// Members with their scores in the range, in ascending order or descending if `rev` is set.
+    pub fn range(
+        &self,
+        by: &ZRangeBy,
+        rev: bool,
+        limit: Option<ZRangeLimit>,
+    ) -> Vec<(Vec<u8>, f64)> {
+        let (start, end) = self.bounds(by, rev);
+        let (offset, count) = match limit {
+            Some(ZRangeLimit { offset, .. }) if offset < 0 => return Vec::new(),
+            // a negative count returns all the members from the offset
+            Some(ZRangeLimit { offset, count }) => (
+                offset as usize,
+                usize::try_from(count).unwrap_or(usize::MAX),
+            ),
+            None => (0, usize::MAX),
+        };
+        let count = count.min((end - start).saturating_sub(offset));
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // the first member of the range in the order it is returned
+        let first = if rev {
+            end - 1 - offset
+        } else {
+            start + offset
+        };
+        self.ordered
+            .iter_from(first, rev)
+            .take(count)
+            .map(|(score, member)| (member.clone(), score.0))
+            .collect()
+    }
+
+    /// The number of members in the range.
+    pub fn count(&self, by: &ZRangeBy) -> usize {
+        let (start, end) = self.bounds(by, false);
+        end - start
+    }
+
+    // the ranks of the members in the range, from the first one to past the last one
+    fn bounds(&self, by: &ZRangeBy, rev: bool) -> (usize, usize) {
+        let (start, end) = match by {
+            ZRangeBy::Index(start, stop) => match index_range(*start, *stop, self.len()) {
+                // the indexes count from the highest score
+                Some((start, stop)) if rev => (self.len() - 1 - stop, self.len() - start),
+                Some((start, stop)) => (start, stop + 1),
+                None => (0, 0),
+            },
+            ZRangeBy::Score(min, max) => (
+                self.ordered
+                    .partition_point(|(score, _)| !min.fits_min(score.0)),
+                self.ordered
+                    .partition_point(|(score, _)| max.fits_max(score.0)),
+            ),
+            // lexicographical ranges are only meaningful when all the members have the same score
+            ZRangeBy::Lex(min, max) => (
+                self.ordered
+                    .partition_point(|(_, member)| !min.fits_min(member)),
+                self.ordered
+                    .partition_point(|(_, member)| max.fits_max(member)),
+            ),
+        };
+        (start, end.max(start))
+    }
+
+    /// Remove and return up to `count` members with the lowest scores, or the highest if `max`
+    /// is set.
+    pub fn pop(&mut self, count: usize, max: bool) -> Vec<(Vec<u8>, f64)> {
+        let mut popped = Vec::with_capacity(count.min(self.len()));
+        while popped.len() < count {
+            let next = if max {
+                self.ordered.last()
+            } else {
+                self.ordered.first()
+            };
+            let Some((score, member)) = next.cloned() else {
+                break;
+            };
+            self.ordered.remove(&(score, member.clone()));
+            self.scores.remove(&member);
+            self.order.remove(member.clone());
+            popped.push((member, score.0));
+        }
+        popped
+    }
+}
+
+impl Backend {
+    /// Add the members with their scores according to the options. Returns the number of added
+    /// members, or of changed members if the CH option is set.
+    pub fn zadd(
+        &self,
+        key: String,
+        members: Vec<(f64, Vec<u8>)>,
+        options: ZAddOptions,
+    ) -> Result<usize, BackendError> {
+        self.upsert(
+            key,
+            || Value::ZSet(SortedSet::new()),
+            |v| {
+                let zset = v.as_zset_mut()?;
+                let mut n = 0;
+                for (score, member) in members {
+                    let old = zset.score(&member);
+                    if let Some(new) = zset.add(member, score, false, &options)? {
+                        if old.is_none() || (options.changed && old != Some(new)) {
+                            n += 1;
+                        }
+                    }
+                }
+                Ok(n)
+            },
+        )
+    }
+
+    /// Increment the score of the member, a missing member is added with the increment as its
+    /// score. Returns the new score, None if the options prevented the update.
+    pub fn zincrby(
+        &self,
+        key: String,
+        increment: f64,
+        member: Vec<u8>,
+        options: ZAddOptions,
+    ) -> Result<Option<f64>, BackendError> {
+        self.upsert(
+            key,
+            || Value::ZSet(SortedSet::new()),
+            |v| v.as_zset_mut()?.add(member, increment, true, &options),
+        )
+    }
+
+    pub fn zrem(&self, key: &str, members: &[Vec<u8>]) -> Result<usize, BackendError> {
+        Ok(self
+            .update(key, |v| {
+                let zset = v.as_zset_mut()?;
+                Ok(members.iter().filter(|m| zset.remove(m)).count())
+            })?
+            .unwrap_or_default())
+    }
+
+    pub fn zcard(&self, key: &str) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_zset()?.len()))?
+            .unwrap_or_default())
+    }
+
+    pub fn zscore(&self, key: &str, member: &[u8]) -> Result<Option<f64>, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_zset()?.score(member)))?
+            .flatten())
+    }
+
+    /// The rank of the member together with its score.
+    pub fn zrank(
+        &self,
+        key: &str,
+        member: &[u8],
+        rev: bool,
+    ) -> Result<Option<(usize, f64)>, BackendError> {
+        Ok(self
+            .read(key, |v| {
+                let zset = v.as_zset()?;
+                Ok(zset.rank(member, rev).zip(zset.score(member)))
+            })?
+            .flatten())
+    }
+
+    pub fn zcount(
+        &self,
+        key: &str,
+        min: ScoreBound,
+        max: ScoreBound,
+    ) -> Result<usize, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_zset()?.count(&ZRangeBy::Score(min, max))))?
+            .unwrap_or_default())
+    }
+
+    pub fn zrange(
+        &self,
+        key: &str,
+        by: &ZRangeBy,
+        rev: bool,
+        limit: Option<ZRangeLimit>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, BackendError> {
+        Ok(self
+            .read(key, |v| Ok(v.as_zset()?.range(by, rev, limit)))?
+            .unwrap_or_default())
+    }
+
+    pub fn zpop(
+        &self,
+        key: &str,
+        count: usize,
+        max: bool,
+    ) -> Result<Vec<(Vec<u8>, f64)>, BackendError> {
+        Ok(self
+            .update(key, |v| Ok(v.as_zset_mut()?.pop(count, max)))?
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,675 @@
+use crate::{
+    Backend, BitOperation, BitRange, BitUnit, BitfieldOp, BitfieldOverflow, BitfieldType,
+    BulkString, RespArray, RespFrame, RespNull,
+};
+
+use super::{
+    extract_args, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, BitCount, BitField, BitOp, BitPos, CommandError, CommandExecutor,
+    GetBit, SetBit,
+};
+
+// a string is at most 512MB, so a bit offset must be below 2^32
+const MAX_BIT_OFFSET: u64 = 1 << 32;
+
+impl CommandExecutor for SetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setbit(self.key, self.offset, self.bit) {
+            Ok(old) => RespFrame::Integer(old as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GetBit {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getbit(&self.key, self.offset) {
+            Ok(bit) => RespFrame::Integer(bit as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitcount(&self.key, self.range) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitpos(&self.key, self.bit, self.range) {
+            Ok(pos) => RespFrame::Integer(pos),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitOp {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitop(self.op, self.destination, &self.keys) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for BitField {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.bitfield(self.key, &self.ops) {
+            Ok(values) => RespArray::new(
+                values
+                    .into_iter()
+                    .map(|v| match v {
+                        Some(v) => RespFrame::Integer(v),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// parse a bit offset, BITFIELD also takes "#N" which means the N-th integer of the type
+fn parse_offset(arg: Option<RespFrame>, ty: Option<BitfieldType>) -> Result<u64, CommandError> {
+    let (arg, multiplier) = match (arg, ty) {
+        (Some(RespFrame::BulkString(v)), Some(ty)) if v.first() == Some(&b'#') => (
+            Some(BulkString::new(v[1..].to_vec()).into()),
+            ty.bits as i64,
+        ),
+        (arg, _) => (arg, 1),
+    };
+
+    let bits = ty.map(|ty| ty.bits as i64).unwrap_or(1);
+    parse_integer(arg)
+        .ok()
+        .and_then(|v| v.checked_mul(multiplier))
+        .filter(|v| *v >= 0 && v.saturating_add(bits) <= MAX_BIT_OFFSET as i64)
+        .map(|v| v as u64)
+        .ok_or_else(|| {
+            CommandError::InvalidArgument(
+                "bit offset is not an integer or out of range".to_string(),
+            )
+        })
+}
+
+// parse the bit argument of SETBIT and BITPOS
+fn parse_bit(arg: Option<RespFrame>, error: &str) -> Result<bool, CommandError> {
+    match parse_integer(arg) {
+        Ok(0) => Ok(false),
+        Ok(1) => Ok(true),
+        _ => Err(CommandError::InvalidArgument(error.to_string())),
+    }
+}
+
+// parse "[start [end [BYTE | BIT]]]", BITCOUNT requires the end when the start is given
+fn parse_range(args: Vec<RespFrame>, end_required: bool) -> Result<Option<BitRange>, CommandError> {
+    let mut args = args.into_iter();
+    let Some(start) = args.next() else {
+        return Ok(None);
+    };
+    let start = parse_integer(Some(start))?;
+    let end = match args.next() {
+        Some(end) => Some(parse_integer(Some(end))?),
+        None if end_required => return Err(CommandError::SyntaxError),
+        None => None,
+    };
+    let unit = match args.next().map(|arg| parse_option(&arg)) {
+        None => BitUnit::Byte,
+        Some(Some(unit)) if unit == "byte" => BitUnit::Byte,
+        Some(Some(unit)) if unit == "bit" => BitUnit::Bit,
+        _ => return Err(CommandError::SyntaxError),
+    };
+    if args.next().is_some() {
+        return Err(CommandError::SyntaxError);
+    }
+
+    Ok(Some(BitRange { start, end, unit }))
+}
+
+// parse a BITFIELD type such as "i16" or "u8"
+fn parse_type(arg: Option<RespFrame>) -> Result<BitfieldType, CommandError> {
+    let ty = arg.as_ref().and_then(parse_option).and_then(|ty| {
+        let signed = match ty.chars().next()? {
+            'i' => true,
+            'u' => false,
+            _ => return None,
+        };
+        let bits = ty[1..].parse::<u32>().ok()?;
+        let max = if signed { 64 } else { 63 };
+        (1..=max)
+            .contains(&bits)
+            .then_some(BitfieldType { signed, bits })
+    });
+    ty.ok_or_else(|| {
+        CommandError::InvalidArgument(
+            "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is."
+                .to_string(),
+        )
+    })
+}
+
+impl TryFrom<RespArray> for SetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setbit"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(SetBit {
+            key: parse_string(args.next())?,
+            offset: parse_offset(args.next(), None)?,
+            bit: parse_bit(args.next(), "bit is not an integer or out of range")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GetBit {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getbit"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(GetBit {
+            key: parse_string(args.next())?,
+            offset: parse_offset(args.next(), None)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for BitCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["bitcount"], 1)?;
+
+        // BITCOUNT key [start end [BYTE | BIT]]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let range = parse_range(args.collect(), true)?;
+        Ok(BitCount { key, range })
+    }
+}
+
+impl TryFrom<RespArray> for BitPos {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["bitpos"], 2)?;
+
+        // BITPOS key bit [start [end [BYTE | BIT]]]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let bit = parse_bit(args.next(), "The bit argument must be 1 or 0.")?;
+        let range = parse_range(args.collect(), false)?;
+        Ok(BitPos { key, bit, range })
+    }
+}
+
+impl TryFrom<RespArray> for BitOp {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["bitop"], 3)?;
+
+        // BITOP AND | OR | XOR | NOT destkey key [key ...]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let op = match args.next().as_ref().and_then(parse_option).as_deref() {
+            Some("and") => BitOperation::And,
+            Some("or") => BitOperation::Or,
+            Some("xor") => BitOperation::Xor,
+            Some("not") => BitOperation::Not,
+            _ => return Err(CommandError::SyntaxError),
+        };
+        let destination = parse_string(args.next())?;
+        let keys = args
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if op == BitOperation::Not && keys.len() != 1 {
+            return Err(CommandError::InvalidArgument(
+                "BITOP NOT must be called with a single source key.".to_string(),
+            ));
+        }
+
+        Ok(BitOp {
+            op,
+            destination,
+            keys,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for BitField {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["bitfield"], 1)?;
+
+        // BITFIELD key [GET encoding offset | [OVERFLOW WRAP | SAT | FAIL]
+        //   SET encoding offset value | INCRBY encoding offset increment ...]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        // OVERFLOW applies to the SET and INCRBY operations after it
+        let mut overflow = BitfieldOverflow::default();
+        let mut ops = Vec::new();
+        while let Some(arg) = args.next() {
+            match parse_option(&arg).as_deref() {
+                Some("get") => {
+                    let ty = parse_type(args.next())?;
+                    let offset = parse_offset(args.next(), Some(ty))?;
+                    ops.push(BitfieldOp::Get { ty, offset });
+                }
+                Some("set") => {
+                    let ty = parse_type(args.next())?;
+                    let offset = parse_offset(args.next(), Some(ty))?;
+                    ops.push(BitfieldOp::Set {
+                        ty,
+                        offset,
+                        value: parse_integer(args.next())?,
+                        overflow,
+                    });
+                }
+                Some("incrby") => {
+                    let ty = parse_type(args.next())?;
+                    let offset = parse_offset(args.next(), Some(ty))?;
+                    ops.push(BitfieldOp::IncrBy {
+                        ty,
+                        offset,
+                        increment: parse_integer(args.next())?,
+                        overflow,
+                    });
+                }
+                Some("overflow") => {
+                    overflow = match args.next().as_ref().and_then(parse_option).as_deref() {
+                        Some("wrap") => BitfieldOverflow::Wrap,
+                        Some("sat") => BitfieldOverflow::Sat,
+                        Some("fail") => BitfieldOverflow::Fail,
+                        _ => {
+                            return Err(CommandError::InvalidArgument(
+                                "Invalid OVERFLOW type specified".to_string(),
+                            ))
+                        }
+                    };
+                }
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(BitField { key, ops })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::{command, run};
+
+    #[test]
+    fn test_setbit_getbit() {
+        let backend = Backend::new();
+        assert_eq!(
+            run::<SetBit>(&backend, "setbit users 7 1"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<SetBit>(&backend, "setbit users 7 0"),
+            RespFrame::Integer(1)
+        );
+        run::<SetBit>(&backend, "setbit users 100 1");
+        assert_eq!(backend.strlen("users").unwrap(), 13);
+        assert_eq!(
+            run::<GetBit>(&backend, "getbit users 100"),
+            RespFrame::Integer(1)
+        );
+        // past the end of the string, and a missing key
+        assert_eq!(
+            run::<GetBit>(&backend, "getbit users 1000"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<GetBit>(&backend, "getbit missing 0"),
+            RespFrame::Integer(0)
+        );
+
+        // bits are numbered from the most significant bit
+        backend.set("s".to_string(), b"\x80".to_vec());
+        assert_eq!(run::<GetBit>(&backend, "getbit s 0"), RespFrame::Integer(1));
+    }
+
+    #[test]
+    fn test_setbit_invalid_arguments() {
+        let err = command::<SetBit>("setbit users 7 2").unwrap_err();
+        assert_eq!(err.to_string(), "ERR bit is not an integer or out of range");
+
+        let err = command::<SetBit>("setbit users -1 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR bit offset is not an integer or out of range"
+        );
+        let err = command::<SetBit>("setbit users 4294967296 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR bit offset is not an integer or out of range"
+        );
+        assert!(command::<SetBit>("setbit users 4294967295 1").is_ok());
+
+        let backend = Backend::new();
+        backend
+            .hset(
+                "h".to_string(),
+                vec![("f".to_string(), RespFrame::Integer(1))],
+            )
+            .unwrap();
+        assert!(matches!(
+            run::<SetBit>(&backend, "setbit h 0 1"),
+            RespFrame::Error(_)
+        ));
+    }
+
+    #[test]
+    fn test_bitcount() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), b"foobar".to_vec());
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s"),
+            RespFrame::Integer(26)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s 0 0"),
+            RespFrame::Integer(4)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s 1 1"),
+            RespFrame::Integer(6)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s -2 -1"),
+            RespFrame::Integer(7)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s 5 30 bit"),
+            RespFrame::Integer(17)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s 1 0"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount missing"),
+            RespFrame::Integer(0)
+        );
+
+        assert!(matches!(
+            command::<BitCount>("bitcount s 0"),
+            Err(CommandError::SyntaxError)
+        ));
+        assert!(matches!(
+            command::<BitCount>("bitcount s 0 1 nibble"),
+            Err(CommandError::SyntaxError)
+        ));
+    }
+
+    #[test]
+    fn test_bitcount_large_bitmap() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), vec![0xff; 4 * 1024 * 1024]);
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s"),
+            RespFrame::Integer(32 * 1024 * 1024)
+        );
+        assert_eq!(
+            run::<BitCount>(&backend, "bitcount s 3 -4 bit"),
+            RespFrame::Integer(32 * 1024 * 1024 - 6)
+        );
+    }
+
+    #[test]
+    fn test_bitpos() {
+        let backend = Backend::new();
+        backend.set("s".to_string(), b"\xff\xf0\x00".to_vec());
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 0"),
+            RespFrame::Integer(12)
+        );
+        backend.set("s".to_string(), b"\x00\xff\xf0".to_vec());
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1 0"),
+            RespFrame::Integer(8)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1 2"),
+            RespFrame::Integer(16)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1 2 -1 byte"),
+            RespFrame::Integer(16)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1 7 15 bit"),
+            RespFrame::Integer(8)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1 7 -3 bit"),
+            RespFrame::Integer(8)
+        );
+
+        // no clear bit: the string is padded with zeros unless an end is given
+        backend.set("s".to_string(), b"\xff\xff".to_vec());
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 0"),
+            RespFrame::Integer(16)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 0 0 -1"),
+            RespFrame::Integer(-1)
+        );
+        backend.set("s".to_string(), b"\x00\x00".to_vec());
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos s 1"),
+            RespFrame::Integer(-1)
+        );
+
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos missing 0"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<BitPos>(&backend, "bitpos missing 1"),
+            RespFrame::Integer(-1)
+        );
+
+        let err = command::<BitPos>("bitpos s 2").unwrap_err();
+        assert_eq!(err.to_string(), "ERR The bit argument must be 1 or 0.");
+    }
+
+    #[test]
+    fn test_bitop() {
+        let backend = Backend::new();
+        backend.set("a".to_string(), b"foobar".to_vec());
+        backend.set("b".to_string(), b"abcdef".to_vec());
+
+        assert_eq!(
+            run::<BitOp>(&backend, "bitop and dest a b"),
+            RespFrame::Integer(6)
+        );
+        assert_eq!(backend.get("dest").unwrap(), Some(b"`bc`ab".to_vec()));
+        run::<BitOp>(&backend, "bitop or dest a b");
+        assert_eq!(backend.get("dest").unwrap(), Some(b"goofev".to_vec()));
+        run::<BitOp>(&backend, "bitop xor dest a b");
+        assert_eq!(
+            backend.get("dest").unwrap(),
+            Some(b"\x07\x0d\x0c\x06\x04\x14".to_vec())
+        );
+        run::<BitOp>(&backend, "bitop not dest a");
+        assert_eq!(
+            backend.get("dest").unwrap(),
+            Some(b"foobar".iter().map(|b| !b).collect())
+        );
+
+        // the shorter strings and the missing keys are padded with zeros
+        backend.set("c".to_string(), b"\xff".to_vec());
+        assert_eq!(
+            run::<BitOp>(&backend, "bitop or dest c missing a"),
+            RespFrame::Integer(6)
+        );
+        assert_eq!(backend.get("dest").unwrap(), Some(b"\xffoobar".to_vec()));
+        run::<BitOp>(&backend, "bitop and dest c a");
+        assert_eq!(backend.get("dest").unwrap(), Some(b"f\0\0\0\0\0".to_vec()));
+
+        // an empty result deletes the destination
+        assert_eq!(
+            run::<BitOp>(&backend, "bitop and dest missing"),
+            RespFrame::Integer(0)
+        );
+        assert!(!backend.exists("dest"));
+
+        let err = command::<BitOp>("bitop not dest a b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR BITOP NOT must be called with a single source key."
+        );
+        assert!(matches!(
+            command::<BitOp>("bitop nand dest a b"),
+            Err(CommandError::SyntaxError)
+        ));
+    }
+
+    #[test]
+    fn test_bitfield_get_set() {
+        let backend = Backend::new();
+        assert_eq!(
+            run::<BitField>(&backend, "bitfield f get u8 0"),
+            RespArray::new(vec![RespFrame::Integer(0)]).into()
+        );
+        // a read only call doesn't create the key
+        assert!(!backend.exists("f"));
+
+        assert_eq!(
+            run::<BitField>(&backend, "bitfield f set i8 0 -100 get u8 0 get i4 0"),
+            RespArray::new(vec![
+                RespFrame::Integer(0),
+                RespFrame::Integer(156),
+                RespFrame::Integer(-7),
+            ])
+            .into()
+        );
+
+        // "#N" addresses the N-th integer of the type
+        run::<BitField>(&backend, "bitfield f set u8 #2 200");
+        assert_eq!(backend.strlen("f").unwrap(), 3);
+        assert_eq!(
+            run::<BitField>(&backend, "bitfield f get u8 16 get u4 #5"),
+            RespArray::new(vec![RespFrame::Integer(200), RespFrame::Integer(8)]).into()
+        );
+
+        run::<BitField>(&backend, "bitfield g set i64 0 -1");
+        assert_eq!(
+            run::<BitField>(&backend, "bitfield g get i64 0 get u63 1"),
+            RespArray::new(vec![RespFrame::Integer(-1), RespFrame::Integer(i64::MAX)]).into()
+        );
+    }
+
+    #[test]
+    fn test_bitfield_overflow() {
+        let backend = Backend::new();
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield f incrby u2 100 1 overflow sat incrby u2 102 1"
+            ),
+            RespArray::new(vec![RespFrame::Integer(1), RespFrame::Integer(1)]).into()
+        );
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield f incrby u2 100 1 overflow sat incrby u2 102 1"
+            ),
+            RespArray::new(vec![RespFrame::Integer(2), RespFrame::Integer(2)]).into()
+        );
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield f incrby u2 100 1 overflow sat incrby u2 102 1"
+            ),
+            RespArray::new(vec![RespFrame::Integer(3), RespFrame::Integer(3)]).into()
+        );
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield f incrby u2 100 1 overflow sat incrby u2 102 1"
+            ),
+            RespArray::new(vec![RespFrame::Integer(0), RespFrame::Integer(3)]).into()
+        );
+
+        // FAIL leaves the value alone
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield f overflow fail incrby u2 102 1 get u2 102"
+            ),
+            RespArray::new(vec![RespFrame::Null(RespNull), RespFrame::Integer(3)]).into()
+        );
+
+        // signed integers wrap and saturate at both ends
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield s set i8 0 127 incrby i8 0 1 overflow sat incrby i8 0 -10 incrby i8 0 -200"
+            ),
+            RespArray::new(vec![
+                RespFrame::Integer(0),
+                RespFrame::Integer(-128),
+                RespFrame::Integer(-128),
+                RespFrame::Integer(-128),
+            ])
+            .into()
+        );
+        assert_eq!(
+            run::<BitField>(&backend, "bitfield s overflow sat set i8 0 1000 get i8 0"),
+            RespArray::new(vec![RespFrame::Integer(-128), RespFrame::Integer(127)]).into()
+        );
+        assert_eq!(
+            run::<BitField>(
+                &backend,
+                "bitfield s overflow wrap incrby i64 8 9223372036854775807 incrby i64 8 1"
+            ),
+            RespArray::new(vec![
+                RespFrame::Integer(i64::MAX),
+                RespFrame::Integer(i64::MIN)
+            ])
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_bitfield_invalid_arguments() {
+        let message =
+            "ERR Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is.";
+        for line in [
+            "bitfield f get u64 0",
+            "bitfield f get i65 0",
+            "bitfield f get i0 0",
+            "bitfield f get x8 0",
+        ] {
+            assert_eq!(command::<BitField>(line).unwrap_err().to_string(), message);
+        }
+
+        let err = command::<BitField>("bitfield f overflow none").unwrap_err();
+        assert_eq!(err.to_string(), "ERR Invalid OVERFLOW type specified");
+        let err = command::<BitField>("bitfield f get u8 -1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR bit offset is not an integer or out of range"
+        );
+        assert!(matches!(
+            command::<BitField>("bitfield f del u8 0"),
+            Err(CommandError::SyntaxError)
+        ));
+    }
+}
@@ -0,0 +1,442 @@
+use std::time::Duration;
+
+use crate::{
+    Backend, BlockingOp, BulkString, ListEnd, RespArray, RespFrame, RespNull, RespNullArray,
+    ServedKey,
+};
+
+use super::{
+    extract_args, list::elements_to_frame, list::parse_list_end, parse_integer, parse_option,
+    parse_string, validate_command, validate_command_multi_args, BLMPop, BLMove, BLPop, BRPop,
+    CommandError, CommandExecutor,
+};
+
+// a blocking command runs a BlockingOp over its keys, only the reply differs between them
+pub(super) trait BlockingCommand {
+    fn into_request(self) -> (Vec<String>, BlockingOp, Option<Duration>);
+
+    fn reply(served: Option<ServedKey>) -> RespFrame;
+}
+
+// outside of a connection which may wait, e.g. inside a transaction, a blocking command behaves
+// like its non-blocking counterpart
+fn execute_now<T: BlockingCommand>(cmd: T, backend: &Backend) -> RespFrame {
+    let (keys, op, _) = cmd.into_request();
+    match backend.try_blocking_op(&keys, &op) {
+        Ok(served) => T::reply(served),
+        Err(e) => e.into(),
+    }
+}
+
+pub(super) async fn execute_blocking<T: BlockingCommand>(cmd: T, backend: &Backend) -> RespFrame {
+    let (keys, op, timeout) = cmd.into_request();
+    match backend.blocking_op(keys, op, timeout).await {
+        Ok(served) => T::reply(served),
+        Err(e) => e.into(),
+    }
+}
+
+impl BlockingCommand for BLPop {
+    fn into_request(self) -> (Vec<String>, BlockingOp, Option<Duration>) {
+        let op = BlockingOp::Pop {
+            end: ListEnd::Left,
+            count: 1,
+        };
+        (self.keys, op, self.timeout)
+    }
+
+    fn reply(served: Option<ServedKey>) -> RespFrame {
+        key_element_reply(served)
+    }
+}
+
+impl BlockingCommand for BRPop {
+    fn into_request(self) -> (Vec<String>, BlockingOp, Option<Duration>) {
+        let op = BlockingOp::Pop {
+            end: ListEnd::Right,
+            count: 1,
+        };
+        (self.keys, op, self.timeout)
+    }
+
+    fn reply(served: Option<ServedKey>) -> RespFrame {
+        key_element_reply(served)
+    }
+}
+
+impl BlockingCommand for BLMove {
+    fn into_request(self) -> (Vec<String>, BlockingOp, Option<Duration>) {
+        let op = BlockingOp::Move {
+            destination: self.destination,
+            from: self.from,
+            to: self.to,
+        };
+        (vec![self.source], op, self.timeout)
+    }
+
+    fn reply(served: Option<ServedKey>) -> RespFrame {
+        match served.and_then(|(_, elements)| elements.into_iter().next()) {
+            Some(element) => BulkString::new(element).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl BlockingCommand for BLMPop {
+    fn into_request(self) -> (Vec<String>, BlockingOp, Option<Duration>) {
+        let op = BlockingOp::Pop {
+            end: self.end,
+            count: self.count,
+        };
+        (self.keys, op, self.timeout)
+    }
+
+    fn reply(served: Option<ServedKey>) -> RespFrame {
+        match served {
+            Some((key, elements)) => {
+                RespArray::new([BulkString::from(key).into(), elements_to_frame(elements)]).into()
+            }
+            None => RespFrame::NullArray(RespNullArray),
+        }
+    }
+}
+
+impl CommandExecutor for BLPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        execute_now(self, backend)
+    }
+}
+
+impl CommandExecutor for BRPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        execute_now(self, backend)
+    }
+}
+
+impl CommandExecutor for BLMove {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        execute_now(self, backend)
+    }
+}
+
+impl CommandExecutor for BLMPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        execute_now(self, backend)
+    }
+}
+
+// BLPOP and BRPOP reply a two elements array: the key and the popped element
+fn key_element_reply(served: Option<ServedKey>) -> RespFrame {
+    match served {
+        Some((key, elements)) => {
+            let mut frames: Vec<RespFrame> = vec![BulkString::from(key).into()];
+            frames.extend(elements.into_iter().map(|e| BulkString::new(e).into()));
+            RespArray::new(frames).into()
+        }
+        None => RespFrame::NullArray(RespNullArray),
+    }
+}
+
+// the timeout is in seconds and may have a fractional part, zero blocks forever
+fn parse_timeout(arg: Option<RespFrame>) -> Result<Option<Duration>, CommandError> {
+    let timeout = match arg {
+        Some(RespFrame::BulkString(v)) => String::from_utf8_lossy(&v).parse::<f64>().ok(),
+        Some(RespFrame::Integer(v)) => Some(v as f64),
+        _ => None,
+    };
+
+    match timeout {
+        Some(timeout) if timeout.is_finite() => {
+            if timeout < 0.0 {
+                Err(CommandError::InvalidArgument(
+                    "timeout is negative".to_string(),
+                ))
+            } else if timeout == 0.0 {
+                Ok(None)
+            } else {
+                Duration::try_from_secs_f64(timeout).map(Some).map_err(|_| {
+                    CommandError::InvalidArgument("timeout is out of range".to_string())
+                })
+            }
+        }
+        _ => Err(CommandError::InvalidArgument(
+            "timeout is not a float or out of range".to_string(),
+        )),
+    }
+}
+
+// parse "key [key ...] timeout"
+fn parse_keys_timeout(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(Vec<String>, Option<Duration>), CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+
+    let mut args = extract_args(value, 1)?;
+    let timeout = parse_timeout(args.pop())?;
+    let keys = args
+        .into_iter()
+        .map(|k| parse_string(Some(k)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((keys, timeout))
+}
+
+impl TryFrom<RespArray> for BLPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (keys, timeout) = parse_keys_timeout(value, "blpop")?;
+        Ok(BLPop { keys, timeout })
+    }
+}
+
+impl TryFrom<RespArray> for BRPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (keys, timeout) = parse_keys_timeout(value, "brpop")?;
+        Ok(BRPop { keys, timeout })
+    }
+}
+
+impl TryFrom<RespArray> for BLMove {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["blmove"], 5)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(BLMove {
+            source: parse_string(args.next())?,
+            destination: parse_string(args.next())?,
+            from: parse_list_end(args.next())?,
+            to: parse_list_end(args.next())?,
+            timeout: parse_timeout(args.next())?,
+        })
+    }
+}
+
+// BLMPOP timeout numkeys key [key ...] LEFT|RIGHT [COUNT count]
+impl TryFrom<RespArray> for BLMPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["blmpop"], 4)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let timeout = parse_timeout(args.next())?;
+        let numkeys = parse_integer(args.next())?;
+        if numkeys <= 0 {
+            return Err(CommandError::InvalidArgument(
+                "numkeys should be greater than 0".to_string(),
+            ));
+        }
+
+        let keys = args
+            .by_ref()
+            .take(numkeys as usize)
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.len() != numkeys as usize {
+            return Err(CommandError::SyntaxError);
+        }
+        let end = parse_list_end(args.next())?;
+
+        let count = match args.next() {
+            Some(arg) if parse_option(&arg).as_deref() == Some("count") => {
+                match parse_integer(args.next())? {
+                    count if count > 0 => count as usize,
+                    _ => {
+                        return Err(CommandError::InvalidArgument(
+                            "count should be greater than 0".to_string(),
+                        ))
+                    }
+                }
+            }
+            Some(_) => return Err(CommandError::SyntaxError),
+            None => 1,
+        };
+        if args.next().is_some() {
+            return Err(CommandError::SyntaxError);
+        }
+
+        Ok(BLMPop {
+            keys,
+            end,
+            count,
+            timeout,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn blpop(keys: &[&str], timeout: Option<Duration>) -> BLPop {
+        BLPop {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            timeout,
+        }
+    }
+
+    fn key_element(key: &str, element: &str) -> RespFrame {
+        RespArray::new([
+            BulkString::from(key).into(),
+            BulkString::from(element).into(),
+        ])
+        .into()
+    }
+
+    #[test]
+    fn test_blpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nblpop\r\n$1\r\na\r\n$1\r\nb\r\n$3\r\n0.5\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: BLPop = frame.try_into()?;
+        assert_eq!(result.keys, vec!["a", "b"]);
+        assert_eq!(result.timeout, Some(Duration::from_millis(500)));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nblpop\r\n$1\r\na\r\n$2\r\n-1\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let err = BLPop::try_from(frame).unwrap_err();
+        assert_eq!(err.to_string(), "ERR timeout is negative");
+
+        // a timeout too long to wait for is rejected rather than panicking
+        let err = command::<BLPop>("blpop a 1e20").unwrap_err();
+        assert_eq!(err.to_string(), "ERR timeout is out of range");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blmpop_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*7\r\n$6\r\nblmpop\r\n$1\r\n0\r\n$1\r\n1\r\n$4\r\nlist\r\n$5\r\nRIGHT\r\n$5\r\nCOUNT\r\n$1\r\n2\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: BLMPop = frame.try_into()?;
+        assert_eq!(result.keys, vec!["list"]);
+        assert_eq!(result.end, ListEnd::Right);
+        assert_eq!(result.count, 2);
+        assert_eq!(result.timeout, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blpop_served_by_push() -> Result<()> {
+        let backend = Backend::new();
+        backend.list_push("b".to_string(), ListEnd::Right, vec![b"1".to_vec()])?;
+
+        // the first non-empty list is served right away
+        let ret = execute_blocking(blpop(&["a", "b"], None), &backend).await;
+        assert_eq!(ret, key_element("b", "1"));
+
+        let cloned = backend.clone();
+        let handle =
+            tokio::spawn(async move { execute_blocking(blpop(&["a", "b"], None), &cloned).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        backend.list_push("a".to_string(), ListEnd::Right, vec![b"2".to_vec()])?;
+
+        assert_eq!(handle.await?, key_element("a", "2"));
+        // the pushed element was handed to the blocked client
+        assert!(!backend.exists("a"));
+        assert_eq!(backend.blocked_clients(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocked_clients_are_served_in_order() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut handles = vec![];
+        for _ in 0..3 {
+            let cloned = backend.clone();
+            handles.push(tokio::spawn(async move {
+                execute_blocking(blpop(&["list"], None), &cloned).await
+            }));
+            // make sure the clients block in the order they are spawned
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let elements = vec![b"1".to_vec(), b"2".to_vec(), b"3".to_vec()];
+        backend.list_push("list".to_string(), ListEnd::Right, elements)?;
+
+        for (handle, element) in handles.into_iter().zip(["1", "2", "3"]) {
+            assert_eq!(handle.await?, key_element("list", element));
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blocking_timeout_and_cancel() -> Result<()> {
+        let backend = Backend::new();
+
+        let ret = execute_blocking(blpop(&["list"], Some(Duration::from_millis(20))), &backend);
+        assert_eq!(ret.await, RespFrame::NullArray(RespNullArray));
+
+        // a client which goes away while blocked, e.g. its connection was closed, is unregistered
+        // and never takes an element
+        let cloned = backend.clone();
+        let handle =
+            tokio::spawn(async move { execute_blocking(blpop(&["list"], None), &cloned).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        handle.abort();
+        let _ = handle.await;
+        assert_eq!(backend.blocked_clients(), 0);
+
+        backend.list_push("list".to_string(), ListEnd::Right, vec![b"1".to_vec()])?;
+        assert_eq!(backend.llen("list")?, 1);
+
+        // without a connection to block, e.g. in a transaction, the command doesn't wait
+        let cmd = blpop(&["missing"], None);
+        assert_eq!(cmd.execute(&backend), RespFrame::NullArray(RespNullArray));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blmove_chains_to_blocked_clients() -> Result<()> {
+        let backend = Backend::new();
+
+        let cloned = backend.clone();
+        let pop =
+            tokio::spawn(async move { execute_blocking(blpop(&["dst"], None), &cloned).await });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let cloned = backend.clone();
+        let mv = tokio::spawn(async move {
+            let cmd = BLMove {
+                source: "src".to_string(),
+                destination: "dst".to_string(),
+                from: ListEnd::Left,
+                to: ListEnd::Right,
+                timeout: None,
+            };
+            execute_blocking(cmd, &cloned).await
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        backend.list_push("src".to_string(), ListEnd::Right, vec![b"1".to_vec()])?;
+        assert_eq!(mv.await?, BulkString::from("1").into());
+        assert_eq!(pop.await?, key_element("dst", "1"));
+        assert!(!backend.exists("src"));
+        assert!(!backend.exists("dst"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SimpleString};
+
+use super::{
+    extract_args, lookup_command, parse_option, validate_command_multi_args, CommandError,
+    CommandExecutor, CommandInfo, CommandSpec, CommandSubcommand, COMMAND_TABLE,
+};
+
+impl CommandExecutor for CommandInfo {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.subcommand {
+            CommandSubcommand::List => {
+                RespArray::new(COMMAND_TABLE.iter().map(spec_info).collect::<Vec<_>>()).into()
+            }
+            CommandSubcommand::Count => RespFrame::Integer(COMMAND_TABLE.len() as i64),
+            CommandSubcommand::Info(names) => RespArray::new(
+                names
+                    .iter()
+                    .map(|name| match lookup_command(name) {
+                        Some(spec) => spec_info(spec),
+                        None => RespFrame::Null(RespNull),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            CommandSubcommand::Docs(names) => {
+                let specs: Vec<&CommandSpec> = if names.is_empty() {
+                    COMMAND_TABLE.iter().collect()
+                } else {
+                    names
+                        .iter()
+                        .filter_map(|name| lookup_command(name))
+                        .collect()
+                };
+                let data = specs
+                    .into_iter()
+                    .flat_map(|spec| {
+                        let docs = RespArray::new([
+                            BulkString::from("group").into(),
+                            BulkString::from(spec.group).into(),
+                        ]);
+                        [BulkString::from(spec.name).into(), docs.into()]
+                    })
+                    .collect::<Vec<RespFrame>>();
+                RespArray::new(data).into()
+            }
+        }
+    }
+}
+
+// name, arity, flags, first key, last key, step, acl categories, tips, key specs, subcommands
+fn spec_info(spec: &CommandSpec) -> RespFrame {
+    let flags = spec
+        .flags
+        .iter()
+        .map(|flag| SimpleString::new(*flag).into())
+        .collect::<Vec<RespFrame>>();
+    let categories = vec![SimpleString::new(format!("@{}", spec.group)).into()];
+
+    RespArray::new([
+        BulkString::from(spec.name).into(),
+        RespFrame::Integer(spec.arity),
+        RespArray::new(flags).into(),
+        RespFrame::Integer(spec.first_key),
+        RespFrame::Integer(spec.last_key),
+        RespFrame::Integer(spec.step),
+        RespArray::new(categories).into(),
+        RespArray::new([]).into(),
+        RespArray::new([]).into(),
+        RespArray::new([]).into(),
+    ])
+    .into()
+}
+
+impl TryFrom<RespArray> for CommandInfo {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["command"], 0)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let subcommand = match args.next() {
+            None => CommandSubcommand::List,
+            Some(arg) => {
+                let names = args
+                    .map(|name| match name {
+                        RespFrame::BulkString(name) => Ok(String::from_utf8(name.0)?),
+                        _ => Err(CommandError::InvalidArgument(
+                            "Invalid command name".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                match parse_option(&arg).as_deref() {
+                    Some("count") if names.is_empty() => CommandSubcommand::Count,
+                    Some("count") => return Err(CommandError::WrongArity("command|count".into())),
+                    Some("info") => CommandSubcommand::Info(names),
+                    Some("docs") => CommandSubcommand::Docs(names),
+                    option => {
+                        return Err(CommandError::InvalidArgument(format!(
+                            "unknown subcommand '{}'. Try COMMAND HELP.",
+                            option.unwrap_or_default()
+                        )))
+                    }
+                }
+            }
+        };
+
+        Ok(CommandInfo { subcommand })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_command_info_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$7\r\ncommand\r\n$4\r\nINFO\r\n$3\r\nget\r\n$3\r\nfoo\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: CommandInfo = frame.try_into()?;
+        assert!(
+            matches!(result.subcommand, CommandSubcommand::Info(ref names) if names == &["get", "foo"])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_info_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::Count,
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(COMMAND_TABLE.len() as i64));
+
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::Info(vec!["get".to_string(), "foo".to_string()]),
+        };
+        let result = cmd.execute(&backend);
+        let RespFrame::Array(result) = result else {
+            panic!("expect an array");
+        };
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[1], RespFrame::Null(RespNull));
+        let RespFrame::Array(ref get) = result[0] else {
+            panic!("expect an array");
+        };
+        assert_eq!(get[0], BulkString::from("get").into());
+        assert_eq!(get[1], RespFrame::Integer(2));
+        assert_eq!(get[3], RespFrame::Integer(1));
+
+        let cmd = CommandInfo {
+            subcommand: CommandSubcommand::Docs(vec!["set".to_string()]),
+        };
+        let result = cmd.execute(&backend);
+        let expected = RespArray::new([
+            BulkString::from("set").into(),
+            RespArray::new([
+                BulkString::from("group").into(),
+                BulkString::from("string").into(),
+            ])
+            .into(),
+        ]);
+        assert_eq!(result, expected.into());
+
+        Ok(())
+    }
+}
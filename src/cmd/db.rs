@@ -0,0 +1,359 @@
+use crate::{Backend, BackendError, RespArray, RespFrame};
+
+use super::{
+    extract_args, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, DbSize, FlushAll, FlushDb, Move,
+    Select, SwapDb, RESP_OK,
+};
+
+impl CommandExecutor for Select {
+    // only checks the index, the connection switches to the database in `execute_select`
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match db_index(self.index).and_then(|index| backend.select(index)) {
+            Ok(_) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl Select {
+    /// Point the handle of the connection to the database.
+    pub fn execute_select(self, backend: &mut Backend) -> RespFrame {
+        match db_index(self.index).and_then(|index| backend.select(index)) {
+            Ok(db) => {
+                *backend = db;
+                RESP_OK.clone()
+            }
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SwapDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let ret = db_index(self.a)
+            .and_then(|a| Ok((a, db_index(self.b)?)))
+            .and_then(|(a, b)| backend.swapdb(a, b));
+        match ret {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for Move {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match db_index(self.db).and_then(|db| backend.move_key(&self.key, db)) {
+            Ok(moved) => RespFrame::Integer(moved as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for FlushDb {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.flushdb(self.lazy);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for FlushAll {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.flushall(self.lazy);
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for DbSize {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.dbsize() as i64)
+    }
+}
+
+// a negative index is out of range like a too large one
+fn db_index(index: i64) -> Result<usize, BackendError> {
+    usize::try_from(index).map_err(|_| BackendError::DbIndexOutOfRange)
+}
+
+impl TryFrom<RespArray> for Select {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["select"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let index = parse_integer(args.next())?;
+        Ok(Select { index })
+    }
+}
+
+impl TryFrom<RespArray> for SwapDb {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["swapdb"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let a = parse_integer(args.next())
+            .map_err(|_| CommandError::InvalidArgument("invalid first DB index".to_string()))?;
+        let b = parse_integer(args.next())
+            .map_err(|_| CommandError::InvalidArgument("invalid second DB index".to_string()))?;
+        Ok(SwapDb { a, b })
+    }
+}
+
+impl TryFrom<RespArray> for Move {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["move"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let db = parse_integer(args.next())?;
+        Ok(Move { key, db })
+    }
+}
+
+impl TryFrom<RespArray> for FlushDb {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["flushdb"], 0)?;
+
+        let lazy = parse_flush_mode(extract_args(value, 1)?)?;
+        Ok(FlushDb { lazy })
+    }
+}
+
+impl TryFrom<RespArray> for FlushAll {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["flushall"], 0)?;
+
+        let lazy = parse_flush_mode(extract_args(value, 1)?)?;
+        Ok(FlushAll { lazy })
+    }
+}
+
+impl TryFrom<RespArray> for DbSize {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["dbsize"], 0)?;
+        Ok(DbSize)
+    }
+}
+
+// [ASYNC | SYNC], returns whether the data is freed in the background
+fn parse_flush_mode(args: Vec<RespFrame>) -> Result<bool, CommandError> {
+    match args.as_slice() {
+        [] => Ok(false),
+        [arg] => match parse_option(arg).as_deref() {
+            Some("async") => Ok(true),
+            Some("sync") => Ok(false),
+            _ => Err(CommandError::SyntaxError),
+        },
+        _ => Err(CommandError::SyntaxError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use crate::SimpleString;
+    use anyhow::Result;
+
+    #[test]
+    fn test_select() -> Result<()> {
+        let mut backend = Backend::new();
+        backend.set("key".to_string(), b"db0".to_vec());
+
+        let cmd: Select = command("select 1")?;
+        assert_eq!(cmd.execute_select(&mut backend), RESP_OK.clone());
+        assert_eq!(backend.index(), 1);
+        assert_eq!(backend.get("key")?, None);
+        backend.set("key".to_string(), b"db1".to_vec());
+        assert_eq!(backend.select(0)?.get("key")?, Some(b"db0".to_vec()));
+
+        for (line, error) in [
+            ("select 16", "ERR DB index is out of range"),
+            ("select -1", "ERR DB index is out of range"),
+            ("select a", "ERR value is not an integer or out of range"),
+        ] {
+            let ret = command::<Select>(line).map(|cmd| cmd.execute_select(&mut backend));
+            let frame = ret.unwrap_or_else(|e| e.into());
+            assert_eq!(frame, RespFrame::Error(error.into()), "{}", line);
+        }
+        assert_eq!(backend.index(), 1);
+
+        // the number of databases is configurable
+        let backend = Backend::with_databases(2);
+        let cmd: Select = command("select 2")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR DB index is out of range".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_swapdb() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("a".to_string(), b"db0".to_vec());
+        let db1 = backend.select(1)?;
+        db1.set("b".to_string(), b"db1".to_vec());
+
+        let cmd: SwapDb = command("swapdb 0 1")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        // a handle keeps the data it was selected with until it selects again
+        let db0 = backend.select(0)?;
+        assert_eq!(db0.get("b")?, Some(b"db1".to_vec()));
+        assert_eq!(db0.get("a")?, None);
+        assert_eq!(backend.select(1)?.get("a")?, Some(b"db0".to_vec()));
+
+        let cmd: SwapDb = command("swapdb 0 16")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR DB index is out of range".into())
+        );
+        assert_eq!(
+            command::<SwapDb>("swapdb a 1").unwrap_err().to_string(),
+            "ERR invalid first DB index"
+        );
+        assert_eq!(
+            command::<SwapDb>("swapdb 1 b").unwrap_err().to_string(),
+            "ERR invalid second DB index"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_swapdb_serves_blocked_clients() -> Result<()> {
+        let backend = Backend::new();
+        let db1 = backend.select(1)?;
+        db1.list_push(
+            "list".to_string(),
+            crate::ListEnd::Left,
+            vec![b"a".to_vec()],
+        )?;
+
+        let op = crate::BlockingOp::Pop {
+            end: crate::ListEnd::Left,
+            count: 1,
+        };
+        let blocked = backend.clone();
+        let handle = tokio::spawn(async move {
+            blocked
+                .blocking_op(vec!["list".to_string()], op, None)
+                .await
+        });
+        while backend.blocked_clients() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        backend.swapdb(0, 1)?;
+        assert_eq!(
+            handle.await??,
+            Some(("list".to_string(), vec![b"a".to_vec()]))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flushdb_wakes_blocked_stream_readers() -> Result<()> {
+        let backend = Backend::new();
+        let xadd = |db: &Backend, ms: u64| {
+            let id = crate::XAddId::Explicit(crate::StreamId::new(ms, 0));
+            let fields = vec![(b"f".to_vec(), b"v".to_vec())];
+            db.xadd("stream".to_string(), id, fields, false, None)
+        };
+        xadd(&backend, 1)?;
+
+        let blocked = backend.clone();
+        let streams = vec![("stream".to_string(), crate::StreamId::new(1, 0))];
+        let handle = tokio::spawn(async move { blocked.xread_blocking(streams, None, None).await });
+        while backend.blocked_clients() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        // the reader sees the entries added to the new data of the database
+        backend.flushdb(false);
+        xadd(&backend.select(0)?, 2)?;
+        let ret = tokio::time::timeout(std::time::Duration::from_secs(1), handle).await???;
+        let ret = ret.expect("the reader should be served");
+        assert_eq!(ret.len(), 1);
+        assert_eq!(ret[0].1[0].0, crate::StreamId::new(2, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_move() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("key".to_string(), b"v".to_vec());
+        backend.expire_at("key", crate::now_ms() + 10_000);
+
+        let cmd: Move = command("move key 1")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("key"));
+        let db1 = backend.select(1)?;
+        assert!(db1.pttl("key") > 0);
+
+        // the key already exists in the target database
+        backend.set("key".to_string(), b"v".to_vec());
+        let cmd: Move = command("move key 1")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd: Move = command("move missing 1")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        let cmd: Move = command("move key 0")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR source and destination objects are the same".into())
+        );
+        let cmd: Move = command("move key 99")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR DB index is out of range".into())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_flush_dbsize() -> Result<()> {
+        let backend = Backend::new();
+        let db1 = backend.select(1)?;
+        for i in 0..10 {
+            backend.set(format!("key:{}", i), b"v".to_vec());
+            db1.set(format!("key:{}", i), b"v".to_vec());
+        }
+
+        let cmd: DbSize = command("dbsize")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(10));
+
+        let cmd: FlushDb = command("flushdb")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        let cmd: DbSize = command("dbsize")?;
+        assert_eq!(cmd.execute(&backend.select(0)?), RespFrame::Integer(0));
+        let cmd: DbSize = command("dbsize")?;
+        assert_eq!(cmd.execute(&backend.select(1)?), RespFrame::Integer(10));
+
+        let cmd: FlushAll = command("flushall ASYNC")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.select(1)?.dbsize(), 0);
+
+        assert!(command::<FlushAll>("flushall lazy").is_err());
+        assert!(command::<FlushDb>("flushdb sync async").is_err());
+        let cmd: FlushDb = command("flushdb sync")?;
+        assert_eq!(cmd.execute(&backend), SimpleString::new("OK").into());
+
+        Ok(())
+    }
+}
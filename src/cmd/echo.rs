@@ -1,6 +1,30 @@
-use crate::{Backend, RespArray, RespFrame, SimpleString};
+use crate::{Backend, BulkString, RespArray, RespFrame, SimpleString};
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, Echo};
+use super::{
+    extract_args, parse_bytes, validate_command, validate_command_multi_args, CommandError,
+    CommandExecutor, Echo, Ping,
+};
+
+impl CommandExecutor for Ping {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.message {
+            Some(message) => BulkString::new(message).into(),
+            None => SimpleString::new("PONG").into(),
+        }
+    }
+}
+
+impl Ping {
+    /// The reply in the subscribed mode of RESP2, where every reply is an array like the
+    /// messages: "pong" and the message, empty if there is none.
+    pub fn execute_subscribed(self) -> RespFrame {
+        RespArray::new([
+            BulkString::from("pong").into(),
+            BulkString::new(self.message.unwrap_or_default()).into(),
+        ])
+        .into()
+    }
+}
 
 impl CommandExecutor for Echo {
     fn execute(self, _backend: &Backend) -> RespFrame {
@@ -8,6 +32,21 @@ impl CommandExecutor for Echo {
     }
 }
 
+impl TryFrom<RespArray> for Ping {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["ping"], 0)?;
+        if value.len() > 2 {
+            return Err(CommandError::WrongArity("ping".to_string()));
+        }
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let message = args.next().map(|arg| parse_bytes(Some(arg))).transpose()?;
+        Ok(Ping { message })
+    }
+}
+
 impl TryFrom<RespArray> for Echo {
     type Error = CommandError;
 
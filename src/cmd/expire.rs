@@ -0,0 +1,251 @@
+use crate::{now_ms, Backend, RespArray, RespFrame};
+
+use super::{
+    extract_args, parse_integer, validate_command, CommandError, CommandExecutor, Expire, PExpire,
+    PTtl, Persist, Ttl,
+};
+
+impl CommandExecutor for Expire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let at = expire_at(self.seconds.saturating_mul(1000));
+        RespFrame::Integer(backend.expire_at(&self.key, at) as i64)
+    }
+}
+
+impl CommandExecutor for PExpire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let at = expire_at(self.milliseconds);
+        RespFrame::Integer(backend.expire_at(&self.key, at) as i64)
+    }
+}
+
+impl CommandExecutor for Ttl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pttl(&self.key) {
+            ttl if ttl < 0 => RespFrame::Integer(ttl),
+            // round to the nearest second, the same way redis does
+            ttl => RespFrame::Integer((ttl + 500) / 1000),
+        }
+    }
+}
+
+impl CommandExecutor for PTtl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.pttl(&self.key))
+    }
+}
+
+impl CommandExecutor for Persist {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.persist(&self.key) as i64)
+    }
+}
+
+// convert a relative timeout in milliseconds to an absolute unix time, a non-positive timeout
+// yields a time in the past
+fn expire_at(milliseconds: i64) -> u64 {
+    if milliseconds <= 0 {
+        0
+    } else {
+        now_ms().saturating_add(milliseconds as u64)
+    }
+}
+
+impl TryFrom<RespArray> for Expire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["expire"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(Expire {
+                key: String::from_utf8(key.0)?,
+                seconds: parse_integer(args.next())?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PExpire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["pexpire"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(PExpire {
+                key: String::from_utf8(key.0)?,
+                milliseconds: parse_integer(args.next())?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Ttl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["ttl"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(Ttl {
+                key: String::from_utf8(key.0)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PTtl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["pttl"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(PTtl {
+                key: String::from_utf8(key.0)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Persist {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["persist"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(Persist {
+                key: String::from_utf8(key.0)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_expire_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$6\r\nexpire\r\n$5\r\nhello\r\n$2\r\n10\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Expire = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+        assert_eq!(result.seconds, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expire_ttl_persist_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = Ttl {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-2));
+
+        let cmd = Expire {
+            key: "hello".to_string(),
+            seconds: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        backend.set("hello".to_string(), b"world".to_vec());
+        let cmd = Ttl {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-1));
+
+        let cmd = Expire {
+            key: "hello".to_string(),
+            seconds: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = Ttl {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(10));
+
+        let cmd = PTtl {
+            key: "hello".to_string(),
+        };
+        match cmd.execute(&backend) {
+            RespFrame::Integer(ttl) => assert!(ttl > 9000 && ttl <= 10000),
+            v => panic!("unexpected reply: {:?}", v),
+        }
+
+        let cmd = Persist {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd = Persist {
+            key: "hello".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        // a non-positive timeout deletes the key
+        let cmd = PExpire {
+            key: "hello".to_string(),
+            milliseconds: -1,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.get("hello")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_active_expire_cycle() -> Result<()> {
+        let backend = Backend::new();
+        backend.hset(
+            "map".to_string(),
+            vec![("hello".to_string(), RespFrame::BulkString(b"world".into()))],
+        )?;
+        backend.expire_at("map", now_ms() - 1);
+        // the key is gone before it is accessed again
+        assert!(!backend.keyspace.contains_key("map"));
+
+        backend.set("hello".to_string(), b"world".to_vec());
+        backend.expires.insert("hello".to_string(), now_ms() - 1);
+        assert!(backend.keyspace.contains_key("hello"));
+        assert_eq!(backend.active_expire_cycle(), 1);
+        assert!(!backend.keyspace.contains_key("hello"));
+        assert_eq!(backend.expires.len(), 0);
+
+        // the keys are sampled a few at a time, again while most of them have expired. A cycle
+        // may run out of its time budget before all of them are sampled
+        for i in 0..200 {
+            let key = format!("key:{}", i);
+            backend.set(key.clone(), b"value".to_vec());
+            backend.expires.insert(key, now_ms() - 1);
+        }
+        let n = (0..100)
+            .map(|_| backend.active_expire_cycle())
+            .sum::<usize>();
+        assert_eq!(n, 200);
+        assert_eq!(backend.keyspace.len(), 0);
+
+        Ok(())
+    }
+}
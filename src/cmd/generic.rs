@@ -0,0 +1,448 @@
+use crate::{Backend, BackendError, BulkString, RespArray, RespFrame, RespNull, SimpleString};
+
+use super::{
+    extract_args, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, Copy, Del, Exists, RandomKey,
+    Rename, RenameNx, Touch, Type, Unlink, RESP_OK,
+};
+
+impl CommandExecutor for Type {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let name = backend.key_type(&self.key).unwrap_or("none");
+        SimpleString::new(name).into()
+    }
+}
+
+impl CommandExecutor for Del {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.del(&self.keys) as i64)
+    }
+}
+
+impl CommandExecutor for Unlink {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.unlink(&self.keys) as i64)
+    }
+}
+
+impl CommandExecutor for Exists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // a key given more than once is counted more than once
+        let n = self.keys.iter().filter(|key| backend.exists(key)).count();
+        RespFrame::Integer(n as i64)
+    }
+}
+
+impl CommandExecutor for Touch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // there is no access time to update, so this is only the count of the existing keys
+        let n = self.keys.iter().filter(|key| backend.exists(key)).count();
+        RespFrame::Integer(n as i64)
+    }
+}
+
+impl CommandExecutor for Rename {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.rename(&self.key, self.newkey, false) {
+            Ok(_) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for RenameNx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.rename(&self.key, self.newkey, true) {
+            Ok(renamed) => RespFrame::Integer(renamed as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for Copy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let db = match self.db.map(usize::try_from).transpose() {
+            Ok(db) => db,
+            Err(_) => return BackendError::DbIndexOutOfRange.into(),
+        };
+        match backend.copy(&self.source, self.destination, db, self.replace) {
+            Ok(copied) => RespFrame::Integer(copied as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for RandomKey {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.random_key() {
+            Some(key) => BulkString::from(key).into(),
+            None => RespFrame::Null(RespNull),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for Type {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["type"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        match args.next() {
+            Some(RespFrame::BulkString(key)) => Ok(Type {
+                key: String::from_utf8(key.0)?,
+            }),
+            _ => Err(CommandError::InvalidArgument("Invalid key".to_string())),
+        }
+    }
+}
+
+fn parse_keys(value: RespArray, names: &[&'static str]) -> Result<Vec<String>, CommandError> {
+    validate_command_multi_args(&value, names, 1)?;
+
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|k| parse_string(Some(k)))
+        .collect()
+}
+
+impl TryFrom<RespArray> for Del {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = parse_keys(value, &["del"])?;
+        Ok(Del { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Unlink {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = parse_keys(value, &["unlink"])?;
+        Ok(Unlink { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Exists {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = parse_keys(value, &["exists"])?;
+        Ok(Exists { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Touch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let keys = parse_keys(value, &["touch"])?;
+        Ok(Touch { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Rename {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["rename"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let newkey = parse_string(args.next())?;
+        Ok(Rename { key, newkey })
+    }
+}
+
+impl TryFrom<RespArray> for RenameNx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["renamenx"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let newkey = parse_string(args.next())?;
+        Ok(RenameNx { key, newkey })
+    }
+}
+
+impl TryFrom<RespArray> for Copy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["copy"], 2)?;
+
+        // COPY source destination [DB destination-db] [REPLACE]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let source = parse_string(args.next())?;
+        let destination = parse_string(args.next())?;
+        let mut db = None;
+        let mut replace = false;
+        while let Some(arg) = args.next() {
+            match parse_option(&arg).as_deref() {
+                Some("db") => db = Some(parse_integer(args.next())?),
+                Some("replace") => replace = true,
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+        Ok(Copy {
+            source,
+            destination,
+            db,
+            replace,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for RandomKey {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["randomkey"], 0)?;
+        Ok(RandomKey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{now_ms, RespDecode};
+
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn populate(backend: &Backend) -> Result<()> {
+        backend.set("string".to_string(), b"world".to_vec());
+        backend.hset(
+            "hash".to_string(),
+            vec![("hello".to_string(), BulkString::from("world").into())],
+        )?;
+        backend.sadd("set".to_string(), vec![b"world".to_vec()])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*2\r\n$4\r\ntype\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Type = frame.try_into()?;
+        assert_eq!(result.key, "hello");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_command() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("string".to_string(), b"world".to_vec());
+        backend.hset(
+            "hash".to_string(),
+            vec![("hello".to_string(), BulkString::from("world").into())],
+        )?;
+        backend.sadd("set".to_string(), vec![b"world".to_vec()])?;
+
+        for (key, expected) in [
+            ("string", "string"),
+            ("hash", "hash"),
+            ("set", "set"),
+            ("missing", "none"),
+        ] {
+            let cmd = Type {
+                key: key.to_string(),
+            };
+            assert_eq!(cmd.execute(&backend), SimpleString::new(expected).into());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrong_type() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("hello".to_string(), b"world".to_vec());
+
+        let ret = backend.hset(
+            "hello".to_string(),
+            vec![("hello".to_string(), BulkString::from("world").into())],
+        );
+        assert_eq!(ret, Err(BackendError::WrongType));
+        assert_eq!(backend.hget("hello", "hello"), Err(BackendError::WrongType));
+        assert_eq!(
+            backend.sismember("hello", b"world"),
+            Err(BackendError::WrongType)
+        );
+
+        // SET overwrites a value of any type
+        backend.sadd("set".to_string(), vec![b"world".to_vec()])?;
+        backend.set("set".to_string(), b"world".to_vec());
+        assert_eq!(backend.key_type("set"), Some("string"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_del_exists() -> Result<()> {
+        let backend = Backend::new();
+        populate(&backend)?;
+
+        let cmd: Exists = command("exists string hash set missing string")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        let cmd: Touch = command("touch string missing")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd: Del = command("del string hash missing hash")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.key_type("hash"), None);
+        let cmd: Exists = command("exists string hash set")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        // the timeout goes away with the key
+        backend.expire_at("set", now_ms() + 10_000);
+        let cmd: Del = command("del set")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        backend.sadd("set".to_string(), vec![b"world".to_vec()])?;
+        assert_eq!(backend.pttl("set"), -1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unlink() -> Result<()> {
+        let backend = Backend::new();
+        populate(&backend)?;
+        let members = (0..1000).map(|i| i.to_string().into_bytes()).collect();
+        backend.sadd("large".to_string(), members)?;
+
+        let cmd: Unlink = command("unlink large string missing")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.key_type("large"), None);
+        assert_eq!(backend.key_type("string"), None);
+        assert_eq!(backend.key_type("hash"), Some("hash"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rename() -> Result<()> {
+        let backend = Backend::new();
+        populate(&backend)?;
+        backend.expire_at("hash", now_ms() + 10_000);
+
+        let cmd: Rename = command("rename hash other")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.key_type("hash"), None);
+        assert_eq!(backend.key_type("other"), Some("hash"));
+        assert!(backend.pttl("other") > 0);
+
+        // the destination is replaced, timeout included
+        backend.expire_at("string", now_ms() + 10_000);
+        let cmd: Rename = command("rename set string")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(backend.key_type("string"), Some("set"));
+        assert_eq!(backend.pttl("string"), -1);
+
+        let cmd: Rename = command("rename missing other")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR no such key".into())
+        );
+        let cmd: Rename = command("rename other other")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_renamenx() -> Result<()> {
+        let backend = Backend::new();
+        populate(&backend)?;
+
+        let cmd: RenameNx = command("renamenx hash set")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(backend.key_type("hash"), Some("hash"));
+        let cmd: RenameNx = command("renamenx hash hash")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd: RenameNx = command("renamenx hash other")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.key_type("other"), Some("hash"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy() -> Result<()> {
+        let backend = Backend::new();
+        populate(&backend)?;
+        backend.expire_at("hash", now_ms() + 10_000);
+
+        let cmd: Copy = command("copy hash other")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(backend.pttl("other") > 0);
+        // the copy is independent of the source
+        backend.hset(
+            "other".to_string(),
+            vec![("field".to_string(), BulkString::from("value").into())],
+        )?;
+        assert_eq!(backend.hlen("hash")?, 1);
+
+        let cmd: Copy = command("copy set other")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd: Copy = command("copy set other REPLACE")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.key_type("other"), Some("set"));
+        assert_eq!(backend.pttl("other"), -1);
+
+        let cmd: Copy = command("copy missing other")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd: Copy = command("copy set set")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR source and destination objects are the same".into())
+        );
+        let cmd: Copy = command("copy set other db 0 replace")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: Copy = command("copy set other db 16")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR DB index is out of range".into())
+        );
+
+        // into another database, where the same key is not the same object
+        let cmd: Copy = command("copy set set db 1")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert_eq!(backend.select(1)?.key_type("set"), Some("set"));
+        assert_eq!(backend.key_type("set"), Some("set"));
+        assert!(command::<Copy>("copy set other force").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_randomkey() -> Result<()> {
+        let backend = Backend::new();
+        let cmd: RandomKey = command("randomkey")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        populate(&backend)?;
+        let mut picked = std::collections::HashSet::new();
+        for _ in 0..200 {
+            let cmd: RandomKey = command("randomkey")?;
+            let RespFrame::BulkString(key) = cmd.execute(&backend) else {
+                panic!("expected a bulk string");
+            };
+            let key = String::from_utf8(key.0)?;
+            assert!(["string", "hash", "set"].contains(&key.as_str()));
+            picked.insert(key);
+        }
+        // every key gets picked, not only the first ones of the keyspace
+        assert_eq!(picked.len(), 3);
+
+        Ok(())
+    }
+}
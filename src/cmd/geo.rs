@@ -0,0 +1,667 @@
+use std::iter::Peekable;
+
+use crate::{
+    Backend, BulkString, GeoMatch, GeoOrigin, GeoQuery, GeoShape, GeoSort, GeoUnit, RespArray,
+    RespFrame, RespNull, RespNullArray, SetCondition, ZAddOptions,
+};
+
+use super::{
+    extract_args, parse_bytes, parse_float, parse_integer, parse_option, parse_string,
+    validate_command_multi_args, CommandError, CommandExecutor, GeoAdd, GeoDist, GeoPos, GeoSearch,
+    GeoSearchStore,
+};
+
+impl CommandExecutor for GeoAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geoadd(self.key, self.points, self.options) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoPos {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geopos(&self.key, &self.members) {
+            Ok(positions) => RespArray::new(
+                positions
+                    .into_iter()
+                    .map(|v| match v {
+                        Some((lon, lat)) => {
+                            RespArray::new([RespFrame::Double(lon), RespFrame::Double(lat)]).into()
+                        }
+                        None => RespFrame::NullArray(RespNullArray),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoDist {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geodist(&self.key, &self.member1, &self.member2, self.unit) {
+            Ok(Some(distance)) => distance_frame(distance),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GeoSearch {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let matches = match backend.geosearch(&self.key, &self.query) {
+            Ok(matches) => matches,
+            Err(e) => return e.into(),
+        };
+
+        let frames = matches
+            .into_iter()
+            .map(|m| self.match_frame(m))
+            .collect::<Vec<_>>();
+        RespArray::new(frames).into()
+    }
+}
+
+impl CommandExecutor for GeoSearchStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.geosearchstore(self.destination, &self.key, &self.query, self.store_dist) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl GeoSearch {
+    // a plain member, or an array of the member followed by the distance, the hash and the
+    // coordinates, in this order, when any of them is asked for
+    fn match_frame(&self, m: GeoMatch) -> RespFrame {
+        if !(self.with_dist || self.with_hash || self.with_coord) {
+            return BulkString::new(m.member).into();
+        }
+
+        let mut frames = vec![BulkString::new(m.member).into()];
+        if self.with_dist {
+            frames.push(distance_frame(m.distance));
+        }
+        if self.with_hash {
+            frames.push(RespFrame::Integer(m.hash as i64));
+        }
+        if self.with_coord {
+            frames.push(
+                RespArray::new([
+                    RespFrame::Double(m.longitude),
+                    RespFrame::Double(m.latitude),
+                ])
+                .into(),
+            );
+        }
+        RespArray::new(frames).into()
+    }
+}
+
+// distances are replied with 4 decimals, like redis does
+fn distance_frame(distance: f64) -> RespFrame {
+    RespFrame::Double((distance * 10000.0).round() / 10000.0)
+}
+
+fn parse_unit(arg: Option<RespFrame>) -> Result<GeoUnit, CommandError> {
+    match arg.as_ref().and_then(parse_option).as_deref() {
+        Some("m") => Ok(GeoUnit::Meters),
+        Some("km") => Ok(GeoUnit::Kilometers),
+        Some("ft") => Ok(GeoUnit::Feet),
+        Some("mi") => Ok(GeoUnit::Miles),
+        _ => Err(CommandError::InvalidArgument(
+            "unsupported unit provided. please use M, KM, FT, MI".to_string(),
+        )),
+    }
+}
+
+// the options of GEOSEARCH and GEOSEARCHSTORE, STOREDIST is only valid for the latter and the
+// WITH options only for the former
+#[derive(Debug, Default)]
+struct SearchOptions {
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+    store_dist: bool,
+}
+
+// parse "FROMMEMBER member | FROMLONLAT longitude latitude
+//   BYRADIUS radius M | KM | FT | MI | BYBOX width height M | KM | FT | MI
+//   [ASC | DESC] [COUNT count [ANY]] [WITHCOORD] [WITHDIST] [WITHHASH] | [STOREDIST]"
+fn parse_query(
+    mut args: Peekable<impl Iterator<Item = RespFrame>>,
+    name: &str,
+    store: bool,
+) -> Result<(GeoQuery, SearchOptions), CommandError> {
+    let (mut origin, mut shape, mut unit) = (None, None, GeoUnit::default());
+    let (mut sort, mut count, mut any) = (None, None, false);
+    let mut options = SearchOptions::default();
+    while let Some(arg) = args.next() {
+        match parse_option(&arg).as_deref() {
+            Some("frommember") if origin.is_none() => {
+                origin = Some(GeoOrigin::Member(parse_bytes(args.next())?));
+            }
+            Some("fromlonlat") if origin.is_none() => {
+                let lon = parse_float(args.next())?;
+                let lat = parse_float(args.next())?;
+                origin = Some(GeoOrigin::LonLat(lon, lat));
+            }
+            Some("frommember" | "fromlonlat") => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+                    name
+                )))
+            }
+            Some("byradius") if shape.is_none() => {
+                let radius = parse_float(args.next())?;
+                if radius < 0.0 {
+                    return Err(CommandError::InvalidArgument(
+                        "radius cannot be negative".to_string(),
+                    ));
+                }
+                unit = parse_unit(args.next())?;
+                shape = Some(GeoShape::Radius(radius));
+            }
+            Some("bybox") if shape.is_none() => {
+                let width = parse_float(args.next())?;
+                let height = parse_float(args.next())?;
+                if width < 0.0 || height < 0.0 {
+                    return Err(CommandError::InvalidArgument(
+                        "height or width cannot be negative".to_string(),
+                    ));
+                }
+                unit = parse_unit(args.next())?;
+                shape = Some(GeoShape::Box { width, height });
+            }
+            Some("byradius" | "bybox") => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "exactly one of BYRADIUS and BYBOX can be specified for {}",
+                    name
+                )))
+            }
+            Some("asc") => sort = Some(GeoSort::Asc),
+            Some("desc") => sort = Some(GeoSort::Desc),
+            Some("count") => {
+                let n = parse_integer(args.next())?;
+                if n <= 0 {
+                    return Err(CommandError::InvalidArgument(
+                        "COUNT must be > 0".to_string(),
+                    ));
+                }
+                count = Some(n as usize);
+                if args.peek().and_then(parse_option).as_deref() == Some("any") {
+                    args.next();
+                    any = true;
+                }
+            }
+            Some("withcoord") if !store => options.with_coord = true,
+            Some("withdist") if !store => options.with_dist = true,
+            Some("withhash") if !store => options.with_hash = true,
+            Some("storedist") if store => options.store_dist = true,
+            _ => return Err(CommandError::SyntaxError),
+        }
+    }
+
+    let Some(origin) = origin else {
+        return Err(CommandError::InvalidArgument(format!(
+            "exactly one of FROMMEMBER or FROMLONLAT can be specified for {}",
+            name
+        )));
+    };
+    let Some(shape) = shape else {
+        return Err(CommandError::InvalidArgument(format!(
+            "exactly one of BYRADIUS and BYBOX can be specified for {}",
+            name
+        )));
+    };
+
+    let query = GeoQuery {
+        origin,
+        shape,
+        unit,
+        sort,
+        count,
+        any,
+    };
+    Ok((query, options))
+}
+
+impl TryFrom<RespArray> for GeoAdd {
+    type Error = CommandError;
+
+    // GEOADD key [NX | XX] [CH] longitude latitude member [longitude latitude member ...]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["geoadd"], 4)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+
+        let mut options = ZAddOptions::default();
+        while let Some(option) = args.peek().and_then(parse_option) {
+            match option.as_str() {
+                "nx" if options.condition.is_none() => {
+                    options.condition = Some(SetCondition::NotExists)
+                }
+                "xx" if options.condition.is_none() => {
+                    options.condition = Some(SetCondition::Exists)
+                }
+                "nx" | "xx" => return Err(CommandError::SyntaxError),
+                "ch" => options.changed = true,
+                _ => break,
+            }
+            args.next();
+        }
+
+        let args = args.collect::<Vec<_>>();
+        if args.is_empty() || args.len() % 3 != 0 {
+            return Err(CommandError::SyntaxError);
+        }
+        let mut points = Vec::with_capacity(args.len() / 3);
+        let mut args = args.into_iter();
+        while let (Some(lon), Some(lat), Some(member)) = (args.next(), args.next(), args.next()) {
+            points.push((
+                parse_float(Some(lon))?,
+                parse_float(Some(lat))?,
+                parse_bytes(Some(member))?,
+            ));
+        }
+
+        Ok(GeoAdd {
+            key,
+            options,
+            points,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GeoPos {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["geopos"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let members = args
+            .map(|m| parse_bytes(Some(m)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GeoPos { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for GeoDist {
+    type Error = CommandError;
+
+    // GEODIST key member1 member2 [M | KM | FT | MI]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["geodist"], 3)?;
+        if value.len() > 5 {
+            return Err(CommandError::SyntaxError);
+        }
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let member1 = parse_bytes(args.next())?;
+        let member2 = parse_bytes(args.next())?;
+        let unit = match args.next() {
+            Some(unit) => parse_unit(Some(unit))?,
+            None => GeoUnit::Meters,
+        };
+        Ok(GeoDist {
+            key,
+            member1,
+            member2,
+            unit,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GeoSearch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["geosearch"], 6)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let (query, options) = parse_query(args.peekable(), "GEOSEARCH", false)?;
+        Ok(GeoSearch {
+            key,
+            query,
+            with_coord: options.with_coord,
+            with_dist: options.with_dist,
+            with_hash: options.with_hash,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GeoSearchStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["geosearchstore"], 7)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let destination = parse_string(args.next())?;
+        let key = parse_string(args.next())?;
+        let (query, options) = parse_query(args.peekable(), "GEOSEARCHSTORE", true)?;
+        Ok(GeoSearchStore {
+            destination,
+            key,
+            query,
+            store_dist: options.store_dist,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+
+    fn sicily(backend: &Backend) -> Result<()> {
+        let cmd: GeoAdd =
+            command("geoadd Sicily 13.361389 38.115556 Palermo 15.087269 37.502669 Catania")?;
+        assert_eq!(cmd.execute(backend), RespFrame::Integer(2));
+        let cmd: GeoAdd =
+            command("geoadd Sicily 12.758489 38.788135 edge1 17.241510 38.788135 edge2")?;
+        assert_eq!(cmd.execute(backend), RespFrame::Integer(2));
+        Ok(())
+    }
+
+    fn members(frame: RespFrame) -> Vec<String> {
+        let RespFrame::Array(array) = frame else {
+            panic!("expected an array, got {:?}", frame);
+        };
+        array
+            .iter()
+            .map(|v| match v {
+                RespFrame::BulkString(v) => String::from_utf8_lossy(v).into_owned(),
+                RespFrame::Array(v) => match &v[0] {
+                    RespFrame::BulkString(v) => String::from_utf8_lossy(v).into_owned(),
+                    v => panic!("expected a member, got {:?}", v),
+                },
+                v => panic!("expected a member, got {:?}", v),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_geoadd_geopos() -> Result<()> {
+        let backend = Backend::new();
+        sicily(&backend)?;
+
+        // the score is the 52 bits geohash, the same as redis
+        assert_eq!(
+            backend.zscore("Sicily", b"Palermo")?,
+            Some(3479099956230698.0)
+        );
+        assert_eq!(
+            backend.zscore("Sicily", b"Catania")?,
+            Some(3479447370796909.0)
+        );
+
+        let cmd: GeoPos = command("geopos Sicily Palermo missing")?;
+        let RespFrame::Array(positions) = cmd.execute(&backend) else {
+            panic!("expected an array");
+        };
+        let RespFrame::Array(ref palermo) = positions[0] else {
+            panic!("expected the coordinates");
+        };
+        let (RespFrame::Double(lon), RespFrame::Double(lat)) = (&palermo[0], &palermo[1]) else {
+            panic!("expected doubles");
+        };
+        assert!((lon - 13.361389).abs() < 1e-5 && (lat - 38.115556).abs() < 1e-5);
+        assert_eq!(positions[1], RespFrame::NullArray(RespNullArray));
+
+        // NX leaves the existing members alone, CH counts the updated ones
+        let cmd: GeoAdd = command("geoadd Sicily nx 0 0 Palermo")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        let cmd: GeoAdd = command("geoadd Sicily xx ch 13.361389 38.115556 Palermo 1 1 Catania")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geoadd_invalid_arguments() -> Result<()> {
+        let backend = Backend::new();
+        let cmd: GeoAdd = command("geoadd points 1 1 a 181 10 b")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR invalid longitude,latitude pair 181.000000,10.000000".into())
+        );
+        // nothing is added
+        assert!(!backend.exists("points"));
+
+        let cmd: GeoAdd = command("geoadd points 1 86 a")?;
+        assert!(matches!(cmd.execute(&backend), RespFrame::Error(_)));
+
+        assert!(matches!(
+            command::<GeoAdd>("geoadd points 1 1 a 2 2"),
+            Err(CommandError::SyntaxError)
+        ));
+        assert!(matches!(
+            command::<GeoAdd>("geoadd points nx xx 1 1 a"),
+            Err(CommandError::SyntaxError)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geodist() -> Result<()> {
+        let backend = Backend::new();
+        sicily(&backend)?;
+
+        let cmd: GeoDist = command("geodist Sicily Palermo Catania")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(166274.1516));
+        let cmd: GeoDist = command("geodist Sicily Palermo Catania km")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(166.2742));
+        let cmd: GeoDist = command("geodist Sicily Palermo Catania mi")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(103.3182));
+        let cmd: GeoDist = command("geodist Sicily Palermo missing")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        let err = command::<GeoDist>("geodist Sicily Palermo Catania yd").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unsupported unit provided. please use M, KM, FT, MI"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geosearch_radius() -> Result<()> {
+        let backend = Backend::new();
+        sicily(&backend)?;
+
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 15 37 byradius 200 km asc")?;
+        assert_eq!(members(cmd.execute(&backend)), ["Catania", "Palermo"]);
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 15 37 byradius 200 km desc")?;
+        assert_eq!(members(cmd.execute(&backend)), ["Palermo", "Catania"]);
+
+        // COUNT without ANY returns the closest members
+        let cmd: GeoSearch =
+            command("geosearch Sicily frommember Palermo byradius 500 km count 2")?;
+        assert_eq!(members(cmd.execute(&backend)), ["Palermo", "edge1"]);
+        let cmd: GeoSearch =
+            command("geosearch Sicily fromlonlat 15 37 byradius 500 km count 1 any")?;
+        assert_eq!(members(cmd.execute(&backend)).len(), 1);
+
+        let cmd: GeoSearch = command(
+            "geosearch Sicily fromlonlat 15 37 byradius 200 km asc withcoord withdist withhash",
+        )?;
+        let RespFrame::Array(matches) = cmd.execute(&backend) else {
+            panic!("expected an array");
+        };
+        let RespFrame::Array(ref catania) = matches[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(catania[0], BulkString::from("Catania").into());
+        assert_eq!(catania[1], RespFrame::Double(56.4413));
+        assert_eq!(catania[2], RespFrame::Integer(3479447370796909));
+        assert!(matches!(catania[3], RespFrame::Array(ref v) if v.len() == 2));
+
+        let cmd: GeoSearch = command("geosearch missing fromlonlat 15 37 byradius 200 km")?;
+        assert_eq!(cmd.execute(&backend), RespArray::new(vec![]).into());
+        let cmd: GeoSearch = command("geosearch Sicily frommember missing byradius 200 km")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR could not decode requested zset member".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geosearch_box() -> Result<()> {
+        let backend = Backend::new();
+        sicily(&backend)?;
+
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 15 37 bybox 400 400 km asc")?;
+        assert_eq!(
+            members(cmd.execute(&backend)),
+            ["Catania", "Palermo", "edge2", "edge1"]
+        );
+        // the edges are in the corners of the box, out of the inscribed circle
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 15 37 byradius 200 km asc")?;
+        assert_eq!(members(cmd.execute(&backend)), ["Catania", "Palermo"]);
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 15 37 bybox 200 400 km asc")?;
+        assert_eq!(members(cmd.execute(&backend)), ["Catania"]);
+
+        let cmd: GeoSearch = command("geosearch Sicily fromlonlat 200 37 bybox 1 1 km")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("ERR invalid longitude,latitude pair 200.000000,37.000000".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geosearch_many_points() -> Result<()> {
+        let backend = Backend::new();
+        // a grid of points around the center, every 0.01 degree
+        let mut points = Vec::new();
+        for i in -50..=50 {
+            for j in -50..=50 {
+                let (lon, lat) = (2.35 + i as f64 * 0.01, 48.85 + j as f64 * 0.01);
+                points.push((lon, lat, format!("{},{}", i, j).into_bytes()));
+            }
+        }
+        backend.geoadd("grid".to_string(), points.clone(), ZAddOptions::default())?;
+
+        // the search finds exactly the points a full scan finds
+        for radius in [0.5, 3.0, 12.0, 40.0] {
+            let query = GeoQuery {
+                origin: GeoOrigin::LonLat(2.35, 48.85),
+                shape: GeoShape::Radius(radius),
+                unit: GeoUnit::Kilometers,
+                sort: None,
+                count: None,
+                any: false,
+            };
+            let found = backend.geosearch("grid", &query)?.len();
+            let expected = points
+                .iter()
+                .filter(|(lon, lat, _)| {
+                    let (dlon, dlat) = ((lon - 2.35).to_radians(), (lat - 48.85).to_radians());
+                    let a = (dlat / 2.0).sin().powi(2)
+                        + 48.85f64.to_radians().cos()
+                            * lat.to_radians().cos()
+                            * (dlon / 2.0).sin().powi(2);
+                    2.0 * 6372.797560856 * a.sqrt().asin() <= radius
+                })
+                .count();
+            // points right on the circle may fall either way after the geohash rounding
+            assert!(found.abs_diff(expected) <= 4, "{} {}", found, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geosearchstore() -> Result<()> {
+        let backend = Backend::new();
+        sicily(&backend)?;
+
+        let cmd: GeoSearchStore =
+            command("geosearchstore dest Sicily fromlonlat 15 37 byradius 200 km")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(
+            backend.zscore("dest", b"Palermo")?,
+            backend.zscore("Sicily", b"Palermo")?
+        );
+
+        let cmd: GeoSearchStore =
+            command("geosearchstore dest Sicily fromlonlat 15 37 byradius 200 km storedist")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        let distance = backend.zscore("dest", b"Catania")?.unwrap_or_default();
+        assert!((distance - 56.4413).abs() < 1e-3);
+
+        // an empty result deletes the destination
+        let cmd: GeoSearchStore =
+            command("geosearchstore dest Sicily fromlonlat 0 0 byradius 1 km")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("dest"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geosearch_invalid_arguments() {
+        let cases = [
+            (
+                "geosearch Sicily byradius 10 km asc count 1",
+                "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH",
+            ),
+            (
+                "geosearch Sicily frommember a fromlonlat 1 1 byradius 10 km",
+                "ERR exactly one of FROMMEMBER or FROMLONLAT can be specified for GEOSEARCH",
+            ),
+            (
+                "geosearch Sicily frommember a byradius 10 km bybox 1 1 km",
+                "ERR exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH",
+            ),
+            (
+                "geosearch Sicily frommember a asc count 1 withdist",
+                "ERR exactly one of BYRADIUS and BYBOX can be specified for GEOSEARCH",
+            ),
+            (
+                "geosearch Sicily frommember a byradius -1 km",
+                "ERR radius cannot be negative",
+            ),
+            (
+                "geosearch Sicily frommember a bybox 1 -1 km",
+                "ERR height or width cannot be negative",
+            ),
+            (
+                "geosearch Sicily frommember a byradius 1 km count 0",
+                "ERR COUNT must be > 0",
+            ),
+            (
+                "geosearch Sicily frommember a byradius 1 km storedist",
+                "ERR syntax error",
+            ),
+            (
+                "geosearchstore dest Sicily frommember a byradius 1 km withdist",
+                "ERR syntax error",
+            ),
+        ];
+        for (line, message) in cases {
+            let err = if line.starts_with("geosearchstore") {
+                command::<GeoSearchStore>(line).unwrap_err()
+            } else {
+                command::<GeoSearch>(line).unwrap_err()
+            };
+            assert_eq!(err.to_string(), message, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{Backend, BulkString, RespArray, RespFrame, RespMap};
+
+use super::{
+    extract_args, parse_integer, parse_option, validate_command_multi_args, CommandError,
+    CommandExecutor, Hello,
+};
+
+impl CommandExecutor for Hello {
+    // only checks the version, the connection switches to it in `execute_hello`
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        match self.resp3() {
+            Ok(resp3) => server_info(resp3.unwrap_or_default()),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl Hello {
+    /// Switch the connection to the protocol version, RESP3 if `resp3` is set, and reply with
+    /// the properties of the server.
+    pub fn execute_hello(self, resp3: &mut bool) -> RespFrame {
+        match self.resp3() {
+            Ok(v) => {
+                *resp3 = v.unwrap_or(*resp3);
+                server_info(*resp3)
+            }
+            Err(e) => e.into(),
+        }
+    }
+
+    // whether the requested version is RESP3, None if the version isn't given
+    fn resp3(&self) -> Result<Option<bool>, CommandError> {
+        match self.protover {
+            None => Ok(None),
+            Some(2) => Ok(Some(false)),
+            Some(3) => Ok(Some(true)),
+            Some(_) => Err(CommandError::NoProto),
+        }
+    }
+}
+
+// a map for RESP3 clients, a flat array of the fields and their values for RESP2 ones
+fn server_info(resp3: bool) -> RespFrame {
+    let fields: [(&str, RespFrame); 6] = [
+        ("server", BulkString::from("simple-redis").into()),
+        (
+            "version",
+            BulkString::from(env!("CARGO_PKG_VERSION")).into(),
+        ),
+        ("proto", RespFrame::Integer(if resp3 { 3 } else { 2 })),
+        ("mode", BulkString::from("standalone").into()),
+        ("role", BulkString::from("master").into()),
+        ("modules", RespArray::new([]).into()),
+    ];
+
+    if resp3 {
+        let mut map = RespMap::new();
+        for (field, value) in fields {
+            map.insert(field.to_string(), value);
+        }
+        map.into()
+    } else {
+        RespArray::new(
+            fields
+                .into_iter()
+                .flat_map(|(field, value)| [BulkString::from(field).into(), value])
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+impl TryFrom<RespArray> for Hello {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["hello"], 0)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let protover = match args.next() {
+            Some(arg) => Some(parse_integer(Some(arg)).map_err(|_| {
+                CommandError::InvalidArgument(
+                    "Protocol version is not an integer or out of range".to_string(),
+                )
+            })?),
+            None => None,
+        };
+        // AUTH and SETNAME are not supported
+        if let Some(arg) = args.next() {
+            return Err(CommandError::InvalidArgument(format!(
+                "Syntax error in HELLO option '{}'",
+                parse_option(&arg).unwrap_or_default()
+            )));
+        }
+
+        Ok(Hello { protover })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use crate::RespEncode;
+    use anyhow::Result;
+
+    #[test]
+    fn test_hello() -> Result<()> {
+        let mut resp3 = false;
+
+        let reply = command::<Hello>("hello 3")?.execute_hello(&mut resp3);
+        assert!(resp3);
+        let RespFrame::Map(map) = reply else {
+            panic!("unexpected reply {:?}", reply);
+        };
+        assert_eq!(map.get("proto"), Some(&RespFrame::Integer(3)));
+        assert_eq!(
+            map.get("server"),
+            Some(&BulkString::from("simple-redis").into())
+        );
+
+        // without a version the protocol doesn't change
+        let reply = command::<Hello>("hello")?.execute_hello(&mut resp3);
+        assert!(resp3);
+        assert!(matches!(reply, RespFrame::Map(_)));
+
+        let reply = command::<Hello>("hello 2")?.execute_hello(&mut resp3);
+        assert!(!resp3);
+        let RespFrame::Array(fields) = reply else {
+            panic!("unexpected reply {:?}", reply);
+        };
+        assert_eq!(fields.len(), 12);
+        assert_eq!(
+            fields[4..6],
+            [BulkString::from("proto").into(), RespFrame::Integer(2)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hello_errors() -> Result<()> {
+        let mut resp3 = false;
+        let reply = command::<Hello>("hello 4")?.execute_hello(&mut resp3);
+        assert_eq!(reply.encode(), b"-NOPROTO unsupported protocol version\r\n");
+        assert!(!resp3);
+
+        for (line, message) in [
+            (
+                "hello x",
+                "ERR Protocol version is not an integer or out of range",
+            ),
+            (
+                "hello 3 auth user pass",
+                "ERR Syntax error in HELLO option 'auth'",
+            ),
+        ] {
+            assert_eq!(command::<Hello>(line).unwrap_err().to_string(), message);
+        }
+
+        Ok(())
+    }
+}
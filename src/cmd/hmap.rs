@@ -1,15 +1,23 @@
-use crate::{Backend, BulkString, RespArray, RespFrame};
+use crate::{
+    now_ms, Backend, BackendError, BulkString, ExpireCondition, RespArray, RespFrame, RespNull,
+};
 
 use super::{
-    extract_args, validate_command, validate_command_multi_args, CommandError, CommandExecutor,
-    HGet, HGetAll, HMGet, HSet, RESP_OK,
+    extract_args, parse_float, parse_integer, parse_option, parse_string, random_count,
+    validate_command, validate_command_multi_args, CommandError, CommandExecutor, HDel, HExists,
+    HExpire, HGet, HGetAll, HIncrBy, HIncrByFloat, HKeys, HLen, HMGet, HPExpire, HPTtl, HPersist,
+    HRandField, HSet, HSetNx, HStrLen, HTtl, HVals,
 };
 
+// the greatest expiration time of a field, in unix milliseconds
+const MAX_FIELD_EXPIRE_TIME: u64 = (1 << 48) - 1;
+
 impl CommandExecutor for HGet {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.hget(&self.key, &self.field) {
-            Some(value) => value,
-            None => RespFrame::Null(crate::RespNull),
+            Ok(Some(value)) => value,
+            Ok(None) => RespFrame::Null(crate::RespNull),
+            Err(e) => e.into(),
         }
     }
 }
@@ -19,8 +27,9 @@ impl CommandExecutor for HMGet {
         let mut data = Vec::with_capacity(self.fields.len());
         for field in self.fields.iter() {
             match backend.hget(&self.key, field) {
-                Some(value) => data.push(value),
-                None => data.push(RespFrame::Null(crate::RespNull)),
+                Ok(Some(value)) => data.push(value),
+                Ok(None) => data.push(RespFrame::Null(crate::RespNull)),
+                Err(e) => return e.into(),
             }
         }
         RespArray::new(data).into()
@@ -29,15 +38,11 @@ impl CommandExecutor for HMGet {
 
 impl CommandExecutor for HGetAll {
     fn execute(self, backend: &Backend) -> RespFrame {
-        let hmap = backend.hmap.get(&self.key);
+        let hmap = backend.hgetall(&self.key);
 
         match hmap {
-            Some(hmap) => {
-                let mut data = Vec::with_capacity(hmap.len());
-                for v in hmap.iter() {
-                    let key = v.key().to_owned();
-                    data.push((key, v.value().clone()));
-                }
+            Ok(Some(hmap)) => {
+                let mut data = hmap.into_iter().collect::<Vec<_>>();
                 if self.sort {
                     data.sort_by(|a, b| a.0.cmp(&b.0));
                 }
@@ -48,15 +53,130 @@ impl CommandExecutor for HGetAll {
 
                 RespArray::new(ret).into()
             }
-            None => RespArray::new([]).into(),
+            Ok(None) => RespArray::new([]).into(),
+            Err(e) => e.into(),
         }
     }
 }
 
 impl CommandExecutor for HSet {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.hset(self.key, self.field, self.value);
-        RESP_OK.clone()
+        match backend.hset(self.key, self.fields) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hdel(&self.key, &self.fields) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HExists {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hexists(&self.key, &self.field) {
+            Ok(exists) => RespFrame::Integer(exists as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hlen(&self.key) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HKeys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hkeys(&self.key) {
+            Ok(fields) => RespArray::new(
+                fields
+                    .into_iter()
+                    .map(|f| BulkString::from(f).into())
+                    .collect::<Vec<RespFrame>>(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HVals {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hvals(&self.key) {
+            Ok(values) => RespArray::new(values).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HIncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hincrby(self.key, self.field, self.increment) {
+            Ok(value) => RespFrame::Integer(value),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HIncrByFloat {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hincrbyfloat(self.key, self.field, self.increment) {
+            Ok(value) => BulkString::from(value.to_string()).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HSetNx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hsetnx(self.key, self.field, self.value) {
+            Ok(set) => RespFrame::Integer(set as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HStrLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hstrlen(&self.key, &self.field) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for HRandField {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match (
+            backend.hrandfield(&self.key, self.count.unwrap_or(1)),
+            self.count,
+        ) {
+            (Ok(fields), Some(_)) => RespArray::new(
+                fields
+                    .into_iter()
+                    .flat_map(|(f, v)| match self.with_values {
+                        true => vec![BulkString::from(f).into(), v],
+                        false => vec![BulkString::from(f).into()],
+                    })
+                    .collect::<Vec<RespFrame>>(),
+            )
+            .into(),
+            (Ok(fields), None) => match fields.into_iter().next() {
+                Some((field, _)) => BulkString::from(field).into(),
+                None => RespFrame::Null(RespNull),
+            },
+            (Err(e), _) => e.into(),
+        }
     }
 }
 
@@ -125,34 +245,397 @@ impl TryFrom<RespArray> for HGetAll {
     }
 }
 
+impl CommandExecutor for HExpire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let milliseconds = self.seconds.checked_mul(1000).unwrap_or(i64::MAX);
+        hexpire(
+            backend,
+            &self.key,
+            milliseconds,
+            self.condition,
+            &self.fields,
+        )
+    }
+}
+
+impl CommandExecutor for HPExpire {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        hexpire(
+            backend,
+            &self.key,
+            self.milliseconds,
+            self.condition,
+            &self.fields,
+        )
+    }
+}
+
+impl CommandExecutor for HTtl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // round to the nearest second, the same way TTL does
+        result_codes(backend.hpttl(&self.key, &self.fields).map(|v| {
+            v.into_iter()
+                .map(|ttl| match ttl {
+                    ttl if ttl < 0 => ttl,
+                    ttl => (ttl + 500) / 1000,
+                })
+                .collect()
+        }))
+    }
+}
+
+impl CommandExecutor for HPTtl {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        result_codes(backend.hpttl(&self.key, &self.fields))
+    }
+}
+
+impl CommandExecutor for HPersist {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        result_codes(backend.hpersist(&self.key, &self.fields))
+    }
+}
+
+fn hexpire(
+    backend: &Backend,
+    key: &str,
+    milliseconds: i64,
+    condition: Option<ExpireCondition>,
+    fields: &[String],
+) -> RespFrame {
+    let at = now_ms().saturating_add(milliseconds as u64);
+    if at > MAX_FIELD_EXPIRE_TIME {
+        return CommandError::InvalidArgument(format!(
+            "invalid expire time, must be >= 0 and <= {}",
+            MAX_FIELD_EXPIRE_TIME
+        ))
+        .into();
+    }
+    result_codes(backend.hexpire_at(key, fields, at, condition))
+}
+
+// the per field result codes of the HEXPIRE family
+fn result_codes(ret: Result<Vec<i64>, BackendError>) -> RespFrame {
+    match ret {
+        Ok(codes) => RespArray::new(
+            codes
+                .into_iter()
+                .map(RespFrame::Integer)
+                .collect::<Vec<_>>(),
+        )
+        .into(),
+        Err(e) => e.into(),
+    }
+}
+
+// parse "key field"
+fn parse_key_field(value: RespArray, name: &'static str) -> Result<(String, String), CommandError> {
+    validate_command(&value, &[name], 2)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    Ok((parse_string(args.next())?, parse_string(args.next())?))
+}
+
+// parse "key"
+fn parse_key(value: RespArray, name: &'static str) -> Result<String, CommandError> {
+    validate_command(&value, &[name], 1)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    parse_string(args.next())
+}
+
 impl TryFrom<RespArray> for HSet {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["hset"], 3)?;
+        validate_command_multi_args(&value, &["hset"], 3)?;
+        // the command name, the key, then field value pairs
+        if !value.len().is_multiple_of(2) {
+            return Err(CommandError::WrongArity("hset".to_string()));
+        }
 
         let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let mut fields = Vec::new();
+        while let (Some(field), Some(value)) = (args.next(), args.next()) {
+            fields.push((parse_string(Some(field))?, value));
+        }
+        Ok(HSet { key, fields })
+    }
+}
+
+impl TryFrom<RespArray> for HDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["hdel"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let fields = args
+            .map(|f| parse_string(Some(f)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(HDel { key, fields })
+    }
+}
+
+impl TryFrom<RespArray> for HExists {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, field) = parse_key_field(value, "hexists")?;
+        Ok(HExists { key, field })
+    }
+}
+
+impl TryFrom<RespArray> for HLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(HLen {
+            key: parse_key(value, "hlen")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HKeys {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(HKeys {
+            key: parse_key(value, "hkeys")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HVals {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(HVals {
+            key: parse_key(value, "hvals")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HIncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hincrby"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(HIncrBy {
+            key: parse_string(args.next())?,
+            field: parse_string(args.next())?,
+            increment: parse_integer(args.next())?,
+        })
+    }
+}
 
+impl TryFrom<RespArray> for HIncrByFloat {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hincrbyfloat"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(HIncrByFloat {
+            key: parse_string(args.next())?,
+            field: parse_string(args.next())?,
+            increment: parse_float(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HSetNx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["hsetnx"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
         match (args.next(), args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(field)), Some(value)) => {
-                Ok(HSet {
-                    key: String::from_utf8(key.0)?,
-                    field: String::from_utf8(field.0)?,
-                    value,
-                })
-            }
-            _ => Err(CommandError::InvalidArgument(
-                "Invalid key, field or value".to_string(),
-            )),
+            (key, field, Some(value)) => Ok(HSetNx {
+                key: parse_string(key)?,
+                field: parse_string(field)?,
+                value,
+            }),
+            _ => Err(CommandError::WrongArity("hsetnx".to_string())),
         }
     }
 }
 
+impl TryFrom<RespArray> for HStrLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, field) = parse_key_field(value, "hstrlen")?;
+        Ok(HStrLen { key, field })
+    }
+}
+
+impl TryFrom<RespArray> for HRandField {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["hrandfield"], 1)?;
+        if value.len() > 4 {
+            return Err(CommandError::SyntaxError);
+        }
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let count = match args.next() {
+            Some(count) => Some(random_count(parse_integer(Some(count))?)?),
+            None => None,
+        };
+        let with_values = match args.next() {
+            Some(arg) if parse_option(&arg).as_deref() == Some("withvalues") => true,
+            Some(_) => return Err(CommandError::SyntaxError),
+            None => false,
+        };
+        Ok(HRandField {
+            key,
+            count,
+            with_values,
+        })
+    }
+}
+
+// parse "key time [NX | XX | GT | LT] FIELDS numfields field [field ...]", the time must not be
+// negative
+fn parse_hexpire(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, i64, Option<ExpireCondition>, Vec<String>), CommandError> {
+    validate_command_multi_args(&value, &[name], 5)?;
+
+    let mut args = extract_args(value, 1)?.into_iter().peekable();
+    let key = parse_string(args.next())?;
+    let time = parse_integer(args.next())?;
+    if time < 0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid expire time, must be >= 0 and <= {}",
+            MAX_FIELD_EXPIRE_TIME
+        )));
+    }
+
+    let condition = match args.peek().and_then(parse_option).as_deref() {
+        Some("nx") => Some(ExpireCondition::NotExists),
+        Some("xx") => Some(ExpireCondition::Exists),
+        Some("gt") => Some(ExpireCondition::Greater),
+        Some("lt") => Some(ExpireCondition::Less),
+        _ => None,
+    };
+    if condition.is_some() {
+        args.next();
+    }
+
+    let fields = parse_fields(args)?;
+    Ok((key, time, condition, fields))
+}
+
+// parse "key FIELDS numfields field [field ...]"
+fn parse_key_fields(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<String>), CommandError> {
+    validate_command_multi_args(&value, &[name], 4)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    Ok((key, parse_fields(args)?))
+}
+
+// parse "FIELDS numfields field [field ...]"
+fn parse_fields(mut args: impl Iterator<Item = RespFrame>) -> Result<Vec<String>, CommandError> {
+    if args.next().as_ref().and_then(parse_option).as_deref() != Some("fields") {
+        return Err(CommandError::InvalidArgument(
+            "Mandatory argument FIELDS is missing or not at the right position".to_string(),
+        ));
+    }
+
+    let n = match parse_integer(args.next())? {
+        n if n <= 0 => {
+            return Err(CommandError::InvalidArgument(
+                "Parameter `numFields` should be greater than 0".to_string(),
+            ))
+        }
+        n => n as usize,
+    };
+    let fields = args
+        .map(|f| parse_string(Some(f)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.len() != n {
+        return Err(CommandError::InvalidArgument(
+            "The `numfields` parameter must match the number of arguments".to_string(),
+        ));
+    }
+    Ok(fields)
+}
+
+impl TryFrom<RespArray> for HExpire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, seconds, condition, fields) = parse_hexpire(value, "hexpire")?;
+        Ok(HExpire {
+            key,
+            seconds,
+            condition,
+            fields,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HPExpire {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, milliseconds, condition, fields) = parse_hexpire(value, "hpexpire")?;
+        Ok(HPExpire {
+            key,
+            milliseconds,
+            condition,
+            fields,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for HTtl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, fields) = parse_key_fields(value, "httl")?;
+        Ok(HTtl { key, fields })
+    }
+}
+
+impl TryFrom<RespArray> for HPTtl {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, fields) = parse_key_fields(value, "hpttl")?;
+        Ok(HPTtl { key, fields })
+    }
+}
+
+impl TryFrom<RespArray> for HPersist {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, fields) = parse_key_fields(value, "hpersist")?;
+        Ok(HPersist { key, fields })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::RespDecode;
+    use crate::{ExpireCondition, Hash, RespDecode};
 
     use super::*;
+    use crate::cmd::command;
     use anyhow::Result;
     use bytes::BytesMut;
 
@@ -192,8 +675,10 @@ mod tests {
 
         let result: HSet = frame.try_into()?;
         assert_eq!(result.key, "map");
-        assert_eq!(result.field, "hello");
-        assert_eq!(result.value, RespFrame::BulkString(b"world".into()));
+        assert_eq!(
+            result.fields,
+            vec![("hello".to_string(), RespFrame::BulkString(b"world".into()))]
+        );
 
         Ok(())
     }
@@ -203,18 +688,23 @@ mod tests {
         let backend = crate::Backend::new();
         let cmd = HSet {
             key: "map".to_string(),
-            field: "hello".to_string(),
-            value: RespFrame::BulkString(b"world".into()),
+            fields: vec![("hello".to_string(), RespFrame::BulkString(b"world".into()))],
         };
         let result = cmd.execute(&backend);
-        assert_eq!(result, RESP_OK.clone());
+        assert_eq!(result, RespFrame::Integer(1));
 
         let cmd = HSet {
             key: "map".to_string(),
-            field: "hello1".to_string(),
-            value: RespFrame::BulkString(b"world1".into()),
+            fields: vec![
+                ("hello".to_string(), RespFrame::BulkString(b"world".into())),
+                (
+                    "hello1".to_string(),
+                    RespFrame::BulkString(b"world1".into()),
+                ),
+            ],
         };
-        cmd.execute(&backend);
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
 
         let cmd = HGet {
             key: "map".to_string(),
@@ -237,4 +727,258 @@ mod tests {
         assert_eq!(result, expected.into());
         Ok(())
     }
+
+    #[test]
+    fn test_hset_multiple_fields() -> Result<()> {
+        let result: HSet = command("hset map a 1 b 2")?;
+        assert_eq!(result.fields.len(), 2);
+
+        let result = command::<HSet>("hset map a 1 b");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'hset' command"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdel_removes_key_with_last_field() -> Result<()> {
+        let backend = Backend::new();
+        command::<HSet>("hset map a 1 b 2")?.execute(&backend);
+
+        let result = command::<HDel>("hdel map a missing")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        let result = command::<HLen>("hlen map")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+
+        let result = command::<HDel>("hdel map b")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        assert!(!backend.exists("map"));
+
+        let result = command::<HDel>("hdel map b")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexists_hstrlen_hkeys_hvals() -> Result<()> {
+        let backend = Backend::new();
+        command::<HSet>("hset map a hello")?.execute(&backend);
+
+        let result = command::<HExists>("hexists map a")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        let result = command::<HExists>("hexists map b")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+
+        let result = command::<HStrLen>("hstrlen map a")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(5));
+        let result = command::<HStrLen>("hstrlen map b")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+
+        let result = command::<HKeys>("hkeys map")?.execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("a").into()]).into()
+        );
+        let result = command::<HVals>("hvals map")?.execute(&backend);
+        assert_eq!(
+            result,
+            RespArray::new([BulkString::from("hello").into()]).into()
+        );
+        let result = command::<HKeys>("hkeys missing")?.execute(&backend);
+        assert_eq!(result, RespArray::new([]).into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrby() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<HIncrBy>("hincrby map a 5")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(5));
+        let result = command::<HIncrBy>("hincrby map a -7")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(-2));
+
+        command::<HSet>("hset map s abc")?.execute(&backend);
+        let result = command::<HIncrBy>("hincrby map s 1")?.execute(&backend);
+        assert_eq!(result, BackendError::HashValueNotInteger.into());
+
+        let cmd = format!("hincrby map a {}", i64::MIN);
+        let result = command::<HIncrBy>(&cmd)?.execute(&backend);
+        assert_eq!(result, BackendError::IncrementOverflow.into());
+        // the value is left untouched
+        let result = command::<HGet>("hget map a")?.execute(&backend);
+        assert_eq!(result, BulkString::from("-2").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hincrbyfloat() -> Result<()> {
+        let backend = Backend::new();
+        command::<HSet>("hset map a 10.5")?.execute(&backend);
+        let result = command::<HIncrByFloat>("hincrbyfloat map a 0.1")?.execute(&backend);
+        assert_eq!(result, BulkString::from("10.6").into());
+        let result = command::<HIncrByFloat>("hincrbyfloat map b -5")?.execute(&backend);
+        assert_eq!(result, BulkString::from("-5").into());
+
+        let result = command::<HIncrByFloat>("hincrbyfloat map a +inf")?.execute(&backend);
+        assert_eq!(result, BackendError::NotFinite.into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hsetnx() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<HSetNx>("hsetnx map a 1")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        let result = command::<HSetNx>("hsetnx map a 2")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+        let result = command::<HGet>("hget map a")?.execute(&backend);
+        assert_eq!(result, BulkString::from("1").into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hrandfield() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<HRandField>("hrandfield map")?.execute(&backend);
+        assert_eq!(result, RespFrame::Null(RespNull));
+
+        command::<HSet>("hset map a 1 b 2")?.execute(&backend);
+        let result = command::<HRandField>("hrandfield map 5")?.execute(&backend);
+        assert!(matches!(result, RespFrame::Array(ref v) if v.len() == 2));
+        let result = command::<HRandField>("hrandfield map -5")?.execute(&backend);
+        assert!(matches!(result, RespFrame::Array(ref v) if v.len() == 5));
+        let result = command::<HRandField>("hrandfield map 1 withvalues")?.execute(&backend);
+        assert!(matches!(result, RespFrame::Array(ref v) if v.len() == 2));
+
+        let result = command::<HRandField>("hrandfield map 1 foo");
+        assert!(matches!(result, Err(CommandError::SyntaxError)));
+
+        // a count too large to reply with is rejected before anything is allocated
+        for line in [
+            "hrandfield map -9223372036854775808",
+            "hrandfield map -1000001",
+        ] {
+            let err = command::<HRandField>(line).unwrap_err();
+            assert_eq!(err.to_string(), "ERR value is out of range", "{}", line);
+        }
+        let result = command::<HRandField>("hrandfield map 4611686018427387903")?.execute(&backend);
+        assert!(matches!(result, RespFrame::Array(ref v) if v.len() == 2));
+        Ok(())
+    }
+
+    fn codes(codes: &[i64]) -> RespFrame {
+        RespArray::new(
+            codes
+                .iter()
+                .map(|v| RespFrame::Integer(*v))
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_hexpire_from_resp_array() -> Result<()> {
+        let result: HExpire = command("hexpire map 10 GT FIELDS 2 a b")?;
+        assert_eq!(result.seconds, 10);
+        assert_eq!(result.condition, Some(ExpireCondition::Greater));
+        assert_eq!(result.fields, vec!["a".to_string(), "b".to_string()]);
+
+        let result = command::<HExpire>("hexpire map 10 FIELDS 2 a");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "ERR The `numfields` parameter must match the number of arguments"
+        );
+        let result = command::<HExpire>("hexpire map 10 NX 1 a");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "ERR Mandatory argument FIELDS is missing or not at the right position"
+        );
+        let result = command::<HExpire>("hexpire map -1 FIELDS 1 a");
+        assert!(matches!(result, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hexpire_httl_hpersist() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<HExpire>("hexpire map 10 FIELDS 1 a")?.execute(&backend);
+        assert_eq!(result, codes(&[-2]));
+
+        command::<HSet>("hset map a 1 b 2 c 3")?.execute(&backend);
+        let result = command::<HExpire>("hexpire map 10 FIELDS 2 a missing")?.execute(&backend);
+        assert_eq!(result, codes(&[1, -2]));
+        let result = command::<HTtl>("httl map FIELDS 3 a b missing")?.execute(&backend);
+        assert_eq!(result, codes(&[10, -1, -2]));
+
+        // NX and GT don't match, XX and LT do
+        let result = command::<HExpire>("hexpire map 20 NX FIELDS 2 a b")?.execute(&backend);
+        assert_eq!(result, codes(&[0, 1]));
+        let result = command::<HExpire>("hexpire map 30 GT FIELDS 1 a")?.execute(&backend);
+        assert_eq!(result, codes(&[1]));
+        let result = command::<HExpire>("hexpire map 5 LT FIELDS 2 a c")?.execute(&backend);
+        assert_eq!(result, codes(&[1, 1]));
+        let result = command::<HExpire>("hexpire map 5 GT FIELDS 1 c")?.execute(&backend);
+        assert_eq!(result, codes(&[0]));
+
+        let result = command::<HPersist>("hpersist map FIELDS 2 a missing")?.execute(&backend);
+        assert_eq!(result, codes(&[1, -2]));
+        let result = command::<HPersist>("hpersist map FIELDS 1 a")?.execute(&backend);
+        assert_eq!(result, codes(&[-1]));
+
+        // HINCRBY keeps the time to live, HSET discards it
+        command::<HIncrBy>("hincrby map b 1")?.execute(&backend);
+        command::<HSet>("hset map c 4")?.execute(&backend);
+        let result = command::<HTtl>("httl map FIELDS 2 b c")?.execute(&backend);
+        assert_eq!(result, codes(&[20, -1]));
+
+        // a time in the past deletes the field
+        let result = command::<HPExpire>("hpexpire map 0 FIELDS 1 a")?.execute(&backend);
+        assert_eq!(result, codes(&[2]));
+        let result = command::<HExists>("hexists map a")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_expired_fields_are_reclaimed() -> Result<()> {
+        let backend = Backend::new();
+        command::<HSet>("hset map a 1 b 2")?.execute(&backend);
+        command::<HSet>("hset other a 1")?.execute(&backend);
+        command::<HPExpire>("hpexpire map 1 FIELDS 1 a")?.execute(&backend);
+        command::<HPExpire>("hpexpire other 1 FIELDS 1 a")?.execute(&backend);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // lazily on access
+        let result = command::<HLen>("hlen map")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        let result = command::<HGet>("hget map a")?.execute(&backend);
+        assert_eq!(result, RespFrame::Null(RespNull));
+
+        // actively, the key goes with its last field
+        assert_eq!(backend.active_expire_cycle(), 1);
+        assert!(!backend.keyspace.contains_key("other"));
+        assert_eq!(backend.volatile_hashes.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fields_expire_in_order() {
+        let mut hash = Hash::new();
+        for field in ["a", "b", "c"] {
+            hash.insert(field.to_string(), RespFrame::BulkString(b"1".into()));
+        }
+        assert_eq!(hash.expire_at("a", 30, None, 0), 1);
+        assert_eq!(hash.expire_at("b", 10, None, 0), 1);
+        assert_eq!(hash.expire_at("c", 40, None, 0), 1);
+        assert_eq!(hash.expire_at("c", 20, None, 0), 1);
+        assert_eq!(hash.next_expire_time(), Some(10));
+
+        assert_eq!(hash.remove_expired(20), 2);
+        assert!(hash.contains_key("a"));
+        assert_eq!(hash.next_expire_time(), Some(30));
+        assert!(hash.persist("a"));
+        assert_eq!(hash.next_expire_time(), None);
+        assert_eq!(hash.remove_expired(u64::MAX), 0);
+    }
 }
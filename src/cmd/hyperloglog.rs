@@ -0,0 +1,258 @@
+use crate::{Backend, RespArray, RespFrame};
+
+use super::{
+    extract_args, parse_bytes, parse_string, validate_command_multi_args, CommandError,
+    CommandExecutor, PfAdd, PfCount, PfMerge, RESP_OK,
+};
+
+impl CommandExecutor for PfAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfadd(self.key, &self.elements) {
+            Ok(updated) => RespFrame::Integer(updated as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfcount(&self.keys) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for PfMerge {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.pfmerge(self.destination, &self.keys) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl TryFrom<RespArray> for PfAdd {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["pfadd"], 1)?;
+
+        // PFADD key [element ...]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let elements = args
+            .map(|e| parse_bytes(Some(e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfAdd { key, elements })
+    }
+}
+
+impl TryFrom<RespArray> for PfCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["pfcount"], 1)?;
+
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfCount { keys })
+    }
+}
+
+impl TryFrom<RespArray> for PfMerge {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["pfmerge"], 1)?;
+
+        // PFMERGE destkey [sourcekey ...]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let destination = parse_string(args.next())?;
+        let keys = args
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(PfMerge { destination, keys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+
+    fn elements(prefix: &str, n: usize) -> Vec<Vec<u8>> {
+        (0..n)
+            .map(|i| format!("{}:{}", prefix, i).into_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_pfadd_pfcount() -> Result<()> {
+        let backend = Backend::new();
+        let cmd: PfAdd = command("pfadd hll a b c d e f g")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: PfAdd = command("pfadd hll a b c")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        let cmd: PfCount = command("pfcount hll")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(7));
+        let cmd: PfCount = command("pfcount missing")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        // PFADD without elements only creates the key
+        let cmd: PfAdd = command("pfadd empty")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: PfAdd = command("pfadd empty")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pfadd_redis_representation() -> Result<()> {
+        let backend = Backend::new();
+        backend.pfadd("hll".to_string(), &[])?;
+        // the same bytes redis stores: a sparse header with a stale cache and one XZERO opcode
+        // for all the 16384 registers
+        assert_eq!(
+            backend.get("hll")?,
+            Some(b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x7f\xff".to_vec())
+        );
+
+        // PFCOUNT caches the cardinality in the header
+        assert_eq!(backend.pfcount(&["hll".to_string()])?, 0);
+        assert_eq!(
+            backend.get("hll")?,
+            Some(b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x7f\xff".to_vec())
+        );
+
+        backend.pfadd("hll".to_string(), &elements("e", 3))?;
+        assert_eq!(backend.get("hll")?.map(|v| v[15] & 0x80), Some(0x80));
+        assert_eq!(backend.pfcount(&["hll".to_string()])?, 3);
+        assert_eq!(backend.get("hll")?.map(|v| v[8]), Some(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pfadd_promotes_to_dense() -> Result<()> {
+        let backend = Backend::new();
+        backend.pfadd("hll".to_string(), &elements("e", 100))?;
+        assert_eq!(backend.get("hll")?.map(|v| v[4]), Some(1));
+
+        backend.pfadd("hll".to_string(), &elements("e", 5000))?;
+        let bytes = backend.get("hll")?.unwrap_or_default();
+        assert_eq!(bytes[4], 0);
+        assert_eq!(bytes.len(), 16 + 16384 * 6 / 8);
+
+        let count = backend.pfcount(&["hll".to_string()])?;
+        assert!(count.abs_diff(5000) < 100, "count {}", count);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pfcount_standard_error() -> Result<()> {
+        let backend = Backend::new();
+        for chunk in elements("visitor", 200_000).chunks(1000) {
+            backend.pfadd("hll".to_string(), chunk)?;
+        }
+        let count = backend.pfcount(&["hll".to_string()])? as f64;
+        // a few times the standard error of 0.81%
+        assert!(
+            (count - 200_000.0).abs() / 200_000.0 < 0.025,
+            "count {}",
+            count
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pfcount_multiple_keys() -> Result<()> {
+        let backend = Backend::new();
+        backend.pfadd("a".to_string(), &elements("e", 1000))?;
+        backend.pfadd("b".to_string(), &elements("e", 2000)[500..])?;
+
+        let cmd: PfCount = command("pfcount a b missing")?;
+        let RespFrame::Integer(count) = cmd.execute(&backend) else {
+            panic!("expected an integer");
+        };
+        assert!(count.abs_diff(2000) < 60, "count {}", count);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pfmerge() -> Result<()> {
+        let backend = Backend::new();
+        backend.pfadd("a".to_string(), &elements("e", 100))?;
+        backend.pfadd("b".to_string(), &elements("e", 200)[50..])?;
+        backend.pfadd("dest".to_string(), &elements("f", 10))?;
+
+        let cmd: PfMerge = command("pfmerge dest a b missing")?;
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        // the destination was one of the sources, and all of them were sparse
+        assert_eq!(backend.get("dest")?.map(|v| v[4]), Some(1));
+        let union = backend.pfcount(&["a".to_string(), "b".to_string(), "dest".to_string()])?;
+        assert_eq!(backend.pfcount(&["dest".to_string()])?, union);
+        assert!(union.abs_diff(210) < 5, "count {}", union);
+
+        // a dense source makes the result dense
+        backend.pfadd("c".to_string(), &elements("g", 5000))?;
+        backend.pfmerge("other".to_string(), &["a".to_string(), "c".to_string()])?;
+        assert_eq!(backend.get("other")?.map(|v| v[4]), Some(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pf_invalid_values() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("s".to_string(), b"hello".to_vec());
+        let cmd: PfAdd = command("pfadd s a")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("WRONGTYPE Key is not a valid HyperLogLog string value.".into())
+        );
+        let cmd: PfCount = command("pfcount s")?;
+        assert!(matches!(cmd.execute(&backend), RespFrame::Error(_)));
+
+        // a dense header with a wrong length
+        backend.set(
+            "d".to_string(),
+            b"HYLL\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80".to_vec(),
+        );
+        let cmd: PfCount = command("pfcount d")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("WRONGTYPE Key is not a valid HyperLogLog string value.".into())
+        );
+
+        // sparse opcodes which don't cover all the registers
+        backend.set(
+            "c".to_string(),
+            b"HYLL\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80\x7f\xfe".to_vec(),
+        );
+        let cmd: PfCount = command("pfcount c")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error("INVALIDOBJ Corrupted HLL object detected".into())
+        );
+
+        backend.sadd("set".to_string(), vec![b"a".to_vec()])?;
+        let cmd: PfMerge = command("pfmerge dest set")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".into()
+            )
+        );
+
+        Ok(())
+    }
+}
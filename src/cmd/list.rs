@@ -0,0 +1,615 @@
+use crate::{
+    Backend, BulkString, InsertPosition, ListEnd, RespArray, RespFrame, RespNull, RespNullArray,
+};
+
+use super::{
+    extract_args, parse_bytes, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, LIndex, LInsert, LLen, LMove, LPop,
+    LPush, LRange, LRem, LSet, LTrim, RPop, RPush, RESP_OK,
+};
+
+impl CommandExecutor for LPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.list_push(self.key, ListEnd::Left, self.elements) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for RPush {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.list_push(self.key, ListEnd::Right, self.elements) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        list_pop(backend, &self.key, ListEnd::Left, self.count)
+    }
+}
+
+impl CommandExecutor for RPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        list_pop(backend, &self.key, ListEnd::Right, self.count)
+    }
+}
+
+impl CommandExecutor for LLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.llen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lrange(&self.key, self.start, self.stop) {
+            Ok(elements) => elements_to_frame(elements),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LIndex {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lindex(&self.key, self.index) {
+            Ok(Some(element)) => BulkString::new(element).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lset(&self.key, self.index, self.element) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lrem(&self.key, self.count, &self.element) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LTrim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.ltrim(&self.key, self.start, self.stop) {
+            Ok(()) => RESP_OK.clone(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LInsert {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.linsert(&self.key, self.position, &self.pivot, self.element) {
+            Ok(len) => RespFrame::Integer(len),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for LMove {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.lmove(&self.source, self.destination, self.from, self.to) {
+            Ok(Some(element)) => BulkString::new(element).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// without a count a single element is replied, with a count an array even if it is empty
+fn list_pop(backend: &Backend, key: &str, end: ListEnd, count: Option<usize>) -> RespFrame {
+    match (backend.list_pop(key, end, count.unwrap_or(1)), count) {
+        (Ok(Some(elements)), Some(_)) => elements_to_frame(elements),
+        (Ok(None), Some(_)) => RespFrame::NullArray(RespNullArray),
+        (Ok(elements), None) => match elements.and_then(|v| v.into_iter().next()) {
+            Some(element) => BulkString::new(element).into(),
+            None => RespFrame::Null(RespNull),
+        },
+        (Err(e), _) => e.into(),
+    }
+}
+
+pub(super) fn elements_to_frame(elements: Vec<Vec<u8>>) -> RespFrame {
+    RespArray::new(
+        elements
+            .into_iter()
+            .map(|e| BulkString::new(e).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into()
+}
+
+// parse "key element [element ...]"
+fn parse_push(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<Vec<u8>>), CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let elements = args
+        .map(|e| parse_bytes(Some(e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((key, elements))
+}
+
+// parse "key [count]", the count must not be negative
+fn parse_pop(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Option<usize>), CommandError> {
+    validate_command_multi_args(&value, &[name], 1)?;
+    if value.len() > 3 {
+        return Err(CommandError::SyntaxError);
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let count = match args.next() {
+        Some(count) => match parse_integer(Some(count))? {
+            count if count < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "value is out of range, must be positive".to_string(),
+                ))
+            }
+            count => Some(count as usize),
+        },
+        None => None,
+    };
+    Ok((key, count))
+}
+
+// parse "key start stop"
+fn parse_range(value: RespArray, name: &'static str) -> Result<(String, i64, i64), CommandError> {
+    validate_command(&value, &[name], 3)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    Ok((
+        parse_string(args.next())?,
+        parse_integer(args.next())?,
+        parse_integer(args.next())?,
+    ))
+}
+
+pub(super) fn parse_list_end(arg: Option<RespFrame>) -> Result<ListEnd, CommandError> {
+    match arg.as_ref().and_then(parse_option).as_deref() {
+        Some("left") => Ok(ListEnd::Left),
+        Some("right") => Ok(ListEnd::Right),
+        _ => Err(CommandError::SyntaxError),
+    }
+}
+
+impl TryFrom<RespArray> for LPush {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, elements) = parse_push(value, "lpush")?;
+        Ok(LPush { key, elements })
+    }
+}
+
+impl TryFrom<RespArray> for RPush {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, elements) = parse_push(value, "rpush")?;
+        Ok(RPush { key, elements })
+    }
+}
+
+impl TryFrom<RespArray> for LPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "lpop")?;
+        Ok(LPop { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for RPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "rpop")?;
+        Ok(RPop { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for LLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["llen"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(LLen {
+            key: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, start, stop) = parse_range(value, "lrange")?;
+        Ok(LRange { key, start, stop })
+    }
+}
+
+impl TryFrom<RespArray> for LTrim {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, start, stop) = parse_range(value, "ltrim")?;
+        Ok(LTrim { key, start, stop })
+    }
+}
+
+impl TryFrom<RespArray> for LIndex {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lindex"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(LIndex {
+            key: parse_string(args.next())?,
+            index: parse_integer(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LSet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lset"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(LSet {
+            key: parse_string(args.next())?,
+            index: parse_integer(args.next())?,
+            element: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LRem {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lrem"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(LRem {
+            key: parse_string(args.next())?,
+            count: parse_integer(args.next())?,
+            element: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LInsert {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["linsert"], 4)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let position = match args.next().as_ref().and_then(parse_option).as_deref() {
+            Some("before") => InsertPosition::Before,
+            Some("after") => InsertPosition::After,
+            _ => return Err(CommandError::SyntaxError),
+        };
+        Ok(LInsert {
+            key,
+            position,
+            pivot: parse_bytes(args.next())?,
+            element: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for LMove {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["lmove"], 4)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(LMove {
+            source: parse_string(args.next())?,
+            destination: parse_string(args.next())?,
+            from: parse_list_end(args.next())?,
+            to: parse_list_end(args.next())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BackendError, RespDecode};
+
+    use super::*;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn rpush(backend: &Backend, key: &str, elements: &[&str]) -> RespFrame {
+        RPush {
+            key: key.to_string(),
+            elements: elements.iter().map(|e| e.as_bytes().to_vec()).collect(),
+        }
+        .execute(backend)
+    }
+
+    fn lrange(backend: &Backend, key: &str, start: i64, stop: i64) -> RespFrame {
+        LRange {
+            key: key.to_string(),
+            start,
+            stop,
+        }
+        .execute(backend)
+    }
+
+    fn array(elements: &[&str]) -> RespFrame {
+        elements_to_frame(elements.iter().map(|e| e.as_bytes().to_vec()).collect())
+    }
+
+    #[test]
+    fn test_lpush_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$5\r\nlpush\r\n$4\r\nlist\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: LPush = frame.try_into()?;
+        assert_eq!(result.key, "list");
+        assert_eq!(result.elements, vec![b"a".to_vec(), b"b".to_vec()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lmove_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nlmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n$4\r\nLEFT\r\n$5\r\nright\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: LMove = frame.try_into()?;
+        assert_eq!(result.source, "src");
+        assert_eq!(result.destination, "dst");
+        assert_eq!(result.from, ListEnd::Left);
+        assert_eq!(result.to, ListEnd::Right);
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$5\r\nlmove\r\n$3\r\nsrc\r\n$3\r\ndst\r\n$4\r\nLEFT\r\n$2\r\nup\r\n",
+        );
+        let frame = RespArray::decode(&mut buf)?;
+        let err = LMove::try_from(frame).unwrap_err();
+        assert_eq!(err.to_string(), "ERR syntax error");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_pop_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        let cmd = LPush {
+            key: "list".to_string(),
+            elements: vec![b"b".to_vec(), b"a".to_vec()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(rpush(&backend, "list", &["c", "d"]), RespFrame::Integer(4));
+        assert_eq!(
+            lrange(&backend, "list", 0, -1),
+            array(&["a", "b", "c", "d"])
+        );
+
+        let cmd = LPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("a").into());
+
+        let cmd = RPop {
+            key: "list".to_string(),
+            count: Some(5),
+        };
+        assert_eq!(cmd.execute(&backend), array(&["d", "c", "b"]));
+        assert!(!backend.exists("list"));
+
+        let cmd = RPop {
+            key: "list".to_string(),
+            count: None,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+        let cmd = LPop {
+            key: "list".to_string(),
+            count: Some(1),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::NullArray(RespNullArray));
+
+        backend.set("string".to_string(), b"hello".to_vec());
+        assert_eq!(
+            rpush(&backend, "string", &["a"]),
+            BackendError::WrongType.into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lrange_lindex_lset_commands() -> Result<()> {
+        let backend = Backend::new();
+        rpush(&backend, "list", &["a", "b", "c"]);
+
+        assert_eq!(lrange(&backend, "list", -100, 100), array(&["a", "b", "c"]));
+        assert_eq!(lrange(&backend, "list", -2, -1), array(&["b", "c"]));
+        assert_eq!(lrange(&backend, "list", 2, 1), array(&[]));
+        assert_eq!(lrange(&backend, "list", 3, 5), array(&[]));
+        assert_eq!(lrange(&backend, "missing", 0, -1), array(&[]));
+
+        let cmd = LIndex {
+            key: "list".to_string(),
+            index: -1,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("c").into());
+        let cmd = LIndex {
+            key: "list".to_string(),
+            index: 3,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        let cmd = LSet {
+            key: "list".to_string(),
+            index: 1,
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(lrange(&backend, "list", 0, -1), array(&["a", "x", "c"]));
+
+        let cmd = LSet {
+            key: "list".to_string(),
+            index: 3,
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::IndexOutOfRange.into());
+        let cmd = LSet {
+            key: "missing".to_string(),
+            index: 0,
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::NoSuchKey.into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lrem_ltrim_linsert_commands() -> Result<()> {
+        let backend = Backend::new();
+        rpush(&backend, "list", &["a", "b", "a", "c", "a"]);
+
+        let cmd = LRem {
+            key: "list".to_string(),
+            count: -2,
+            element: b"a".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(lrange(&backend, "list", 0, -1), array(&["a", "b", "c"]));
+
+        let cmd = LInsert {
+            key: "list".to_string(),
+            position: InsertPosition::After,
+            pivot: b"b".to_vec(),
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(4));
+        let cmd = LInsert {
+            key: "list".to_string(),
+            position: InsertPosition::Before,
+            pivot: b"y".to_vec(),
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(-1));
+        let cmd = LInsert {
+            key: "missing".to_string(),
+            position: InsertPosition::Before,
+            pivot: b"a".to_vec(),
+            element: b"x".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(
+            lrange(&backend, "list", 0, -1),
+            array(&["a", "b", "x", "c"])
+        );
+
+        let cmd = LTrim {
+            key: "list".to_string(),
+            start: 1,
+            stop: -2,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert_eq!(lrange(&backend, "list", 0, -1), array(&["b", "x"]));
+
+        // an empty range deletes the key
+        let cmd = LTrim {
+            key: "list".to_string(),
+            start: 5,
+            stop: 10,
+        };
+        assert_eq!(cmd.execute(&backend), RESP_OK.clone());
+        assert!(!backend.exists("list"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lmove_command() -> Result<()> {
+        let backend = Backend::new();
+        rpush(&backend, "src", &["a", "b", "c"]);
+
+        let cmd = LMove {
+            source: "src".to_string(),
+            destination: "dst".to_string(),
+            from: ListEnd::Right,
+            to: ListEnd::Left,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("c").into());
+        assert_eq!(lrange(&backend, "dst", 0, -1), array(&["c"]));
+
+        // rotate the list in place
+        let cmd = LMove {
+            source: "src".to_string(),
+            destination: "src".to_string(),
+            from: ListEnd::Left,
+            to: ListEnd::Right,
+        };
+        assert_eq!(cmd.execute(&backend), BulkString::from("a").into());
+        assert_eq!(lrange(&backend, "src", 0, -1), array(&["b", "a"]));
+
+        backend.set("string".to_string(), b"hello".to_vec());
+        let cmd = LMove {
+            source: "src".to_string(),
+            destination: "string".to_string(),
+            from: ListEnd::Left,
+            to: ListEnd::Right,
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::WrongType.into());
+        // nothing is popped from the source on error
+        assert_eq!(lrange(&backend, "src", 0, -1), array(&["b", "a"]));
+
+        let cmd = LMove {
+            source: "missing".to_string(),
+            destination: "dst".to_string(),
+            from: ListEnd::Left,
+            to: ListEnd::Right,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        Ok(())
+    }
+}
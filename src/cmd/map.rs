@@ -1,24 +1,225 @@
-use crate::{Backend, RespArray, RespFrame};
+use crate::{
+    now_ms, Backend, BackendError, BulkString, RespArray, RespFrame, RespNull, SetCondition,
+    SetExpiration,
+};
 
-use super::{extract_args, validate_command, CommandError, CommandExecutor, Get, Set, RESP_OK};
+use super::{
+    extract_args, parse_bytes, parse_float, parse_integer, parse_option, parse_string,
+    validate_command, validate_command_multi_args, Append, CommandError, CommandExecutor, Decr,
+    DecrBy, Get, GetDel, GetEx, GetRange, Incr, IncrBy, IncrByFloat, MGet, MSet, MSetNx, Set,
+    SetExpire, SetRange, StrLen, RESP_OK,
+};
 
 impl CommandExecutor for Get {
     fn execute(self, backend: &Backend) -> RespFrame {
-        match backend.get(&self.key) {
-            Some(value) => value,
-            None => RespFrame::Null(crate::RespNull),
-        }
+        bulk_or_null(backend.get(&self.key))
     }
 }
 
 impl CommandExecutor for Set {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.set(self.key, self.value);
+        let expiration = self.expire.map(expiration);
+        let ret =
+            backend.set_with_options(self.key, self.value, self.condition, expiration, self.get);
+
+        match (self.get, ret) {
+            (true, Ok((_, old))) => bulk_or_null(Ok(old)),
+            (false, Ok((true, _))) => RESP_OK.clone(),
+            (false, Ok((false, _))) => RespFrame::Null(RespNull),
+            (_, Err(e)) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for Incr {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, self.key, 1)
+    }
+}
+
+impl CommandExecutor for Decr {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, self.key, -1)
+    }
+}
+
+impl CommandExecutor for IncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        incr_by(backend, self.key, self.increment)
+    }
+}
 
+impl CommandExecutor for DecrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        // the parser rejects i64::MIN, so this can't overflow
+        incr_by(backend, self.key, -self.decrement)
+    }
+}
+
+impl CommandExecutor for IncrByFloat {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.incr_by_float(self.key, self.increment) {
+            Ok(value) => BulkString::new(value).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for Append {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.append(self.key, &self.value) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for StrLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.strlen(&self.key) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GetRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.getrange(&self.key, self.start, self.end) {
+            Ok(value) => BulkString::new(value).into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SetRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.setrange(self.key, self.offset, &self.value) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for GetDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        bulk_or_null(backend.getdel(&self.key))
+    }
+}
+
+impl CommandExecutor for GetEx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let at = self.expire.map(expiration).and_then(|v| match v {
+            SetExpiration::At(at) => Some(at),
+            SetExpiration::Keep => None,
+        });
+        bulk_or_null(backend.getex(&self.key, at, self.persist))
+    }
+}
+
+impl CommandExecutor for MGet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespArray::new(
+            backend
+                .mget(&self.keys)
+                .into_iter()
+                .map(|v| bulk_or_null(Ok(v)))
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    }
+}
+
+impl CommandExecutor for MSet {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        backend.mset(self.pairs);
         RESP_OK.clone()
     }
 }
 
+impl CommandExecutor for MSetNx {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.msetnx(self.pairs) as i64)
+    }
+}
+
+// resolve the expiration options shared by SET and GETEX to an absolute time
+fn expiration(expire: SetExpire) -> SetExpiration {
+    match expire {
+        SetExpire::Ex(seconds) => {
+            SetExpiration::At(now_ms().saturating_add(seconds.saturating_mul(1000)))
+        }
+        SetExpire::Px(milliseconds) => SetExpiration::At(now_ms().saturating_add(milliseconds)),
+        SetExpire::ExAt(seconds) => SetExpiration::At(seconds.saturating_mul(1000)),
+        SetExpire::PxAt(milliseconds) => SetExpiration::At(milliseconds),
+        SetExpire::KeepTtl => SetExpiration::Keep,
+    }
+}
+
+fn incr_by(backend: &Backend, key: String, increment: i64) -> RespFrame {
+    match backend.incr_by(key, increment) {
+        Ok(value) => RespFrame::Integer(value),
+        Err(e) => e.into(),
+    }
+}
+
+fn bulk_or_null(ret: Result<Option<Vec<u8>>, BackendError>) -> RespFrame {
+    match ret {
+        Ok(Some(value)) => BulkString::new(value).into(),
+        Ok(None) => RespFrame::Null(RespNull),
+        Err(e) => e.into(),
+    }
+}
+
+// parse "key"
+fn parse_key(value: RespArray, name: &'static str) -> Result<String, CommandError> {
+    validate_command(&value, &[name], 1)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    parse_string(args.next())
+}
+
+// parse "key value [key value ...]"
+fn parse_pairs(
+    value: RespArray,
+    name: &'static str,
+) -> Result<Vec<(String, Vec<u8>)>, CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+    // the command name, then key value pairs
+    if value.len().is_multiple_of(2) {
+        return Err(CommandError::WrongArity(name.to_string()));
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let mut pairs = Vec::new();
+    while let (Some(key), Some(value)) = (args.next(), args.next()) {
+        pairs.push((parse_string(Some(key))?, parse_bytes(Some(value))?));
+    }
+    Ok(pairs)
+}
+
+// parse the value of EX, PX, EXAT or PXAT, which must be positive
+fn parse_expire(
+    option: &str,
+    arg: Option<RespFrame>,
+    name: &str,
+) -> Result<SetExpire, CommandError> {
+    let v = parse_integer(arg)?;
+    if v <= 0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid expire time in '{}' command",
+            name
+        )));
+    }
+    let v = v as u64;
+    Ok(match option {
+        "ex" => SetExpire::Ex(v),
+        "px" => SetExpire::Px(v),
+        "exat" => SetExpire::ExAt(v),
+        _ => SetExpire::PxAt(v),
+    })
+}
+
 impl TryFrom<RespArray> for Get {
     type Error = CommandError;
 
@@ -39,26 +240,246 @@ impl TryFrom<RespArray> for Set {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["set"], 2)?;
+        validate_command_multi_args(&value, &["set"], 2)?;
 
         let mut args = extract_args(value, 1)?.into_iter();
-        match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(value)) => Ok(Set {
-                key: String::from_utf8(key.0)?,
-                value,
-            }),
-            _ => Err(CommandError::InvalidArgument(
-                "Invalid key or value".to_string(),
-            )),
+        let key = parse_string(args.next())?;
+        let value = parse_bytes(args.next())?;
+
+        // SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+        //   EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+        let (mut expire, mut condition, mut get) = (None, None, false);
+        while let Some(arg) = args.next() {
+            let option = parse_option(&arg);
+            match option.as_deref() {
+                Some("nx") if condition.is_none() => condition = Some(SetCondition::NotExists),
+                Some("xx") if condition.is_none() => condition = Some(SetCondition::Exists),
+                Some("get") if !get => get = true,
+                Some("keepttl") if expire.is_none() => expire = Some(SetExpire::KeepTtl),
+                Some(option @ ("ex" | "px" | "exat" | "pxat")) if expire.is_none() => {
+                    expire = Some(parse_expire(option, args.next(), "set")?);
+                }
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(Set {
+            key,
+            value,
+            expire,
+            condition,
+            get,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Incr {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Incr {
+            key: parse_key(value, "incr")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Decr {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(Decr {
+            key: parse_key(value, "decr")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for IncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["incrby"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(IncrBy {
+            key: parse_string(args.next())?,
+            increment: parse_integer(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for DecrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["decrby"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let decrement = parse_integer(args.next())?;
+        if decrement == i64::MIN {
+            return Err(CommandError::InvalidArgument(
+                "decrement would overflow".to_string(),
+            ));
+        }
+        Ok(DecrBy { key, decrement })
+    }
+}
+
+impl TryFrom<RespArray> for IncrByFloat {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["incrbyfloat"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(IncrByFloat {
+            key: parse_string(args.next())?,
+            increment: parse_float(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Append {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["append"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(Append {
+            key: parse_string(args.next())?,
+            value: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for StrLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(StrLen {
+            key: parse_key(value, "strlen")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GetRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["getrange"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(GetRange {
+            key: parse_string(args.next())?,
+            start: parse_integer(args.next())?,
+            end: parse_integer(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SetRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["setrange"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let offset = match parse_integer(args.next())? {
+            offset if offset < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "offset is out of range".to_string(),
+                ))
+            }
+            offset => offset as usize,
+        };
+        Ok(SetRange {
+            key,
+            offset,
+            value: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GetDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(GetDel {
+            key: parse_key(value, "getdel")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for GetEx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["getex"], 1)?;
+
+        // GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+        //   PXAT unix-time-milliseconds | PERSIST]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let (mut expire, mut persist) = (None, false);
+        while let Some(arg) = args.next() {
+            match parse_option(&arg).as_deref() {
+                Some("persist") if expire.is_none() && !persist => persist = true,
+                Some(option @ ("ex" | "px" | "exat" | "pxat")) if expire.is_none() && !persist => {
+                    expire = Some(parse_expire(option, args.next(), "getex")?);
+                }
+                _ => return Err(CommandError::SyntaxError),
+            }
         }
+        Ok(GetEx {
+            key,
+            expire,
+            persist,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for MGet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["mget"], 1)?;
+
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MGet { keys })
+    }
+}
+
+impl TryFrom<RespArray> for MSet {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(MSet {
+            pairs: parse_pairs(value, "mset")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for MSetNx {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(MSetNx {
+            pairs: parse_pairs(value, "msetnx")?,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::RespDecode;
+    use crate::{RespDecode, StringValue};
 
     use super::*;
+    use crate::cmd::command;
     use anyhow::Result;
     use bytes::BytesMut;
 
@@ -86,7 +507,35 @@ mod tests {
         let result: Set = frame.try_into()?;
 
         assert_eq!(result.key, "hello");
-        assert_eq!(result.value, RespFrame::BulkString(b"world".into()));
+        assert_eq!(result.value, b"world".to_vec());
+        assert_eq!(result.expire, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_with_expire_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nPX\r\n$3\r\n100\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Set = frame.try_into()?;
+
+        assert_eq!(result.key, "hello");
+        assert_eq!(result.expire, Some(SetExpire::Px(100)));
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(
+            b"*5\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n$2\r\nex\r\n$2\r\n-1\r\n",
+        );
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Result<Set, _> = frame.try_into();
+        assert!(result.is_err());
 
         Ok(())
     }
@@ -96,7 +545,10 @@ mod tests {
         let backend = Backend::new();
         let cmd = Set {
             key: "hello".to_string(),
-            value: RespFrame::BulkString(b"world".into()),
+            value: b"world".to_vec(),
+            expire: None,
+            condition: None,
+            get: false,
         };
         let result = cmd.execute(&backend);
         assert_eq!(result, RESP_OK.clone());
@@ -109,4 +561,303 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_set_with_expire_command() -> Result<()> {
+        let backend = Backend::new();
+        let cmd = Set {
+            key: "hello".to_string(),
+            value: b"world".to_vec(),
+            expire: Some(SetExpire::Px(1)),
+            condition: None,
+            get: false,
+        };
+        cmd.execute(&backend);
+        assert!(backend.pttl("hello") >= 0);
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        let cmd = Get {
+            key: "hello".to_string(),
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::Null(crate::RespNull));
+        assert_eq!(backend.pttl("hello"), -2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_with_options_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*7\r\n$3\r\nset\r\n$4\r\nlock\r\n$1\r\n1\r\n$2\r\nNX\r\n$3\r\nGET\r\n$4\r\nPXAT\r\n$3\r\n100\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: Set = frame.try_into()?;
+        assert_eq!(result.condition, Some(SetCondition::NotExists));
+        assert!(result.get);
+        assert_eq!(result.expire, Some(SetExpire::PxAt(100)));
+
+        // NX and XX are mutually exclusive, so are the expiration options
+        for args in [
+            b"*5\r\n$3\r\nset\r\n$4\r\nlock\r\n$1\r\n1\r\n$2\r\nnx\r\n$2\r\nxx\r\n".as_ref(),
+            b"*6\r\n$3\r\nset\r\n$4\r\nlock\r\n$1\r\n1\r\n$7\r\nkeepttl\r\n$2\r\nex\r\n$1\r\n1\r\n"
+                .as_ref(),
+            b"*4\r\n$3\r\nset\r\n$4\r\nlock\r\n$1\r\n1\r\n$2\r\nex\r\n".as_ref(),
+        ] {
+            let mut buf = BytesMut::from(args);
+            let frame = RespArray::decode(&mut buf)?;
+            let result: Result<Set, _> = frame.try_into();
+            assert!(result.is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_conditional_commands() -> Result<()> {
+        let backend = Backend::new();
+        let set = |condition, get, expire| Set {
+            key: "lock".to_string(),
+            value: b"owner1".to_vec(),
+            expire,
+            condition,
+            get,
+        };
+
+        let result = set(Some(SetCondition::Exists), false, None).execute(&backend);
+        assert_eq!(result, RespFrame::Null(RespNull));
+        assert_eq!(backend.get("lock")?, None);
+
+        let result = set(
+            Some(SetCondition::NotExists),
+            false,
+            Some(SetExpire::Px(30000)),
+        )
+        .execute(&backend);
+        assert_eq!(result, RESP_OK.clone());
+        assert!(backend.pttl("lock") > 0);
+
+        let result = set(Some(SetCondition::NotExists), false, None).execute(&backend);
+        assert_eq!(result, RespFrame::Null(RespNull));
+
+        // GET returns the old value, KEEPTTL retains the timeout
+        let cmd = Set {
+            key: "lock".to_string(),
+            value: b"owner2".to_vec(),
+            expire: Some(SetExpire::KeepTtl),
+            condition: Some(SetCondition::Exists),
+            get: true,
+        };
+        let result = cmd.execute(&backend);
+        assert_eq!(result, RespFrame::BulkString(b"owner1".into()));
+        assert_eq!(backend.get("lock")?, Some(b"owner2".to_vec()));
+        assert!(backend.pttl("lock") > 0);
+
+        // a plain SET discards the timeout
+        let result = set(None, true, None).execute(&backend);
+        assert_eq!(result, RespFrame::BulkString(b"owner2".into()));
+        assert_eq!(backend.pttl("lock"), -1);
+
+        // an absolute time in the past expires the key right away
+        set(None, false, Some(SetExpire::PxAt(1))).execute(&backend);
+        assert_eq!(backend.get("lock")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_integer_encoding() {
+        assert_eq!(StringValue::from(b"42".to_vec()), StringValue::Int(42));
+        assert_eq!(StringValue::from(b"-7".to_vec()), StringValue::Int(-7));
+        // values which don't read back byte for byte stay raw
+        for raw in ["007", "+1", "-0", " 1", "9223372036854775808", ""] {
+            assert_eq!(
+                StringValue::from(raw.as_bytes().to_vec()),
+                StringValue::Raw(raw.as_bytes().to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn test_incr_decr_commands() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<Incr>("incr counter")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+        let result = command::<IncrBy>("incrby counter 10")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(11));
+        let result = command::<DecrBy>("decrby counter 20")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(-9));
+        let result = command::<Decr>("decr counter")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(-10));
+        assert!(matches!(
+            backend.read("counter", |v| Ok(v.as_string()?.clone()))?,
+            Some(StringValue::Int(-10))
+        ));
+
+        command::<Set>("set counter 9223372036854775807")?.execute(&backend);
+        let result = command::<Incr>("incr counter")?.execute(&backend);
+        assert_eq!(result, BackendError::IncrementOverflow.into());
+
+        command::<Set>("set counter 007")?.execute(&backend);
+        let result = command::<Incr>("incr counter")?.execute(&backend);
+        assert_eq!(result, BackendError::NotInteger.into());
+
+        let result = command::<DecrBy>("decrby counter -9223372036854775808");
+        assert!(matches!(result, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_incr_keeps_the_timeout() -> Result<()> {
+        let backend = Backend::new();
+        command::<Set>("set counter 1 px 10000")?.execute(&backend);
+        command::<Incr>("incr counter")?.execute(&backend);
+        assert!(backend.pttl("counter") > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_incr() -> Result<()> {
+        let backend = Backend::new();
+        let handles = (0..8)
+            .map(|_| {
+                let backend = backend.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        backend.incr_by("counter".to_string(), 1).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(backend.get("counter")?, Some(b"8000".to_vec()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_incrbyfloat_command() -> Result<()> {
+        let backend = Backend::new();
+        command::<Set>("set f 10.5")?.execute(&backend);
+        let result = command::<IncrByFloat>("incrbyfloat f 0.1")?.execute(&backend);
+        assert_eq!(result, BulkString::from("10.6").into());
+        let result = command::<IncrByFloat>("incrbyfloat f -0.6")?.execute(&backend);
+        assert_eq!(result, BulkString::from("10").into());
+        // at most 17 decimals and no exponent, like redis
+        let result = command::<IncrByFloat>("incrbyfloat f 1e-20")?.execute(&backend);
+        assert_eq!(result, BulkString::from("10").into());
+        let result = command::<IncrByFloat>("incrbyfloat tiny 1e-20")?.execute(&backend);
+        assert_eq!(result, BulkString::from("0").into());
+        let result = command::<IncrByFloat>("incrbyfloat tiny -1e-20")?.execute(&backend);
+        assert_eq!(result, BulkString::from("0").into());
+        let result = command::<IncrByFloat>("incrbyfloat big 1e20")?.execute(&backend);
+        assert_eq!(result, BulkString::from("100000000000000000000").into());
+
+        let result = command::<IncrByFloat>("incrbyfloat missing +inf")?.execute(&backend);
+        assert_eq!(result, BackendError::NotFinite.into());
+        assert!(!backend.exists("missing"));
+
+        command::<Set>("set s abc")?.execute(&backend);
+        let result = command::<IncrByFloat>("incrbyfloat s 1")?.execute(&backend);
+        assert_eq!(result, BackendError::NotAFloat.into());
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_strlen_getrange_setrange() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<Append>("append s Hello")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(5));
+        let result = command::<Append>("append s World")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(10));
+        let result = command::<StrLen>("strlen s")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(10));
+
+        for (range, expected) in [
+            ("0 4", "Hello"),
+            ("-5 -1", "World"),
+            ("0 -100", "H"),
+            ("-1 -5", ""),
+            ("5 100", "World"),
+            ("20 30", ""),
+        ] {
+            let cmd = format!("getrange s {}", range);
+            let result = command::<GetRange>(&cmd)?.execute(&backend);
+            assert_eq!(result, BulkString::from(expected).into(), "{}", range);
+        }
+
+        let result = command::<SetRange>("setrange s 5 Redis")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(10));
+        assert_eq!(backend.get("s")?, Some(b"HelloRedis".to_vec()));
+
+        // a missing key is padded with zero bytes, unless the value is empty
+        let result = command::<SetRange>("setrange p 2 ab")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(4));
+        assert_eq!(backend.get("p")?, Some(b"\0\0ab".to_vec()));
+        let result = command::<SetRange>("setrange missing 2 ")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+        assert!(!backend.exists("missing"));
+
+        let result = command::<SetRange>("setrange s -1 x");
+        assert!(matches!(result, Err(CommandError::InvalidArgument(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_getdel_getex_commands() -> Result<()> {
+        let backend = Backend::new();
+        command::<Set>("set s hello")?.execute(&backend);
+        let result = command::<GetEx>("getex s px 10000")?.execute(&backend);
+        assert_eq!(result, BulkString::from("hello").into());
+        assert!(backend.pttl("s") > 0);
+        command::<GetEx>("getex s persist")?.execute(&backend);
+        assert_eq!(backend.pttl("s"), -1);
+        command::<GetEx>("getex s pxat 1")?.execute(&backend);
+        assert!(!backend.exists("s"));
+
+        let result = command::<GetEx>("getex s persist ex 10");
+        assert!(matches!(result, Err(CommandError::SyntaxError)));
+
+        command::<Set>("set s hello")?.execute(&backend);
+        let result = command::<GetDel>("getdel s")?.execute(&backend);
+        assert_eq!(result, BulkString::from("hello").into());
+        let result = command::<GetDel>("getdel s")?.execute(&backend);
+        assert_eq!(result, RespFrame::Null(RespNull));
+
+        backend.sadd("set".to_string(), vec![b"a".to_vec()])?;
+        let result = command::<GetDel>("getdel set")?.execute(&backend);
+        assert_eq!(result, BackendError::WrongType.into());
+        assert!(backend.exists("set"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mget_mset_msetnx_commands() -> Result<()> {
+        let backend = Backend::new();
+        let result = command::<MSet>("mset a 1 b 2")?.execute(&backend);
+        assert_eq!(result, RESP_OK.clone());
+        backend.sadd("set".to_string(), vec![b"a".to_vec()])?;
+
+        let result = command::<MGet>("mget a b missing set")?.execute(&backend);
+        let expected = RespArray::new([
+            BulkString::from("1").into(),
+            BulkString::from("2").into(),
+            RespFrame::Null(RespNull),
+            RespFrame::Null(RespNull),
+        ]);
+        assert_eq!(result, expected.into());
+
+        let result = command::<MSetNx>("msetnx b 3 c 4")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(0));
+        assert!(!backend.exists("c"));
+        let result = command::<MSetNx>("msetnx c 3 d 4")?.execute(&backend);
+        assert_eq!(result, RespFrame::Integer(1));
+
+        let result = command::<MSet>("mset a 1 b");
+        assert!(matches!(result, Err(CommandError::WrongArity(_))));
+        Ok(())
+    }
 }
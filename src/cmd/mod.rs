@@ -1,109 +1,1303 @@
+mod bitmap;
+mod blocking;
+mod command;
+mod db;
 mod echo;
+mod expire;
+mod generic;
+mod geo;
+mod hello;
 mod hmap;
+mod hyperloglog;
+mod list;
 mod map;
+mod pubsub;
+mod registry;
+mod scan;
 mod set;
+mod stream;
+mod stream_group;
+mod transaction;
+mod zset;
+
+pub use registry::{lookup_command, CommandParser, CommandSpec, COMMAND_TABLE};
+pub use transaction::Transaction;
+
+use std::{ops::Bound, time::Duration};
 
 use enum_dispatch::enum_dispatch;
 use lazy_static::lazy_static;
 use thiserror::Error;
 
-use crate::{Backend, RespArray, RespError, RespFrame, SimpleString};
+use crate::{
+    Backend, BackendError, BitOperation, BitRange, BitfieldOp, BulkString, Claim, ExpireCondition,
+    GeoQuery, GeoUnit, InsertPosition, ListEnd, PendingRange, RespArray, RespError, RespFrame,
+    ScoreBound, SetCondition, SimpleError, SimpleString, StreamFields, StreamId, StreamTrim,
+    XAddId, XClaimOptions, ZAddOptions, ZRangeBy, ZRangeLimit,
+};
+
+// you could also use once_cell instead of lazy_static
+lazy_static! {
+    static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
+}
+
+// the error message starts with an error prefix (ERR, WRONGTYPE, ...) like redis does, so that
+// client libraries could tell the kind of the error
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("ERR Invalid command: {0}")]
+    InvalidCommand(String),
+    #[error("ERR {0}")]
+    InvalidArgument(String),
+    #[error("ERR wrong number of arguments for '{0}' command")]
+    WrongArity(String),
+    #[error("ERR syntax error")]
+    SyntaxError,
+    #[error("ERR value is not an integer or out of range")]
+    NotInteger,
+    #[error("ERR unknown command '{0}', with args beginning with: {1}")]
+    UnknownCommand(String, String),
+    #[error("NOPROTO unsupported protocol version")]
+    NoProto,
+
+    #[error(transparent)]
+    BackendError(#[from] BackendError),
+    #[error("ERR Protocol error: {0}")]
+    RespError(#[from] RespError),
+    #[error("ERR invalid utf8: {0}")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+}
+
+#[enum_dispatch]
+pub trait CommandExecutor {
+    fn execute(self, backend: &Backend) -> RespFrame;
+}
+
+#[enum_dispatch(CommandExecutor)]
+#[derive(Debug)]
+pub enum Command {
+    Ping(Ping),
+    Echo(Echo),
+    Get(Get),
+    Set(Set),
+    Incr(Incr),
+    Decr(Decr),
+    IncrBy(IncrBy),
+    DecrBy(DecrBy),
+    IncrByFloat(IncrByFloat),
+    Append(Append),
+    StrLen(StrLen),
+    GetRange(GetRange),
+    SetRange(SetRange),
+    GetDel(GetDel),
+    GetEx(GetEx),
+    MGet(MGet),
+    MSet(MSet),
+    MSetNx(MSetNx),
+    SetBit(SetBit),
+    GetBit(GetBit),
+    BitCount(BitCount),
+    BitPos(BitPos),
+    BitOp(BitOp),
+    BitField(BitField),
+    PfAdd(PfAdd),
+    PfCount(PfCount),
+    PfMerge(PfMerge),
+    HGet(HGet),
+    HMGet(HMGet),
+    HSet(HSet),
+    HGetAll(HGetAll),
+    HDel(HDel),
+    HExists(HExists),
+    HLen(HLen),
+    HKeys(HKeys),
+    HVals(HVals),
+    HIncrBy(HIncrBy),
+    HIncrByFloat(HIncrByFloat),
+    HSetNx(HSetNx),
+    HStrLen(HStrLen),
+    HRandField(HRandField),
+    HExpire(HExpire),
+    HPExpire(HPExpire),
+    HTtl(HTtl),
+    HPTtl(HPTtl),
+    HPersist(HPersist),
+    HScan(HScan),
+    SAdd(SAdd),
+    SRem(SRem),
+    SMembers(SMembers),
+    SCard(SCard),
+    SIsMember(SIsMember),
+    SMIsMember(SMIsMember),
+    SPop(SPop),
+    SRandMember(SRandMember),
+    SMove(SMove),
+    SInter(SInter),
+    SUnion(SUnion),
+    SDiff(SDiff),
+    SInterStore(SInterStore),
+    SUnionStore(SUnionStore),
+    SDiffStore(SDiffStore),
+    SScan(SScan),
+    LPush(LPush),
+    RPush(RPush),
+    LPop(LPop),
+    RPop(RPop),
+    LLen(LLen),
+    LRange(LRange),
+    LIndex(LIndex),
+    LSet(LSet),
+    LRem(LRem),
+    LTrim(LTrim),
+    LInsert(LInsert),
+    LMove(LMove),
+    BLPop(BLPop),
+    BRPop(BRPop),
+    BLMove(BLMove),
+    BLMPop(BLMPop),
+    ZAdd(ZAdd),
+    ZCard(ZCard),
+    ZScore(ZScore),
+    ZIncrBy(ZIncrBy),
+    ZRem(ZRem),
+    ZRank(ZRank),
+    ZRevRank(ZRevRank),
+    ZCount(ZCount),
+    ZRange(ZRange),
+    ZRangeByScore(ZRangeByScore),
+    ZRevRangeByScore(ZRevRangeByScore),
+    ZPopMin(ZPopMin),
+    ZPopMax(ZPopMax),
+    ZScan(ZScan),
+    GeoAdd(GeoAdd),
+    GeoPos(GeoPos),
+    GeoDist(GeoDist),
+    GeoSearch(GeoSearch),
+    GeoSearchStore(GeoSearchStore),
+    XAdd(XAdd),
+    XLen(XLen),
+    XRange(XRange),
+    XRevRange(XRevRange),
+    XTrim(XTrim),
+    XDel(XDel),
+    XRead(XRead),
+    XGroup(XGroup),
+    XReadGroup(XReadGroup),
+    XAck(XAck),
+    XPending(XPending),
+    XClaim(XClaim),
+    XAutoClaim(XAutoClaim),
+    Expire(Expire),
+    PExpire(PExpire),
+    Ttl(Ttl),
+    PTtl(PTtl),
+    Persist(Persist),
+    Type(Type),
+    Del(Del),
+    Unlink(Unlink),
+    Exists(Exists),
+    Touch(Touch),
+    Rename(Rename),
+    RenameNx(RenameNx),
+    Copy(Copy),
+    RandomKey(RandomKey),
+    Scan(Scan),
+    Keys(Keys),
+    Move(Move),
+    Select(Select),
+    SwapDb(SwapDb),
+    FlushDb(FlushDb),
+    FlushAll(FlushAll),
+    DbSize(DbSize),
+    Multi(Multi),
+    Exec(Exec),
+    Discard(Discard),
+    Watch(Watch),
+    Unwatch(Unwatch),
+    Subscribe(Subscribe),
+    Unsubscribe(Unsubscribe),
+    PSubscribe(PSubscribe),
+    PUnsubscribe(PUnsubscribe),
+    Publish(Publish),
+    SSubscribe(SSubscribe),
+    SUnsubscribe(SUnsubscribe),
+    SPublish(SPublish),
+    PubSub(PubSub),
+    Hello(Hello),
+    CommandInfo(CommandInfo),
+}
+
+#[derive(Debug)]
+pub struct Ping {
+    message: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct Echo {
+    message: String,
+}
+
+#[derive(Debug)]
+pub struct Get {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Set {
+    key: String,
+    value: Vec<u8>,
+    expire: Option<SetExpire>,
+    condition: Option<SetCondition>,
+    get: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetExpire {
+    // EX seconds
+    Ex(u64),
+    // PX milliseconds
+    Px(u64),
+    // EXAT unix-time-seconds
+    ExAt(u64),
+    // PXAT unix-time-milliseconds
+    PxAt(u64),
+    KeepTtl,
+}
+
+#[derive(Debug)]
+pub struct Incr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Decr {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct IncrBy {
+    key: String,
+    increment: i64,
+}
+
+#[derive(Debug)]
+pub struct DecrBy {
+    key: String,
+    decrement: i64,
+}
+
+#[derive(Debug)]
+pub struct IncrByFloat {
+    key: String,
+    increment: f64,
+}
+
+#[derive(Debug)]
+pub struct Append {
+    key: String,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct StrLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct GetRange {
+    key: String,
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug)]
+pub struct SetRange {
+    key: String,
+    offset: usize,
+    value: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct GetDel {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct GetEx {
+    key: String,
+    // KEEPTTL is not an option of GETEX
+    expire: Option<SetExpire>,
+    persist: bool,
+}
+
+#[derive(Debug)]
+pub struct MGet {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MSet {
+    pairs: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct MSetNx {
+    pairs: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct SetBit {
+    key: String,
+    offset: u64,
+    bit: bool,
+}
+
+#[derive(Debug)]
+pub struct GetBit {
+    key: String,
+    offset: u64,
+}
+
+#[derive(Debug)]
+pub struct BitCount {
+    key: String,
+    range: Option<BitRange>,
+}
+
+#[derive(Debug)]
+pub struct BitPos {
+    key: String,
+    bit: bool,
+    range: Option<BitRange>,
+}
+
+#[derive(Debug)]
+pub struct BitOp {
+    op: BitOperation,
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct BitField {
+    key: String,
+    ops: Vec<BitfieldOp>,
+}
+
+#[derive(Debug)]
+pub struct PfAdd {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct PfCount {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PfMerge {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HGet {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HMGet {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HSet {
+    key: String,
+    fields: Vec<(String, RespFrame)>,
+}
+
+#[derive(Debug)]
+pub struct HGetAll {
+    key: String,
+    sort: bool,
+}
+
+#[derive(Debug)]
+pub struct HDel {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HExists {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HKeys {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HVals {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct HIncrBy {
+    key: String,
+    field: String,
+    increment: i64,
+}
+
+#[derive(Debug)]
+pub struct HIncrByFloat {
+    key: String,
+    field: String,
+    increment: f64,
+}
+
+#[derive(Debug)]
+pub struct HSetNx {
+    key: String,
+    field: String,
+    value: RespFrame,
+}
+
+#[derive(Debug)]
+pub struct HStrLen {
+    key: String,
+    field: String,
+}
+
+#[derive(Debug)]
+pub struct HRandField {
+    key: String,
+    count: Option<i64>,
+    with_values: bool,
+}
+
+#[derive(Debug)]
+pub struct HExpire {
+    key: String,
+    seconds: i64,
+    condition: Option<ExpireCondition>,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HPExpire {
+    key: String,
+    milliseconds: i64,
+    condition: Option<ExpireCondition>,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HTtl {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HPTtl {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HPersist {
+    key: String,
+    fields: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SAdd {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SRem {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SMembers {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct SIsMember {
+    key: String,
+    member: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct SMIsMember {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct SPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct SRandMember {
+    key: String,
+    count: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct SMove {
+    source: String,
+    destination: String,
+    member: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct SInter {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SUnion {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiff {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SInterStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SUnionStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SDiffStore {
+    destination: String,
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct LPush {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct RPush {
+    key: String,
+    elements: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct LPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct RPop {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct LLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct LRange {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LIndex {
+    key: String,
+    index: i64,
+}
+
+#[derive(Debug)]
+pub struct LSet {
+    key: String,
+    index: i64,
+    element: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct LRem {
+    key: String,
+    count: i64,
+    element: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct LTrim {
+    key: String,
+    start: i64,
+    stop: i64,
+}
+
+#[derive(Debug)]
+pub struct LInsert {
+    key: String,
+    position: InsertPosition,
+    pivot: Vec<u8>,
+    element: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct LMove {
+    source: String,
+    destination: String,
+    from: ListEnd,
+    to: ListEnd,
+}
+
+// a timeout of None blocks forever
+#[derive(Debug)]
+pub struct BLPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct BRPop {
+    keys: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct BLMove {
+    source: String,
+    destination: String,
+    from: ListEnd,
+    to: ListEnd,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct BLMPop {
+    keys: Vec<String>,
+    end: ListEnd,
+    count: usize,
+    timeout: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub struct ZAdd {
+    key: String,
+    options: ZAddOptions,
+    // INCR: behave like ZINCRBY, only a single score-member pair is allowed
+    incr: bool,
+    members: Vec<(f64, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct ZCard {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct ZScore {
+    key: String,
+    member: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ZIncrBy {
+    key: String,
+    increment: f64,
+    member: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ZRem {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct ZRank {
+    key: String,
+    member: Vec<u8>,
+    with_score: bool,
+}
+
+#[derive(Debug)]
+pub struct ZRevRank {
+    key: String,
+    member: Vec<u8>,
+    with_score: bool,
+}
+
+#[derive(Debug)]
+pub struct ZCount {
+    key: String,
+    min: ScoreBound,
+    max: ScoreBound,
+}
+
+#[derive(Debug)]
+pub struct ZRange {
+    key: String,
+    by: ZRangeBy,
+    rev: bool,
+    limit: Option<ZRangeLimit>,
+    with_scores: bool,
+}
+
+// the legacy forms of ZRANGE ... BYSCORE [REV]
+#[derive(Debug)]
+pub struct ZRangeByScore(ZRange);
+
+#[derive(Debug)]
+pub struct ZRevRangeByScore(ZRange);
+
+#[derive(Debug)]
+pub struct ZPopMin {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct ZPopMax {
+    key: String,
+    count: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct GeoAdd {
+    key: String,
+    // NX, XX and CH, the same as ZADD
+    options: ZAddOptions,
+    // longitude, latitude, member
+    points: Vec<(f64, f64, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub struct GeoPos {
+    key: String,
+    members: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct GeoDist {
+    key: String,
+    member1: Vec<u8>,
+    member2: Vec<u8>,
+    unit: GeoUnit,
+}
+
+#[derive(Debug)]
+pub struct GeoSearch {
+    key: String,
+    query: GeoQuery,
+    with_coord: bool,
+    with_dist: bool,
+    with_hash: bool,
+}
+
+#[derive(Debug)]
+pub struct GeoSearchStore {
+    destination: String,
+    key: String,
+    query: GeoQuery,
+    store_dist: bool,
+}
+
+#[derive(Debug)]
+pub struct XAdd {
+    key: String,
+    // NOMKSTREAM: don't create a missing stream
+    nomkstream: bool,
+    trim: Option<StreamTrim>,
+    id: XAddId,
+    fields: StreamFields,
+}
+
+#[derive(Debug)]
+pub struct XLen {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct XRange {
+    key: String,
+    start: Bound<StreamId>,
+    end: Bound<StreamId>,
+    count: Option<usize>,
+    rev: bool,
+}
+
+// XREVRANGE takes the end of the range before its start
+#[derive(Debug)]
+pub struct XRevRange(XRange);
+
+#[derive(Debug)]
+pub struct XTrim {
+    key: String,
+    trim: StreamTrim,
+}
 
-// you could also use once_cell instead of lazy_static
-lazy_static! {
-    static ref RESP_OK: RespFrame = SimpleString::new("OK").into();
+#[derive(Debug)]
+pub struct XDel {
+    key: String,
+    ids: Vec<StreamId>,
 }
 
-#[derive(Debug, Error)]
-pub enum CommandError {
-    #[error("Invalid command: {0}")]
-    InvalidCommand(String),
-    #[error("Invalid argument: {0}")]
-    InvalidArgument(String),
+#[derive(Debug)]
+pub struct XRead {
+    count: Option<usize>,
+    // BLOCK: wait for new entries, a timeout of None blocks forever
+    block: bool,
+    timeout: Option<Duration>,
+    // an ID of None is "$", the last ID of the stream when the command runs
+    streams: Vec<(String, Option<StreamId>)>,
+}
 
-    #[error("{0}")]
-    RespError(#[from] RespError),
-    #[error("Utf8 error: {0}")]
-    Utf8Error(#[from] std::string::FromUtf8Error),
+#[derive(Debug)]
+pub struct XGroup {
+    subcommand: XGroupSubcommand,
 }
 
-#[enum_dispatch]
-pub trait CommandExecutor {
-    fn execute(self, backend: &Backend) -> RespFrame;
+// an ID of None is "$", the last ID of the stream
+#[derive(Debug)]
+pub enum XGroupSubcommand {
+    // XGROUP CREATE key group <id | $> [MKSTREAM]
+    Create {
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+        mkstream: bool,
+    },
+    // XGROUP SETID key group <id | $>
+    SetId {
+        key: String,
+        group: String,
+        id: Option<StreamId>,
+    },
+    // XGROUP DESTROY key group
+    Destroy {
+        key: String,
+        group: String,
+    },
+    // XGROUP CREATECONSUMER key group consumer
+    CreateConsumer {
+        key: String,
+        group: String,
+        consumer: String,
+    },
+    // XGROUP DELCONSUMER key group consumer
+    DelConsumer {
+        key: String,
+        group: String,
+        consumer: String,
+    },
 }
 
-#[enum_dispatch(CommandExecutor)]
 #[derive(Debug)]
-pub enum Command {
-    Echo(Echo),
-    Get(Get),
-    Set(Set),
-    HGet(HGet),
-    HMGet(HMGet),
-    HSet(HSet),
-    HGetAll(HGetAll),
-    SAdd(SAdd),
-    SIsMember(SIsMember),
+pub struct XReadGroup {
+    group: String,
+    consumer: String,
+    count: Option<usize>,
+    // BLOCK: wait for new entries, a timeout of None blocks forever
+    block: bool,
+    timeout: Option<Duration>,
+    // NOACK: don't add the delivered entries to the pending entries list
+    noack: bool,
+    // an ID of None is ">", the entries never delivered to the group
+    streams: Vec<(String, Option<StreamId>)>,
+}
 
-    // unrecognized command
-    Unrecognized(Unrecognized),
+#[derive(Debug)]
+pub struct XAck {
+    key: String,
+    group: String,
+    ids: Vec<StreamId>,
 }
 
 #[derive(Debug)]
-pub struct Echo {
-    message: String,
+pub struct XPending {
+    key: String,
+    group: String,
+    // None replies the summary of the pending entries
+    range: Option<PendingRange>,
 }
 
 #[derive(Debug)]
-pub struct Get {
+pub struct XClaim {
     key: String,
+    claim: Claim,
+    ids: Vec<StreamId>,
+    options: XClaimOptions,
 }
 
 #[derive(Debug)]
-pub struct Set {
+pub struct XAutoClaim {
     key: String,
-    value: RespFrame,
+    claim: Claim,
+    start: StreamId,
+    count: usize,
 }
 
 #[derive(Debug)]
-pub struct HGet {
+pub struct Expire {
     key: String,
-    field: String,
+    seconds: i64,
 }
 
 #[derive(Debug)]
-pub struct HMGet {
+pub struct PExpire {
     key: String,
-    fields: Vec<String>,
+    milliseconds: i64,
 }
 
 #[derive(Debug)]
-pub struct HSet {
+pub struct Ttl {
     key: String,
-    field: String,
-    value: RespFrame,
 }
 
 #[derive(Debug)]
-pub struct HGetAll {
+pub struct PTtl {
     key: String,
-    sort: bool,
 }
 
 #[derive(Debug)]
-pub struct SAdd {
+pub struct Persist {
     key: String,
-    member: RespFrame,
 }
 
 #[derive(Debug)]
-pub struct SIsMember {
+pub struct Type {
+    key: String,
+}
+
+#[derive(Debug)]
+pub struct Del {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unlink {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Exists {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Touch {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Rename {
+    key: String,
+    newkey: String,
+}
+
+#[derive(Debug)]
+pub struct RenameNx {
+    key: String,
+    newkey: String,
+}
+
+#[derive(Debug)]
+pub struct Copy {
+    source: String,
+    destination: String,
+    db: Option<i64>,
+    replace: bool,
+}
+
+#[derive(Debug)]
+pub struct RandomKey;
+
+#[derive(Debug)]
+pub struct Scan {
+    cursor: u64,
+    count: usize,
+    pattern: Option<Vec<u8>>,
+    type_name: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Keys {
+    pattern: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Move {
+    key: String,
+    db: i64,
+}
+
+#[derive(Debug)]
+pub struct Select {
+    index: i64,
+}
+
+#[derive(Debug)]
+pub struct SwapDb {
+    a: i64,
+    b: i64,
+}
+
+#[derive(Debug)]
+pub struct FlushDb {
+    lazy: bool,
+}
+
+#[derive(Debug)]
+pub struct FlushAll {
+    lazy: bool,
+}
+
+#[derive(Debug)]
+pub struct DbSize;
+
+#[derive(Debug)]
+pub struct Multi;
+
+#[derive(Debug)]
+pub struct Exec;
+
+#[derive(Debug)]
+pub struct Discard;
+
+#[derive(Debug)]
+pub struct Watch {
+    keys: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Unwatch;
+
+#[derive(Debug)]
+pub struct Subscribe {
+    channels: Vec<String>,
+}
+
+// no channel means every channel the connection subscribes to
+#[derive(Debug)]
+pub struct Unsubscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PSubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct PUnsubscribe {
+    patterns: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Publish {
+    channel: String,
+    message: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct SSubscribe {
+    channels: Vec<String>,
+}
+
+// no channel means every shard channel the connection subscribes to
+#[derive(Debug)]
+pub struct SUnsubscribe {
+    channels: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SPublish {
+    channel: String,
+    message: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct PubSub {
+    subcommand: PubSubSubcommand,
+}
+
+#[derive(Debug)]
+pub enum PubSubSubcommand {
+    // PUBSUB CHANNELS [pattern]
+    Channels(Option<Vec<u8>>),
+    // PUBSUB NUMSUB [channel ...]
+    NumSub(Vec<String>),
+    // PUBSUB NUMPAT
+    NumPat,
+    // PUBSUB SHARDCHANNELS [pattern]
+    ShardChannels(Option<Vec<u8>>),
+    // PUBSUB SHARDNUMSUB [shardchannel ...]
+    ShardNumSub(Vec<String>),
+}
+
+// a missing version keeps the protocol of the connection
+#[derive(Debug)]
+pub struct Hello {
+    protover: Option<i64>,
+}
+
+#[derive(Debug)]
+pub struct HScan {
+    key: String,
+    cursor: u64,
+    count: usize,
+    pattern: Option<Vec<u8>>,
+    no_values: bool,
+}
+
+#[derive(Debug)]
+pub struct SScan {
+    key: String,
+    cursor: u64,
+    count: usize,
+    pattern: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct ZScan {
     key: String,
-    member: String,
+    cursor: u64,
+    count: usize,
+    pattern: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct CommandInfo {
+    subcommand: CommandSubcommand,
 }
 
 #[derive(Debug)]
-pub struct Unrecognized;
+pub enum CommandSubcommand {
+    // COMMAND
+    List,
+    // COMMAND COUNT
+    Count,
+    // COMMAND INFO [command-name ...]
+    Info(Vec<String>),
+    // COMMAND DOCS [command-name ...]
+    Docs(Vec<String>),
+}
+
+/// The keys a command runs against, they are locked while it runs.
+#[derive(Debug, Default)]
+pub struct CommandKeys {
+    pub keys: Vec<String>,
+    // whether the command may modify them
+    pub write: bool,
+}
+
+impl CommandKeys {
+    /// The keys of the command the frame holds, found with the key specification of the
+    /// command in the registry, none for a frame which isn't a known command.
+    pub fn of(frame: &RespFrame) -> Self {
+        let RespFrame::Array(args) = frame else {
+            return Self::default();
+        };
+        let spec = match args.first() {
+            Some(RespFrame::BulkString(name)) => lookup_command(&String::from_utf8_lossy(name)),
+            _ => None,
+        };
+        match spec {
+            Some(spec) if spec.check_arity(args.len()) => CommandKeys {
+                keys: spec.keys(args),
+                write: spec.is_write(),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+impl Command {
+    /// Execute the command, a blocking command waits until it is served or times out instead
+    /// of replying right away. SELECT points the handle of the connection to another database.
+    pub async fn execute_async(self, backend: &mut Backend, keys: CommandKeys) -> RespFrame {
+        match self {
+            // they replace the data of whole databases, which no command may be running against
+            cmd @ (Command::SwapDb(_) | Command::FlushDb(_) | Command::FlushAll(_)) => {
+                backend.exclusive(|backend| cmd.execute_now(backend))
+            }
+            Command::BLPop(cmd) => blocking::execute_blocking(cmd, backend).await,
+            Command::BRPop(cmd) => blocking::execute_blocking(cmd, backend).await,
+            Command::BLMove(cmd) => blocking::execute_blocking(cmd, backend).await,
+            Command::BLMPop(cmd) => blocking::execute_blocking(cmd, backend).await,
+            Command::XRead(cmd) => cmd.execute_blocking(backend).await,
+            Command::XReadGroup(cmd) => cmd.execute_blocking(backend).await,
+            cmd => backend.shared(&keys.keys, keys.write, |backend| cmd.execute_now(backend)),
+        }
+    }
+
+    // execute the command without waiting, a blocking command behaves like its non-blocking
+    // counterpart
+    fn execute_now(self, backend: &mut Backend) -> RespFrame {
+        match self {
+            Command::Select(cmd) => cmd.execute_select(backend),
+            cmd => cmd.execute(backend),
+        }
+    }
+
+    /// Whether the command replies with the entries read from each stream, see `resp2_reply`.
+    pub fn replies_streams(&self) -> bool {
+        matches!(self, Command::XRead(_) | Command::XReadGroup(_))
+    }
+}
+
+/// The reply of a command for a RESP2 client, see `RespFrame::into_resp2`. Like redis, the
+/// entries read from each stream are sent as an array of [key, entries] pairs rather than as a
+/// flat array, `streams` is set for the commands which reply with them.
+pub fn resp2_reply(frame: RespFrame, streams: bool) -> RespFrame {
+    match frame {
+        RespFrame::Map(map) if streams => RespArray::new(
+            map.0
+                .into_iter()
+                .map(|(key, entries)| {
+                    RespArray::new([BulkString::from(key).into(), entries.into_resp2()]).into()
+                })
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into(),
+        frame => frame.into_resp2(),
+    }
+}
 
 impl TryFrom<RespFrame> for Command {
     type Error = CommandError;
@@ -122,30 +1316,61 @@ impl TryFrom<RespArray> for Command {
     type Error = CommandError;
 
     fn try_from(v: RespArray) -> Result<Self, Self::Error> {
-        match v.first() {
-            Some(RespFrame::BulkString(ref cmd)) => match cmd.as_ref() {
-                b"echo" => Ok(Echo::try_from(v)?.into()),
-                b"get" => Ok(Get::try_from(v)?.into()),
-                b"set" => Ok(Set::try_from(v)?.into()),
-                b"hget" => Ok(HGet::try_from(v)?.into()),
-                b"hmget" => Ok(HMGet::try_from(v)?.into()),
-                b"hset" => Ok(HSet::try_from(v)?.into()),
-                b"hgetall" => Ok(HGetAll::try_from(v)?.into()),
-                b"sadd" => Ok(SAdd::try_from(v)?.into()),
-                b"sismember" => Ok(SIsMember::try_from(v)?.into()),
-                _ => Ok(Unrecognized.into()),
-            },
-            _ => Err(CommandError::InvalidCommand(
-                "Command must have a BulkString as the first argument".to_string(),
-            )),
+        let spec = match v.first() {
+            Some(RespFrame::BulkString(ref cmd)) => {
+                lookup_command(&String::from_utf8_lossy(cmd)).ok_or_else(|| unknown_command(&v))?
+            }
+            _ => {
+                return Err(CommandError::InvalidCommand(
+                    "Command must have a BulkString as the first argument".to_string(),
+                ))
+            }
+        };
+
+        if !spec.check_arity(v.len()) {
+            return Err(CommandError::WrongArity(spec.name.to_string()));
         }
+
+        (spec.parser)(v)
+    }
+}
+
+impl From<CommandError> for RespFrame {
+    fn from(e: CommandError) -> Self {
+        SimpleError::new(e.to_string()).into()
     }
 }
 
-impl CommandExecutor for Unrecognized {
-    fn execute(self, _backend: &Backend) -> RespFrame {
-        RESP_OK.clone()
+impl From<BackendError> for RespFrame {
+    fn from(e: BackendError) -> Self {
+        CommandError::from(e).into()
+    }
+}
+
+// build the same error redis replies for an unknown command, the quoted arguments are capped to
+// about 128 bytes
+fn unknown_command(v: &RespArray) -> CommandError {
+    let to_string = |frame: &RespFrame| match frame {
+        RespFrame::BulkString(s) => String::from_utf8_lossy(s).into_owned(),
+        frame => format!("{:?}", frame),
+    };
+
+    let name = v.first().map(to_string).unwrap_or_default();
+    let mut args = String::new();
+    for arg in v.iter().skip(1) {
+        if args.len() >= 128 {
+            break;
+        }
+        let arg = to_string(arg);
+        let arg = arg
+            .char_indices()
+            .take_while(|(i, _)| *i < 128 - args.len())
+            .map(|(_, c)| c)
+            .collect::<String>();
+        args.push_str(&format!("'{}' ", arg));
     }
+
+    CommandError::UnknownCommand(name, args)
 }
 
 fn validate_command(
@@ -154,11 +1379,7 @@ fn validate_command(
     n_args: usize,
 ) -> Result<(), CommandError> {
     if value.len() != n_args + names.len() {
-        return Err(CommandError::InvalidArgument(format!(
-            "{} command must have exactly {} argument",
-            names.join(" "),
-            n_args
-        )));
+        return Err(CommandError::WrongArity(names.join("|")));
     }
 
     for (i, name) in names.iter().enumerate() {
@@ -189,11 +1410,7 @@ fn validate_command_multi_args(
     min_args: usize,
 ) -> Result<(), CommandError> {
     if value.len() < min_args + names.len() {
-        return Err(CommandError::InvalidArgument(format!(
-            "{} command must have exactly least {} argument",
-            names.join(" "),
-            min_args
-        )));
+        return Err(CommandError::WrongArity(names.join("|")));
     }
 
     for (i, name) in names.iter().enumerate() {
@@ -222,12 +1439,103 @@ fn extract_args(value: RespArray, start: usize) -> Result<Vec<RespFrame>, Comman
     Ok(value.0.into_iter().skip(start).collect::<Vec<RespFrame>>())
 }
 
+// parse a BulkString argument such as "100" into an integer
+fn parse_integer(arg: Option<RespFrame>) -> Result<i64, CommandError> {
+    match arg {
+        Some(RespFrame::BulkString(v)) => String::from_utf8_lossy(&v)
+            .parse()
+            .map_err(|_| CommandError::NotInteger),
+        Some(RespFrame::Integer(v)) => Ok(v),
+        _ => Err(CommandError::NotInteger),
+    }
+}
+
+// parse a BulkString argument such as "1.5" or "+inf" into a float, NaN is rejected
+fn parse_float(arg: Option<RespFrame>) -> Result<f64, CommandError> {
+    let v = match arg {
+        Some(RespFrame::BulkString(v)) => String::from_utf8_lossy(&v).parse::<f64>().ok(),
+        Some(RespFrame::Integer(v)) => Some(v as f64),
+        Some(RespFrame::Double(v)) => Some(v),
+        _ => None,
+    };
+    v.filter(|v| !v.is_nan())
+        .ok_or_else(|| CommandError::InvalidArgument("value is not a valid float".to_string()))
+}
+
+// the most members or fields SRANDMEMBER and HRANDFIELD reply with for a negative count, which
+// may repeat them. Redis only rejects counts below -LONG_MAX/2, but the reply is built in
+// memory before it is sent
+const RANDOM_COUNT_MAX: u64 = 1_000_000;
+
+// a count of SRANDMEMBER or HRANDFIELD, rejected before anything is allocated for the reply
+fn random_count(count: i64) -> Result<i64, CommandError> {
+    if count < 0 && count.unsigned_abs() > RANDOM_COUNT_MAX {
+        return Err(CommandError::InvalidArgument(
+            "value is out of range".to_string(),
+        ));
+    }
+    Ok(count)
+}
+
+// a BulkString argument as raw bytes, e.g. a value or a member
+fn parse_bytes(arg: Option<RespFrame>) -> Result<Vec<u8>, CommandError> {
+    match arg {
+        Some(RespFrame::BulkString(v)) => Ok(v.0),
+        _ => Err(CommandError::InvalidArgument(
+            "Invalid argument".to_string(),
+        )),
+    }
+}
+
+// a BulkString argument as an utf8 string, e.g. a key
+fn parse_string(arg: Option<RespFrame>) -> Result<String, CommandError> {
+    Ok(String::from_utf8(parse_bytes(arg)?)?)
+}
+
+// options like NX, EX are case insensitive, normalize them to lowercase
+fn parse_option(arg: &RespFrame) -> Option<String> {
+    match arg {
+        RespFrame::BulkString(v) => Some(String::from_utf8_lossy(v).to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+// the request a client sends for a space separated line, e.g. "hset map field value"
+#[cfg(test)]
+pub(crate) fn request_array(line: &str) -> RespArray {
+    let frames = line
+        .split(' ')
+        .map(|v| crate::BulkString::from(v).into())
+        .collect::<Vec<RespFrame>>();
+    RespArray::new(frames)
+}
+
+// build the command from a space separated line
+#[cfg(test)]
+pub(crate) fn command<T>(line: &str) -> Result<T, CommandError>
+where
+    T: TryFrom<RespArray, Error = CommandError>,
+{
+    request_array(line).try_into()
+}
+
+// execute the command on the line, a parse error is replied like the server does
+#[cfg(test)]
+pub(crate) fn run<T>(backend: &Backend, line: &str) -> RespFrame
+where
+    T: TryFrom<RespArray, Error = CommandError> + CommandExecutor,
+{
+    command::<T>(line)
+        .map(|cmd| cmd.execute(backend))
+        .unwrap_or_else(Into::into)
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
     use bytes::BytesMut;
 
-    use crate::{RespDecode, RespNull};
+    use crate::{RespDecode, RespEncode, RespNull};
 
     use super::*;
 
@@ -248,4 +1556,62 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_command_is_case_insensitive() -> Result<()> {
+        let backend = Backend::new();
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: Command = frame.try_into()?;
+        assert!(matches!(cmd, Command::Set(_)));
+        cmd.execute(&backend);
+
+        buf.extend_from_slice(b"*2\r\n$3\r\nGeT\r\n$5\r\nhello\r\n");
+        let frame = RespArray::decode(&mut buf)?;
+        let cmd: Command = frame.try_into()?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::BulkString(b"world".into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_error_to_frame() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*1\r\n$3\r\nget\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let err = Command::try_from(frame).unwrap_err();
+        let frame: RespFrame = err.into();
+        assert_eq!(
+            frame.encode(),
+            b"-ERR wrong number of arguments for 'get' command\r\n"
+        );
+
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*3\r\n$5\r\nhsett\r\n$3\r\nmap\r\n$5\r\nhello\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let err = Command::try_from(frame).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR unknown command 'hsett', with args beginning with: 'map' 'hello' "
+        );
+
+        let frame: RespFrame = BackendError::WrongType.into();
+        assert_eq!(
+            frame,
+            RespFrame::Error(SimpleError::new(
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            ))
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,737 @@
+use crate::{
+    Backend, BulkString, PubSubMessage, RespArray, RespFrame, RespNullBulkString, Subscriber,
+};
+
+use super::{
+    extract_args, parse_bytes, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, PSubscribe, PUnsubscribe, PubSub,
+    PubSubSubcommand, Publish, SPublish, SSubscribe, SUnsubscribe, Subscribe, Unsubscribe,
+};
+
+impl CommandExecutor for Publish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.publish(&self.channel, &self.message) as i64)
+    }
+}
+
+impl CommandExecutor for SPublish {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        RespFrame::Integer(backend.spublish(&self.channel, &self.message) as i64)
+    }
+}
+
+impl CommandExecutor for PubSub {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match self.subcommand {
+            PubSubSubcommand::Channels(pattern) => {
+                channels_reply(backend.pubsub_channels(pattern.as_deref()))
+            }
+            PubSubSubcommand::NumSub(channels) => {
+                let counts = backend.pubsub_numsub(&channels);
+                numsub_reply(channels, counts)
+            }
+            PubSubSubcommand::NumPat => RespFrame::Integer(backend.pubsub_numpat() as i64),
+            PubSubSubcommand::ShardChannels(pattern) => {
+                channels_reply(backend.pubsub_shardchannels(pattern.as_deref()))
+            }
+            PubSubSubcommand::ShardNumSub(channels) => {
+                let counts = backend.pubsub_shardnumsub(&channels);
+                numsub_reply(channels, counts)
+            }
+        }
+    }
+}
+
+// the subscriptions live in the connection, which puts itself in the subscribed mode and replies
+// once per channel, see `execute_subscribe` and friends. Without a connection there is nobody to
+// deliver the messages to
+impl CommandExecutor for Subscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("subscribe")
+    }
+}
+
+impl CommandExecutor for Unsubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("unsubscribe")
+    }
+}
+
+impl CommandExecutor for PSubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("psubscribe")
+    }
+}
+
+impl CommandExecutor for PUnsubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("punsubscribe")
+    }
+}
+
+impl CommandExecutor for SSubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("ssubscribe")
+    }
+}
+
+impl CommandExecutor for SUnsubscribe {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        no_connection("sunsubscribe")
+    }
+}
+
+impl Subscribe {
+    /// Subscribe to the channels, with one reply per channel.
+    pub fn execute_subscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        self.channels
+            .into_iter()
+            .map(|channel| {
+                let n = subscriber.subscribe(channel.clone());
+                subscription_reply("subscribe", Some(channel), n)
+            })
+            .collect()
+    }
+}
+
+impl Unsubscribe {
+    /// Unsubscribe from the channels, or from every channel if none is given, with one reply
+    /// per channel. The messages published before are delivered first.
+    pub fn execute_unsubscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        let channels = match self.channels.is_empty() {
+            true => subscriber.channels(),
+            false => self.channels,
+        };
+        let replies = channels
+            .into_iter()
+            .map(|channel| {
+                let n = subscriber.unsubscribe(&channel);
+                subscription_reply("unsubscribe", Some(channel), n)
+            })
+            .collect();
+        unsubscribe_replies("unsubscribe", subscriber, replies)
+    }
+}
+
+impl PSubscribe {
+    /// Subscribe to the patterns, with one reply per pattern.
+    pub fn execute_psubscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        self.patterns
+            .into_iter()
+            .map(|pattern| {
+                let n = subscriber.psubscribe(pattern.clone());
+                subscription_reply("psubscribe", Some(pattern), n)
+            })
+            .collect()
+    }
+}
+
+impl PUnsubscribe {
+    /// Unsubscribe from the patterns, or from every pattern if none is given, see
+    /// `Unsubscribe::execute_unsubscribe`.
+    pub fn execute_punsubscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        let patterns = match self.patterns.is_empty() {
+            true => subscriber.patterns(),
+            false => self.patterns,
+        };
+        let replies = patterns
+            .into_iter()
+            .map(|pattern| {
+                let n = subscriber.punsubscribe(&pattern);
+                subscription_reply("punsubscribe", Some(pattern), n)
+            })
+            .collect();
+        unsubscribe_replies("punsubscribe", subscriber, replies)
+    }
+}
+
+impl SSubscribe {
+    /// Subscribe to the shard channels, with one reply per channel which counts the shard
+    /// channels only.
+    pub fn execute_ssubscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        self.channels
+            .into_iter()
+            .map(|channel| {
+                let n = subscriber.ssubscribe(channel.clone());
+                subscription_reply("ssubscribe", Some(channel), n)
+            })
+            .collect()
+    }
+}
+
+impl SUnsubscribe {
+    /// Unsubscribe from the shard channels, or from every shard channel if none is given, see
+    /// `Unsubscribe::execute_unsubscribe`.
+    pub fn execute_sunsubscribe(self, subscriber: &mut Subscriber) -> Vec<RespFrame> {
+        let channels = match self.channels.is_empty() {
+            true => subscriber.shard_channels(),
+            false => self.channels,
+        };
+        let replies = channels
+            .into_iter()
+            .map(|channel| {
+                let n = subscriber.sunsubscribe(&channel);
+                subscription_reply("sunsubscribe", Some(channel), n)
+            })
+            .collect();
+        unsubscribe_replies("sunsubscribe", subscriber, replies)
+    }
+}
+
+// a message is pushed to the subscriber as ["message", channel, payload], as
+// ["pmessage", pattern, channel, payload] if it matched a pattern, or as
+// ["smessage", channel, payload] if it was published to a shard channel
+impl From<PubSubMessage> for RespFrame {
+    fn from(message: PubSubMessage) -> Self {
+        let mut frames: Vec<RespFrame> = match (message.pattern, message.sharded) {
+            (Some(pattern), _) => vec![
+                BulkString::from("pmessage").into(),
+                BulkString::from(pattern).into(),
+            ],
+            (None, true) => vec![BulkString::from("smessage").into()],
+            (None, false) => vec![BulkString::from("message").into()],
+        };
+        frames.push(BulkString::from(message.channel).into());
+        frames.push(BulkString::new(message.payload).into());
+        RespArray::new(frames).into()
+    }
+}
+
+// [kind, channel or pattern, the number of subscriptions left]
+fn subscription_reply(kind: &str, name: Option<String>, n: usize) -> RespFrame {
+    let name = match name {
+        Some(name) => BulkString::from(name).into(),
+        None => RespFrame::NullBulkString(RespNullBulkString),
+    };
+    RespArray::new([
+        BulkString::from(kind).into(),
+        name,
+        RespFrame::Integer(n as i64),
+    ])
+    .into()
+}
+
+// the messages already buffered were published before the subscriptions ended, so they go first.
+// Unsubscribing from nothing still gets a reply
+fn unsubscribe_replies(
+    kind: &str,
+    subscriber: &mut Subscriber,
+    replies: Vec<RespFrame>,
+) -> Vec<RespFrame> {
+    let mut frames = std::iter::from_fn(|| subscriber.try_recv())
+        .map(RespFrame::from)
+        .collect::<Vec<_>>();
+    if replies.is_empty() {
+        let n = match kind {
+            "sunsubscribe" => subscriber.shard_channel_count(),
+            _ => subscriber.channel_count(),
+        };
+        frames.push(subscription_reply(kind, None, n));
+    }
+    frames.extend(replies);
+    frames
+}
+
+fn channels_reply(channels: Vec<String>) -> RespFrame {
+    RespArray::new(
+        channels
+            .into_iter()
+            .map(|channel| BulkString::from(channel).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into()
+}
+
+// [channel, count, channel, count, ...]
+fn numsub_reply(channels: Vec<String>, counts: Vec<usize>) -> RespFrame {
+    RespArray::new(
+        channels
+            .into_iter()
+            .zip(counts)
+            .flat_map(|(channel, n)| {
+                [
+                    BulkString::from(channel).into(),
+                    RespFrame::Integer(n as i64),
+                ]
+            })
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into()
+}
+
+fn no_connection(name: &str) -> RespFrame {
+    CommandError::InvalidArgument(format!("{} needs a client connection", name.to_uppercase()))
+        .into()
+}
+
+impl TryFrom<RespArray> for Subscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["subscribe"], 1)?;
+        Ok(Subscribe {
+            channels: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Unsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["unsubscribe"], 0)?;
+        Ok(Unsubscribe {
+            channels: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PSubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["psubscribe"], 1)?;
+        Ok(PSubscribe {
+            patterns: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for PUnsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["punsubscribe"], 0)?;
+        Ok(PUnsubscribe {
+            patterns: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SSubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["ssubscribe"], 1)?;
+        Ok(SSubscribe {
+            channels: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SUnsubscribe {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["sunsubscribe"], 0)?;
+        Ok(SUnsubscribe {
+            channels: parse_names(value)?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SPublish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["spublish"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let channel = parse_string(args.next())?;
+        let message = parse_bytes(args.next())?;
+        Ok(SPublish { channel, message })
+    }
+}
+
+impl TryFrom<RespArray> for Publish {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["publish"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let channel = parse_string(args.next())?;
+        let message = parse_bytes(args.next())?;
+        Ok(Publish { channel, message })
+    }
+}
+
+impl TryFrom<RespArray> for PubSub {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["pubsub"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let subcommand = args.next().as_ref().and_then(parse_option);
+        let args = args.collect::<Vec<_>>();
+        let subcommand = match (subcommand.as_deref(), args.len()) {
+            (Some("channels"), 0) => PubSubSubcommand::Channels(None),
+            (Some("channels"), 1) => {
+                PubSubSubcommand::Channels(Some(parse_bytes(args.into_iter().next())?))
+            }
+            (Some("numsub"), _) => PubSubSubcommand::NumSub(
+                args.into_iter()
+                    .map(|v| parse_string(Some(v)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            (Some("numpat"), 0) => PubSubSubcommand::NumPat,
+            (Some("shardchannels"), 0) => PubSubSubcommand::ShardChannels(None),
+            (Some("shardchannels"), 1) => {
+                PubSubSubcommand::ShardChannels(Some(parse_bytes(args.into_iter().next())?))
+            }
+            (Some("shardnumsub"), _) => PubSubSubcommand::ShardNumSub(
+                args.into_iter()
+                    .map(|v| parse_string(Some(v)))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            (Some(name @ ("channels" | "numpat" | "shardchannels")), _) => {
+                return Err(CommandError::WrongArity(format!("pubsub|{}", name)))
+            }
+            (option, _) => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'. Try PUBSUB HELP.",
+                    option.unwrap_or_default()
+                )))
+            }
+        };
+        Ok(PubSub { subcommand })
+    }
+}
+
+fn parse_names(value: RespArray) -> Result<Vec<String>, CommandError> {
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|v| parse_string(Some(v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use crate::key_hash_slot;
+    use anyhow::Result;
+
+    fn publish(backend: &Backend, line: &str) -> Result<RespFrame> {
+        Ok(command::<Publish>(line)?.execute(backend))
+    }
+
+    fn strings(values: &[&str]) -> RespFrame {
+        RespArray::new(
+            values
+                .iter()
+                .map(|v| BulkString::from(*v).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_subscribe_command() -> Result<()> {
+        let cmd: Subscribe = command("subscribe news sport")?;
+        assert_eq!(cmd.channels, ["news", "sport"]);
+
+        let cmd: Unsubscribe = command("unsubscribe")?;
+        assert!(cmd.channels.is_empty());
+
+        let cmd: PubSub = command("pubsub channels n*")?;
+        assert!(matches!(
+            cmd.subcommand,
+            PubSubSubcommand::Channels(Some(ref p)) if p == b"n*"
+        ));
+
+        assert_eq!(
+            command::<Subscribe>("subscribe").unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'subscribe' command"
+        );
+        assert_eq!(
+            command::<Publish>("publish news").unwrap_err().to_string(),
+            "ERR wrong number of arguments for 'publish' command"
+        );
+        for (line, message) in [
+            (
+                "pubsub numpat x",
+                "ERR wrong number of arguments for 'pubsub|numpat' command",
+            ),
+            (
+                "pubsub channels a b",
+                "ERR wrong number of arguments for 'pubsub|channels' command",
+            ),
+            (
+                "pubsub foo",
+                "ERR unknown subcommand 'foo'. Try PUBSUB HELP.",
+            ),
+        ] {
+            assert_eq!(command::<PubSub>(line).unwrap_err().to_string(), message);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_publish_subscribe() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriber = backend.subscriber(16);
+
+        let replies =
+            command::<Subscribe>("subscribe news sport news")?.execute_subscribe(&mut subscriber);
+        assert_eq!(
+            replies,
+            [
+                subscription_reply("subscribe", Some("news".to_string()), 1),
+                subscription_reply("subscribe", Some("sport".to_string()), 2),
+                subscription_reply("subscribe", Some("news".to_string()), 2),
+            ]
+        );
+        let replies = command::<PSubscribe>("psubscribe n*")?.execute_psubscribe(&mut subscriber);
+        assert_eq!(
+            replies,
+            [subscription_reply("psubscribe", Some("n*".to_string()), 3)]
+        );
+
+        // the channel and the pattern both receive it
+        assert_eq!(
+            publish(&backend, "publish news hello")?,
+            RespFrame::Integer(2)
+        );
+        assert_eq!(
+            publish(&backend, "publish weather rain")?,
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            RespFrame::from(subscriber.recv().await.unwrap()),
+            strings(&["message", "news", "hello"])
+        );
+        assert_eq!(
+            RespFrame::from(subscriber.recv().await.unwrap()),
+            strings(&["pmessage", "n*", "news", "hello"])
+        );
+        assert_eq!(subscriber.try_recv(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsubscribe() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriber = backend.subscriber(16);
+        command::<Subscribe>("subscribe news")?.execute_subscribe(&mut subscriber);
+        command::<PSubscribe>("psubscribe n*")?.execute_psubscribe(&mut subscriber);
+
+        // the message published before is delivered before the reply
+        publish(&backend, "publish news hello")?;
+        let replies = command::<Unsubscribe>("unsubscribe")?.execute_unsubscribe(&mut subscriber);
+        assert_eq!(
+            replies,
+            [
+                strings(&["message", "news", "hello"]),
+                strings(&["pmessage", "n*", "news", "hello"]),
+                subscription_reply("unsubscribe", Some("news".to_string()), 1),
+            ]
+        );
+        assert_eq!(
+            publish(&backend, "publish news hello")?,
+            RespFrame::Integer(1)
+        );
+
+        let replies =
+            command::<PUnsubscribe>("punsubscribe n* x*")?.execute_punsubscribe(&mut subscriber);
+        assert_eq!(
+            replies[1..],
+            [
+                subscription_reply("punsubscribe", Some("n*".to_string()), 0),
+                subscription_reply("punsubscribe", Some("x*".to_string()), 0),
+            ]
+        );
+
+        // unsubscribing from nothing still replies
+        let replies = command::<Unsubscribe>("unsubscribe")?.execute_unsubscribe(&mut subscriber);
+        assert_eq!(replies, [subscription_reply("unsubscribe", None, 0)]);
+        assert_eq!(
+            publish(&backend, "publish news hello")?,
+            RespFrame::Integer(0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pubsub() -> Result<()> {
+        let backend = Backend::new();
+        let mut a = backend.subscriber(16);
+        let mut b = backend.subscriber(16);
+        a.subscribe("news".to_string());
+        a.subscribe("sport".to_string());
+        b.subscribe("news".to_string());
+        a.psubscribe("n*".to_string());
+        b.psubscribe("n*".to_string());
+        b.psubscribe("s*".to_string());
+
+        let mut channels = match command::<PubSub>("pubsub channels")?.execute(&backend) {
+            RespFrame::Array(v) => v.0,
+            frame => panic!("unexpected reply {:?}", frame),
+        };
+        channels.sort_by_key(|v| format!("{:?}", v));
+        assert_eq!(
+            RespFrame::from(RespArray::new(channels)),
+            strings(&["news", "sport"])
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub channels s*")?.execute(&backend),
+            strings(&["sport"])
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub numsub news sport weather")?.execute(&backend),
+            RespArray::new([
+                BulkString::from("news").into(),
+                RespFrame::Integer(2),
+                BulkString::from("sport").into(),
+                RespFrame::Integer(1),
+                BulkString::from("weather").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub numpat")?.execute(&backend),
+            RespFrame::Integer(2)
+        );
+
+        // a subscriber which goes away takes its subscriptions with it
+        drop(b);
+        assert_eq!(
+            command::<PubSub>("pubsub numsub news")?.execute(&backend),
+            RespArray::new([BulkString::from("news").into(), RespFrame::Integer(1)]).into()
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub numpat")?.execute(&backend),
+            RespFrame::Integer(1)
+        );
+        a.unsubscribe("sport");
+        assert_eq!(
+            command::<PubSub>("pubsub channels")?.execute(&backend),
+            strings(&["news"])
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber() -> Result<()> {
+        let backend = Backend::new();
+        let mut slow = backend.subscriber(2);
+        let mut fast = backend.subscriber(16);
+        slow.subscribe("news".to_string());
+        fast.subscribe("news".to_string());
+
+        assert_eq!(publish(&backend, "publish news 1")?, RespFrame::Integer(2));
+        assert_eq!(publish(&backend, "publish news 2")?, RespFrame::Integer(2));
+        // the buffer of the slow one is full, the publisher doesn't wait for it
+        assert_eq!(publish(&backend, "publish news 3")?, RespFrame::Integer(1));
+        assert_eq!(publish(&backend, "publish news 4")?, RespFrame::Integer(1));
+
+        assert!(slow.is_overflowed());
+        assert_eq!(slow.recv().await, None);
+        for payload in ["1", "2", "3", "4"] {
+            assert_eq!(
+                fast.recv().await.map(|v| v.payload),
+                Some(payload.as_bytes().to_vec())
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sharded_pubsub() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriber = backend.subscriber(16);
+        subscriber.subscribe("news".to_string());
+        subscriber.psubscribe("*".to_string());
+
+        let replies = command::<SSubscribe>("ssubscribe {user}.a {user}.b")?
+            .execute_ssubscribe(&mut subscriber);
+        // the shard channels are counted apart from the other subscriptions
+        assert_eq!(
+            replies,
+            [
+                subscription_reply("ssubscribe", Some("{user}.a".to_string()), 1),
+                subscription_reply("ssubscribe", Some("{user}.b".to_string()), 2),
+            ]
+        );
+        assert_eq!(subscriber.count(), 4);
+
+        // neither the channel of the same name nor the patterns see it
+        let cmd: SPublish = command("spublish {user}.a hello")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: SPublish = command("spublish news hello")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert_eq!(
+            RespFrame::from(subscriber.recv().await.unwrap()),
+            strings(&["smessage", "{user}.a", "hello"])
+        );
+        assert_eq!(subscriber.try_recv(), None);
+        // and the shard channels don't see PUBLISH
+        assert_eq!(
+            publish(&backend, "publish {user}.a hello")?,
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            subscriber.try_recv().and_then(|v| v.pattern),
+            Some("*".to_string())
+        );
+
+        assert_eq!(
+            command::<PubSub>("pubsub shardchannels *.a")?.execute(&backend),
+            strings(&["{user}.a"])
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub shardnumsub {user}.b news")?.execute(&backend),
+            RespArray::new([
+                BulkString::from("{user}.b").into(),
+                RespFrame::Integer(1),
+                BulkString::from("news").into(),
+                RespFrame::Integer(0),
+            ])
+            .into()
+        );
+        assert_eq!(
+            command::<PubSub>("pubsub channels")?.execute(&backend),
+            strings(&["news"])
+        );
+
+        let replies =
+            command::<SUnsubscribe>("sunsubscribe")?.execute_sunsubscribe(&mut subscriber);
+        assert_eq!(replies.len(), 2);
+        let replies =
+            command::<SUnsubscribe>("sunsubscribe")?.execute_sunsubscribe(&mut subscriber);
+        assert_eq!(replies, [subscription_reply("sunsubscribe", None, 0)]);
+        assert_eq!(
+            command::<PubSub>("pubsub shardchannels")?.execute(&backend),
+            strings(&[])
+        );
+        assert_eq!(subscriber.count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_hash_slot() {
+        for (key, slot) in [
+            ("123456789", 0x31c3),
+            ("foo", 12182),
+            ("{user1000}.following", key_hash_slot(b"user1000")),
+            ("{user1000}.followers", key_hash_slot(b"user1000")),
+            // the first tag counts
+            ("foo{{bar}}zap", key_hash_slot(b"{bar")),
+            ("foo{bar}{zap}", key_hash_slot(b"bar")),
+        ] {
+            assert_eq!(key_hash_slot(key.as_bytes()), slot, "{}", key);
+        }
+        // an empty tag doesn't, the whole key is hashed
+        assert_ne!(key_hash_slot(b"foo{}{bar}"), key_hash_slot(b"bar"));
+        assert_ne!(key_hash_slot(b"foo{}{bar}"), key_hash_slot(b""));
+        assert!(key_hash_slot(b"") < crate::CLUSTER_SLOTS);
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::{RespArray, RespFrame};
+
+use super::{
+    Append, BLMPop, BLMove, BLPop, BRPop, BitCount, BitField, BitOp, BitPos, Command, CommandError,
+    CommandInfo, Copy, DbSize, Decr, DecrBy, Del, Discard, Echo, Exec, Exists, Expire, FlushAll,
+    FlushDb, GeoAdd, GeoDist, GeoPos, GeoSearch, GeoSearchStore, Get, GetBit, GetDel, GetEx,
+    GetRange, HDel, HExists, HExpire, HGet, HGetAll, HIncrBy, HIncrByFloat, HKeys, HLen, HMGet,
+    HPExpire, HPTtl, HPersist, HRandField, HScan, HSet, HSetNx, HStrLen, HTtl, HVals, Hello, Incr,
+    IncrBy, IncrByFloat, Keys, LIndex, LInsert, LLen, LMove, LPop, LPush, LRange, LRem, LSet,
+    LTrim, MGet, MSet, MSetNx, Move, Multi, PExpire, PSubscribe, PTtl, PUnsubscribe, Persist,
+    PfAdd, PfCount, PfMerge, Ping, PubSub, Publish, RPop, RPush, RandomKey, Rename, RenameNx, SAdd,
+    SCard, SDiff, SDiffStore, SInter, SInterStore, SIsMember, SMIsMember, SMembers, SMove, SPop,
+    SPublish, SRandMember, SRem, SScan, SSubscribe, SUnion, SUnionStore, SUnsubscribe, Scan,
+    Select, Set, SetBit, SetRange, StrLen, Subscribe, SwapDb, Touch, Ttl, Type, Unlink,
+    Unsubscribe, Unwatch, Watch, XAck, XAdd, XAutoClaim, XClaim, XDel, XGroup, XLen, XPending,
+    XRange, XRead, XReadGroup, XRevRange, XTrim, ZAdd, ZCard, ZCount, ZIncrBy, ZPopMax, ZPopMin,
+    ZRange, ZRangeByScore, ZRank, ZRem, ZRevRangeByScore, ZRevRank, ZScan, ZScore,
+};
+
+pub type CommandParser = fn(RespArray) -> Result<Command, CommandError>;
+
+/// Static description of a command, the same information redis reports in `COMMAND INFO`.
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub group: &'static str,
+    // a positive arity means exactly that many arguments (the command name included), a negative
+    // one means at least that many
+    pub arity: i64,
+    pub flags: &'static [&'static str],
+    pub first_key: i64,
+    pub last_key: i64,
+    pub step: i64,
+    pub parser: CommandParser,
+}
+
+impl CommandSpec {
+    pub fn check_arity(&self, n: usize) -> bool {
+        let n = n as i64;
+        if self.arity >= 0 {
+            n == self.arity
+        } else {
+            n >= -self.arity
+        }
+    }
+
+    /// Whether the command may modify the keys it runs against.
+    pub fn is_write(&self) -> bool {
+        self.flags.contains(&"write")
+    }
+
+    /// The keys among the arguments of the command (the command name included), found with its
+    /// first key, last key and step like redis does. A negative last key counts from the end.
+    pub fn keys(&self, args: &RespArray) -> Vec<String> {
+        let n = args.len() as i64;
+        let last = if self.last_key < 0 {
+            n + self.last_key
+        } else {
+            self.last_key.min(n - 1)
+        };
+        if self.first_key <= 0 || self.step <= 0 {
+            return Vec::new();
+        }
+
+        (self.first_key..=last)
+            .step_by(self.step as usize)
+            .filter_map(|i| match &args[i as usize] {
+                RespFrame::BulkString(key) => Some(String::from_utf8_lossy(key).into_owned()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+const fn spec(
+    name: &'static str,
+    group: &'static str,
+    arity: i64,
+    flags: &'static [&'static str],
+    (first_key, last_key, step): (i64, i64, i64),
+    parser: CommandParser,
+) -> CommandSpec {
+    CommandSpec {
+        name,
+        group,
+        arity,
+        flags,
+        first_key,
+        last_key,
+        step,
+        parser,
+    }
+}
+
+fn parse<T>(v: RespArray) -> Result<Command, CommandError>
+where
+    T: TryFrom<RespArray, Error = CommandError> + Into<Command>,
+{
+    Ok(T::try_from(v)?.into())
+}
+
+const NO_KEYS: (i64, i64, i64) = (0, 0, 0);
+const ONE_KEY: (i64, i64, i64) = (1, 1, 1);
+const ALL_KEYS: (i64, i64, i64) = (1, -1, 1);
+
+#[rustfmt::skip]
+pub const COMMAND_TABLE: &[CommandSpec] = &[
+    // connection
+    spec("ping", "connection", -1, &["fast"], NO_KEYS, parse::<Ping>),
+    spec("echo", "connection", 2, &["fast"], NO_KEYS, parse::<Echo>),
+    spec("select", "connection", 2, &["loading", "stale", "fast"], NO_KEYS, parse::<Select>),
+    spec("hello", "connection", -1, &["noscript", "loading", "stale", "fast"], NO_KEYS, parse::<Hello>),
+    // transactions
+    spec("multi", "transactions", 1, &["noscript", "loading", "stale", "fast"], NO_KEYS, parse::<Multi>),
+    spec("exec", "transactions", 1, &["noscript", "loading", "stale", "skip_slowlog"], NO_KEYS, parse::<Exec>),
+    spec("discard", "transactions", 1, &["noscript", "loading", "stale", "fast"], NO_KEYS, parse::<Discard>),
+    spec("watch", "transactions", -2, &["noscript", "loading", "stale", "fast"], ALL_KEYS, parse::<Watch>),
+    spec("unwatch", "transactions", 1, &["noscript", "loading", "stale", "fast"], NO_KEYS, parse::<Unwatch>),
+    // pub/sub
+    spec("subscribe", "pubsub", -2, &["pubsub", "noscript", "loading", "stale"], NO_KEYS, parse::<Subscribe>),
+    spec("unsubscribe", "pubsub", -1, &["pubsub", "noscript", "loading", "stale"], NO_KEYS, parse::<Unsubscribe>),
+    spec("psubscribe", "pubsub", -2, &["pubsub", "noscript", "loading", "stale"], NO_KEYS, parse::<PSubscribe>),
+    spec("punsubscribe", "pubsub", -1, &["pubsub", "noscript", "loading", "stale"], NO_KEYS, parse::<PUnsubscribe>),
+    spec("publish", "pubsub", 3, &["pubsub", "loading", "stale", "fast"], NO_KEYS, parse::<Publish>),
+    spec("ssubscribe", "pubsub", -2, &["pubsub", "noscript", "loading", "stale"], ALL_KEYS, parse::<SSubscribe>),
+    spec("sunsubscribe", "pubsub", -1, &["pubsub", "noscript", "loading", "stale"], ALL_KEYS, parse::<SUnsubscribe>),
+    spec("spublish", "pubsub", 3, &["pubsub", "loading", "stale", "fast"], ONE_KEY, parse::<SPublish>),
+    spec("pubsub", "pubsub", -2, &["pubsub", "loading", "stale"], NO_KEYS, parse::<PubSub>),
+    // server
+    spec("command", "server", -1, &["loading", "stale"], NO_KEYS, parse::<CommandInfo>),
+    spec("swapdb", "server", 3, &["write", "fast"], NO_KEYS, parse::<SwapDb>),
+    spec("flushdb", "server", -1, &["write"], NO_KEYS, parse::<FlushDb>),
+    spec("flushall", "server", -1, &["write"], NO_KEYS, parse::<FlushAll>),
+    spec("dbsize", "server", 1, &["readonly", "fast"], NO_KEYS, parse::<DbSize>),
+    // generic
+    spec("expire", "generic", 3, &["write", "fast"], ONE_KEY, parse::<Expire>),
+    spec("pexpire", "generic", 3, &["write", "fast"], ONE_KEY, parse::<PExpire>),
+    spec("ttl", "generic", 2, &["readonly", "fast"], ONE_KEY, parse::<Ttl>),
+    spec("pttl", "generic", 2, &["readonly", "fast"], ONE_KEY, parse::<PTtl>),
+    spec("persist", "generic", 2, &["write", "fast"], ONE_KEY, parse::<Persist>),
+    spec("type", "generic", 2, &["readonly", "fast"], ONE_KEY, parse::<Type>),
+    spec("del", "generic", -2, &["write"], ALL_KEYS, parse::<Del>),
+    spec("unlink", "generic", -2, &["write", "fast"], ALL_KEYS, parse::<Unlink>),
+    spec("exists", "generic", -2, &["readonly", "fast"], ALL_KEYS, parse::<Exists>),
+    spec("touch", "generic", -2, &["readonly", "fast"], ALL_KEYS, parse::<Touch>),
+    spec("rename", "generic", 3, &["write"], (1, 2, 1), parse::<Rename>),
+    spec("renamenx", "generic", 3, &["write", "fast"], (1, 2, 1), parse::<RenameNx>),
+    spec("copy", "generic", -3, &["write", "denyoom"], (1, 2, 1), parse::<Copy>),
+    spec("randomkey", "generic", 1, &["readonly"], NO_KEYS, parse::<RandomKey>),
+    spec("scan", "generic", -2, &["readonly"], NO_KEYS, parse::<Scan>),
+    spec("keys", "generic", 2, &["readonly"], NO_KEYS, parse::<Keys>),
+    spec("move", "generic", 3, &["write", "fast"], ONE_KEY, parse::<Move>),
+    // string
+    spec("get", "string", 2, &["readonly", "fast"], ONE_KEY, parse::<Get>),
+    spec("set", "string", -3, &["write", "denyoom"], ONE_KEY, parse::<Set>),
+    spec("incr", "string", 2, &["write", "denyoom", "fast"], ONE_KEY, parse::<Incr>),
+    spec("decr", "string", 2, &["write", "denyoom", "fast"], ONE_KEY, parse::<Decr>),
+    spec("incrby", "string", 3, &["write", "denyoom", "fast"], ONE_KEY, parse::<IncrBy>),
+    spec("decrby", "string", 3, &["write", "denyoom", "fast"], ONE_KEY, parse::<DecrBy>),
+    spec("incrbyfloat", "string", 3, &["write", "denyoom", "fast"], ONE_KEY, parse::<IncrByFloat>),
+    spec("append", "string", 3, &["write", "denyoom", "fast"], ONE_KEY, parse::<Append>),
+    spec("strlen", "string", 2, &["readonly", "fast"], ONE_KEY, parse::<StrLen>),
+    spec("getrange", "string", 4, &["readonly"], ONE_KEY, parse::<GetRange>),
+    spec("setrange", "string", 4, &["write", "denyoom"], ONE_KEY, parse::<SetRange>),
+    spec("getdel", "string", 2, &["write", "fast"], ONE_KEY, parse::<GetDel>),
+    spec("getex", "string", -2, &["write", "fast"], ONE_KEY, parse::<GetEx>),
+    spec("mget", "string", -2, &["readonly", "fast"], ALL_KEYS, parse::<MGet>),
+    spec("mset", "string", -3, &["write", "denyoom"], (1, -1, 2), parse::<MSet>),
+    spec("msetnx", "string", -3, &["write", "denyoom"], (1, -1, 2), parse::<MSetNx>),
+    spec("setbit", "bitmap", 4, &["write", "denyoom"], ONE_KEY, parse::<SetBit>),
+    spec("getbit", "bitmap", 3, &["readonly", "fast"], ONE_KEY, parse::<GetBit>),
+    spec("bitcount", "bitmap", -2, &["readonly"], ONE_KEY, parse::<BitCount>),
+    spec("bitpos", "bitmap", -3, &["readonly"], ONE_KEY, parse::<BitPos>),
+    spec("bitop", "bitmap", -4, &["write", "denyoom"], (2, -1, 1), parse::<BitOp>),
+    spec("bitfield", "bitmap", -2, &["write", "denyoom"], ONE_KEY, parse::<BitField>),
+    spec("pfadd", "hyperloglog", -2, &["write", "denyoom", "fast"], ONE_KEY, parse::<PfAdd>),
+    spec("pfcount", "hyperloglog", -2, &["readonly"], ALL_KEYS, parse::<PfCount>),
+    spec("pfmerge", "hyperloglog", -2, &["write", "denyoom"], ALL_KEYS, parse::<PfMerge>),
+    // hash
+    spec("hget", "hash", 3, &["readonly", "fast"], ONE_KEY, parse::<HGet>),
+    spec("hmget", "hash", -3, &["readonly", "fast"], ONE_KEY, parse::<HMGet>),
+    spec("hset", "hash", -4, &["write", "denyoom", "fast"], ONE_KEY, parse::<HSet>),
+    spec("hgetall", "hash", 2, &["readonly"], ONE_KEY, parse::<HGetAll>),
+    spec("hdel", "hash", -3, &["write", "fast"], ONE_KEY, parse::<HDel>),
+    spec("hexists", "hash", 3, &["readonly", "fast"], ONE_KEY, parse::<HExists>),
+    spec("hlen", "hash", 2, &["readonly", "fast"], ONE_KEY, parse::<HLen>),
+    spec("hkeys", "hash", 2, &["readonly"], ONE_KEY, parse::<HKeys>),
+    spec("hvals", "hash", 2, &["readonly"], ONE_KEY, parse::<HVals>),
+    spec("hincrby", "hash", 4, &["write", "denyoom", "fast"], ONE_KEY, parse::<HIncrBy>),
+    spec("hincrbyfloat", "hash", 4, &["write", "denyoom", "fast"], ONE_KEY, parse::<HIncrByFloat>),
+    spec("hsetnx", "hash", 4, &["write", "denyoom", "fast"], ONE_KEY, parse::<HSetNx>),
+    spec("hstrlen", "hash", 3, &["readonly", "fast"], ONE_KEY, parse::<HStrLen>),
+    spec("hrandfield", "hash", -2, &["readonly"], ONE_KEY, parse::<HRandField>),
+    spec("hexpire", "hash", -6, &["write", "fast"], ONE_KEY, parse::<HExpire>),
+    spec("hpexpire", "hash", -6, &["write", "fast"], ONE_KEY, parse::<HPExpire>),
+    spec("httl", "hash", -5, &["readonly", "fast"], ONE_KEY, parse::<HTtl>),
+    spec("hpttl", "hash", -5, &["readonly", "fast"], ONE_KEY, parse::<HPTtl>),
+    spec("hpersist", "hash", -5, &["write", "fast"], ONE_KEY, parse::<HPersist>),
+    spec("hscan", "hash", -3, &["readonly"], ONE_KEY, parse::<HScan>),
+    // set
+    spec("sadd", "set", -3, &["write", "denyoom", "fast"], ONE_KEY, parse::<SAdd>),
+    spec("srem", "set", -3, &["write", "fast"], ONE_KEY, parse::<SRem>),
+    spec("smembers", "set", 2, &["readonly"], ONE_KEY, parse::<SMembers>),
+    spec("scard", "set", 2, &["readonly", "fast"], ONE_KEY, parse::<SCard>),
+    spec("sismember", "set", 3, &["readonly", "fast"], ONE_KEY, parse::<SIsMember>),
+    spec("smismember", "set", -3, &["readonly", "fast"], ONE_KEY, parse::<SMIsMember>),
+    spec("spop", "set", -2, &["write", "fast"], ONE_KEY, parse::<SPop>),
+    spec("srandmember", "set", -2, &["readonly"], ONE_KEY, parse::<SRandMember>),
+    spec("smove", "set", 4, &["write", "fast"], (1, 2, 1), parse::<SMove>),
+    spec("sinter", "set", -2, &["readonly"], ALL_KEYS, parse::<SInter>),
+    spec("sunion", "set", -2, &["readonly"], ALL_KEYS, parse::<SUnion>),
+    spec("sdiff", "set", -2, &["readonly"], ALL_KEYS, parse::<SDiff>),
+    spec("sinterstore", "set", -3, &["write", "denyoom"], ALL_KEYS, parse::<SInterStore>),
+    spec("sunionstore", "set", -3, &["write", "denyoom"], ALL_KEYS, parse::<SUnionStore>),
+    spec("sdiffstore", "set", -3, &["write", "denyoom"], ALL_KEYS, parse::<SDiffStore>),
+    spec("sscan", "set", -3, &["readonly"], ONE_KEY, parse::<SScan>),
+    // list
+    spec("lpush", "list", -3, &["write", "denyoom", "fast"], ONE_KEY, parse::<LPush>),
+    spec("rpush", "list", -3, &["write", "denyoom", "fast"], ONE_KEY, parse::<RPush>),
+    spec("lpop", "list", -2, &["write", "fast"], ONE_KEY, parse::<LPop>),
+    spec("rpop", "list", -2, &["write", "fast"], ONE_KEY, parse::<RPop>),
+    spec("llen", "list", 2, &["readonly", "fast"], ONE_KEY, parse::<LLen>),
+    spec("lrange", "list", 4, &["readonly"], ONE_KEY, parse::<LRange>),
+    spec("lindex", "list", 3, &["readonly"], ONE_KEY, parse::<LIndex>),
+    spec("lset", "list", 4, &["write", "denyoom"], ONE_KEY, parse::<LSet>),
+    spec("lrem", "list", 4, &["write"], ONE_KEY, parse::<LRem>),
+    spec("ltrim", "list", 4, &["write"], ONE_KEY, parse::<LTrim>),
+    spec("linsert", "list", 5, &["write", "denyoom"], ONE_KEY, parse::<LInsert>),
+    spec("lmove", "list", 5, &["write", "denyoom"], (1, 2, 1), parse::<LMove>),
+    spec("blpop", "list", -3, &["write", "blocking"], (1, -2, 1), parse::<BLPop>),
+    spec("brpop", "list", -3, &["write", "blocking"], (1, -2, 1), parse::<BRPop>),
+    spec("blmove", "list", 6, &["write", "denyoom", "blocking"], (1, 2, 1), parse::<BLMove>),
+    spec("blmpop", "list", -5, &["write", "blocking"], NO_KEYS, parse::<BLMPop>),
+    // sorted set
+    spec("zadd", "sorted_set", -4, &["write", "denyoom", "fast"], ONE_KEY, parse::<ZAdd>),
+    spec("zcard", "sorted_set", 2, &["readonly", "fast"], ONE_KEY, parse::<ZCard>),
+    spec("zscore", "sorted_set", 3, &["readonly", "fast"], ONE_KEY, parse::<ZScore>),
+    spec("zincrby", "sorted_set", 4, &["write", "denyoom", "fast"], ONE_KEY, parse::<ZIncrBy>),
+    spec("zrem", "sorted_set", -3, &["write", "fast"], ONE_KEY, parse::<ZRem>),
+    spec("zrank", "sorted_set", -3, &["readonly", "fast"], ONE_KEY, parse::<ZRank>),
+    spec("zrevrank", "sorted_set", -3, &["readonly", "fast"], ONE_KEY, parse::<ZRevRank>),
+    spec("zcount", "sorted_set", 4, &["readonly", "fast"], ONE_KEY, parse::<ZCount>),
+    spec("zrange", "sorted_set", -4, &["readonly"], ONE_KEY, parse::<ZRange>),
+    spec("zrangebyscore", "sorted_set", -4, &["readonly"], ONE_KEY, parse::<ZRangeByScore>),
+    spec("zrevrangebyscore", "sorted_set", -4, &["readonly"], ONE_KEY, parse::<ZRevRangeByScore>),
+    spec("zpopmin", "sorted_set", -2, &["write", "fast"], ONE_KEY, parse::<ZPopMin>),
+    spec("zpopmax", "sorted_set", -2, &["write", "fast"], ONE_KEY, parse::<ZPopMax>),
+    spec("zscan", "sorted_set", -3, &["readonly"], ONE_KEY, parse::<ZScan>),
+    spec("geoadd", "geo", -5, &["write", "denyoom"], ONE_KEY, parse::<GeoAdd>),
+    spec("geopos", "geo", -2, &["readonly"], ONE_KEY, parse::<GeoPos>),
+    spec("geodist", "geo", -4, &["readonly"], ONE_KEY, parse::<GeoDist>),
+    spec("geosearch", "geo", -7, &["readonly"], ONE_KEY, parse::<GeoSearch>),
+    spec("geosearchstore", "geo", -8, &["write", "denyoom"], (1, 2, 1), parse::<GeoSearchStore>),
+    // stream
+    spec("xadd", "stream", -5, &["write", "denyoom", "fast"], ONE_KEY, parse::<XAdd>),
+    spec("xlen", "stream", 2, &["readonly", "fast"], ONE_KEY, parse::<XLen>),
+    spec("xrange", "stream", -4, &["readonly"], ONE_KEY, parse::<XRange>),
+    spec("xrevrange", "stream", -4, &["readonly"], ONE_KEY, parse::<XRevRange>),
+    spec("xtrim", "stream", -4, &["write"], ONE_KEY, parse::<XTrim>),
+    spec("xdel", "stream", -3, &["write", "fast"], ONE_KEY, parse::<XDel>),
+    spec("xread", "stream", -4, &["readonly", "blocking"], NO_KEYS, parse::<XRead>),
+    spec("xgroup", "stream", -2, &["write"], (2, 2, 1), parse::<XGroup>),
+    spec("xreadgroup", "stream", -7, &["write", "blocking"], NO_KEYS, parse::<XReadGroup>),
+    spec("xack", "stream", -4, &["write", "fast"], ONE_KEY, parse::<XAck>),
+    spec("xpending", "stream", -3, &["readonly"], ONE_KEY, parse::<XPending>),
+    spec("xclaim", "stream", -6, &["write", "fast"], ONE_KEY, parse::<XClaim>),
+    spec("xautoclaim", "stream", -6, &["write", "fast"], ONE_KEY, parse::<XAutoClaim>),
+];
+
+lazy_static! {
+    static ref COMMANDS: HashMap<&'static str, &'static CommandSpec> =
+        COMMAND_TABLE.iter().map(|spec| (spec.name, spec)).collect();
+}
+
+/// Look up a command by its name, the name is case insensitive.
+pub fn lookup_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS
+        .get(name)
+        .or_else(|| COMMANDS.get(name.to_ascii_lowercase().as_str()))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_table_is_unique() {
+        assert_eq!(COMMANDS.len(), COMMAND_TABLE.len());
+    }
+
+    #[test]
+    fn test_check_arity() {
+        let spec = lookup_command("get").unwrap();
+        assert!(spec.check_arity(2));
+        assert!(!spec.check_arity(3));
+
+        let spec = lookup_command("set").unwrap();
+        assert!(!spec.check_arity(2));
+        assert!(spec.check_arity(3));
+        assert!(spec.check_arity(5));
+    }
+
+    #[test]
+    fn test_lookup_command() {
+        let spec = lookup_command("GET").unwrap();
+        assert_eq!(spec.name, "get");
+        assert_eq!(spec.arity, 2);
+        assert_eq!((spec.first_key, spec.last_key, spec.step), (1, 1, 1));
+
+        assert!(lookup_command("hsett").is_none());
+    }
+
+    #[test]
+    fn test_command_keys() {
+        let keys = |line: &str| {
+            let args = crate::cmd::request_array(line);
+            let spec = lookup_command(&line[..line.find(' ').unwrap_or(line.len())]).unwrap();
+            spec.keys(&args)
+        };
+
+        assert_eq!(keys("get key"), vec!["key"]);
+        assert_eq!(keys("mset a 1 b 2"), vec!["a", "b"]);
+        assert_eq!(keys("blpop a b 0"), vec!["a", "b"]);
+        assert_eq!(keys("bitop AND dest a b"), vec!["dest", "a", "b"]);
+        assert_eq!(keys("xgroup HELP"), Vec::<String>::new());
+        assert_eq!(keys("dbsize"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,465 @@
+use crate::{Backend, BulkString, RespArray, RespFrame, ScanPage};
+
+use super::{
+    extract_args, parse_bytes, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, HScan, Keys, SScan, Scan, ZScan,
+};
+
+// the number of elements a page holds when COUNT is not given
+const DEFAULT_COUNT: usize = 10;
+
+impl CommandExecutor for Scan {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let page = backend.scan(
+            self.cursor,
+            self.count,
+            self.pattern.as_deref(),
+            self.type_name.as_deref(),
+        );
+        page_to_frame(page, |key| vec![BulkString::from(key).into()])
+    }
+}
+
+impl CommandExecutor for Keys {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let keys = backend
+            .keys(&self.pattern)
+            .into_iter()
+            .map(|key| BulkString::from(key).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new(keys).into()
+    }
+}
+
+impl CommandExecutor for HScan {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.hscan(&self.key, self.cursor, self.count, self.pattern.as_deref()) {
+            Ok(page) => page_to_frame(page, |(field, value)| {
+                if self.no_values {
+                    vec![BulkString::from(field).into()]
+                } else {
+                    vec![BulkString::from(field).into(), value]
+                }
+            }),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SScan {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.sscan(&self.key, self.cursor, self.count, self.pattern.as_deref()) {
+            Ok(page) => page_to_frame(page, |member| vec![BulkString::new(member).into()]),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZScan {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zscan(&self.key, self.cursor, self.count, self.pattern.as_deref()) {
+            Ok(page) => page_to_frame(page, |(member, score)| {
+                vec![BulkString::new(member).into(), RespFrame::Double(score)]
+            }),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// the cursor of the next call as a bulk string, followed by the flat array of the elements
+fn page_to_frame<T>(page: ScanPage<T>, f: impl Fn(T) -> Vec<RespFrame>) -> RespFrame {
+    let elements = page.elements.into_iter().flat_map(f).collect::<Vec<_>>();
+    RespArray::new(vec![
+        BulkString::from(page.cursor.to_string()).into(),
+        RespArray::new(elements).into(),
+    ])
+    .into()
+}
+
+impl TryFrom<RespArray> for Scan {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["scan"], 1)?;
+
+        // SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let cursor = parse_cursor(args.next())?;
+        let options = parse_scan_options(args, &["type"])?;
+        Ok(Scan {
+            cursor,
+            count: options.count,
+            pattern: options.pattern,
+            type_name: options.type_name,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for Keys {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["keys"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let pattern = parse_bytes(args.next())?;
+        Ok(Keys { pattern })
+    }
+}
+
+impl TryFrom<RespArray> for HScan {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["hscan"], 2)?;
+
+        // HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let cursor = parse_cursor(args.next())?;
+        let options = parse_scan_options(args, &["novalues"])?;
+        Ok(HScan {
+            key,
+            cursor,
+            count: options.count,
+            pattern: options.pattern,
+            no_values: options.no_values,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SScan {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["sscan"], 2)?;
+
+        // SSCAN key cursor [MATCH pattern] [COUNT count]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let cursor = parse_cursor(args.next())?;
+        let options = parse_scan_options(args, &[])?;
+        Ok(SScan {
+            key,
+            cursor,
+            count: options.count,
+            pattern: options.pattern,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZScan {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["zscan"], 2)?;
+
+        // ZSCAN key cursor [MATCH pattern] [COUNT count]
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let cursor = parse_cursor(args.next())?;
+        let options = parse_scan_options(args, &[])?;
+        Ok(ZScan {
+            key,
+            cursor,
+            count: options.count,
+            pattern: options.pattern,
+        })
+    }
+}
+
+struct ScanOptions {
+    count: usize,
+    pattern: Option<Vec<u8>>,
+    type_name: Option<String>,
+    no_values: bool,
+}
+
+fn parse_cursor(arg: Option<RespFrame>) -> Result<u64, CommandError> {
+    String::from_utf8_lossy(&parse_bytes(arg)?)
+        .parse()
+        .map_err(|_| CommandError::InvalidArgument("invalid cursor".to_string()))
+}
+
+// MATCH and COUNT are accepted by every command of the family, `extra` lists the other
+// options the command takes
+fn parse_scan_options(
+    mut args: impl Iterator<Item = RespFrame>,
+    extra: &[&str],
+) -> Result<ScanOptions, CommandError> {
+    let mut options = ScanOptions {
+        count: DEFAULT_COUNT,
+        pattern: None,
+        type_name: None,
+        no_values: false,
+    };
+    while let Some(arg) = args.next() {
+        let option = parse_option(&arg).unwrap_or_default();
+        match option.as_str() {
+            "match" => options.pattern = Some(parse_bytes(args.next())?),
+            "count" => {
+                let count = parse_integer(args.next())?;
+                if count < 1 {
+                    return Err(CommandError::SyntaxError);
+                }
+                options.count = count as usize;
+            }
+            "type" if extra.contains(&"type") => {
+                options.type_name = Some(parse_string(args.next())?)
+            }
+            "novalues" if extra.contains(&"novalues") => options.no_values = true,
+            _ => return Err(CommandError::SyntaxError),
+        }
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::cmd::command;
+    use crate::{cmd::Command, glob_match};
+    use anyhow::Result;
+
+    // run the command with the cursor and return the next cursor with the elements
+    fn page(backend: &Backend, line: &str) -> Result<(String, Vec<String>)> {
+        let cmd: Command = command(line)?;
+        let RespFrame::Array(frames) = cmd.execute(backend) else {
+            panic!("expected an array");
+        };
+        let mut frames = frames.0.into_iter();
+        let (Some(RespFrame::BulkString(cursor)), Some(RespFrame::Array(elements))) =
+            (frames.next(), frames.next())
+        else {
+            panic!("expected a cursor and an array");
+        };
+        let elements = elements
+            .0
+            .into_iter()
+            .map(|v| match v {
+                RespFrame::BulkString(v) => String::from_utf8_lossy(&v).into_owned(),
+                RespFrame::Double(v) => v.to_string(),
+                v => format!("{:?}", v),
+            })
+            .collect();
+        Ok((String::from_utf8(cursor.0)?, elements))
+    }
+
+    // iterate until the cursor is back to 0, calling `between` after every page
+    fn scan_all(
+        backend: &Backend,
+        line: &str,
+        mut between: impl FnMut(&Backend),
+    ) -> Result<Vec<String>> {
+        let mut cursor = "0".to_string();
+        let mut all = Vec::new();
+        loop {
+            let line = line.replacen("{}", &cursor, 1);
+            let (next, elements) = page(backend, &line)?;
+            all.extend(elements);
+            if next == "0" {
+                return Ok(all);
+            }
+            cursor = next;
+            between(backend);
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        for (pattern, string, expected) in [
+            ("*", "", true),
+            ("*", "hello", true),
+            ("h?llo", "hello", true),
+            ("h?llo", "hllo", false),
+            ("h*llo", "heeeello", true),
+            ("h*llo", "hello world", false),
+            ("*o*o*", "hello world", true),
+            ("h[ae]llo", "hallo", true),
+            ("h[ae]llo", "hillo", false),
+            ("h[^e]llo", "hallo", true),
+            ("h[^e]llo", "hello", false),
+            ("h[a-b]llo", "hbllo", true),
+            ("h[b-a]llo", "hallo", true),
+            ("h[a-b]llo", "hcllo", false),
+            ("h\\*llo", "h*llo", true),
+            ("h\\*llo", "hello", false),
+            ("h[\\]]llo", "h]llo", true),
+            ("user:*:name", "user:1000:name", true),
+            ("user:*:name", "user:1000:age", false),
+            ("a*a*a*a*a*a*a*b", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", false),
+        ] {
+            assert_eq!(
+                glob_match(pattern.as_bytes(), string.as_bytes()),
+                expected,
+                "{} {}",
+                pattern,
+                string
+            );
+        }
+    }
+
+    #[test]
+    fn test_keys() -> Result<()> {
+        let backend = Backend::new();
+        for key in ["hello", "hallo", "hillo", "world"] {
+            backend.set(key.to_string(), b"v".to_vec());
+        }
+
+        let cmd: Keys = command("keys h[ae]llo")?;
+        let RespFrame::Array(keys) = cmd.execute(&backend) else {
+            panic!("expected an array");
+        };
+        let keys = keys
+            .0
+            .into_iter()
+            .map(|v| match v {
+                RespFrame::BulkString(v) => String::from_utf8_lossy(&v).into_owned(),
+                v => panic!("unexpected {:?}", v),
+            })
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            keys,
+            HashSet::from(["hello".to_string(), "hallo".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan() -> Result<()> {
+        let backend = Backend::new();
+        for i in 0..100 {
+            backend.set(format!("key:{}", i), b"v".to_vec());
+        }
+        backend.sadd("set".to_string(), vec![b"a".to_vec()])?;
+
+        let keys = scan_all(&backend, "scan {} count 7", |_| {})?;
+        assert_eq!(keys.len(), 101);
+        assert_eq!(keys.iter().collect::<HashSet<_>>().len(), 101);
+
+        let keys = scan_all(&backend, "scan {} match key:1? count 20", |_| {})?;
+        assert_eq!(keys.len(), 10);
+        let keys = scan_all(&backend, "scan {} type set", |_| {})?;
+        assert_eq!(keys, vec!["set".to_string()]);
+
+        // a page picks the keys before filtering them, so it may be empty
+        let (cursor, keys) = page(&backend, "scan 0 match set count 1")?;
+        assert_ne!(cursor, "0");
+        assert!(keys.len() <= 1);
+
+        // the cursors don't depend on the process, a client may resume after a restart
+        let backend = Backend::new();
+        backend.sadd("set".to_string(), vec![b"a".to_vec(), b"b".to_vec()])?;
+        assert_eq!(
+            page(&backend, "sscan set 0 count 1")?,
+            ("4482684837372322821".to_string(), vec!["a".to_string()])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_while_resizing() -> Result<()> {
+        let backend = Backend::new();
+        for i in 0..50 {
+            backend.set(format!("key:{}", i), b"v".to_vec());
+        }
+
+        // the keyspace grows a lot and some keys come and go during the iteration
+        let mut n = 0;
+        let keys = scan_all(&backend, "scan {} count 5", |backend| {
+            if n < 2000 {
+                for _ in 0..200 {
+                    backend.set(format!("new:{}", n), b"v".to_vec());
+                    n += 1;
+                }
+            }
+            backend.del(&["key:0".to_string(), format!("new:{}", n / 2)]);
+        })?;
+        let keys = keys.into_iter().collect::<HashSet<_>>();
+        for i in 1..50 {
+            assert!(keys.contains(&format!("key:{}", i)), "missing key:{}", i);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hscan_sscan_zscan() -> Result<()> {
+        let backend = Backend::new();
+        let fields = (0..30)
+            .map(|i| {
+                (
+                    format!("f{}", i),
+                    BulkString::from(format!("v{}", i)).into(),
+                )
+            })
+            .collect();
+        backend.hset("hash".to_string(), fields)?;
+        let members = (0..30).map(|i| format!("m{}", i).into_bytes()).collect();
+        backend.sadd("set".to_string(), members)?;
+        backend.zadd(
+            "zset".to_string(),
+            vec![(1.5, b"a".to_vec()), (2.0, b"b".to_vec())],
+            Default::default(),
+        )?;
+
+        let elements = scan_all(&backend, "hscan hash {} count 4", |_| {})?;
+        assert_eq!(elements.len(), 60);
+        let pairs = elements
+            .chunks(2)
+            .map(|v| (v[0].clone(), v[1].clone()))
+            .collect::<HashSet<_>>();
+        assert!(pairs.contains(&("f7".to_string(), "v7".to_string())));
+        assert_eq!(pairs.len(), 30);
+
+        let elements = scan_all(&backend, "hscan hash {} match f1* novalues", |_| {})?;
+        assert_eq!(elements.len(), 11);
+        assert!(elements.iter().all(|v| v.starts_with("f1")));
+
+        let elements = scan_all(&backend, "sscan set {} count 3", |_| {})?;
+        assert_eq!(elements.iter().collect::<HashSet<_>>().len(), 30);
+
+        let elements = scan_all(&backend, "zscan zset {}", |_| {})?;
+        let pairs = elements
+            .chunks(2)
+            .map(|v| v.join(" "))
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            pairs,
+            HashSet::from(["a 1.5".to_string(), "b 2".to_string()])
+        );
+
+        let (cursor, elements) = page(&backend, "sscan missing 0")?;
+        assert_eq!(cursor, "0");
+        assert!(elements.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_errors() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("string".to_string(), b"v".to_vec());
+
+        assert!(command::<Scan>("scan abc").is_err());
+        assert!(command::<Scan>("scan -1").is_err());
+        assert!(command::<Scan>("scan 0 count 0").is_err());
+        assert!(command::<Scan>("scan 0 novalues").is_err());
+        assert!(command::<SScan>("sscan key 0 type set").is_err());
+        assert!(command::<HScan>("hscan key 0 match").is_err());
+
+        let cmd: SScan = command("sscan string 0")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespFrame::Error(
+                "WRONGTYPE Operation against a key holding the wrong kind of value".into()
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -1,42 +1,260 @@
-use crate::{Backend, RespArray, RespFrame};
+use crate::{Backend, BulkString, RespArray, RespFrame, RespNull, SetOperation};
 
 use super::{
-    extract_args, validate_command, CommandError, CommandExecutor, SAdd, SIsMember, RESP_OK,
+    extract_args, parse_bytes, parse_integer, parse_string, random_count, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, SAdd, SCard, SDiff, SDiffStore,
+    SInter, SInterStore, SIsMember, SMIsMember, SMembers, SMove, SPop, SRandMember, SRem, SUnion,
+    SUnionStore,
 };
 
 impl CommandExecutor for SAdd {
     fn execute(self, backend: &Backend) -> RespFrame {
-        backend.sadd(self.key, self.member);
+        match backend.sadd(self.key, self.members) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.srem(&self.key, &self.members) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
 
-        RESP_OK.clone()
+impl CommandExecutor for SMembers {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.smembers(&self.key) {
+            Ok(members) => members_to_frame(members),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.scard(&self.key) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
     }
 }
 
 impl CommandExecutor for SIsMember {
     fn execute(self, backend: &Backend) -> RespFrame {
         match backend.sismember(&self.key, &self.member) {
-            true => RespFrame::Integer(1),
-            false => RespFrame::Integer(0),
+            Ok(true) => RespFrame::Integer(1),
+            Ok(false) => RespFrame::Integer(0),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SMIsMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.smismember(&self.key, &self.members) {
+            Ok(ret) => RespArray::new(
+                ret.into_iter()
+                    .map(|v| RespFrame::Integer(v as i64))
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SPop {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match (backend.spop(&self.key, self.count.unwrap_or(1)), self.count) {
+            (Ok(members), Some(_)) => members_to_frame(members),
+            (Ok(members), None) => match members.into_iter().next() {
+                Some(member) => BulkString::new(member).into(),
+                None => RespFrame::Null(RespNull),
+            },
+            (Err(e), _) => e.into(),
         }
     }
 }
 
+impl CommandExecutor for SRandMember {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match (
+            backend.srandmember(&self.key, self.count.unwrap_or(1)),
+            self.count,
+        ) {
+            (Ok(members), Some(_)) => members_to_frame(members),
+            (Ok(members), None) => match members.into_iter().next() {
+                Some(member) => BulkString::new(member).into(),
+                None => RespFrame::Null(RespNull),
+            },
+            (Err(e), _) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SMove {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.smove(&self.source, self.destination, self.member) {
+            Ok(moved) => RespFrame::Integer(moved as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for SInter {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation(backend, SetOperation::Inter, &self.keys)
+    }
+}
+
+impl CommandExecutor for SUnion {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation(backend, SetOperation::Union, &self.keys)
+    }
+}
+
+impl CommandExecutor for SDiff {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation(backend, SetOperation::Diff, &self.keys)
+    }
+}
+
+impl CommandExecutor for SInterStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation_store(backend, SetOperation::Inter, self.destination, &self.keys)
+    }
+}
+
+impl CommandExecutor for SUnionStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation_store(backend, SetOperation::Union, self.destination, &self.keys)
+    }
+}
+
+impl CommandExecutor for SDiffStore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        set_operation_store(backend, SetOperation::Diff, self.destination, &self.keys)
+    }
+}
+
+fn set_operation(backend: &Backend, op: SetOperation, keys: &[String]) -> RespFrame {
+    match backend.set_operation(op, keys) {
+        Ok(members) => members_to_frame(members),
+        Err(e) => e.into(),
+    }
+}
+
+fn set_operation_store(
+    backend: &Backend,
+    op: SetOperation,
+    destination: String,
+    keys: &[String],
+) -> RespFrame {
+    match backend.set_operation_store(op, destination, keys) {
+        Ok(n) => RespFrame::Integer(n as i64),
+        Err(e) => e.into(),
+    }
+}
+
+fn members_to_frame(members: impl IntoIterator<Item = Vec<u8>>) -> RespFrame {
+    RespArray::new(
+        members
+            .into_iter()
+            .map(|m| BulkString::new(m).into())
+            .collect::<Vec<RespFrame>>(),
+    )
+    .into()
+}
+
+// parse "key member [member ...]"
+fn parse_key_members(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<Vec<u8>>), CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let members = args
+        .map(|m| parse_bytes(Some(m)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((key, members))
+}
+
+// parse "key [key ...]"
+fn parse_keys(value: RespArray, name: &'static str) -> Result<Vec<String>, CommandError> {
+    validate_command_multi_args(&value, &[name], 1)?;
+
+    extract_args(value, 1)?
+        .into_iter()
+        .map(|k| parse_string(Some(k)))
+        .collect()
+}
+
+// parse "key [count]", count is optional
+fn parse_key_count(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Option<i64>), CommandError> {
+    validate_command_multi_args(&value, &[name], 1)?;
+    if value.len() > 3 {
+        return Err(CommandError::SyntaxError);
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let count = match args.next() {
+        Some(count) => Some(parse_integer(Some(count))?),
+        None => None,
+    };
+    Ok((key, count))
+}
+
 impl TryFrom<RespArray> for SAdd {
     type Error = CommandError;
 
     fn try_from(value: RespArray) -> Result<Self, Self::Error> {
-        validate_command(&value, &["sadd"], 2)?;
+        let (key, members) = parse_key_members(value, "sadd")?;
+        Ok(SAdd { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SRem {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = parse_key_members(value, "srem")?;
+        Ok(SRem { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SMembers {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["smembers"], 1)?;
 
         let mut args = extract_args(value, 1)?.into_iter();
-        match (args.next(), args.next()) {
-            (Some(RespFrame::BulkString(key)), Some(member)) => Ok(SAdd {
-                key: String::from_utf8(key.0)?,
-                member,
-            }),
-            _ => Err(CommandError::InvalidArgument(
-                "Invalid key or member".to_string(),
-            )),
-        }
+        Ok(SMembers {
+            key: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SCard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["scard"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(SCard {
+            key: parse_string(args.next())?,
+        })
     }
 }
 
@@ -51,7 +269,7 @@ impl TryFrom<RespArray> for SIsMember {
             (Some(RespFrame::BulkString(key)), Some(RespFrame::BulkString(member))) => {
                 Ok(SIsMember {
                     key: String::from_utf8(key.0)?,
-                    member: String::from_utf8(member.0)?,
+                    member: member.0,
                 })
             }
             _ => Err(CommandError::InvalidArgument(
@@ -60,3 +278,367 @@ impl TryFrom<RespArray> for SIsMember {
         }
     }
 }
+
+impl TryFrom<RespArray> for SMIsMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = parse_key_members(value, "smismember")?;
+        Ok(SMIsMember { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for SPop {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_key_count(value, "spop")?;
+        let count = match count {
+            Some(count) if count < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "value is out of range, must be positive".to_string(),
+                ))
+            }
+            count => count.map(|v| v as usize),
+        };
+        Ok(SPop { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for SRandMember {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_key_count(value, "srandmember")?;
+        let count = count.map(random_count).transpose()?;
+        Ok(SRandMember { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for SMove {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["smove"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(SMove {
+            source: parse_string(args.next())?,
+            destination: parse_string(args.next())?,
+            member: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SInter {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(SInter {
+            keys: parse_keys(value, "sinter")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SUnion {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(SUnion {
+            keys: parse_keys(value, "sunion")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SDiff {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(SDiff {
+            keys: parse_keys(value, "sdiff")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SInterStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut keys = parse_keys(value, "sinterstore")?;
+        Ok(SInterStore {
+            destination: keys.remove(0),
+            keys,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SUnionStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut keys = parse_keys(value, "sunionstore")?;
+        Ok(SUnionStore {
+            destination: keys.remove(0),
+            keys,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for SDiffStore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let mut keys = parse_keys(value, "sdiffstore")?;
+        Ok(SDiffStore {
+            destination: keys.remove(0),
+            keys,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RespDecode;
+
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn members(frame: RespFrame) -> Vec<Vec<u8>> {
+        let RespFrame::Array(array) = frame else {
+            panic!("expect an array");
+        };
+        let mut ret = array
+            .0
+            .into_iter()
+            .map(|v| match v {
+                RespFrame::BulkString(v) => v.0,
+                v => panic!("unexpected member: {:?}", v),
+            })
+            .collect::<Vec<_>>();
+        ret.sort();
+        ret
+    }
+
+    fn sadd(backend: &Backend, key: &str, items: &[&str]) -> RespFrame {
+        SAdd {
+            key: key.to_string(),
+            members: items.iter().map(|m| m.as_bytes().to_vec()).collect(),
+        }
+        .execute(backend)
+    }
+
+    #[test]
+    fn test_sadd_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$4\r\nsadd\r\n$3\r\nset\r\n$5\r\nhello\r\n$5\r\nworld\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SAdd = frame.try_into()?;
+        assert_eq!(result.key, "set");
+        assert_eq!(result.members, vec![b"hello".to_vec(), b"world".to_vec()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sinterstore_from_resp_array() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b"*4\r\n$11\r\nsinterstore\r\n$3\r\ndst\r\n$1\r\na\r\n$1\r\nb\r\n");
+
+        let frame = RespArray::decode(&mut buf)?;
+
+        let result: SInterStore = frame.try_into()?;
+        assert_eq!(result.destination, "dst");
+        assert_eq!(result.keys, vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_srandmember_from_resp_array() -> Result<()> {
+        let result: SRandMember = command("srandmember set -1000000")?;
+        assert_eq!(result.count, Some(-1000000));
+        // a positive count never returns more than the members of the set
+        let result: SRandMember = command("srandmember set 9223372036854775807")?;
+        assert_eq!(result.count, Some(i64::MAX));
+
+        // a count too large to reply with is rejected before anything is allocated
+        for line in [
+            "srandmember set -9223372036854775808",
+            "srandmember set -1000001",
+        ] {
+            let err = command::<SRandMember>(line).unwrap_err();
+            assert_eq!(err.to_string(), "ERR value is out of range", "{}", line);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sadd_srem_smembers_commands() -> Result<()> {
+        let backend = Backend::new();
+
+        assert_eq!(
+            sadd(&backend, "set", &["hello", "world", "hello"]),
+            RespFrame::Integer(2)
+        );
+        assert_eq!(sadd(&backend, "set", &["hello"]), RespFrame::Integer(0));
+
+        let cmd = SIsMember {
+            key: "set".to_string(),
+            member: b"hello".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = SMIsMember {
+            key: "set".to_string(),
+            members: vec![b"hello".to_vec(), b"foo".to_vec()],
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([RespFrame::Integer(1), RespFrame::Integer(0)]).into()
+        );
+
+        let cmd = SMembers {
+            key: "set".to_string(),
+        };
+        assert_eq!(
+            members(cmd.execute(&backend)),
+            vec![b"hello".to_vec(), b"world".to_vec()]
+        );
+
+        let cmd = SRem {
+            key: "set".to_string(),
+            members: vec![b"hello".to_vec(), b"foo".to_vec()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        let cmd = SCard {
+            key: "set".to_string(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        // removing the last member deletes the key
+        let cmd = SRem {
+            key: "set".to_string(),
+            members: vec![b"world".to_vec()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("set"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spop_srandmember_smove_commands() -> Result<()> {
+        let backend = Backend::new();
+        sadd(&backend, "set", &["a", "b", "c"]);
+
+        let cmd = SRandMember {
+            key: "set".to_string(),
+            count: Some(-5),
+        };
+        let RespFrame::Array(ret) = cmd.execute(&backend) else {
+            panic!("expect an array");
+        };
+        assert_eq!(ret.len(), 5);
+
+        let cmd = SRandMember {
+            key: "set".to_string(),
+            count: Some(5),
+        };
+        assert_eq!(members(cmd.execute(&backend)).len(), 3);
+
+        let cmd = SPop {
+            key: "set".to_string(),
+            count: None,
+        };
+        let RespFrame::BulkString(popped) = cmd.execute(&backend) else {
+            panic!("expect a bulk string");
+        };
+        assert!(!backend.sismember("set", &popped)?);
+
+        let cmd = SMove {
+            source: "set".to_string(),
+            destination: "other".to_string(),
+            member: popped.0,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+
+        let cmd = SPop {
+            key: "set".to_string(),
+            count: Some(1),
+        };
+        let popped = members(cmd.execute(&backend));
+        assert_eq!(popped.len(), 1);
+        let remaining = members(
+            SMembers {
+                key: "set".to_string(),
+            }
+            .execute(&backend),
+        );
+
+        let cmd = SMove {
+            source: "set".to_string(),
+            destination: "other".to_string(),
+            member: remaining[0].clone(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("set"));
+        assert!(backend.sismember("other", &remaining[0])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_operation_commands() -> Result<()> {
+        let backend = Backend::new();
+        sadd(&backend, "a", &["1", "2", "3"]);
+        sadd(&backend, "b", &["2", "3", "4"]);
+
+        let keys = vec!["a".to_string(), "b".to_string()];
+        let cmd = SInter { keys: keys.clone() };
+        assert_eq!(
+            members(cmd.execute(&backend)),
+            vec![b"2".to_vec(), b"3".to_vec()]
+        );
+
+        let cmd = SUnion { keys: keys.clone() };
+        assert_eq!(members(cmd.execute(&backend)).len(), 4);
+
+        let cmd = SDiff { keys: keys.clone() };
+        assert_eq!(members(cmd.execute(&backend)), vec![b"1".to_vec()]);
+
+        let cmd = SInter {
+            keys: vec!["a".to_string(), "missing".to_string()],
+        };
+        assert_eq!(members(cmd.execute(&backend)), Vec::<Vec<u8>>::new());
+
+        let cmd = SDiffStore {
+            destination: "dst".to_string(),
+            keys: keys.clone(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(backend.sismember("dst", b"1")?);
+
+        // an empty result deletes the destination
+        let cmd = SInterStore {
+            destination: "dst".to_string(),
+            keys: vec!["a".to_string(), "missing".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(0));
+        assert!(!backend.exists("dst"));
+
+        backend.set("string".to_string(), b"hello".to_vec());
+        let cmd = SUnionStore {
+            destination: "dst".to_string(),
+            keys: vec!["a".to_string(), "string".to_string()],
+        };
+        assert_eq!(cmd.execute(&backend), crate::BackendError::WrongType.into());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,734 @@
+use std::{iter::Peekable, ops::Bound, time::Duration};
+
+use crate::{
+    Backend, BackendError, BulkString, RespArray, RespFrame, RespMap, RespNull, RespNullArray,
+    StreamEntry, StreamId, StreamTrim, StreamTrimStrategy, XAddId,
+};
+
+use super::{
+    extract_args, parse_bytes, parse_integer, parse_option, parse_string, validate_command,
+    validate_command_multi_args, CommandError, CommandExecutor, XAdd, XDel, XLen, XRange, XRead,
+    XRevRange, XTrim,
+};
+
+impl CommandExecutor for XAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xadd(self.key, self.id, self.fields, self.nomkstream, self.trim) {
+            Ok(Some(id)) => BulkString::from(id.to_string()).into(),
+            Ok(None) => RespFrame::Null(RespNull),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XLen {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xlen(&self.key) {
+            Ok(len) => RespFrame::Integer(len as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xrange(&self.key, self.start, self.end, self.count, self.rev) {
+            Ok(entries) => entries_to_frame(entries),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XRevRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.0.execute(backend)
+    }
+}
+
+impl CommandExecutor for XTrim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xtrim(&self.key, &self.trim) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XDel {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xdel(&self.key, &self.ids) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// without a connection which may wait, XREAD BLOCK behaves like a plain XREAD
+impl CommandExecutor for XRead {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let streams = match resolve_last_ids(backend, self.streams) {
+            Ok(streams) => streams,
+            Err(e) => return e.into(),
+        };
+
+        match backend.xread(&streams, self.count) {
+            Ok(streams) if streams.is_empty() => RespFrame::NullArray(RespNullArray),
+            Ok(streams) => streams_to_frame(streams),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl XRead {
+    pub(super) async fn execute_blocking(self, backend: &Backend) -> RespFrame {
+        // like any other command, it must not run in the middle of a transaction
+        let guard = backend.shared_guard();
+        if !self.block {
+            return self.execute(backend);
+        }
+
+        let streams = match resolve_last_ids(backend, self.streams) {
+            Ok(streams) => streams,
+            Err(e) => return e.into(),
+        };
+        // the wait takes the guard again between its attempts
+        drop(guard);
+        match backend
+            .xread_blocking(streams, self.count, self.timeout)
+            .await
+        {
+            Ok(Some(streams)) => streams_to_frame(streams),
+            Ok(None) => RespFrame::NullArray(RespNullArray),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+// "$" only returns the entries added after the command started
+fn resolve_last_ids(
+    backend: &Backend,
+    streams: Vec<(String, Option<StreamId>)>,
+) -> Result<Vec<(String, StreamId)>, BackendError> {
+    streams
+        .into_iter()
+        .map(|(key, id)| match id {
+            Some(id) => Ok((key, id)),
+            None => Ok((key.clone(), backend.stream_last_id(&key)?)),
+        })
+        .collect()
+}
+
+// an entry is a two elements array: the ID and a flat array of fields and values
+pub(super) fn entry_to_frame((id, fields): StreamEntry) -> RespFrame {
+    let fields = fields
+        .into_iter()
+        .flat_map(|(field, value)| [BulkString::new(field).into(), BulkString::new(value).into()])
+        .collect::<Vec<RespFrame>>();
+    RespArray::new([
+        BulkString::from(id.to_string()).into(),
+        RespArray::new(fields).into(),
+    ])
+    .into()
+}
+
+pub(super) fn entries_to_frame(entries: Vec<StreamEntry>) -> RespFrame {
+    RespArray::new(entries.into_iter().map(entry_to_frame).collect::<Vec<_>>()).into()
+}
+
+// the entries read from each stream, keyed by the name of the stream
+pub(super) fn streams_to_frame(streams: Vec<(String, Vec<StreamEntry>)>) -> RespFrame {
+    let mut map = RespMap::new();
+    for (key, entries) in streams {
+        map.insert(key, entries_to_frame(entries));
+    }
+    map.into()
+}
+
+fn invalid_stream_id() -> CommandError {
+    CommandError::InvalidArgument(
+        "Invalid stream ID specified as stream command argument".to_string(),
+    )
+}
+
+// "<ms>-<seq>", or "<ms>" alone in which case the sequence number is `seq`
+pub(super) fn parse_stream_id(arg: Option<RespFrame>, seq: u64) -> Result<StreamId, CommandError> {
+    let v = parse_string(arg).map_err(|_| invalid_stream_id())?;
+    let id = match v.split_once('-') {
+        Some((ms, seq)) => ms.parse().ok().zip(seq.parse().ok()),
+        None => v.parse().ok().map(|ms| (ms, seq)),
+    };
+    id.map(|(ms, seq)| StreamId::new(ms, seq))
+        .ok_or_else(invalid_stream_id)
+}
+
+// "*", "<ms>-*" or an explicit ID
+fn parse_xadd_id(arg: Option<RespFrame>) -> Result<XAddId, CommandError> {
+    match arg {
+        Some(RespFrame::BulkString(ref v)) if v.as_slice() == b"*" => Ok(XAddId::Auto),
+        Some(RespFrame::BulkString(ref v)) if v.ends_with(b"-*") => {
+            let ms = String::from_utf8_lossy(&v[..v.len() - 2]).parse();
+            ms.map(XAddId::AutoSeq).map_err(|_| invalid_stream_id())
+        }
+        arg => Ok(XAddId::Explicit(parse_stream_id(arg, 0)?)),
+    }
+}
+
+// "-" and "+" are the smallest and greatest IDs and "(" makes the bound exclusive. An ID without
+// a sequence number stands for all the IDs of that millisecond
+pub(super) fn parse_range_bound(
+    arg: Option<RespFrame>,
+    start: bool,
+) -> Result<Bound<StreamId>, CommandError> {
+    let v = parse_bytes(arg).map_err(|_| invalid_stream_id())?;
+    let seq = if start { 0 } else { u64::MAX };
+    match v.as_slice() {
+        b"-" => Ok(Bound::Included(StreamId::MIN)),
+        b"+" => Ok(Bound::Included(StreamId::MAX)),
+        [b'(', id @ ..] => {
+            let id = parse_stream_id(Some(BulkString::new(id.to_vec()).into()), seq)?;
+            Ok(Bound::Excluded(id))
+        }
+        _ => Ok(Bound::Included(parse_stream_id(
+            Some(BulkString::new(v).into()),
+            seq,
+        )?)),
+    }
+}
+
+// parse "[= | ~] threshold [LIMIT count]" following MAXLEN or MINID
+fn parse_trim(
+    strategy: &str,
+    args: &mut Peekable<impl Iterator<Item = RespFrame>>,
+) -> Result<StreamTrim, CommandError> {
+    let approximate = match args.peek().and_then(parse_option).as_deref() {
+        Some("~") => true,
+        Some("=") => false,
+        _ => {
+            return parse_trim_threshold(strategy, false, args);
+        }
+    };
+    args.next();
+    parse_trim_threshold(strategy, approximate, args)
+}
+
+fn parse_trim_threshold(
+    strategy: &str,
+    approximate: bool,
+    args: &mut Peekable<impl Iterator<Item = RespFrame>>,
+) -> Result<StreamTrim, CommandError> {
+    let strategy = match strategy {
+        "maxlen" => match parse_integer(args.next())? {
+            len if len < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "The MAXLEN argument must be >= 0.".to_string(),
+                ))
+            }
+            len => StreamTrimStrategy::MaxLen(len as usize),
+        },
+        _ => StreamTrimStrategy::MinId(parse_stream_id(args.next(), 0)?),
+    };
+
+    let limit = match args.peek().and_then(parse_option).as_deref() {
+        Some("limit") => {
+            args.next();
+            let limit = parse_integer(args.next())?;
+            if limit < 0 {
+                return Err(CommandError::InvalidArgument(
+                    "The LIMIT argument must be >= 0.".to_string(),
+                ));
+            }
+            if !approximate {
+                return Err(CommandError::InvalidArgument(
+                    "syntax error, LIMIT cannot be used without the special ~ option".to_string(),
+                ));
+            }
+            Some(limit as usize)
+        }
+        _ => None,
+    };
+
+    Ok(StreamTrim {
+        strategy,
+        approximate,
+        limit,
+    })
+}
+
+// XRANGE key start end [COUNT count], XREVRANGE takes end before start
+fn parse_xrange(value: RespArray, name: &'static str, rev: bool) -> Result<XRange, CommandError> {
+    validate_command_multi_args(&value, &[name], 3)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let (first, second) = (args.next(), args.next());
+    let (start, end) = if rev {
+        (
+            parse_range_bound(second, true)?,
+            parse_range_bound(first, false)?,
+        )
+    } else {
+        (
+            parse_range_bound(first, true)?,
+            parse_range_bound(second, false)?,
+        )
+    };
+
+    let count = match (args.next(), args.next()) {
+        (None, None) => None,
+        (Some(arg), count) if parse_option(&arg).as_deref() == Some("count") => {
+            // a negative count returns nothing
+            Some(parse_integer(count)?.max(0) as usize)
+        }
+        _ => return Err(CommandError::SyntaxError),
+    };
+    if args.next().is_some() {
+        return Err(CommandError::SyntaxError);
+    }
+
+    Ok(XRange {
+        key,
+        start,
+        end,
+        count,
+        rev,
+    })
+}
+
+impl TryFrom<RespArray> for XAdd {
+    type Error = CommandError;
+
+    // XADD key [NOMKSTREAM] [MAXLEN | MINID [= | ~] threshold [LIMIT count]] <* | id> field value
+    // [field value ...]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xadd"], 4)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+
+        let mut nomkstream = false;
+        let mut trim = None;
+        loop {
+            match args.peek().and_then(parse_option).as_deref() {
+                Some("nomkstream") => nomkstream = true,
+                Some(strategy @ ("maxlen" | "minid")) => {
+                    let strategy = strategy.to_string();
+                    args.next();
+                    trim = Some(parse_trim(&strategy, &mut args)?);
+                    continue;
+                }
+                _ => break,
+            }
+            args.next();
+        }
+
+        let id = parse_xadd_id(args.next())?;
+        let args = args.collect::<Vec<_>>();
+        if args.is_empty() || !args.len().is_multiple_of(2) {
+            return Err(CommandError::WrongArity("xadd".to_string()));
+        }
+
+        let mut fields = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let Some(field) = args.next() {
+            fields.push((parse_bytes(Some(field))?, parse_bytes(args.next())?));
+        }
+
+        Ok(XAdd {
+            key,
+            nomkstream,
+            trim,
+            id,
+            fields,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for XLen {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["xlen"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(XLen {
+            key: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for XRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        parse_xrange(value, "xrange", false)
+    }
+}
+
+impl TryFrom<RespArray> for XRevRange {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(XRevRange(parse_xrange(value, "xrevrange", true)?))
+    }
+}
+
+impl TryFrom<RespArray> for XTrim {
+    type Error = CommandError;
+
+    // XTRIM key MAXLEN | MINID [= | ~] threshold [LIMIT count]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xtrim"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+        let trim = match args.next().as_ref().and_then(parse_option).as_deref() {
+            Some(strategy @ ("maxlen" | "minid")) => parse_trim(strategy, &mut args)?,
+            _ => return Err(CommandError::SyntaxError),
+        };
+        if args.next().is_some() {
+            return Err(CommandError::SyntaxError);
+        }
+
+        Ok(XTrim { key, trim })
+    }
+}
+
+impl TryFrom<RespArray> for XDel {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xdel"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let ids = args
+            .map(|id| parse_stream_id(Some(id), 0))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(XDel { key, ids })
+    }
+}
+
+// the BLOCK timeout is in milliseconds, zero blocks forever
+pub(super) fn parse_block_timeout(
+    arg: Option<RespFrame>,
+) -> Result<Option<Duration>, CommandError> {
+    match parse_integer(arg) {
+        Ok(timeout) if timeout < 0 => Err(CommandError::InvalidArgument(
+            "timeout is negative".to_string(),
+        )),
+        Ok(0) => Ok(None),
+        Ok(timeout) => Ok(Some(Duration::from_millis(timeout as u64))),
+        Err(_) => Err(CommandError::InvalidArgument(
+            "timeout is not an integer or out of range".to_string(),
+        )),
+    }
+}
+
+// parse "key [key ...] id [id ...]" following STREAMS, the special ID ("$" or ">") is returned
+// as None
+pub(super) fn parse_streams(
+    args: Vec<RespFrame>,
+    name: &str,
+    special: &str,
+) -> Result<Vec<(String, Option<StreamId>)>, CommandError> {
+    if args.is_empty() || !args.len().is_multiple_of(2) {
+        return Err(CommandError::InvalidArgument(format!(
+            "Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            name, special
+        )));
+    }
+
+    let mut keys = args;
+    let ids = keys.split_off(keys.len() / 2);
+    keys.into_iter()
+        .zip(ids)
+        .map(|(key, id)| {
+            let id = match id {
+                RespFrame::BulkString(ref v) if v.as_slice() == special.as_bytes() => None,
+                id => Some(parse_stream_id(Some(id), 0)?),
+            };
+            Ok((parse_string(Some(key))?, id))
+        })
+        .collect()
+}
+
+impl TryFrom<RespArray> for XRead {
+    type Error = CommandError;
+
+    // XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xread"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let mut count = None;
+        let (mut block, mut timeout) = (false, None);
+        loop {
+            match args.next().as_ref().and_then(parse_option).as_deref() {
+                // zero or a negative count returns all the new entries
+                Some("count") => count = Some(parse_integer(args.next())?).filter(|v| *v > 0),
+                Some("block") => {
+                    block = true;
+                    timeout = parse_block_timeout(args.next())?;
+                }
+                Some("streams") => break,
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(XRead {
+            count: count.map(|v| v as usize),
+            block,
+            timeout,
+            streams: parse_streams(args.collect(), "xread", "$")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::command;
+    use anyhow::Result;
+
+    fn xadd(backend: &Backend, line: &str) -> Result<RespFrame> {
+        Ok(command::<XAdd>(&format!("xadd {}", line))?.execute(backend))
+    }
+
+    fn entry(id: &str, fields: &[&str]) -> RespFrame {
+        let fields = fields
+            .iter()
+            .map(|v| BulkString::from(*v).into())
+            .collect::<Vec<RespFrame>>();
+        RespArray::new([BulkString::from(id).into(), RespArray::new(fields).into()]).into()
+    }
+
+    #[test]
+    fn test_xadd_from_resp_array() -> Result<()> {
+        let cmd: XAdd = command("xadd stream NOMKSTREAM MAXLEN ~ 10 LIMIT 5 5-* a 1 b 2")?;
+        assert_eq!(cmd.key, "stream");
+        assert!(cmd.nomkstream);
+        assert_eq!(
+            cmd.trim,
+            Some(StreamTrim {
+                strategy: StreamTrimStrategy::MaxLen(10),
+                approximate: true,
+                limit: Some(5),
+            })
+        );
+        assert_eq!(cmd.id, XAddId::AutoSeq(5));
+        assert_eq!(
+            cmd.fields,
+            vec![
+                (b"a".to_vec(), b"1".to_vec()),
+                (b"b".to_vec(), b"2".to_vec())
+            ]
+        );
+
+        let cmd: XAdd = command("xadd stream MINID 3 3 a 1")?;
+        assert_eq!(
+            cmd.trim.map(|v| v.strategy),
+            Some(StreamTrimStrategy::MinId(StreamId::new(3, 0)))
+        );
+        assert_eq!(cmd.id, XAddId::Explicit(StreamId::new(3, 0)));
+
+        let err = command::<XAdd>("xadd stream MAXLEN 10 LIMIT 5 * a 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR syntax error, LIMIT cannot be used without the special ~ option"
+        );
+        let err = command::<XAdd>("xadd stream * a 1 b").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'xadd' command"
+        );
+        let err = command::<XAdd>("xadd stream 1-x a 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR Invalid stream ID specified as stream command argument"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xadd_ids() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            xadd(&backend, "stream 1-1 a 1")?,
+            BulkString::from("1-1").into()
+        );
+        assert_eq!(
+            xadd(&backend, "stream 1-* a 2")?,
+            BulkString::from("1-2").into()
+        );
+        assert_eq!(
+            xadd(&backend, "stream 2 a 3")?,
+            BulkString::from("2-0").into()
+        );
+        assert_eq!(
+            xadd(&backend, "stream 2-0 a 4")?,
+            BackendError::StreamIdTooSmall.into()
+        );
+        assert_eq!(
+            xadd(&backend, "stream 1-* a 4")?,
+            BackendError::StreamIdTooSmall.into()
+        );
+        assert_eq!(
+            xadd(&backend, "other 0-0 a 1")?,
+            BackendError::StreamIdZero.into()
+        );
+        assert_eq!(
+            xadd(&backend, "other 0-* a 1")?,
+            BulkString::from("0-1").into()
+        );
+
+        // generated IDs are never lower than the last one, whatever the clock says
+        backend.xadd(
+            "future".to_string(),
+            XAddId::Explicit(StreamId::new(u64::MAX - 1, 5)),
+            vec![],
+            false,
+            None,
+        )?;
+        assert_eq!(
+            xadd(&backend, "future * a 1")?,
+            BulkString::from(format!("{}-6", u64::MAX - 1)).into()
+        );
+
+        assert_eq!(
+            xadd(&backend, "missing NOMKSTREAM * a 1")?,
+            RespFrame::Null(RespNull)
+        );
+        assert!(!backend.exists("missing"));
+
+        let cmd: XLen = command("xlen stream")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xrange_command() -> Result<()> {
+        let backend = Backend::new();
+        for id in ["1-1", "1-2", "2-1", "3-1"] {
+            xadd(&backend, &format!("stream {} f {}", id, id))?;
+        }
+
+        let cmd: XRange = command("xrange stream - +")?;
+        let all: XRange = command("xrange stream 0 3")?;
+        assert_eq!(cmd.execute(&backend), all.execute(&backend));
+
+        let cmd: XRange = command("xrange stream 1 2 COUNT 2")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([entry("1-1", &["f", "1-1"]), entry("1-2", &["f", "1-2"])]).into()
+        );
+
+        let cmd: XRange = command("xrange stream (1-2 +")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([entry("2-1", &["f", "2-1"]), entry("3-1", &["f", "3-1"])]).into()
+        );
+
+        let cmd: XRevRange = command("xrevrange stream + (2-1 COUNT 5")?;
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([entry("3-1", &["f", "3-1"])]).into()
+        );
+
+        let cmd: XRange = command("xrange stream (3-1 3-1")?;
+        assert_eq!(cmd.execute(&backend), RespArray::new([]).into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xtrim_xdel_commands() -> Result<()> {
+        let backend = Backend::new();
+        for id in 1..=5 {
+            xadd(&backend, &format!("stream {} f v", id))?;
+        }
+
+        let cmd: XTrim = command("xtrim stream MAXLEN = 4")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: XTrim = command("xtrim stream MINID ~ 5 LIMIT 1")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd: XTrim = command("xtrim stream MINID 4")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+
+        xadd(&backend, "stream MAXLEN 2 6 f v")?;
+        assert_eq!(backend.xlen("stream")?, 2);
+
+        let cmd: XDel = command("xdel stream 5 6-0 7")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        // an empty stream is kept, together with its last ID
+        assert_eq!(backend.key_type("stream"), Some("stream"));
+        assert_eq!(
+            xadd(&backend, "stream 6 f v")?,
+            BackendError::StreamIdTooSmall.into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xread_command() -> Result<()> {
+        let backend = Backend::new();
+        xadd(&backend, "s1 1 a 1")?;
+        xadd(&backend, "s1 2 a 2")?;
+        xadd(&backend, "s2 1 b 1")?;
+
+        let cmd: XRead = command("xread COUNT 1 STREAMS s1 s2 missing 0 1 0")?;
+        let mut expected = RespMap::new();
+        expected.insert(
+            "s1".to_string(),
+            RespArray::new([entry("1-0", &["a", "1"])]).into(),
+        );
+        assert_eq!(cmd.execute(&backend), expected.into());
+
+        let cmd: XRead = command("xread STREAMS s1 $")?;
+        assert_eq!(cmd.execute(&backend), RespFrame::NullArray(RespNullArray));
+
+        let err = command::<XRead>("xread STREAMS s1 s2 0").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified."
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_xread_block() -> Result<()> {
+        let backend = Backend::new();
+        xadd(&backend, "stream 1 a 1")?;
+
+        let cmd: XRead = command("xread BLOCK 0 STREAMS other stream $ $")?;
+        let reader = {
+            let backend = backend.clone();
+            tokio::spawn(async move { cmd.execute_blocking(&backend).await })
+        };
+        while backend.blocked_clients() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        xadd(&backend, "stream 2 a 2")?;
+        let mut expected = RespMap::new();
+        expected.insert(
+            "stream".to_string(),
+            RespArray::new([entry("2-0", &["a", "2"])]).into(),
+        );
+        assert_eq!(reader.await?, expected.into());
+        assert_eq!(backend.blocked_clients(), 0);
+
+        let cmd: XRead = command("xread BLOCK 10 STREAMS stream 2")?;
+        assert_eq!(
+            cmd.execute_blocking(&backend).await,
+            RespFrame::NullArray(RespNullArray)
+        );
+        assert_eq!(backend.blocked_clients(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,805 @@
+use std::ops::Bound;
+
+use crate::{
+    Backend, BulkString, Claim, GroupEntry, PendingRange, PendingSummary, RespArray, RespFrame,
+    RespMap, RespNull, RespNullArray, StreamEntry, StreamId, XClaimOptions,
+};
+
+use super::{
+    extract_args, parse_integer, parse_option, parse_string,
+    stream::{
+        entries_to_frame, entry_to_frame, parse_block_timeout, parse_range_bound, parse_stream_id,
+        parse_streams,
+    },
+    validate_command_multi_args, CommandError, CommandExecutor, XAck, XAutoClaim, XClaim, XGroup,
+    XGroupSubcommand, XPending, XReadGroup, RESP_OK,
+};
+
+impl CommandExecutor for XGroup {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let ret = match self.subcommand {
+            XGroupSubcommand::Create {
+                key,
+                group,
+                id,
+                mkstream,
+            } => backend
+                .xgroup_create(key, group, id, mkstream)
+                .map(|_| RESP_OK.clone()),
+            XGroupSubcommand::SetId { key, group, id } => backend
+                .xgroup_setid(&key, &group, id)
+                .map(|_| RESP_OK.clone()),
+            XGroupSubcommand::Destroy { key, group } => backend
+                .xgroup_destroy(&key, &group)
+                .map(|v| RespFrame::Integer(v as i64)),
+            XGroupSubcommand::CreateConsumer {
+                key,
+                group,
+                consumer,
+            } => backend
+                .xgroup_create_consumer(&key, &group, consumer)
+                .map(|v| RespFrame::Integer(v as i64)),
+            XGroupSubcommand::DelConsumer {
+                key,
+                group,
+                consumer,
+            } => backend
+                .xgroup_del_consumer(&key, &group, &consumer)
+                .map(|v| RespFrame::Integer(v as i64)),
+        };
+
+        ret.unwrap_or_else(|e| e.into())
+    }
+}
+
+// without a connection which may wait, XREADGROUP BLOCK behaves like a plain XREADGROUP
+impl CommandExecutor for XReadGroup {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xreadgroup(
+            &self.group,
+            &self.consumer,
+            &self.streams,
+            self.count,
+            self.noack,
+        ) {
+            Ok(streams) if streams.is_empty() => RespFrame::NullArray(RespNullArray),
+            Ok(streams) => group_streams_to_frame(streams),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl XReadGroup {
+    // reading the history of the pending entries never blocks
+    pub(super) async fn execute_blocking(self, backend: &Backend) -> RespFrame {
+        if !self.block || self.streams.iter().any(|(_, id)| id.is_some()) {
+            // like any other command, it must not run in the middle of a transaction
+            let _guard = backend.shared_guard();
+            return self.execute(backend);
+        }
+
+        match backend
+            .xreadgroup_blocking(
+                &self.group,
+                &self.consumer,
+                self.streams,
+                self.count,
+                self.noack,
+                self.timeout,
+            )
+            .await
+        {
+            Ok(Some(streams)) => group_streams_to_frame(streams),
+            Ok(None) => RespFrame::NullArray(RespNullArray),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XAck {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xack(&self.key, &self.group, &self.ids) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XPending {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let Some(range) = self.range else {
+            return match backend.xpending_summary(&self.key, &self.group) {
+                Ok(summary) => summary_to_frame(summary),
+                Err(e) => e.into(),
+            };
+        };
+
+        match backend.xpending(&self.key, &self.group, &range) {
+            Ok(pending) => RespArray::new(
+                pending
+                    .into_iter()
+                    .map(|(id, pending)| {
+                        RespArray::new([
+                            id_to_frame(id),
+                            BulkString::from(pending.consumer.as_str()).into(),
+                            RespFrame::Integer(pending.idle() as i64),
+                            RespFrame::Integer(pending.delivery_count as i64),
+                        ])
+                        .into()
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XClaim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xclaim(&self.key, &self.claim, &self.ids, self.options) {
+            Ok(entries) => claimed_to_frame(entries, self.claim.just_id),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for XAutoClaim {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.xautoclaim(&self.key, &self.claim, self.start, self.count) {
+            Ok((next, claimed, deleted)) => RespArray::new([
+                id_to_frame(next),
+                claimed_to_frame(claimed, self.claim.just_id),
+                RespArray::new(deleted.into_iter().map(id_to_frame).collect::<Vec<_>>()).into(),
+            ])
+            .into(),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+fn id_to_frame(id: StreamId) -> RespFrame {
+    BulkString::from(id.to_string()).into()
+}
+
+// the entries read from each stream keyed by the name of the stream, like XREAD. A pending
+// entry deleted from the stream has no fields
+fn group_streams_to_frame(streams: Vec<(String, Vec<GroupEntry>)>) -> RespFrame {
+    let mut map = RespMap::new();
+    for (key, entries) in streams {
+        let entries = entries
+            .into_iter()
+            .map(|(id, fields)| match fields {
+                Some(fields) => entry_to_frame((id, fields)),
+                None => {
+                    RespArray::new([id_to_frame(id), RespFrame::NullArray(RespNullArray)]).into()
+                }
+            })
+            .collect::<Vec<_>>();
+        map.insert(key, RespArray::new(entries).into());
+    }
+    map.into()
+}
+
+// the number of pending entries, the smallest and greatest pending IDs, and the number of
+// pending entries of each consumer
+fn summary_to_frame(summary: PendingSummary) -> RespFrame {
+    let (min, max) = match summary.ids {
+        Some((min, max)) => (id_to_frame(min), id_to_frame(max)),
+        None => (RespFrame::Null(RespNull), RespFrame::Null(RespNull)),
+    };
+    let consumers = if summary.consumers.is_empty() {
+        RespFrame::NullArray(RespNullArray)
+    } else {
+        RespArray::new(
+            summary
+                .consumers
+                .into_iter()
+                .map(|(consumer, n)| {
+                    RespArray::new([
+                        BulkString::from(consumer).into(),
+                        BulkString::from(n.to_string()).into(),
+                    ])
+                    .into()
+                })
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    };
+
+    RespArray::new([
+        RespFrame::Integer(summary.count as i64),
+        min,
+        max,
+        consumers,
+    ])
+    .into()
+}
+
+// JUSTID replies the IDs of the claimed entries only
+fn claimed_to_frame(entries: Vec<StreamEntry>, just_id: bool) -> RespFrame {
+    if just_id {
+        RespArray::new(
+            entries
+                .into_iter()
+                .map(|(id, _)| id_to_frame(id))
+                .collect::<Vec<_>>(),
+        )
+        .into()
+    } else {
+        entries_to_frame(entries)
+    }
+}
+
+// an ID or "$", the last ID of the stream
+fn parse_group_id(arg: Option<RespFrame>) -> Result<Option<StreamId>, CommandError> {
+    match arg {
+        Some(RespFrame::BulkString(ref v)) if v.as_slice() == b"$" => Ok(None),
+        arg => Ok(Some(parse_stream_id(arg, 0)?)),
+    }
+}
+
+// the idle times are in milliseconds, a negative one is zero
+fn parse_idle(arg: Option<RespFrame>) -> Result<u64, CommandError> {
+    Ok(parse_integer(arg)?.max(0) as u64)
+}
+
+impl TryFrom<RespArray> for XGroup {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xgroup"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let subcommand = args.next().as_ref().and_then(parse_option);
+        let args = args.collect::<Vec<_>>();
+        let n_args = args.len();
+        let mut args = args.into_iter();
+        let subcommand = match (subcommand.as_deref(), n_args) {
+            (Some("create"), 3 | 4) => {
+                let key = parse_string(args.next())?;
+                let group = parse_string(args.next())?;
+                let id = parse_group_id(args.next())?;
+                let mkstream = match args.next() {
+                    Some(arg) if parse_option(&arg).as_deref() == Some("mkstream") => true,
+                    Some(_) => return Err(CommandError::SyntaxError),
+                    None => false,
+                };
+                XGroupSubcommand::Create {
+                    key,
+                    group,
+                    id,
+                    mkstream,
+                }
+            }
+            (Some("setid"), 3) => XGroupSubcommand::SetId {
+                key: parse_string(args.next())?,
+                group: parse_string(args.next())?,
+                id: parse_group_id(args.next())?,
+            },
+            (Some("destroy"), 2) => XGroupSubcommand::Destroy {
+                key: parse_string(args.next())?,
+                group: parse_string(args.next())?,
+            },
+            (Some("createconsumer"), 3) => XGroupSubcommand::CreateConsumer {
+                key: parse_string(args.next())?,
+                group: parse_string(args.next())?,
+                consumer: parse_string(args.next())?,
+            },
+            (Some("delconsumer"), 3) => XGroupSubcommand::DelConsumer {
+                key: parse_string(args.next())?,
+                group: parse_string(args.next())?,
+                consumer: parse_string(args.next())?,
+            },
+            (
+                Some(name @ ("create" | "setid" | "destroy" | "createconsumer" | "delconsumer")),
+                _,
+            ) => return Err(CommandError::WrongArity(format!("xgroup|{}", name))),
+            (subcommand, _) => {
+                return Err(CommandError::InvalidArgument(format!(
+                    "unknown subcommand '{}'. Try XGROUP HELP.",
+                    subcommand.unwrap_or_default()
+                )))
+            }
+        };
+
+        Ok(XGroup { subcommand })
+    }
+}
+
+impl TryFrom<RespArray> for XReadGroup {
+    type Error = CommandError;
+
+    // XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds] [NOACK] STREAMS key
+    // [key ...] id [id ...]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xreadgroup"], 6)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        if args.next().as_ref().and_then(parse_option).as_deref() != Some("group") {
+            return Err(CommandError::SyntaxError);
+        }
+        let group = parse_string(args.next())?;
+        let consumer = parse_string(args.next())?;
+
+        let mut count = None;
+        let (mut block, mut timeout) = (false, None);
+        let mut noack = false;
+        loop {
+            match args.next().as_ref().and_then(parse_option).as_deref() {
+                // zero or a negative count returns all the new entries
+                Some("count") => count = Some(parse_integer(args.next())?).filter(|v| *v > 0),
+                Some("block") => {
+                    block = true;
+                    timeout = parse_block_timeout(args.next())?;
+                }
+                Some("noack") => noack = true,
+                Some("streams") => break,
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(XReadGroup {
+            group,
+            consumer,
+            count: count.map(|v| v as usize),
+            block,
+            timeout,
+            noack,
+            streams: parse_streams(args.collect(), "xreadgroup", ">")?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for XAck {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xack"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let group = parse_string(args.next())?;
+        let ids = args
+            .map(|id| parse_stream_id(Some(id), 0))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(XAck { key, group, ids })
+    }
+}
+
+impl TryFrom<RespArray> for XPending {
+    type Error = CommandError;
+
+    // XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xpending"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+        let group = parse_string(args.next())?;
+        if args.peek().is_none() {
+            return Ok(XPending {
+                key,
+                group,
+                range: None,
+            });
+        }
+
+        let min_idle = match args.peek().and_then(parse_option).as_deref() {
+            Some("idle") => {
+                args.next();
+                Some(parse_idle(args.next())?)
+            }
+            _ => None,
+        };
+        let start = parse_range_bound(args.next(), true)?;
+        let end = parse_range_bound(args.next(), false)?;
+        let count = match args.next() {
+            Some(count) => parse_integer(Some(count))?.max(0) as usize,
+            None => return Err(CommandError::SyntaxError),
+        };
+        let consumer = args.next().map(|v| parse_string(Some(v))).transpose()?;
+        if args.next().is_some() {
+            return Err(CommandError::SyntaxError);
+        }
+
+        Ok(XPending {
+            key,
+            group,
+            range: Some(PendingRange {
+                min_idle,
+                start,
+                end,
+                count,
+                consumer,
+            }),
+        })
+    }
+}
+
+impl TryFrom<RespArray> for XClaim {
+    type Error = CommandError;
+
+    // XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms] [TIME unix-time-milliseconds]
+    // [RETRYCOUNT count] [FORCE] [JUSTID] [LASTID lastid]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xclaim"], 5)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+        let mut claim = Claim {
+            group: parse_string(args.next())?,
+            consumer: parse_string(args.next())?,
+            min_idle: parse_idle(args.next())?,
+            just_id: false,
+        };
+
+        // the IDs go on until the first option
+        let mut ids = vec![];
+        while let Some(id) = args.peek() {
+            match parse_stream_id(Some(id.clone()), 0) {
+                Ok(id) => ids.push(id),
+                Err(_) if !ids.is_empty() => break,
+                Err(e) => return Err(e),
+            }
+            args.next();
+        }
+
+        let mut options = XClaimOptions::default();
+        while let Some(arg) = args.next() {
+            match parse_option(&arg).as_deref() {
+                Some("idle") => options.idle = Some(parse_idle(args.next())?),
+                Some("time") => options.time = Some(parse_idle(args.next())?),
+                Some("retrycount") => options.retry_count = Some(parse_idle(args.next())?),
+                Some("force") => options.force = true,
+                Some("justid") => claim.just_id = true,
+                Some("lastid") => options.last_id = Some(parse_stream_id(args.next(), 0)?),
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(XClaim {
+            key,
+            claim,
+            ids,
+            options,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for XAutoClaim {
+    type Error = CommandError;
+
+    // XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["xautoclaim"], 5)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let mut claim = Claim {
+            group: parse_string(args.next())?,
+            consumer: parse_string(args.next())?,
+            min_idle: parse_idle(args.next())?,
+            just_id: false,
+        };
+        let start = match parse_range_bound(args.next(), true)? {
+            Bound::Included(id) => id,
+            Bound::Excluded(id) => id.next().unwrap_or(StreamId::MAX),
+            Bound::Unbounded => StreamId::MIN,
+        };
+
+        let mut count = 100;
+        while let Some(arg) = args.next() {
+            match parse_option(&arg).as_deref() {
+                Some("count") => match parse_integer(args.next())? {
+                    n if n <= 0 => {
+                        return Err(CommandError::InvalidArgument(
+                            "COUNT must be > 0".to_string(),
+                        ))
+                    }
+                    n => count = n as usize,
+                },
+                Some("justid") => claim.just_id = true,
+                _ => return Err(CommandError::SyntaxError),
+            }
+        }
+
+        Ok(XAutoClaim {
+            key,
+            claim,
+            start,
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BackendError;
+
+    use super::*;
+    use crate::cmd::{command, run};
+    use anyhow::Result;
+
+    // a stream with the entries 1-0, 2-0 and 3-0 and the group "group" reading from the start
+    fn setup() -> Result<Backend> {
+        let backend = Backend::new();
+        for ms in 1..=3 {
+            backend.xadd(
+                "stream".to_string(),
+                crate::XAddId::Explicit(StreamId::new(ms, 0)),
+                vec![(b"n".to_vec(), ms.to_string().into_bytes())],
+                false,
+                None,
+            )?;
+        }
+        run::<XGroup>(&backend, "xgroup CREATE stream group 0");
+        Ok(backend)
+    }
+
+    fn entry(id: &str, n: &str) -> RespFrame {
+        RespArray::new([
+            BulkString::from(id).into(),
+            RespArray::new([BulkString::from("n").into(), BulkString::from(n).into()]).into(),
+        ])
+        .into()
+    }
+
+    fn stream_reply(entries: Vec<RespFrame>) -> RespFrame {
+        let mut map = RespMap::new();
+        map.insert("stream".to_string(), RespArray::new(entries).into());
+        map.into()
+    }
+
+    #[test]
+    fn test_xgroup_command() -> Result<()> {
+        let backend = setup()?;
+
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup CREATE stream group $"),
+            BackendError::BusyGroup.into()
+        );
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup CREATE missing group $"),
+            BackendError::NoStream.into()
+        );
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup CREATE missing group $ MKSTREAM"),
+            RESP_OK.clone()
+        );
+        assert_eq!(backend.key_type("missing"), Some("stream"));
+
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup CREATECONSUMER stream group alice"),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup CREATECONSUMER stream group alice"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup SETID stream other 0"),
+            BackendError::NoGroup("stream".to_string(), "other".to_string()).into()
+        );
+
+        // moving the group to the end leaves nothing to read
+        run::<XGroup>(&backend, "xgroup SETID stream group $");
+        assert_eq!(
+            run::<XReadGroup>(&backend, "xreadgroup GROUP group alice STREAMS stream >"),
+            RespFrame::NullArray(RespNullArray)
+        );
+
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup DESTROY stream group"),
+            RespFrame::Integer(1)
+        );
+        assert_eq!(
+            run::<XGroup>(&backend, "xgroup DESTROY stream group"),
+            RespFrame::Integer(0)
+        );
+
+        let err = command::<XGroup>("xgroup CREATE stream").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for 'xgroup|create' command"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xreadgroup_and_xack() -> Result<()> {
+        let backend = setup()?;
+
+        assert_eq!(
+            run::<XReadGroup>(
+                &backend,
+                "xreadgroup GROUP group alice COUNT 2 STREAMS stream >"
+            ),
+            stream_reply(vec![entry("1-0", "1"), entry("2-0", "2")])
+        );
+        assert_eq!(
+            run::<XReadGroup>(&backend, "xreadgroup GROUP group bob STREAMS stream >"),
+            stream_reply(vec![entry("3-0", "3")])
+        );
+
+        // the history of alice's pending entries, an entry deleted since has no fields
+        backend.xdel("stream", &[StreamId::new(1, 0)])?;
+        assert_eq!(
+            run::<XReadGroup>(&backend, "xreadgroup GROUP group alice STREAMS stream 0"),
+            stream_reply(vec![
+                RespArray::new([
+                    BulkString::from("1-0").into(),
+                    RespFrame::NullArray(RespNullArray)
+                ])
+                .into(),
+                entry("2-0", "2")
+            ])
+        );
+
+        assert_eq!(
+            run::<XAck>(&backend, "xack stream group 1-0 2-0 9-0"),
+            RespFrame::Integer(2)
+        );
+        assert_eq!(
+            run::<XReadGroup>(&backend, "xreadgroup GROUP group alice STREAMS stream 0"),
+            stream_reply(vec![])
+        );
+
+        assert_eq!(
+            run::<XReadGroup>(&backend, "xreadgroup GROUP other alice STREAMS stream >"),
+            BackendError::NoGroup("stream".to_string(), "other".to_string()).into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xpending_command() -> Result<()> {
+        let backend = setup()?;
+        run::<XReadGroup>(
+            &backend,
+            "xreadgroup GROUP group alice COUNT 2 STREAMS stream >",
+        );
+        run::<XReadGroup>(&backend, "xreadgroup GROUP group bob STREAMS stream >");
+
+        assert_eq!(
+            run::<XPending>(&backend, "xpending stream group"),
+            RespArray::new([
+                RespFrame::Integer(3),
+                BulkString::from("1-0").into(),
+                BulkString::from("3-0").into(),
+                RespArray::new([
+                    RespArray::new([
+                        BulkString::from("alice").into(),
+                        BulkString::from("2").into()
+                    ])
+                    .into(),
+                    RespArray::new([BulkString::from("bob").into(), BulkString::from("1").into()])
+                        .into(),
+                ])
+                .into(),
+            ])
+            .into()
+        );
+
+        let RespFrame::Array(pending) =
+            run::<XPending>(&backend, "xpending stream group - + 10 bob")
+        else {
+            panic!("expected an array");
+        };
+        assert_eq!(pending.len(), 1);
+        let RespFrame::Array(ref pending) = pending[0] else {
+            panic!("expected an array");
+        };
+        assert_eq!(pending[0], BulkString::from("3-0").into());
+        assert_eq!(pending[1], BulkString::from("bob").into());
+        assert_eq!(pending[3], RespFrame::Integer(1));
+
+        assert_eq!(
+            run::<XPending>(&backend, "xpending stream group IDLE 60000 - + 10"),
+            RespArray::new([]).into()
+        );
+
+        run::<XAck>(&backend, "xack stream group 1-0 2-0 3-0");
+        assert_eq!(
+            run::<XPending>(&backend, "xpending stream group"),
+            RespArray::new([
+                RespFrame::Integer(0),
+                RespFrame::Null(RespNull),
+                RespFrame::Null(RespNull),
+                RespFrame::NullArray(RespNullArray),
+            ])
+            .into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xclaim_and_xautoclaim() -> Result<()> {
+        let backend = setup()?;
+        run::<XReadGroup>(&backend, "xreadgroup GROUP group alice STREAMS stream >");
+
+        // nothing is idle for a minute yet
+        assert_eq!(
+            run::<XClaim>(&backend, "xclaim stream group bob 60000 1-0 2-0"),
+            RespArray::new([]).into()
+        );
+        assert_eq!(
+            run::<XClaim>(&backend, "xclaim stream group bob 0 1-0 2-0 RETRYCOUNT 5"),
+            RespArray::new([entry("1-0", "1"), entry("2-0", "2")]).into()
+        );
+        let pending = backend.xpending(
+            "stream",
+            "group",
+            &PendingRange {
+                min_idle: None,
+                start: Bound::Unbounded,
+                end: Bound::Unbounded,
+                count: 10,
+                consumer: Some("bob".to_string()),
+            },
+        )?;
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].1.delivery_count, 5);
+
+        backend.xdel("stream", &[StreamId::new(2, 0)])?;
+        assert_eq!(
+            run::<XAutoClaim>(&backend, "xautoclaim stream group carol 0 0 COUNT 1 JUSTID"),
+            RespArray::new([
+                BulkString::from("2-0").into(),
+                RespArray::new([BulkString::from("1-0").into()]).into(),
+                RespArray::new([]).into(),
+            ])
+            .into()
+        );
+        assert_eq!(
+            run::<XAutoClaim>(&backend, "xautoclaim stream group carol 0 2-0"),
+            RespArray::new([
+                BulkString::from("0-0").into(),
+                RespArray::new([entry("3-0", "3")]).into(),
+                RespArray::new([BulkString::from("2-0").into()]).into(),
+            ])
+            .into()
+        );
+
+        let err = command::<XAutoClaim>("xautoclaim stream group carol 0 0 COUNT 0").unwrap_err();
+        assert_eq!(err.to_string(), "ERR COUNT must be > 0");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_xreadgroup_block() -> Result<()> {
+        let backend = setup()?;
+        run::<XGroup>(&backend, "xgroup SETID stream group $");
+
+        let cmd: XReadGroup = command("xreadgroup GROUP group alice BLOCK 0 STREAMS stream >")?;
+        let reader = {
+            let backend = backend.clone();
+            tokio::spawn(async move { cmd.execute_blocking(&backend).await })
+        };
+        while backend.blocked_clients() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        backend.xadd(
+            "stream".to_string(),
+            crate::XAddId::Explicit(StreamId::new(4, 0)),
+            vec![(b"n".to_vec(), b"4".to_vec())],
+            false,
+            None,
+        )?;
+        assert_eq!(reader.await?, stream_reply(vec![entry("4-0", "4")]));
+        assert_eq!(backend.xpending_summary("stream", "group")?.count, 1);
+
+        let cmd: XReadGroup = command("xreadgroup GROUP group alice BLOCK 10 STREAMS stream >")?;
+        assert_eq!(
+            cmd.execute_blocking(&backend).await,
+            RespFrame::NullArray(RespNullArray)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{Backend, RespArray, RespFrame, RespNullArray, SimpleString, WatchedKey};
+
+use super::{
+    extract_args, parse_string, resp2_reply, validate_command, validate_command_multi_args,
+    Command, CommandError, CommandExecutor, Discard, Exec, Multi, Unwatch, Watch, RESP_OK,
+};
+
+/// The commands a connection queued after MULTI, they are executed together by EXEC.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    commands: Vec<Command>,
+    // a command was rejected while queuing, EXEC discards the transaction then
+    aborted: bool,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the command, or the error it was parsed with, which aborts the transaction.
+    pub fn queue(&mut self, cmd: Result<Command, CommandError>) -> RespFrame {
+        match cmd {
+            Ok(Command::Multi(_)) => {
+                CommandError::InvalidArgument("MULTI calls can not be nested".to_string()).into()
+            }
+            Ok(Command::Watch(_)) => {
+                CommandError::InvalidArgument("WATCH inside MULTI is not allowed".to_string())
+                    .into()
+            }
+            Ok(
+                Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::SSubscribe(_)
+                | Command::SUnsubscribe(_),
+            ) => CommandError::InvalidArgument(
+                "Command not allowed inside a transaction".to_string(),
+            )
+            .into(),
+            Ok(cmd) => {
+                self.commands.push(cmd);
+                SimpleString::new("QUEUED").into()
+            }
+            Err(e) => {
+                self.aborted = true;
+                e.into()
+            }
+        }
+    }
+
+    /// Execute the queued commands one after the other, no command of another connection is
+    /// interleaved with them. Replies with the array of their replies, or with a null array
+    /// without executing anything if one of the watched keys was modified. The keys are
+    /// unwatched either way. The replies are sent to a RESP2 client unless `resp3` is set.
+    pub fn exec(self, backend: &mut Backend, watched: Vec<WatchedKey>, resp3: bool) -> RespFrame {
+        if self.aborted {
+            return RespFrame::Error(
+                "EXECABORT Transaction discarded because of previous errors.".into(),
+            );
+        }
+
+        backend.exclusive(|backend| {
+            if watched.iter().any(|v| v.is_modified()) {
+                return RespFrame::NullArray(RespNullArray);
+            }
+
+            let replies = self
+                .commands
+                .into_iter()
+                .map(|cmd| {
+                    // a SWAPDB or FLUSHDB of the transaction replaces the data of the database
+                    backend.reselect();
+                    let streams = cmd.replies_streams();
+                    match cmd.execute_now(backend) {
+                        reply if resp3 => reply,
+                        reply => resp2_reply(reply, streams),
+                    }
+                })
+                .collect::<Vec<_>>();
+            RespArray::new(replies).into()
+        })
+    }
+}
+
+// the transaction lives in the connection, which handles MULTI, EXEC and DISCARD itself. Outside
+// of a transaction only MULTI makes sense
+impl CommandExecutor for Multi {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl CommandExecutor for Exec {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        CommandError::InvalidArgument("EXEC without MULTI".to_string()).into()
+    }
+}
+
+impl CommandExecutor for Discard {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        CommandError::InvalidArgument("DISCARD without MULTI".to_string()).into()
+    }
+}
+
+// like MULTI, the watched keys live in the connection, see `execute_watch`
+impl CommandExecutor for Watch {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl Watch {
+    /// Add the keys to the ones the connection watches.
+    pub fn execute_watch(self, backend: &Backend, watched: &mut Vec<WatchedKey>) -> RespFrame {
+        watched.extend(self.keys.into_iter().map(|key| backend.watch(key)));
+        RESP_OK.clone()
+    }
+}
+
+// inside a transaction the keys are already checked, so UNWATCH has nothing left to do
+impl CommandExecutor for Unwatch {
+    fn execute(self, _backend: &Backend) -> RespFrame {
+        RESP_OK.clone()
+    }
+}
+
+impl TryFrom<RespArray> for Multi {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["multi"], 0)?;
+        Ok(Multi)
+    }
+}
+
+impl TryFrom<RespArray> for Exec {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["exec"], 0)?;
+        Ok(Exec)
+    }
+}
+
+impl TryFrom<RespArray> for Discard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["discard"], 0)?;
+        Ok(Discard)
+    }
+}
+
+impl TryFrom<RespArray> for Watch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["watch"], 1)?;
+
+        let keys = extract_args(value, 1)?
+            .into_iter()
+            .map(|k| parse_string(Some(k)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Watch { keys })
+    }
+}
+
+impl TryFrom<RespArray> for Unwatch {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["unwatch"], 0)?;
+        Ok(Unwatch)
+    }
+}
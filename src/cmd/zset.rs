@@ -0,0 +1,947 @@
+use std::cmp::Ordering;
+
+use crate::{
+    Backend, BulkString, LexBound, RespArray, RespFrame, RespNull, ScoreBound, SetCondition,
+    ZAddOptions, ZRangeBy, ZRangeLimit,
+};
+
+use super::{
+    extract_args, parse_bytes, parse_float, parse_integer, parse_option, parse_string,
+    validate_command, validate_command_multi_args, CommandError, CommandExecutor, ZAdd, ZCard,
+    ZCount, ZIncrBy, ZPopMax, ZPopMin, ZRange, ZRangeByScore, ZRank, ZRem, ZRevRangeByScore,
+    ZRevRank, ZScore,
+};
+
+impl CommandExecutor for ZAdd {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        if self.incr {
+            let (increment, member) = self.members.into_iter().next().unwrap_or_default();
+            return match backend.zincrby(self.key, increment, member, self.options) {
+                Ok(score) => score_to_frame(score),
+                Err(e) => e.into(),
+            };
+        }
+
+        match backend.zadd(self.key, self.members, self.options) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZCard {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zcard(&self.key) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZScore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zscore(&self.key, &self.member) {
+            Ok(score) => score_to_frame(score),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZIncrBy {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        let options = ZAddOptions::default();
+        match backend.zincrby(self.key, self.increment, self.member, options) {
+            Ok(score) => score_to_frame(score),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZRem {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrem(&self.key, &self.members) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZRank {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        zrank(backend, &self.key, &self.member, false, self.with_score)
+    }
+}
+
+impl CommandExecutor for ZRevRank {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        zrank(backend, &self.key, &self.member, true, self.with_score)
+    }
+}
+
+impl CommandExecutor for ZCount {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zcount(&self.key, self.min, self.max) {
+            Ok(n) => RespFrame::Integer(n as i64),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZRange {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zrange(&self.key, &self.by, self.rev, self.limit) {
+            Ok(members) => scored_members_to_frame(members, self.with_scores),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZRangeByScore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.0.execute(backend)
+    }
+}
+
+impl CommandExecutor for ZRevRangeByScore {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        self.0.execute(backend)
+    }
+}
+
+impl CommandExecutor for ZPopMin {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zpop(&self.key, self.count.unwrap_or(1), false) {
+            Ok(members) => scored_members_to_frame(members, true),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+impl CommandExecutor for ZPopMax {
+    fn execute(self, backend: &Backend) -> RespFrame {
+        match backend.zpop(&self.key, self.count.unwrap_or(1), true) {
+            Ok(members) => scored_members_to_frame(members, true),
+            Err(e) => e.into(),
+        }
+    }
+}
+
+fn zrank(backend: &Backend, key: &str, member: &[u8], rev: bool, with_score: bool) -> RespFrame {
+    match backend.zrank(key, member, rev) {
+        Ok(Some((rank, score))) if with_score => {
+            RespArray::new([RespFrame::Integer(rank as i64), RespFrame::Double(score)]).into()
+        }
+        Ok(Some((rank, _))) => RespFrame::Integer(rank as i64),
+        Ok(None) => RespFrame::Null(RespNull),
+        Err(e) => e.into(),
+    }
+}
+
+fn score_to_frame(score: Option<f64>) -> RespFrame {
+    match score {
+        Some(score) => RespFrame::Double(score),
+        None => RespFrame::Null(RespNull),
+    }
+}
+
+// a flat array of members, each followed by its score if `with_scores` is set
+fn scored_members_to_frame(members: Vec<(Vec<u8>, f64)>, with_scores: bool) -> RespFrame {
+    let mut frames = Vec::with_capacity(members.len() * if with_scores { 2 } else { 1 });
+    for (member, score) in members {
+        frames.push(BulkString::new(member).into());
+        if with_scores {
+            frames.push(RespFrame::Double(score));
+        }
+    }
+    RespArray::new(frames).into()
+}
+
+// "1.5" is an inclusive bound, "(1.5" an exclusive one, "-inf" and "+inf" are allowed
+fn parse_score_bound(arg: Option<RespFrame>) -> Result<ScoreBound, CommandError> {
+    let err = || CommandError::InvalidArgument("min or max is not a float".to_string());
+    let v = parse_bytes(arg).map_err(|_| err())?;
+    let (exclusive, v) = match v.strip_prefix(b"(") {
+        Some(v) => (true, v),
+        None => (false, v.as_slice()),
+    };
+
+    let v = parse_float(Some(BulkString::new(v.to_vec()).into())).map_err(|_| err())?;
+    Ok(if exclusive {
+        ScoreBound::Exclusive(v)
+    } else {
+        ScoreBound::Inclusive(v)
+    })
+}
+
+// "-" and "+" are the lowest and highest strings, "[a" is an inclusive bound and "(a" an
+// exclusive one
+fn parse_lex_bound(arg: Option<RespFrame>) -> Result<LexBound, CommandError> {
+    let v = parse_bytes(arg)?;
+    match v.split_first() {
+        Some((b'-', [])) => Ok(LexBound::Min),
+        Some((b'+', [])) => Ok(LexBound::Max),
+        Some((b'[', v)) => Ok(LexBound::Inclusive(v.to_vec())),
+        Some((b'(', v)) => Ok(LexBound::Exclusive(v.to_vec())),
+        _ => Err(CommandError::InvalidArgument(
+            "min or max not valid string range item".to_string(),
+        )),
+    }
+}
+
+// parse "key member [member ...]"
+fn parse_key_members(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<Vec<u8>>), CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let members = args
+        .map(|m| parse_bytes(Some(m)))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((key, members))
+}
+
+// parse "key member [WITHSCORE]"
+fn parse_rank(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Vec<u8>, bool), CommandError> {
+    validate_command_multi_args(&value, &[name], 2)?;
+    if value.len() > 4 {
+        return Err(CommandError::SyntaxError);
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let member = parse_bytes(args.next())?;
+    let with_score = match args.next() {
+        Some(arg) if parse_option(&arg).as_deref() == Some("withscore") => true,
+        Some(_) => return Err(CommandError::SyntaxError),
+        None => false,
+    };
+    Ok((key, member, with_score))
+}
+
+// parse "key [count]", the count must not be negative
+fn parse_pop(
+    value: RespArray,
+    name: &'static str,
+) -> Result<(String, Option<usize>), CommandError> {
+    validate_command_multi_args(&value, &[name], 1)?;
+    if value.len() > 3 {
+        return Err(CommandError::SyntaxError);
+    }
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let count = match args.next() {
+        Some(count) => match parse_integer(Some(count))? {
+            count if count < 0 => {
+                return Err(CommandError::InvalidArgument(
+                    "value is out of range, must be positive".to_string(),
+                ))
+            }
+            count => Some(count as usize),
+        },
+        None => None,
+    };
+    Ok((key, count))
+}
+
+// the options following "key start stop" in ZRANGE, the legacy commands only accept
+// WITHSCORES and LIMIT
+#[derive(Debug, Default)]
+struct ZRangeOptions {
+    by_score: bool,
+    by_lex: bool,
+    rev: bool,
+    limit: Option<ZRangeLimit>,
+    with_scores: bool,
+}
+
+fn parse_zrange_options(
+    args: &mut impl Iterator<Item = RespFrame>,
+    legacy: bool,
+) -> Result<ZRangeOptions, CommandError> {
+    let mut options = ZRangeOptions::default();
+    while let Some(arg) = args.next() {
+        match parse_option(&arg).as_deref() {
+            Some("byscore") if !legacy => options.by_score = true,
+            Some("bylex") if !legacy => options.by_lex = true,
+            Some("rev") if !legacy => options.rev = true,
+            Some("withscores") => options.with_scores = true,
+            Some("limit") => {
+                let offset = parse_integer(args.next()).map_err(|_| CommandError::SyntaxError)?;
+                let count = parse_integer(args.next()).map_err(|_| CommandError::SyntaxError)?;
+                options.limit = Some(ZRangeLimit { offset, count });
+            }
+            _ => return Err(CommandError::SyntaxError),
+        }
+    }
+    Ok(options)
+}
+
+impl TryFrom<RespArray> for ZAdd {
+    type Error = CommandError;
+
+    // ZADD key [NX | XX] [GT | LT] [CH] [INCR] score member [score member ...]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["zadd"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter().peekable();
+        let key = parse_string(args.next())?;
+
+        let mut options = ZAddOptions::default();
+        let mut incr = false;
+        let (mut nx, mut xx, mut gt, mut lt) = (false, false, false, false);
+        while let Some(option) = args.peek().and_then(parse_option) {
+            match option.as_str() {
+                "nx" => nx = true,
+                "xx" => xx = true,
+                "gt" => gt = true,
+                "lt" => lt = true,
+                "ch" => options.changed = true,
+                "incr" => incr = true,
+                _ => break,
+            }
+            args.next();
+        }
+
+        if nx && xx {
+            return Err(CommandError::InvalidArgument(
+                "XX and NX options at the same time are not compatible".to_string(),
+            ));
+        }
+        if [nx, gt, lt].into_iter().filter(|v| *v).count() > 1 {
+            return Err(CommandError::InvalidArgument(
+                "GT, LT, and/or NX options at the same time are not compatible".to_string(),
+            ));
+        }
+        options.comparison = match (gt, lt) {
+            (true, _) => Some(Ordering::Greater),
+            (_, true) => Some(Ordering::Less),
+            _ => None,
+        };
+        options.condition = match (nx, xx) {
+            (true, _) => Some(SetCondition::NotExists),
+            (_, true) => Some(SetCondition::Exists),
+            _ => None,
+        };
+
+        let args = args.collect::<Vec<_>>();
+        if args.is_empty() || args.len() % 2 != 0 {
+            return Err(CommandError::SyntaxError);
+        }
+        if incr && args.len() > 2 {
+            return Err(CommandError::InvalidArgument(
+                "INCR option supports a single increment-element pair".to_string(),
+            ));
+        }
+
+        let mut members = Vec::with_capacity(args.len() / 2);
+        let mut args = args.into_iter();
+        while let Some(score) = args.next() {
+            members.push((parse_float(Some(score))?, parse_bytes(args.next())?));
+        }
+
+        Ok(ZAdd {
+            key,
+            options,
+            incr,
+            members,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZCard {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zcard"], 1)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZCard {
+            key: parse_string(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZScore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zscore"], 2)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZScore {
+            key: parse_string(args.next())?,
+            member: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZIncrBy {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zincrby"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZIncrBy {
+            key: parse_string(args.next())?,
+            increment: parse_float(args.next())?,
+            member: parse_bytes(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZRem {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, members) = parse_key_members(value, "zrem")?;
+        Ok(ZRem { key, members })
+    }
+}
+
+impl TryFrom<RespArray> for ZRank {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, member, with_score) = parse_rank(value, "zrank")?;
+        Ok(ZRank {
+            key,
+            member,
+            with_score,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZRevRank {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, member, with_score) = parse_rank(value, "zrevrank")?;
+        Ok(ZRevRank {
+            key,
+            member,
+            with_score,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZCount {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command(&value, &["zcount"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        Ok(ZCount {
+            key: parse_string(args.next())?,
+            min: parse_score_bound(args.next())?,
+            max: parse_score_bound(args.next())?,
+        })
+    }
+}
+
+impl TryFrom<RespArray> for ZRange {
+    type Error = CommandError;
+
+    // ZRANGE key start stop [BYSCORE | BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        validate_command_multi_args(&value, &["zrange"], 3)?;
+
+        let mut args = extract_args(value, 1)?.into_iter();
+        let key = parse_string(args.next())?;
+        let (start, stop) = (args.next(), args.next());
+        let options = parse_zrange_options(&mut args, false)?;
+
+        if options.by_score && options.by_lex {
+            return Err(CommandError::SyntaxError);
+        }
+        if options.limit.is_some() && !options.by_score && !options.by_lex {
+            return Err(CommandError::InvalidArgument(
+                "syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX"
+                    .to_string(),
+            ));
+        }
+        if options.with_scores && options.by_lex {
+            return Err(CommandError::InvalidArgument(
+                "syntax error, WITHSCORES not supported in combination with BYLEX".to_string(),
+            ));
+        }
+
+        // with REV the range of scores or strings is given from max to min
+        let (min, max) = if options.rev && (options.by_score || options.by_lex) {
+            (stop, start)
+        } else {
+            (start, stop)
+        };
+        let by = if options.by_score {
+            ZRangeBy::Score(parse_score_bound(min)?, parse_score_bound(max)?)
+        } else if options.by_lex {
+            ZRangeBy::Lex(parse_lex_bound(min)?, parse_lex_bound(max)?)
+        } else {
+            ZRangeBy::Index(parse_integer(min)?, parse_integer(max)?)
+        };
+
+        Ok(ZRange {
+            key,
+            by,
+            rev: options.rev,
+            limit: options.limit,
+            with_scores: options.with_scores,
+        })
+    }
+}
+
+// ZRANGEBYSCORE key min max [WITHSCORES] [LIMIT offset count], ZREVRANGEBYSCORE takes max
+// before min
+fn parse_range_by_score(
+    value: RespArray,
+    name: &'static str,
+    rev: bool,
+) -> Result<ZRange, CommandError> {
+    validate_command_multi_args(&value, &[name], 3)?;
+
+    let mut args = extract_args(value, 1)?.into_iter();
+    let key = parse_string(args.next())?;
+    let (start, stop) = (args.next(), args.next());
+    let (min, max) = if rev { (stop, start) } else { (start, stop) };
+    let by = ZRangeBy::Score(parse_score_bound(min)?, parse_score_bound(max)?);
+    let options = parse_zrange_options(&mut args, true)?;
+
+    Ok(ZRange {
+        key,
+        by,
+        rev,
+        limit: options.limit,
+        with_scores: options.with_scores,
+    })
+}
+
+impl TryFrom<RespArray> for ZRangeByScore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ZRangeByScore(parse_range_by_score(
+            value,
+            "zrangebyscore",
+            false,
+        )?))
+    }
+}
+
+impl TryFrom<RespArray> for ZRevRangeByScore {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        Ok(ZRevRangeByScore(parse_range_by_score(
+            value,
+            "zrevrangebyscore",
+            true,
+        )?))
+    }
+}
+
+impl TryFrom<RespArray> for ZPopMin {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "zpopmin")?;
+        Ok(ZPopMin { key, count })
+    }
+}
+
+impl TryFrom<RespArray> for ZPopMax {
+    type Error = CommandError;
+
+    fn try_from(value: RespArray) -> Result<Self, Self::Error> {
+        let (key, count) = parse_pop(value, "zpopmax")?;
+        Ok(ZPopMax { key, count })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BackendError, RespDecode};
+
+    use super::*;
+    use crate::cmd::{command, run};
+    use anyhow::Result;
+    use bytes::BytesMut;
+
+    fn decode(data: &[u8]) -> Result<RespArray> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(data);
+        Ok(RespArray::decode(&mut buf)?)
+    }
+
+    fn zadd(backend: &Backend, key: &str, members: &[(f64, &str)]) -> RespFrame {
+        ZAdd {
+            key: key.to_string(),
+            options: ZAddOptions::default(),
+            incr: false,
+            members: members
+                .iter()
+                .map(|(s, m)| (*s, m.as_bytes().to_vec()))
+                .collect(),
+        }
+        .execute(backend)
+    }
+
+    fn members(members: &[&str]) -> RespFrame {
+        RespArray::new(
+            members
+                .iter()
+                .map(|m| BulkString::from(*m).into())
+                .collect::<Vec<RespFrame>>(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_zadd_from_resp_array() -> Result<()> {
+        let frame = decode(
+            b"*7\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$2\r\nXX\r\n$2\r\nch\r\n$2\r\nGT\r\n$3\r\n1.5\r\n$1\r\na\r\n",
+        )?;
+
+        let result: ZAdd = frame.try_into()?;
+        assert_eq!(result.key, "zset");
+        assert_eq!(result.options.condition, Some(SetCondition::Exists));
+        assert_eq!(result.options.comparison, Some(Ordering::Greater));
+        assert!(result.options.changed);
+        assert!(!result.incr);
+        assert_eq!(result.members, vec![(1.5, b"a".to_vec())]);
+
+        for line in ["zadd zset NX LT 1 a", "zadd zset GT LT 1 a"] {
+            let err = command::<ZAdd>(line).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "ERR GT, LT, and/or NX options at the same time are not compatible",
+                "{}",
+                line
+            );
+        }
+
+        let frame = decode(b"*4\r\n$4\r\nzadd\r\n$4\r\nzset\r\n$3\r\nnan\r\n$1\r\na\r\n")?;
+        let err = ZAdd::try_from(frame).unwrap_err();
+        assert_eq!(err.to_string(), "ERR value is not a valid float");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zadd_options() -> Result<()> {
+        let backend = Backend::new();
+        assert_eq!(
+            zadd(&backend, "zset", &[(1.0, "a"), (2.0, "b")]),
+            RespFrame::Integer(2)
+        );
+
+        let cmd = ZAdd {
+            key: "zset".to_string(),
+            options: ZAddOptions {
+                comparison: Some(Ordering::Greater),
+                changed: true,
+                ..Default::default()
+            },
+            incr: false,
+            members: vec![
+                (0.5, b"a".to_vec()),
+                (3.0, b"b".to_vec()),
+                (1.0, b"c".to_vec()),
+            ],
+        };
+        // "a" is not updated as the score is lower, "b" is changed and "c" is added
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+        assert_eq!(backend.zscore("zset", b"a")?, Some(1.0));
+        assert_eq!(backend.zscore("zset", b"b")?, Some(3.0));
+
+        let cmd = ZAdd {
+            key: "zset".to_string(),
+            options: ZAddOptions {
+                condition: Some(SetCondition::Exists),
+                ..Default::default()
+            },
+            incr: true,
+            members: vec![(2.5, b"a".to_vec())],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(3.5));
+
+        let cmd = ZAdd {
+            key: "zset".to_string(),
+            options: ZAddOptions {
+                condition: Some(SetCondition::Exists),
+                ..Default::default()
+            },
+            incr: true,
+            members: vec![(2.5, b"d".to_vec())],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        let cmd = ZIncrBy {
+            key: "zset".to_string(),
+            increment: f64::INFINITY,
+            member: b"c".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Double(f64::INFINITY));
+        let cmd = ZIncrBy {
+            key: "zset".to_string(),
+            increment: f64::NEG_INFINITY,
+            member: b"c".to_vec(),
+        };
+        assert_eq!(cmd.execute(&backend), BackendError::NotANumber.into());
+
+        backend.set("string".to_string(), b"hello".to_vec());
+        assert_eq!(
+            zadd(&backend, "string", &[(1.0, "a")]),
+            BackendError::WrongType.into()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrange_command() -> Result<()> {
+        let backend = Backend::new();
+        zadd(
+            &backend,
+            "zset",
+            &[(1.0, "a"), (2.0, "b"), (2.0, "c"), (3.0, "d")],
+        );
+
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset 0 -1"),
+            members(&["a", "b", "c", "d"])
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset 0 1 REV"),
+            members(&["d", "c"])
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset 0 0 WITHSCORES"),
+            RespArray::new([BulkString::from("a").into(), RespFrame::Double(1.0)]).into()
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset (1 +inf BYSCORE"),
+            members(&["b", "c", "d"])
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset 2 -inf BYSCORE REV LIMIT 1 5"),
+            members(&["b", "a"])
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset [b (d BYLEX"),
+            members(&["b", "c"])
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset + - BYLEX REV LIMIT 0 1"),
+            members(&["d"])
+        );
+        assert_eq!(run::<ZRange>(&backend, "zrange missing 0 -1"), members(&[]));
+
+        let err = ZRange::try_from(decode(
+            b"*6\r\n$6\r\nzrange\r\n$4\r\nzset\r\n$1\r\n0\r\n$1\r\n1\r\n$5\r\nlimit\r\n$1\r\n0\r\n",
+        )?)
+        .unwrap_err();
+        assert_eq!(err.to_string(), "ERR syntax error");
+
+        let cmd: ZRevRangeByScore = decode(
+            b"*7\r\n$16\r\nzrevrangebyscore\r\n$4\r\nzset\r\n$1\r\n3\r\n$2\r\n(1\r\n$5\r\nLIMIT\r\n$1\r\n0\r\n$1\r\n2\r\n",
+        )?
+        .try_into()?;
+        assert_eq!(cmd.execute(&backend), members(&["d", "c"]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrank_zcount_zrem_zpop_commands() -> Result<()> {
+        let backend = Backend::new();
+        zadd(&backend, "zset", &[(1.0, "a"), (2.0, "b"), (3.0, "c")]);
+
+        let cmd = ZRank {
+            key: "zset".to_string(),
+            member: b"b".to_vec(),
+            with_score: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        let cmd = ZRevRank {
+            key: "zset".to_string(),
+            member: b"a".to_vec(),
+            with_score: true,
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([RespFrame::Integer(2), RespFrame::Double(1.0)]).into()
+        );
+        let cmd = ZRank {
+            key: "zset".to_string(),
+            member: b"x".to_vec(),
+            with_score: false,
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Null(RespNull));
+
+        let cmd = ZCount {
+            key: "zset".to_string(),
+            min: ScoreBound::Exclusive(1.0),
+            max: ScoreBound::Inclusive(f64::INFINITY),
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(2));
+
+        let cmd = ZPopMax {
+            key: "zset".to_string(),
+            count: Some(2),
+        };
+        assert_eq!(
+            cmd.execute(&backend),
+            RespArray::new([
+                BulkString::from("c").into(),
+                RespFrame::Double(3.0),
+                BulkString::from("b").into(),
+                RespFrame::Double(2.0),
+            ])
+            .into()
+        );
+
+        let cmd = ZRem {
+            key: "zset".to_string(),
+            members: vec![b"a".to_vec(), b"x".to_vec()],
+        };
+        assert_eq!(cmd.execute(&backend), RespFrame::Integer(1));
+        assert!(!backend.exists("zset"));
+
+        let cmd = ZPopMin {
+            key: "zset".to_string(),
+            count: None,
+        };
+        assert_eq!(cmd.execute(&backend), members(&[]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zrange_by_score_and_lex() {
+        let backend = Backend::new();
+        zadd(
+            &backend,
+            "zset",
+            &[(1.0, "a"), (2.0, "b"), (3.0, "c"), (4.0, "d"), (5.0, "e")],
+        );
+        zadd(
+            &backend,
+            "lex",
+            &[(0.0, "a"), (0.0, "b"), (0.0, "c"), (0.0, "d"), (0.0, "e")],
+        );
+
+        for (line, expected) in [
+            ("zrange zset 2 4 BYSCORE", &["b", "c", "d"][..]),
+            ("zrange zset (2 (5 BYSCORE", &["c", "d"]),
+            ("zrange zset (2 (3 BYSCORE", &[]),
+            ("zrange zset 6 +inf BYSCORE", &[]),
+            ("zrange zset -inf +inf BYSCORE LIMIT 1 2", &["b", "c"]),
+            ("zrange zset -inf +inf BYSCORE LIMIT 3 -1", &["d", "e"]),
+            ("zrange zset -inf +inf BYSCORE LIMIT 5 1", &[]),
+            ("zrange zset -inf +inf BYSCORE LIMIT -1 1", &[]),
+            ("zrange zset +inf -inf BYSCORE REV LIMIT 0 2", &["e", "d"]),
+            ("zrange zset (4 2 BYSCORE REV", &["c", "b"]),
+            ("zrange zset 1 -2 REV", &["d", "c", "b"]),
+            ("zrange zset -2 10", &["d", "e"]),
+            ("zrange zset 5 10", &[]),
+            ("zrange lex - + BYLEX LIMIT 1 3", &["b", "c", "d"]),
+            ("zrange lex (b [d BYLEX", &["c", "d"]),
+            ("zrange lex (e [b BYLEX REV", &["d", "c", "b"]),
+            ("zrange lex + (c BYLEX REV LIMIT 1 5", &["d"]),
+            ("zrange lex [c [a BYLEX", &[]),
+            ("zrange lex - + BYLEX LIMIT 10 1", &[]),
+        ] {
+            assert_eq!(run::<ZRange>(&backend, line), members(expected), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_zrank_follows_the_scores() {
+        let backend = Backend::new();
+        let all = (0..100)
+            .map(|i| (i as f64, format!("m{}", i)))
+            .collect::<Vec<_>>();
+        let all = all
+            .iter()
+            .map(|(s, m)| (*s, m.as_str()))
+            .collect::<Vec<_>>();
+        zadd(&backend, "zset", &all);
+
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m0"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m42"),
+            RespFrame::Integer(42)
+        );
+        assert_eq!(
+            run::<ZRevRank>(&backend, "zrevrank zset m42 withscore"),
+            RespArray::new([RespFrame::Integer(57), RespFrame::Double(42.0)]).into()
+        );
+
+        // moving a member moves the ranks of the members it passes
+        assert_eq!(
+            run::<ZIncrBy>(&backend, "zincrby zset 50.5 m10"),
+            RespFrame::Double(60.5)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m10"),
+            RespFrame::Integer(60)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m42"),
+            RespFrame::Integer(41)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m61"),
+            RespFrame::Integer(61)
+        );
+        assert_eq!(
+            run::<ZRange>(&backend, "zrange zset 59 61"),
+            members(&["m60", "m10", "m61"])
+        );
+
+        // a missing member is added with the increment as its score
+        assert_eq!(
+            run::<ZIncrBy>(&backend, "zincrby zset -1 new"),
+            RespFrame::Double(-1.0)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset new"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<ZRevRank>(&backend, "zrevrank zset new"),
+            RespFrame::Integer(100)
+        );
+
+        run::<ZRem>(&backend, "zrem zset new m0 m1");
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset m2"),
+            RespFrame::Integer(0)
+        );
+        assert_eq!(
+            run::<ZRank>(&backend, "zrank zset new"),
+            RespFrame::Null(RespNull)
+        );
+
+        backend.set("string".to_string(), b"hello".to_vec());
+        assert_eq!(
+            run::<ZIncrBy>(&backend, "zincrby string 1 a"),
+            BackendError::WrongType.into()
+        );
+        assert_eq!(
+            run::<ZIncrBy>(&backend, "zincrby zset abc a"),
+            RespFrame::Error("ERR value is not a valid float".into())
+        );
+    }
+}
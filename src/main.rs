@@ -1,7 +1,11 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use simple_redis::{network, Backend};
+use simple_redis::{network, Backend, DEFAULT_DATABASES};
 use tokio::net::TcpListener;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
+
+const ACTIVE_EXPIRE_INTERVAL: Duration = Duration::from_millis(100);
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -12,7 +16,28 @@ async fn main() -> Result<()> {
     info!("Simple-Redis-Server is listening on {}", addr);
     let listener = TcpListener::bind(addr).await?;
 
-    let backend = Backend::new();
+    // the number of logical databases can be set with `--databases <n>`, like redis
+    let mut args = std::env::args().skip_while(|v| v != "--databases").skip(1);
+    let databases = match args.next() {
+        Some(v) => v.parse()?,
+        None => DEFAULT_DATABASES,
+    };
+    let backend = Backend::with_databases(databases);
+    info!("Serving {} databases", backend.database_count());
+
+    // reclaim expired keys which are never accessed again
+    let expire_backend = backend.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ACTIVE_EXPIRE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let n = expire_backend.active_expire_cycle();
+            if n > 0 {
+                debug!("Active expire cycle removed {} keys", n);
+            }
+        }
+    });
+
     loop {
         let (stream, remote_addr) = listener.accept().await?;
         info!("Accepted connection from: {}", remote_addr);
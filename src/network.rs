@@ -1,14 +1,17 @@
-use anyhow::Result;
+use std::collections::VecDeque;
+
+use anyhow::{anyhow, Result};
 use bytes::BytesMut;
 use futures::SinkExt;
 use tokio::net::TcpStream;
 use tokio_stream::StreamExt;
 use tokio_util::codec::{Decoder, Encoder, Framed};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::{
-    cmd::{Command, CommandExecutor},
-    Backend, RespDecodeV2, RespEncode, RespError, RespFrame,
+    cmd::{resp2_reply, Command, CommandError, CommandKeys, Transaction},
+    Backend, PubSubMessage, RespDecodeV2, RespEncode, RespError, RespFrame, RespPush, SimpleString,
+    Subscriber, WatchedKey, DEFAULT_PUBSUB_BUFFER,
 };
 
 #[derive(Debug)]
@@ -20,44 +23,251 @@ struct RedisRequest {
     backend: Backend,
 }
 
+// most commands have a single reply, SUBSCRIBE and friends have one per channel
 #[derive(Debug)]
 struct RedisResponse {
-    frame: RespFrame,
+    frames: Vec<RespFrame>,
+}
+
+// the state a connection keeps between its commands
+#[derive(Debug, Default)]
+struct ConnectionContext {
+    // the database selected by the connection
+    db: usize,
+    // the commands queued since MULTI
+    transaction: Option<Transaction>,
+    // the keys watched since WATCH, until EXEC, DISCARD or UNWATCH
+    watched: Vec<WatchedKey>,
+    // the channels and patterns subscribed to, the connection is in the subscribed mode as long
+    // as there are some
+    subscriber: Option<Subscriber>,
+    // switched to RESP3 by HELLO 3
+    resp3: bool,
 }
 
+// the most frames a client may pipeline while a command is blocked, the stream isn't read
+// further until they are handled
+const MAX_PENDING_FRAMES: usize = 1024;
+
 pub async fn stream_handler(stream: TcpStream, backend: Backend) -> Result<()> {
     // how to get a frame from the stream?
     let mut framed = Framed::new(stream, RespFrameCodec);
+    // frames pipelined by the client while a command was blocked
+    let mut pending = VecDeque::new();
+    let mut context = ConnectionContext::default();
     loop {
-        match framed.next().await {
-            Some(Ok(frame)) => {
-                info!("Received frame: {:?}", frame);
-                let request = RedisRequest {
-                    frame,
-                    backend: backend.clone(),
-                };
-
-                let response = request_handler(request).await?;
+        let frame = match pending.pop_front() {
+            Some(frame) => frame,
+            None => tokio::select! {
+                // forward the published messages before reading more commands
+                biased;
+                message = next_message(&mut context.subscriber) => match message {
+                    Some(message) => {
+                        let frame = push_frame(message.into(), context.resp3);
+                        send(&mut framed, vec![frame], &context.subscriber).await?;
+                        continue;
+                    }
+                    None => return Err(slow_subscriber()),
+                },
+                frame = framed.next() => match frame {
+                    Some(Ok(frame)) => frame,
+                    Some(Err(e)) => return Err(e),
+                    None => return Ok(()),
+                },
+            },
+        };
 
-                info!("Sending response: {:?}", response.frame);
+        info!("Received frame: {:?}", frame);
+        // select the database again for every command, another connection may have swapped or
+        // flushed it
+        let request = RedisRequest {
+            frame,
+            backend: backend.select(context.db)?,
+        };
 
-                framed.send(response.frame).await?;
+        // keep reading the stream while the command runs, so that closing the connection
+        // cancels a blocked command, which unregisters it from the backend. A client which
+        // pipelines too many frames meanwhile is only read again once the command is done
+        let response = {
+            let response = request_handler(request, &mut context);
+            tokio::pin!(response);
+            loop {
+                tokio::select! {
+                    biased;
+                    response = &mut response => break response?,
+                    frame = framed.next(), if pending.len() < MAX_PENDING_FRAMES => match frame {
+                        Some(Ok(frame)) => pending.push_back(frame),
+                        Some(Err(e)) => return Err(e),
+                        None => return Ok(()),
+                    },
+                }
             }
-            Some(Err(e)) => return Err(e),
-            None => return Ok(()),
+        };
+
+        send(&mut framed, response.frames, &context.subscriber).await?;
+    }
+}
+
+// a subscriber stops waiting for the client as soon as its buffer overflows, or a client which
+// doesn't read would hold the connection forever
+async fn send(
+    framed: &mut Framed<TcpStream, RespFrameCodec>,
+    frames: Vec<RespFrame>,
+    subscriber: &Option<Subscriber>,
+) -> Result<()> {
+    let send = async {
+        for frame in frames {
+            info!("Sending response: {:?}", frame);
+            framed.feed(frame).await?;
         }
+        framed.flush().await
+    };
+    match subscriber {
+        Some(subscriber) => tokio::select! {
+            ret = send => ret,
+            _ = subscriber.overflowed() => Err(slow_subscriber()),
+        },
+        None => send.await,
     }
 }
 
-async fn request_handler(request: RedisRequest) -> Result<RedisResponse> {
-    let (frame, backend) = (request.frame, request.backend);
-    let cmd = Command::try_from(frame)?;
+// redis drops the clients over the pubsub output buffer limit the same way
+fn slow_subscriber() -> anyhow::Error {
+    anyhow!("subscriber is too slow, closing the connection")
+}
 
-    info!("Executing command: {:?}", cmd);
+// the next message published to the subscriptions of the connection, it never comes if there
+// are none. None if the subscriber overflowed
+async fn next_message(subscriber: &mut Option<Subscriber>) -> Option<PubSubMessage> {
+    match subscriber {
+        Some(subscriber) => subscriber.recv().await,
+        None => std::future::pending().await,
+    }
+}
 
-    let frame = cmd.execute(&backend);
+async fn request_handler(
+    request: RedisRequest,
+    context: &mut ConnectionContext,
+) -> Result<RedisResponse> {
+    let (frame, mut backend) = (request.frame, request.backend);
+    let name = command_name(&frame);
+    let keys = CommandKeys::of(&frame);
+    let cmd = Command::try_from(frame);
+    if let Err(e) = &cmd {
+        warn!("Invalid command: {}", e);
+    }
+    let streams = cmd.as_ref().is_ok_and(Command::replies_streams);
 
-    Ok(RedisResponse { frame })
+    // in the subscribed mode of RESP2 only the subscriptions can change, a RESP3 connection can
+    // tell the messages from the replies so it runs any command
+    let subscribed = context.subscriber.is_some() && !context.resp3;
+    let frames = match (cmd, context.transaction.take()) {
+        (
+            Ok(
+                cmd @ (Command::Subscribe(_)
+                | Command::Unsubscribe(_)
+                | Command::PSubscribe(_)
+                | Command::PUnsubscribe(_)
+                | Command::SSubscribe(_)
+                | Command::SUnsubscribe(_)),
+            ),
+            None,
+        ) => {
+            let subscriber = context
+                .subscriber
+                .get_or_insert_with(|| backend.subscriber(DEFAULT_PUBSUB_BUFFER));
+            let frames = match cmd {
+                Command::Subscribe(cmd) => cmd.execute_subscribe(subscriber),
+                Command::Unsubscribe(cmd) => cmd.execute_unsubscribe(subscriber),
+                Command::PSubscribe(cmd) => cmd.execute_psubscribe(subscriber),
+                Command::PUnsubscribe(cmd) => cmd.execute_punsubscribe(subscriber),
+                Command::SSubscribe(cmd) => cmd.execute_ssubscribe(subscriber),
+                Command::SUnsubscribe(cmd) => cmd.execute_sunsubscribe(subscriber),
+                _ => unreachable!(),
+            };
+            frames
+                .into_iter()
+                .map(|frame| push_frame(frame, context.resp3))
+                .collect()
+        }
+        // a RESP2 subscriber can still check that the connection is alive
+        (Ok(Command::Ping(cmd)), None) if subscribed => vec![cmd.execute_subscribed()],
+        (Ok(_), None) if subscribed => vec![CommandError::InvalidArgument(format!(
+            "Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING are allowed in this context",
+            name
+        ))
+        .into()],
+        (Ok(Command::Hello(cmd)), None) => vec![cmd.execute_hello(&mut context.resp3)],
+        (Ok(Command::Multi(_)), None) => {
+            context.transaction = Some(Transaction::new());
+            vec![SimpleString::new("OK").into()]
+        }
+        (Ok(Command::Exec(_)), Some(transaction)) => {
+            info!("Executing transaction: {:?}", transaction);
+            vec![transaction.exec(
+                &mut backend,
+                std::mem::take(&mut context.watched),
+                context.resp3,
+            )]
+        }
+        (Ok(Command::Discard(_)), Some(_)) => {
+            context.watched.clear();
+            vec![SimpleString::new("OK").into()]
+        }
+        (cmd, Some(mut transaction)) => {
+            let frame = transaction.queue(cmd);
+            context.transaction = Some(transaction);
+            vec![frame]
+        }
+        (Ok(Command::Watch(cmd)), None) => {
+            vec![cmd.execute_watch(&backend, &mut context.watched)]
+        }
+        (Ok(Command::Unwatch(_)), None) => {
+            context.watched.clear();
+            vec![SimpleString::new("OK").into()]
+        }
+        (Ok(cmd), None) => {
+            info!("Executing command: {:?}", cmd);
+            vec![cmd.execute_async(&mut backend, keys).await]
+        }
+        // a bad command is reported to the client as an error reply, the connection stays alive
+        (Err(e), None) => vec![e.into()],
+    };
+    context.db = backend.index();
+    // after HELLO the reply is sent with the protocol it switched to
+    let frames = match context.resp3 {
+        true => frames,
+        false => frames
+            .into_iter()
+            .map(|frame| resp2_reply(frame, streams))
+            .collect(),
+    };
+    // the connection leaves the subscribed mode with its last subscription
+    if context.subscriber.as_ref().is_some_and(|v| v.count() == 0) {
+        context.subscriber = None;
+    }
+
+    Ok(RedisResponse { frames })
+}
+
+// RESP3 clients get the messages and the replies about their subscriptions as push frames, so
+// that they can tell them from the replies of their other commands
+fn push_frame(frame: RespFrame, resp3: bool) -> RespFrame {
+    match frame {
+        RespFrame::Array(array) if resp3 => RespPush::new(array.0).into(),
+        frame => frame,
+    }
+}
+
+// the lowercase name of the command the frame holds, empty if it doesn't look like a command
+fn command_name(frame: &RespFrame) -> String {
+    match frame {
+        RespFrame::Array(array) => match array.first() {
+            Some(RespFrame::BulkString(name)) => String::from_utf8_lossy(name).to_ascii_lowercase(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
 }
 
 impl Encoder<RespFrame> for RespFrameCodec {
@@ -82,3 +292,673 @@ impl Decoder for RespFrameCodec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::request_array;
+    use crate::{BulkString, RespArray, RespNullArray, SimpleError};
+    use anyhow::Result;
+    use std::collections::HashSet;
+
+    // run the command line through the connection, selecting the database like stream_handler
+    async fn requests(
+        backend: &Backend,
+        context: &mut ConnectionContext,
+        line: &str,
+    ) -> Result<Vec<RespFrame>> {
+        let request = RedisRequest {
+            frame: request_array(line).into(),
+            backend: backend.select(context.db)?,
+        };
+        Ok(request_handler(request, context).await?.frames)
+    }
+
+    // like `requests`, for the commands with a single reply
+    async fn request(
+        backend: &Backend,
+        context: &mut ConnectionContext,
+        line: &str,
+    ) -> Result<RespFrame> {
+        let mut frames = requests(backend, context, line).await?;
+        assert_eq!(frames.len(), 1, "{}", line);
+        Ok(frames.remove(0))
+    }
+
+    fn ok() -> RespFrame {
+        SimpleString::new("OK").into()
+    }
+
+    fn queued() -> RespFrame {
+        SimpleString::new("QUEUED").into()
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_replies_error() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        let frame = request(&backend, &mut context, "get").await?;
+        assert_eq!(
+            frame,
+            SimpleError::new("ERR wrong number of arguments for 'get' command").into()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_handler_select() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        assert_eq!(request(&backend, &mut context, "select 3").await?, ok());
+        assert_eq!(context.db, 3);
+        request(&backend, &mut context, "set key value").await?;
+        assert!(backend.select(3)?.exists("key"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        assert_eq!(request(&backend, &mut context, "multi").await?, ok());
+        assert_eq!(
+            request(&backend, &mut context, "set key 1").await?,
+            queued()
+        );
+        assert_eq!(request(&backend, &mut context, "incr key").await?, queued());
+        // a runtime error doesn't abort the transaction
+        assert_eq!(
+            request(&backend, &mut context, "lpush key a").await?,
+            queued()
+        );
+        assert_eq!(request(&backend, &mut context, "select 1").await?, queued());
+        assert_eq!(
+            request(&backend, &mut context, "set key 3").await?,
+            queued()
+        );
+        assert!(!backend.exists("key"));
+
+        let frame = request(&backend, &mut context, "exec").await?;
+        assert_eq!(
+            frame,
+            RespArray::new([
+                ok(),
+                RespFrame::Integer(2),
+                SimpleError::new(
+                    "WRONGTYPE Operation against a key holding the wrong kind of value"
+                )
+                .into(),
+                ok(),
+                ok(),
+            ])
+            .into()
+        );
+        assert_eq!(backend.get("key")?, Some(b"2".to_vec()));
+        assert_eq!(backend.select(1)?.get("key")?, Some(b"3".to_vec()));
+        assert_eq!(context.db, 1);
+        assert!(context.transaction.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_flushdb() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        // the commands after FLUSHDB or SWAPDB run against the new data of the database
+        for line in [
+            "set a 1",
+            "multi",
+            "flushdb",
+            "set b 2",
+            "swapdb 0 1",
+            "set c 3",
+        ] {
+            request(&backend, &mut context, line).await?;
+        }
+        request(&backend, &mut context, "exec").await?;
+        let db1 = backend.select(1)?;
+        assert_eq!(db1.get("a")?, None);
+        assert_eq!(db1.get("b")?, Some(b"2".to_vec()));
+        assert_eq!(backend.select(0)?.get("c")?, Some(b"3".to_vec()));
+        assert_eq!(backend.select(0)?.dbsize(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_errors() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            SimpleError::new("ERR EXEC without MULTI").into()
+        );
+        assert_eq!(
+            request(&backend, &mut context, "discard").await?,
+            SimpleError::new("ERR DISCARD without MULTI").into()
+        );
+
+        request(&backend, &mut context, "multi").await?;
+        assert_eq!(
+            request(&backend, &mut context, "multi").await?,
+            SimpleError::new("ERR MULTI calls can not be nested").into()
+        );
+        request(&backend, &mut context, "set key 1").await?;
+        assert_eq!(request(&backend, &mut context, "discard").await?, ok());
+        assert!(!backend.exists("key"));
+
+        // a command rejected while queuing aborts the whole transaction
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "set key 1").await?;
+        assert!(matches!(
+            request(&backend, &mut context, "nosuchcommand").await?,
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            request(&backend, &mut context, "get").await?,
+            RespFrame::Error(_)
+        ));
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            SimpleError::new("EXECABORT Transaction discarded because of previous errors.").into()
+        );
+        assert!(!backend.exists("key"));
+        assert!(context.transaction.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_transaction_is_atomic() -> Result<()> {
+        let backend = Backend::new();
+        backend.set("counter".to_string(), b"0".to_vec());
+
+        // another connection keeps incrementing the counter
+        let other = backend.clone();
+        let handle = tokio::spawn(async move {
+            let mut context = ConnectionContext::default();
+            for _ in 0..2000 {
+                request(&other, &mut context, "incr counter").await?;
+            }
+            anyhow::Ok(())
+        });
+
+        // the transaction sees the counter at 0 after its own SET, whatever the other
+        // connection does in between
+        let mut context = ConnectionContext::default();
+        for _ in 0..50 {
+            request(&backend, &mut context, "multi").await?;
+            request(&backend, &mut context, "set counter 0").await?;
+            for _ in 0..20 {
+                request(&backend, &mut context, "get counter").await?;
+            }
+            let RespFrame::Array(replies) = request(&backend, &mut context, "exec").await? else {
+                panic!("expected an array");
+            };
+            for reply in replies.0.into_iter().skip(1) {
+                assert_eq!(reply, BulkString::from("0").into());
+            }
+        }
+        handle.await??;
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_msetnx_is_atomic() -> Result<()> {
+        let backend = Backend::new();
+
+        // two connections race to set the same keys, in a different order
+        for i in 0..200 {
+            let keys = (0..50).map(|k| format!("{i}:{k}")).collect::<Vec<_>>();
+            let line = |keys: &mut dyn Iterator<Item = &String>, value: &str| {
+                let pairs = keys.map(|key| format!("{key} {value}"));
+                format!("msetnx {}", pairs.collect::<Vec<_>>().join(" "))
+            };
+            let lines = [
+                line(&mut keys.iter(), "1"),
+                line(&mut keys.iter().rev(), "2"),
+            ];
+            let handles = lines.map(|line| {
+                let backend = backend.clone();
+                tokio::spawn(async move {
+                    let mut context = ConnectionContext::default();
+                    request(&backend, &mut context, &line).await
+                })
+            });
+            let mut set = 0;
+            for handle in handles {
+                set += (handle.await?? == RespFrame::Integer(1)) as usize;
+            }
+            assert_eq!(set, 1);
+            let values = keys
+                .iter()
+                .map(|key| backend.get(key))
+                .collect::<Result<HashSet<_>, _>>()?;
+            assert_eq!(values.len(), 1);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_panic() -> Result<()> {
+        let backend = Backend::new();
+
+        // a command which panics in a transaction must not leave the backend unusable
+        let mut cloned = backend.clone();
+        let ret = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cloned.exclusive(|_| panic!("command panicked"))
+        }));
+        assert!(ret.is_err());
+
+        let mut context = ConnectionContext::default();
+        assert_eq!(request(&backend, &mut context, "set key 1").await?, ok());
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "incr key").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([RespFrame::Integer(2)]).into()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+        let mut other = ConnectionContext::default();
+        request(&backend, &mut context, "set key 1").await?;
+
+        // nobody touches the watched keys
+        assert_eq!(
+            request(&backend, &mut context, "watch key missing").await?,
+            ok()
+        );
+        request(&backend, &mut other, "set unrelated 1").await?;
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "incr key").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([RespFrame::Integer(2)]).into()
+        );
+
+        // EXEC unwatched the keys, so this write doesn't matter anymore
+        request(&backend, &mut other, "set missing 1").await?;
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "incr key").await?;
+        assert!(matches!(
+            request(&backend, &mut context, "exec").await?,
+            RespFrame::Array(_)
+        ));
+
+        // another connection creates a watched key
+        request(&backend, &mut context, "watch key other").await?;
+        request(&backend, &mut other, "set other 1").await?;
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "incr key").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespFrame::NullArray(RespNullArray)
+        );
+        assert_eq!(backend.get("key")?, Some(b"3".to_vec()));
+        assert!(context.watched.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch_touched_by_writes() -> Result<()> {
+        let server = Backend::new();
+        let mut other = ConnectionContext::default();
+
+        for line in [
+            "del key",
+            "append key a",
+            "expire key 100",
+            "persist key",
+            "rename key other",
+            "flushdb",
+            "flushall",
+        ] {
+            // a flush replaces the data the handle holds
+            let backend = server.select(0)?;
+            backend.del(&["key".to_string(), "other".to_string()]);
+            backend.set("key".to_string(), b"v".to_vec());
+            backend.expire_at("key", crate::now_ms() + 10_000);
+
+            let watched = backend.watch("key".to_string());
+            assert!(!watched.is_modified(), "{}", line);
+            request(&backend, &mut other, line).await?;
+            assert!(watched.is_modified(), "{}", line);
+        }
+
+        // a write which fails doesn't modify the key
+        let backend = server.select(0)?;
+        backend.set("key".to_string(), b"v".to_vec());
+        let watched = backend.watch("key".to_string());
+        request(&backend, &mut other, "lpush key a").await?;
+        request(&backend, &mut other, "get key").await?;
+        assert!(!watched.is_modified());
+
+        // neither does flushing a database without the key
+        request(&backend, &mut other, "select 1").await?;
+        request(&backend, &mut other, "flushdb").await?;
+        assert!(!watched.is_modified());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch_expired_key() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+        request(&backend, &mut context, "set key 1 px 20").await?;
+
+        request(&backend, &mut context, "watch key").await?;
+        tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "set other 1").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespFrame::NullArray(RespNullArray)
+        );
+        assert!(!backend.exists("other"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unwatch_discard() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+        let mut other = ConnectionContext::default();
+
+        request(&backend, &mut context, "watch key").await?;
+        assert_eq!(request(&backend, &mut context, "unwatch").await?, ok());
+        assert!(context.watched.is_empty());
+
+        request(&backend, &mut context, "watch key").await?;
+        request(&backend, &mut context, "multi").await?;
+        assert_eq!(
+            request(&backend, &mut context, "watch key").await?,
+            SimpleError::new("ERR WATCH inside MULTI is not allowed").into()
+        );
+        assert_eq!(request(&backend, &mut context, "discard").await?, ok());
+        assert!(context.watched.is_empty());
+
+        // nobody watches the key anymore
+        request(&backend, &mut other, "set key 1").await?;
+        request(&backend, &mut context, "multi").await?;
+        request(&backend, &mut context, "get key").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([BulkString::from("1").into()]).into()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pubsub() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriber = ConnectionContext::default();
+        let mut publisher = ConnectionContext::default();
+
+        let frames = requests(&backend, &mut subscriber, "subscribe news sport").await?;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[1],
+            RespArray::new([
+                BulkString::from("subscribe").into(),
+                BulkString::from("sport").into(),
+                RespFrame::Integer(2),
+            ])
+            .into()
+        );
+
+        // only the subscriptions can change in the subscribed mode
+        assert_eq!(
+            request(&backend, &mut subscriber, "get news").await?,
+            SimpleError::new("ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING are allowed in this context").into()
+        );
+        // but the connection can still be checked
+        assert_eq!(
+            request(&backend, &mut subscriber, "ping").await?,
+            RespArray::new([bulk("pong"), bulk("")]).into()
+        );
+        assert_eq!(
+            request(&backend, &mut subscriber, "ping hi").await?,
+            RespArray::new([bulk("pong"), bulk("hi")]).into()
+        );
+        request(&backend, &mut subscriber, "psubscribe s*").await?;
+
+        assert_eq!(
+            request(&backend, &mut publisher, "publish sport goal").await?,
+            RespFrame::Integer(2)
+        );
+        let message = next_message(&mut subscriber.subscriber).await.unwrap();
+        assert_eq!(message.channel, "sport");
+        assert_eq!(message.payload, b"goal");
+
+        // the message still buffered is delivered before the reply
+        let frames = requests(&backend, &mut subscriber, "unsubscribe").await?;
+        assert_eq!(frames.len(), 3);
+        assert!(subscriber.subscriber.is_some());
+        let frames = requests(&backend, &mut subscriber, "punsubscribe").await?;
+        assert_eq!(frames.len(), 1);
+
+        // the last subscription is gone, the connection is back to normal
+        assert!(subscriber.subscriber.is_none());
+        assert_eq!(
+            request(&backend, &mut subscriber, "get news").await?,
+            RespFrame::NullBulkString(crate::RespNullBulkString)
+        );
+        assert_eq!(
+            request(&backend, &mut subscriber, "ping").await?,
+            SimpleString::new("PONG").into()
+        );
+        assert_eq!(
+            request(&backend, &mut subscriber, "ping hi").await?,
+            bulk("hi")
+        );
+        assert_eq!(
+            request(&backend, &mut subscriber, "ping a b").await?,
+            SimpleError::new("ERR wrong number of arguments for 'ping' command").into()
+        );
+        assert_eq!(
+            request(&backend, &mut publisher, "publish sport goal").await?,
+            RespFrame::Integer(0)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_in_transaction() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+
+        request(&backend, &mut context, "multi").await?;
+        assert_eq!(
+            request(&backend, &mut context, "subscribe news").await?,
+            SimpleError::new("ERR Command not allowed inside a transaction").into()
+        );
+        request(&backend, &mut context, "publish news hello").await?;
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([RespFrame::Integer(0)]).into()
+        );
+        assert!(context.subscriber.is_none());
+
+        Ok(())
+    }
+
+    fn bulk(s: &str) -> RespFrame {
+        BulkString::from(s).into()
+    }
+
+    #[tokio::test]
+    async fn test_resp2_replies() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+        for line in ["zadd z 1.5 a 1e20 b inf c", "xadd s 1-1 f v"] {
+            request(&backend, &mut context, line).await?;
+        }
+
+        // the RESP3 types are sent as their RESP2 counterparts
+        assert_eq!(
+            request(&backend, &mut context, "zscore z a").await?,
+            bulk("1.5")
+        );
+        assert_eq!(
+            request(&backend, &mut context, "zrange z 1 -1 withscores").await?,
+            RespArray::new([bulk("b"), bulk("1e+20"), bulk("c"), bulk("inf")]).into()
+        );
+        assert_eq!(
+            request(&backend, &mut context, "get missing").await?,
+            RespFrame::NullBulkString(crate::RespNullBulkString)
+        );
+        // the entries of each stream are a [key, entries] pair
+        let streams: RespFrame = RespArray::new([RespArray::new([
+            bulk("s"),
+            RespArray::new([RespArray::new([
+                bulk("1-1"),
+                RespArray::new([bulk("f"), bulk("v")]).into(),
+            ])
+            .into()])
+            .into(),
+        ])
+        .into()])
+        .into();
+        assert_eq!(
+            request(&backend, &mut context, "xread streams s 0").await?,
+            streams
+        );
+
+        // and so are the replies of a transaction
+        for line in ["multi", "zscore z a", "xread streams s 0"] {
+            request(&backend, &mut context, line).await?;
+        }
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([bulk("1.5"), streams]).into()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp3_replies() -> Result<()> {
+        let backend = Backend::new();
+        let mut context = ConnectionContext::default();
+        for line in ["hello 3", "zadd z 1.5 a", "xadd s 1-1 f v"] {
+            request(&backend, &mut context, line).await?;
+        }
+
+        assert_eq!(
+            request(&backend, &mut context, "zscore z a").await?,
+            RespFrame::Double(1.5)
+        );
+        assert_eq!(
+            request(&backend, &mut context, "get missing").await?,
+            RespFrame::Null(crate::RespNull)
+        );
+        let mut streams = crate::RespMap::new();
+        streams.insert(
+            "s".to_string(),
+            RespArray::new([RespArray::new([
+                bulk("1-1"),
+                RespArray::new([bulk("f"), bulk("v")]).into(),
+            ])
+            .into()])
+            .into(),
+        );
+        let streams: RespFrame = streams.into();
+        assert_eq!(
+            request(&backend, &mut context, "xread streams s 0").await?,
+            streams
+        );
+
+        for line in ["multi", "zscore z a", "xread streams s 0"] {
+            request(&backend, &mut context, line).await?;
+        }
+        assert_eq!(
+            request(&backend, &mut context, "exec").await?,
+            RespArray::new([RespFrame::Double(1.5), streams]).into()
+        );
+
+        // back to RESP2
+        request(&backend, &mut context, "hello 2").await?;
+        assert_eq!(
+            request(&backend, &mut context, "zscore z a").await?,
+            bulk("1.5")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resp3_pubsub() -> Result<()> {
+        let backend = Backend::new();
+        let mut subscriber = ConnectionContext::default();
+        let mut publisher = ConnectionContext::default();
+
+        assert!(matches!(
+            request(&backend, &mut subscriber, "hello 3").await?,
+            RespFrame::Map(_)
+        ));
+        assert!(subscriber.resp3);
+
+        // the replies about the subscriptions are pushed too
+        assert_eq!(
+            request(&backend, &mut subscriber, "ssubscribe news").await?,
+            RespPush::new([
+                BulkString::from("ssubscribe").into(),
+                BulkString::from("news").into(),
+                RespFrame::Integer(1),
+            ])
+            .into()
+        );
+        // any command runs while subscribed
+        assert_eq!(request(&backend, &mut subscriber, "set key 1").await?, ok());
+
+        assert_eq!(
+            request(&backend, &mut publisher, "spublish news hello").await?,
+            RespFrame::Integer(1)
+        );
+        let message = next_message(&mut subscriber.subscriber).await.unwrap();
+        assert_eq!(
+            push_frame(message.into(), subscriber.resp3),
+            RespPush::new([
+                BulkString::from("smessage").into(),
+                BulkString::from("news").into(),
+                BulkString::from("hello").into(),
+            ])
+            .into()
+        );
+
+        // back to RESP2, the subscribed mode applies again
+        request(&backend, &mut subscriber, "hello 2").await?;
+        assert!(matches!(
+            request(&backend, &mut subscriber, "get key").await?,
+            RespFrame::Error(_)
+        ));
+        assert!(matches!(
+            request(&backend, &mut subscriber, "sunsubscribe").await?,
+            RespFrame::Array(_)
+        ));
+        assert!(subscriber.subscriber.is_none());
+
+        Ok(())
+    }
+}
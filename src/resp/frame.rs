@@ -2,9 +2,9 @@ use bytes::BytesMut;
 use enum_dispatch::enum_dispatch;
 
 use super::{
-    array::RespArray, bulk_string::BulkString, map::RespMap, null::RespNull, set::RespSet,
-    simple_error::SimpleError, simple_string::SimpleString, RespDecode, RespError, RespNullArray,
-    RespNullBulkString,
+    array::RespArray, bulk_string::BulkString, map::RespMap, null::RespNull, push::RespPush,
+    set::RespSet, simple_error::SimpleError, simple_string::SimpleString, RespDecode, RespError,
+    RespNullArray, RespNullBulkString,
 };
 
 #[enum_dispatch(RespEncode)]
@@ -23,6 +23,7 @@ pub enum RespFrame {
     Double(f64),
     Map(RespMap),
     Set(RespSet),
+    Push(RespPush),
 }
 
 impl RespDecode for RespFrame {
@@ -85,6 +86,10 @@ impl RespDecode for RespFrame {
                 let frame = RespSet::decode(buf)?;
                 Ok(frame.into())
             }
+            Some(b'>') => {
+                let frame = RespPush::decode(buf)?;
+                Ok(frame.into())
+            }
             None => Err(RespError::NotComplete),
             _ => Err(RespError::InvalidFrameType(format!(
                 "expect_length: unknown frame type: {:?}",
@@ -99,6 +104,7 @@ impl RespDecode for RespFrame {
             Some(b'*') => RespArray::expect_length(buf),
             Some(b'~') => RespSet::expect_length(buf),
             Some(b'%') => RespMap::expect_length(buf),
+            Some(b'>') => RespPush::expect_length(buf),
             Some(b'$') => BulkString::expect_length(buf),
             Some(b':') => i64::expect_length(buf),
             Some(b'+') => SimpleString::expect_length(buf),
@@ -111,6 +117,60 @@ impl RespDecode for RespFrame {
     }
 }
 
+impl RespFrame {
+    /// The frame for a RESP2 client, which can't parse the types RESP3 added: a double becomes a
+    /// bulk string, a map a flat array of its keys and values, a set or a push an array, a
+    /// boolean an integer and a null a null bulk string, recursively.
+    pub fn into_resp2(self) -> RespFrame {
+        match self {
+            RespFrame::Array(array) => array_into_resp2(array.0),
+            RespFrame::Set(set) => array_into_resp2(set.0),
+            RespFrame::Push(push) => array_into_resp2(push.0),
+            RespFrame::Map(map) => RespArray::new(
+                map.0
+                    .into_iter()
+                    .flat_map(|(key, value)| [BulkString::from(key).into(), value.into_resp2()])
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RespFrame::Double(v) => BulkString::from(format_double(v)).into(),
+            RespFrame::Boolean(v) => RespFrame::Integer(v as i64),
+            RespFrame::Null(_) => RespFrame::NullBulkString(RespNullBulkString),
+            frame => frame,
+        }
+    }
+}
+
+fn array_into_resp2(frames: Vec<RespFrame>) -> RespFrame {
+    RespArray::new(
+        frames
+            .into_iter()
+            .map(RespFrame::into_resp2)
+            .collect::<Vec<_>>(),
+    )
+    .into()
+}
+
+// a double the way redis sends it to RESP2 clients, like printf's "%.17g" with the shortest
+// digits: 1.5, 3, 1e+20, 1e-05, inf
+fn format_double(v: f64) -> String {
+    if v.is_infinite() {
+        return if v > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let exponent = v.abs().log10().floor();
+    if v == 0.0 || (-4.0..17.0).contains(&exponent) {
+        return v.to_string();
+    }
+    let s = format!("{:e}", v);
+    match s.split_once('e') {
+        Some((mantissa, exponent)) if exponent.starts_with('-') => {
+            format!("{}e-{:0>2}", mantissa, &exponent[1..])
+        }
+        Some((mantissa, exponent)) => format!("{}e+{:0>2}", mantissa, exponent),
+        None => s,
+    }
+}
+
 impl From<&str> for RespFrame {
     fn from(s: &str) -> Self {
         SimpleString(s.to_string()).into()
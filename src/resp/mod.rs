@@ -6,6 +6,7 @@ mod frame;
 mod integer;
 mod map;
 mod null;
+mod push;
 mod set;
 mod simple_error;
 mod simple_string;
@@ -16,6 +17,7 @@ pub use self::{
     frame::RespFrame,
     map::RespMap,
     null::RespNull,
+    push::RespPush,
     set::RespSet,
     simple_error::SimpleError,
     simple_string::SimpleString,
@@ -123,8 +125,8 @@ fn calc_total_length(buf: &[u8], end: usize, len: usize, prefix: &str) -> Result
     let mut data = &buf[total..];
 
     match prefix {
-        "*" | "~" => {
-            // find nth CRLF in the buffer, for array and set, we need to find 1 CRLF for each element
+        "*" | "~" | ">" => {
+            // find nth CRLF in the buffer, for array, set and push, we need to find 1 CRLF for each element
             for _ in 0..len {
                 let len = RespFrame::expect_length(data)?;
                 data = &data[len..];
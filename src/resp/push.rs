@@ -0,0 +1,111 @@
+use std::ops::Deref;
+
+use bytes::{Buf, BytesMut};
+
+use super::{
+    calc_total_length, parse_length, RespDecode, RespEncode, RespError, RespFrame, BUF_CAP,
+    CRLF_LEN,
+};
+
+// out of band data of RESP3, e.g. the messages of pub/sub. It looks like an array, but a client
+// can tell it from the reply of a command
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct RespPush(pub(crate) Vec<RespFrame>);
+
+impl RespPush {
+    pub fn new(v: impl Into<Vec<RespFrame>>) -> Self {
+        RespPush(v.into())
+    }
+}
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespEncode for RespPush {
+    fn encode(self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(BUF_CAP);
+        buf.extend_from_slice(format!(">{}\r\n", self.0.len()).as_bytes());
+        for frame in self.0 {
+            buf.extend_from_slice(&frame.encode());
+        }
+        buf
+    }
+}
+
+// - push: "><number-of-elements>\r\n<element-1>...<element-n>"
+impl RespDecode for RespPush {
+    const PREFIX: &'static str = ">";
+
+    fn decode(buf: &mut BytesMut) -> Result<Self, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        let total_len = calc_total_length(buf, end, len, Self::PREFIX)?;
+
+        if buf.len() < total_len {
+            return Err(RespError::NotComplete);
+        }
+
+        buf.advance(end + CRLF_LEN);
+
+        let mut frames = Vec::with_capacity(len);
+        for _ in 0..len {
+            frames.push(RespFrame::decode(buf)?);
+        }
+
+        Ok(Self::new(frames))
+    }
+
+    fn expect_length(buf: &[u8]) -> Result<usize, RespError> {
+        let (end, len) = parse_length(buf, Self::PREFIX)?;
+        calc_total_length(buf, end, len, Self::PREFIX)
+    }
+}
+
+impl Deref for RespPush {
+    type Target = Vec<RespFrame>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BulkString;
+    use anyhow::Result;
+
+    #[test]
+    fn test_push_encode() {
+        let frame: RespFrame = RespPush::new(vec![
+            BulkString::new("message").into(),
+            BulkString::new("news").into(),
+            BulkString::new("hello").into(),
+        ])
+        .into();
+
+        assert_eq!(
+            &frame.encode(),
+            b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n"
+        );
+    }
+
+    #[test]
+    fn test_push_decode() -> Result<()> {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(b">2\r\n$7\r\nmessage\r\n$4\r\nnews\r\n");
+
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespPush::new([b"message".into(), b"news".into()]).into()
+        );
+
+        buf.extend_from_slice(b">2\r\n$7\r\nmessage\r\n");
+        let ret = RespPush::decode(&mut buf);
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+
+        buf.extend_from_slice(b"$4\r\nnews\r\n");
+        let frame = RespPush::decode(&mut buf)?;
+        assert_eq!(frame, RespPush::new([b"message".into(), b"news".into()]));
+
+        Ok(())
+    }
+}
@@ -29,7 +29,7 @@ mod test {
     use std::collections::BTreeMap;
 
     use super::*;
-    use crate::{RespFrame, RespNullArray, RespNullBulkString};
+    use crate::{RespFrame, RespNullArray, RespNullBulkString, RespPush};
     use anyhow::Result;
 
     #[test]
@@ -161,6 +161,31 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn respv2_push_length_should_work() -> Result<()> {
+        let buf = b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n";
+        let len = RespFrame::expect_length(buf)?;
+        assert_eq!(len, buf.len());
+
+        let ret = RespFrame::expect_length(b">3\r\n$7\r\nmessage\r\n");
+        assert_eq!(ret.unwrap_err(), RespError::NotComplete);
+        Ok(())
+    }
+
+    #[test]
+    fn respv2_push_should_work() -> Result<()> {
+        let mut buf = BytesMut::from(">2\r\n$7\r\nmessage\r\n$4\r\nnews\r\n");
+        let frame = RespFrame::decode(&mut buf)?;
+        assert_eq!(
+            frame,
+            RespFrame::Push(RespPush::new([
+                RespFrame::BulkString("message".into()),
+                RespFrame::BulkString("news".into())
+            ]))
+        );
+        Ok(())
+    }
+
     #[test]
     fn respv2_map_length_should_work() -> Result<()> {
         let buf = b"%1\r\n+OK\r\n-ERR\r\n";
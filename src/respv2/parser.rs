@@ -10,7 +10,7 @@ use winnow::{
 
 use crate::{
     BulkString, RespArray, RespError, RespFrame, RespMap, RespNull, RespNullArray,
-    RespNullBulkString, SimpleError, SimpleString,
+    RespNullBulkString, RespPush, SimpleError, SimpleString,
 };
 
 const CRLF: &[u8] = b"\r\n";
@@ -42,6 +42,7 @@ fn parse_frame_len(input: &mut &[u8]) -> PResult<()> {
         b'#' => simple_parser,
         b',' => simple_parser,
         b'%' => map_len,
+        b'>' => array_len,
         // b'~' => set,
         _v => fail::<_, _, _>,
     }
@@ -60,6 +61,7 @@ pub fn parse_frame(input: &mut &[u8]) -> PResult<RespFrame> {
         b'#' => boolean.map(RespFrame::Boolean),
         b',' => double.map(RespFrame::Double),
         b'%' => map.map(RespFrame::Map),
+        b'>' => push.map(RespFrame::Push),
         // b'~' => set,
         _v=> fail::<_, _, _>,
     }
@@ -148,6 +150,11 @@ fn array(input: &mut &[u8]) -> PResult<RespArray> {
     Ok(RespArray(frames))
 }
 
+// - push: ">2\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n", the same layout as an array
+fn push(input: &mut &[u8]) -> PResult<RespPush> {
+    array.map(|v| RespPush(v.0)).parse_next(input)
+}
+
 fn array_len(input: &mut &[u8]) -> PResult<()> {
     let len = integer.parse_next(input)?;
     if len < -1 {